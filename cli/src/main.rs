@@ -0,0 +1,1793 @@
+// Command line client for a deployed multisig.
+//
+// Everyday flow:
+//   1. `multisig-cli create --owner <KEY> --owner <KEY> --threshold 2` prints the new multisig
+//   2. any owner: `multisig-cli propose <MULTISIG> transfer-token --mint ... --to ... --amount ...`
+//   3. owners: `multisig-cli show <TRANSACTION>`, then `multisig-cli approve <TRANSACTION>`
+//   4. anyone: `multisig-cli execute <TRANSACTION>`; the veto authority may instead
+//      `multisig-cli cancel <TRANSACTION>`. `multisig-cli show <MULTISIG>` shows the wallet
+//
+// `-u` picks the cluster (`devnet`, `mainnet-beta`, ... or an RPC URL, localnet by default) and
+// `--program-id` a deployment other than the cluster's.
+//
+// Escrowed deploy/upgrade flow:
+//   1. developer: `solana program write-buffer program.so` then
+//      `solana program set-buffer-authority <BUFFER> --new-buffer-authority <VAULT>`
+//      (`multisig-cli vault <MULTISIG>` prints the vault address)
+//   2. any owner: `multisig-cli propose-deploy ...` / `multisig-cli propose-upgrade ...`
+//   3. owners: `multisig-cli show <TRANSACTION> --so program.so` to check the bytecode hash,
+//      then `multisig-cli approve <TRANSACTION>`
+//   4. anyone: `multisig-cli execute <TRANSACTION>` (deploys also need `--program-keypair`)
+//
+// `multisig-cli inspect <SIGNATURE>` decodes a landed transaction for incident reviews,
+// `multisig-cli history <MULTISIG>` lists every multisig instruction that touched the wallet.
+// Owners who ran `multisig-cli inbox register` get their inbox flagged by every `propose`.
+//
+// Disaster recovery: `multisig-cli export <MULTISIG> --out bundle.json` writes a signed snapshot,
+// `multisig-cli import bundle.json` verifies it offline (and can write the accounts out for
+// `solana-test-validator`, or compare them with the chain).
+//
+// Migration: `multisig-cli migrate-from squads-v4 <MULTISIG>` (or `squads-v3`, or
+// `spl-governance <GOVERNANCE>`) creates a multisig with the source treasury's owners and threshold,
+// `--dry-run` only prints what it read.
+//
+// Key compromise: every owner runs `multisig-cli respond-compromise <MULTISIG> --compromised <KEY>
+// --replacement <KEY> --cold-address <ADDRESS>`. It freezes the wallet and vetoes open proposals
+// when the `--keypair` holds those roles, approves the owner swap and sends it once enough owners
+// have run it, then prints the `multisig-cli sweep` that moves the funds to the cold address.
+use clap::{Parser, Subcommand, ValueEnum};
+use multisig_client::cluster::Cluster;
+use multisig_client::{compute_budget, display, export, fetch, migrate, setup, simulate};
+use solana_client::client_error::ClientError;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_multisig_wallet::instruction::ProposeOptions;
+use solana_multisig_wallet::state::load;
+use solana_multisig_wallet::{
+    buffer_hash, find_inbox_address, find_indexed_vault_address, find_owner_index_address,
+    find_receipt_address, find_receipt_ledger_address, find_vault_address, instruction, Inbox,
+    Multisig, MultisigError, MultisigInstruction, ProposalAction, Receipt, ReceiptLedger,
+    Transaction,
+};
+use solana_program::hash::hash;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedTransaction, UiInstruction, UiMessage, UiParsedInstruction, UiParsedMessage,
+    UiPartiallyDecodedInstruction, UiTransactionEncoding, UiTransactionStatusMeta,
+};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::PathBuf;
+
+type CliResult = Result<(), Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(
+    name = "multisig-cli",
+    about = "Interact with a deployed multisig wallet"
+)]
+struct Cli {
+    /// Cluster: mainnet-beta, devnet, testnet, localnet, or an RPC URL
+    #[arg(long, short = 'u', default_value = "localnet")]
+    url: Cluster,
+    /// Keypair paying fees and signing as owner
+    #[arg(long, short = 'k', default_value = "~/.config/solana/id.json")]
+    keypair: String,
+    /// Multisig program id, the cluster's deployment by default
+    #[arg(long)]
+    program_id: Option<Pubkey>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a multisig, the `--keypair` pays for the account
+    Create {
+        /// Owner key, repeat for each owner
+        #[arg(long = "owner", required = true)]
+        owners: Vec<Pubkey>,
+        #[arg(long)]
+        threshold: u8,
+        /// Lamports the `--keypair` sends to the main vault
+        #[arg(long, default_value_t = 0)]
+        funding: u64,
+    },
+    /// Propose a transfer from a vault
+    Propose {
+        multisig: Pubkey,
+        /// Vault paying
+        #[arg(long, default_value_t = 0)]
+        vault: u8,
+        /// Lamports the vault pays whoever executes the approved proposal (`execute --collect-fee`)
+        #[arg(long, default_value_t = 0)]
+        executor_fee: u64,
+        /// Document the proposal is about (an invoice, a governance doc), its SHA-256 is part of
+        /// what owners approve and gets logged on execution
+        #[arg(long)]
+        attachment: Option<PathBuf>,
+        #[command(subcommand)]
+        action: ProposeAction,
+    },
+    /// Print a vault PDA (buffer / upgrade authority) of a multisig
+    Vault {
+        multisig: Pubkey,
+        /// Vault index, 0 is the main vault
+        #[arg(long, default_value_t = 0)]
+        index: u8,
+    },
+    /// Propose deploying a new program from a buffer owned by the vault
+    ProposeDeploy {
+        #[arg(long)]
+        multisig: Pubkey,
+        #[arg(long)]
+        buffer: Pubkey,
+        /// Address of the program to deploy (its keypair is needed at execution)
+        #[arg(long)]
+        program: Pubkey,
+        #[arg(long)]
+        max_data_len: u64,
+        /// Vault paying for and owning the program
+        #[arg(long, default_value_t = 0)]
+        vault: u8,
+    },
+    /// Propose upgrading a program whose upgrade authority is the vault
+    ProposeUpgrade {
+        #[arg(long)]
+        multisig: Pubkey,
+        #[arg(long)]
+        buffer: Pubkey,
+        #[arg(long)]
+        program: Pubkey,
+        /// Vault holding the upgrade authority
+        #[arg(long, default_value_t = 0)]
+        vault: u8,
+    },
+    /// Propose handing the vault's upgrade authority over a program to another key
+    ProposeSetUpgradeAuthority {
+        #[arg(long)]
+        multisig: Pubkey,
+        #[arg(long)]
+        program: Pubkey,
+        /// New upgrade authority, omit to make the program immutable
+        #[arg(long)]
+        new_authority: Option<Pubkey>,
+        /// Vault holding the upgrade authority
+        #[arg(long, default_value_t = 0)]
+        vault: u8,
+    },
+    /// Propose replacing one owner with another, keeping the owner count and threshold
+    ProposeSwapOwner {
+        #[arg(long)]
+        multisig: Pubkey,
+        #[arg(long)]
+        old_owner: Pubkey,
+        #[arg(long)]
+        new_owner: Pubkey,
+    },
+    /// Show a multisig or a proposal, optionally checking the proposal against a local program
+    /// binary
+    Show {
+        /// Multisig or proposal address
+        address: Pubkey,
+        #[arg(long)]
+        so: Option<PathBuf>,
+    },
+    /// Approve a proposal as the `--keypair` owner
+    Approve { transaction: Pubkey },
+    /// Sign an approval of a proposal as the `--keypair` owner without sending anything, prints
+    /// the signature for `relay-approval`
+    SignApproval { transaction: Pubkey },
+    /// Submit an owner's signed approval, the `--keypair` pays for the transaction
+    RelayApproval {
+        transaction: Pubkey,
+        owner: Pubkey,
+        signature: Signature,
+    },
+    /// Show, register or clear the `--keypair` owner's notification inbox
+    Inbox {
+        #[arg(value_enum, default_value_t = InboxAction::Show)]
+        action: InboxAction,
+    },
+    /// Execute a fully approved proposal
+    Execute {
+        transaction: Pubkey,
+        #[arg(long)]
+        program_keypair: Option<String>,
+        /// Only simulate, printing whether it would succeed and the balances it would change
+        #[arg(long)]
+        dry_run: bool,
+        /// Collect the proposal's executor fee, for keepers executing on the owners' behalf
+        #[arg(long)]
+        collect_fee: bool,
+        /// Size the compute unit limit from a simulation and add a priority fee from recent ones,
+        /// for landing during congestion
+        #[arg(long)]
+        priority: bool,
+    },
+    /// List the multisigs in an owner's index, the `--keypair` owner's by default
+    Multisigs { owner: Option<Pubkey> },
+    /// Create the `--keypair` owner's index, listing the given multisigs they belong to
+    RegisterOwnerIndex { multisigs: Vec<Pubkey> },
+    /// Cancel a pending proposal as the `--keypair` veto authority, refunding its proposer
+    Cancel { transaction: Pubkey },
+    /// Close a proposal past its grace period after execution, cancellation or expiry, refunding
+    /// its proposer; `--keypair` pays the fee
+    Close { transaction: Pubkey },
+    /// Decode the multisig instructions of a landed transaction, with the CPIs they made
+    Inspect { signature: Signature },
+    /// Chronological audit report of the wallet and its open proposals
+    History {
+        multisig: Pubkey,
+        #[arg(long, value_enum, default_value_t = HistoryFormat::Text)]
+        format: HistoryFormat,
+    },
+    /// Payments a destination received from the multisig, from its receipt ledger
+    Receipts {
+        multisig: Pubkey,
+        destination: Pubkey,
+    },
+    /// Guided response to a leaked owner key: freeze, cancel, replace the key, draft a sweep
+    RespondCompromise {
+        multisig: Pubkey,
+        #[arg(long)]
+        compromised: Pubkey,
+        /// Owner key taking the compromised key's place
+        #[arg(long)]
+        replacement: Pubkey,
+        /// Where the sweep sends the funds
+        #[arg(long)]
+        cold_address: Pubkey,
+        /// Only print the steps
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Send the multisig's whole balance above rent to an address (needs the threshold)
+    Sweep {
+        multisig: Pubkey,
+        #[arg(long)]
+        to: Pubkey,
+    },
+    /// Pay lamports from `--keypair` into the multisig's main vault, recorded as a deposit
+    Deposit {
+        multisig: Pubkey,
+        #[arg(long)]
+        lamports: u64,
+    },
+    /// Label the wallet with a name and an icon URI (needs the threshold)
+    SetMetadata {
+        multisig: Pubkey,
+        #[arg(long)]
+        name: String,
+        #[arg(long, default_value = "")]
+        uri: String,
+    },
+    /// Move the multisig account's balance above rent into its main vault, for wallets that
+    /// predate vaults
+    MigrateToVault { multisig: Pubkey },
+    /// Write a disaster-recovery bundle of the wallet, signed by `--keypair`
+    Export {
+        multisig: Pubkey,
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Verify a disaster-recovery bundle
+    Import {
+        bundle: PathBuf,
+        /// Fail unless the bundle was signed by this key
+        #[arg(long)]
+        signer: Option<Pubkey>,
+        /// Write every account as a `solana-test-validator --account` file into this directory
+        #[arg(long)]
+        accounts_dir: Option<PathBuf>,
+        /// Compare the bundled accounts with the current chain state
+        #[arg(long)]
+        compare: bool,
+    },
+    /// Create a multisig with the owners and threshold of a Squads or SPL-Governance treasury
+    MigrateFrom {
+        #[arg(value_enum)]
+        source: MigrationSource,
+        /// Squads multisig account, or the SPL-Governance governance
+        address: Pubkey,
+        /// The `--keypair` funds the new main vault with the source vault's balance
+        #[arg(long)]
+        fund: bool,
+        /// Only print the imported configuration
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProposeAction {
+    /// Tokens from the vault's associated token account to a token account
+    TransferToken {
+        #[arg(long)]
+        mint: Pubkey,
+        /// Destination token account
+        #[arg(long)]
+        to: Pubkey,
+        /// Amount in base units, transfer fee included
+        #[arg(long)]
+        amount: u64,
+        /// Highest transfer fee (Token-2022) the execution accepts
+        #[arg(long, default_value_t = 0)]
+        max_fee: u64,
+        /// Decimals of the mint the amount was worked out with, execution fails on any other
+        #[arg(long)]
+        decimals: u8,
+        #[arg(long, default_value_t = spl_token::id())]
+        token_program: Pubkey,
+    },
+    /// Lamports from the vault, paid as wrapped SOL to a token account
+    TransferWrappedSol {
+        /// Destination wrapped SOL token account
+        #[arg(long)]
+        to: Pubkey,
+        #[arg(long)]
+        lamports: u64,
+    },
+    /// A schedule paying lamports from the vault every interval
+    RecurringPayment {
+        #[arg(long)]
+        to: Pubkey,
+        /// Lamports per payment
+        #[arg(long)]
+        lamports: u64,
+        #[arg(long)]
+        interval_seconds: i64,
+        #[arg(long)]
+        count: u32,
+    },
+}
+
+impl From<ProposeAction> for ProposalAction {
+    fn from(action: ProposeAction) -> Self {
+        match action {
+            ProposeAction::TransferToken {
+                mint,
+                to,
+                amount,
+                max_fee,
+                decimals,
+                token_program,
+            } => ProposalAction::TransferToken {
+                token_program,
+                mint,
+                destination: to,
+                amount,
+                max_fee,
+                expected_decimals: decimals,
+            },
+            ProposeAction::TransferWrappedSol { to, lamports } => {
+                ProposalAction::TransferWrappedSol {
+                    destination: to,
+                    amount: lamports,
+                }
+            }
+            ProposeAction::RecurringPayment {
+                to,
+                lamports,
+                interval_seconds,
+                count,
+            } => ProposalAction::CreateRecurringPayment {
+                destination: to,
+                amount: lamports,
+                interval_seconds,
+                count,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum InboxAction {
+    Show,
+    Register,
+    Clear,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum MigrationSource {
+    SquadsV3,
+    SquadsV4,
+    SplGovernance,
+}
+
+impl From<MigrationSource> for migrate::Source {
+    fn from(source: MigrationSource) -> Self {
+        match source {
+            MigrationSource::SquadsV3 => migrate::Source::SquadsV3,
+            MigrationSource::SquadsV4 => migrate::Source::SquadsV4,
+            MigrationSource::SplGovernance => migrate::Source::SplGovernance,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum HistoryFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+fn main() -> CliResult {
+    let cli = Cli::parse();
+    let cluster = match cli.program_id {
+        Some(program_id) => cli.url.clone().with_program_id(program_id),
+        None => cli.url.clone(),
+    };
+    let program_id = cluster.program_id();
+    let rpc = cluster.rpc_client(CommitmentConfig::confirmed());
+    let read_payer = || read_keypair_file(expand_tilde(&cli.keypair));
+
+    match cli.command {
+        Command::Create {
+            owners,
+            threshold,
+            funding,
+        } => create(
+            &rpc,
+            &read_payer()?,
+            &program_id,
+            owners,
+            threshold,
+            funding,
+        ),
+        Command::Propose {
+            multisig,
+            vault,
+            executor_fee,
+            attachment,
+            action,
+        } => propose_with_options(
+            &rpc,
+            &read_payer()?,
+            &program_id,
+            multisig,
+            action.into(),
+            ProposeOptions {
+                vault,
+                executor_fee,
+                attachment: attachment
+                    .map(|path| std::fs::read(path).map(|document| hash(&document).to_bytes()))
+                    .transpose()?,
+                ..ProposeOptions::default()
+            },
+        ),
+        Command::Vault { multisig, index } => {
+            let (vault, _) = find_indexed_vault_address(&multisig, index, &program_id);
+            println!("{vault}");
+            Ok(())
+        }
+        Command::ProposeDeploy {
+            multisig,
+            buffer,
+            program,
+            max_data_len,
+            vault,
+        } => {
+            let buffer_hash = fetch_buffer_hash(&rpc, &buffer)?;
+            let action = ProposalAction::DeployProgram {
+                program,
+                buffer,
+                max_data_len,
+                buffer_hash,
+            };
+            propose(&rpc, &read_payer()?, &program_id, multisig, vault, action)
+        }
+        Command::ProposeUpgrade {
+            multisig,
+            buffer,
+            program,
+            vault,
+        } => {
+            let buffer_hash = fetch_buffer_hash(&rpc, &buffer)?;
+            let action = ProposalAction::UpgradeProgram {
+                program,
+                buffer,
+                buffer_hash,
+            };
+            propose(&rpc, &read_payer()?, &program_id, multisig, vault, action)
+        }
+        Command::ProposeSetUpgradeAuthority {
+            multisig,
+            program,
+            new_authority,
+            vault,
+        } => {
+            let action = ProposalAction::SetUpgradeAuthority {
+                program,
+                new_authority,
+            };
+            propose(&rpc, &read_payer()?, &program_id, multisig, vault, action)
+        }
+        Command::ProposeSwapOwner {
+            multisig,
+            old_owner,
+            new_owner,
+        } => {
+            let action = ProposalAction::SwapOwner {
+                old_owner,
+                new_owner,
+            };
+            propose(&rpc, &read_payer()?, &program_id, multisig, 0, action)
+        }
+        Command::Show { address, so } => {
+            let account = rpc.get_account(&address)?;
+            if account.owner == program_id && account.data.len() == Multisig::LEN {
+                show_multisig(&rpc, &program_id, &address)
+            } else {
+                show(&rpc, &program_id, address, so)
+            }
+        }
+        Command::Approve { transaction } => {
+            let payer = read_payer()?;
+            let proposal = fetch::fetch_transaction(&rpc, &program_id, &transaction)?;
+            let ix = instruction::approve(
+                &program_id,
+                &payer.pubkey(),
+                &proposal.multisig,
+                &transaction,
+                &proposal.action,
+            );
+            send(&rpc, &[ix], &payer, &[])
+        }
+        Command::SignApproval { transaction } => {
+            let owner = read_payer()?;
+            let proposal = fetch::fetch_transaction(&rpc, &program_id, &transaction)?;
+            let digest = proposal.digest(&program_id, &transaction);
+            println!("Digest: {digest}");
+            println!("Owner: {}", owner.pubkey());
+            println!("Signature: {}", owner.sign_message(digest.as_ref()));
+            Ok(())
+        }
+        Command::RelayApproval {
+            transaction,
+            owner,
+            signature,
+        } => {
+            let payer = read_payer()?;
+            let proposal = fetch::fetch_transaction(&rpc, &program_id, &transaction)?;
+            let digest = proposal.digest(&program_id, &transaction);
+            if !signature.verify(owner.as_ref(), digest.as_ref()) {
+                return Err(format!("Not {owner}'s approval of {transaction}").into());
+            }
+            let instructions = instruction::approve_with_signature(
+                &program_id,
+                &owner,
+                signature.as_ref().try_into()?,
+                &transaction,
+                &proposal,
+            );
+            send(&rpc, &instructions, &payer, &[])
+        }
+        Command::Inbox { action } => inbox(&rpc, &read_payer()?, &program_id, action),
+        Command::Execute {
+            transaction,
+            program_keypair,
+            dry_run,
+            collect_fee,
+            priority,
+        } => {
+            let payer = read_payer()?;
+            let proposal = fetch::fetch_transaction(&rpc, &program_id, &transaction)?;
+            let program_keypair = program_keypair
+                .map(|path| read_keypair_file(expand_tilde(&path)))
+                .transpose()?;
+            if matches!(proposal.action, ProposalAction::DeployProgram { .. })
+                && program_keypair.is_none()
+                && !dry_run
+            {
+                return Err("deploy proposals need --program-keypair".into());
+            }
+            let mut ix = if collect_fee {
+                instruction::crank_transaction(
+                    &program_id,
+                    &payer.pubkey(),
+                    &proposal.multisig,
+                    &transaction,
+                    proposal.vault,
+                    &proposal.action,
+                )
+            } else {
+                instruction::execute_transaction_from_vault(
+                    &program_id,
+                    &proposal.multisig,
+                    &transaction,
+                    proposal.vault,
+                    &proposal.action,
+                )
+            };
+            // the old owner's approvals on other open proposals must not carry over
+            if let ProposalAction::SwapOwner {
+                old_owner,
+                new_owner,
+            } = proposal.action
+            {
+                let pending = fetch::fetch_transactions(&rpc, &program_id, &proposal.multisig)?;
+                ix.accounts.extend(
+                    pending
+                        .iter()
+                        .filter(|(key, pending)| *key != transaction && !pending.executed)
+                        .map(|(key, _)| AccountMeta::new(*key, false)),
+                );
+                let indexed = registered_owner_indexes(&rpc, &program_id, &[old_owner, new_owner])?;
+                ix.accounts
+                    .extend(instruction::owner_index_accounts(&program_id, &indexed));
+            }
+            if dry_run {
+                let simulation = simulate::simulate_instruction(&rpc, &payer.pubkey(), ix)?;
+                print_simulation(&simulation);
+                return Ok(());
+            }
+            let extra_signers: Vec<&Keypair> = program_keypair.iter().collect();
+            let instructions = if priority {
+                compute_budget::estimate_budget(&rpc, &payer.pubkey(), &[ix.clone()])?
+                    .prepend(vec![ix])
+            } else {
+                compute_budget::with_compute_budget(&proposal.action, ix)
+            };
+            send(&rpc, &instructions, &payer, &extra_signers)
+        }
+        Command::Multisigs { owner } => {
+            let owner = match owner {
+                Some(owner) => owner,
+                None => read_payer()?.pubkey(),
+            };
+            // owners who never registered an index are found by scanning the program's accounts
+            let multisigs = match fetch::fetch_owner_multisigs(&rpc, &program_id, &owner) {
+                Err(fetch::FetchError::AccountNotFound(_)) => {
+                    fetch::fetch_multisigs_with_owner(&rpc, &program_id, &owner)?
+                }
+                result => result?,
+            };
+            for multisig in multisigs {
+                println!("{multisig}");
+            }
+            Ok(())
+        }
+        Command::RegisterOwnerIndex { multisigs } => {
+            let payer = read_payer()?;
+            let ix = instruction::register_owner_index(&program_id, &payer.pubkey(), &multisigs);
+            send(&rpc, &[ix], &payer, &[])
+        }
+        Command::Cancel { transaction } => {
+            let payer = read_payer()?;
+            let proposal = fetch::fetch_transaction(&rpc, &program_id, &transaction)?;
+            let multisig = load::<Multisig>(&rpc.get_account_data(&proposal.multisig)?)?;
+            match multisig.veto_authority {
+                Some(authority) if authority == payer.pubkey() => {}
+                Some(authority) => {
+                    return Err(format!("only the veto authority {authority} can cancel").into())
+                }
+                None => return Err("the multisig has no veto authority".into()),
+            }
+            let ix = instruction::veto_transaction(
+                &program_id,
+                &payer.pubkey(),
+                &proposal.multisig,
+                &transaction,
+                &proposal.proposer,
+            );
+            send(&rpc, &[ix], &payer, &[])
+        }
+        Command::Close { transaction } => {
+            let payer = read_payer()?;
+            let proposal = fetch::fetch_transaction(&rpc, &program_id, &transaction)?;
+            let ix = instruction::close_transaction(
+                &program_id,
+                &proposal.multisig,
+                &transaction,
+                &proposal.proposer,
+            );
+            send(&rpc, &[ix], &payer, &[])
+        }
+        Command::Inspect { signature } => inspect(&rpc, &program_id, &signature),
+        Command::History { multisig, format } => history(&rpc, &program_id, &multisig, format),
+        Command::Receipts {
+            multisig,
+            destination,
+        } => receipts(&rpc, &program_id, &multisig, &destination),
+        Command::RespondCompromise {
+            multisig,
+            compromised,
+            replacement,
+            cold_address,
+            dry_run,
+        } => respond_compromise(
+            &rpc,
+            &read_payer()?,
+            &program_id,
+            &multisig,
+            &Compromise {
+                compromised,
+                replacement,
+                cold_address,
+            },
+            dry_run,
+        ),
+        Command::Sweep { multisig, to } => {
+            let amount = sweepable_balance(&rpc, &multisig)?;
+            let ix = instruction::execute(&program_id, &multisig, amount, &to);
+            send(&rpc, &[ix], &read_payer()?, &[])
+        }
+        Command::Deposit { multisig, lamports } => {
+            let payer = read_payer()?;
+            let ix = instruction::deposit(&program_id, &payer.pubkey(), &multisig, lamports);
+            send(&rpc, &[ix], &payer, &[])
+        }
+        Command::SetMetadata {
+            multisig,
+            name,
+            uri,
+        } => {
+            let payer = read_payer()?;
+            let ix =
+                instruction::set_metadata(&program_id, &payer.pubkey(), &multisig, &name, &uri);
+            send(&rpc, &[ix], &payer, &[])
+        }
+        Command::MigrateToVault { multisig } => {
+            let ix = instruction::migrate_to_vault(&program_id, &multisig);
+            send(&rpc, &[ix], &read_payer()?, &[])
+        }
+        Command::Export { multisig, out } => {
+            let bundle = export::export(&rpc, &program_id, &multisig, &read_payer()?)?;
+            std::fs::write(&out, serde_json::to_string_pretty(&bundle)?)?;
+            println!("Bundle: {}", out.display());
+            Ok(())
+        }
+        Command::Import {
+            bundle,
+            signer,
+            accounts_dir,
+            compare,
+        } => import(&rpc, bundle, signer, accounts_dir, compare),
+        Command::MigrateFrom {
+            source,
+            address,
+            fund,
+            dry_run,
+        } => {
+            let treasury = migrate::fetch_treasury(&rpc, source.into(), &address)?;
+            println!("Source vault: {}", treasury.config.vault);
+            println!(
+                "Source balance: {}",
+                display::format_sol(treasury.vault_lamports)
+            );
+            println!(
+                "Threshold: {} of {}",
+                treasury.config.threshold,
+                treasury.config.owners.len()
+            );
+            for owner in &treasury.config.owners {
+                println!("Owner: {owner}");
+            }
+            if dry_run {
+                return Ok(());
+            }
+            let funding = if fund { treasury.vault_lamports } else { 0 };
+            create(
+                &rpc,
+                &read_payer()?,
+                &program_id,
+                treasury.config.owners,
+                treasury.config.threshold,
+                funding,
+            )
+        }
+    }
+}
+
+fn create(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    owners: Vec<Pubkey>,
+    threshold: u8,
+    funding: u64,
+) -> CliResult {
+    let multisig_keypair = Keypair::new();
+    let indexed = registered_owner_indexes(rpc, program_id, &owners)?;
+    let mut instructions = setup::create_multisig_instructions(
+        program_id,
+        &payer.pubkey(),
+        &multisig_keypair.pubkey(),
+        owners,
+        threshold,
+        rpc.get_minimum_balance_for_rent_exemption(Multisig::LEN)?,
+        funding,
+    )?;
+    if let Some(create) = instructions
+        .iter_mut()
+        .find(|ix| ix.program_id == *program_id)
+    {
+        create
+            .accounts
+            .extend(instruction::owner_index_accounts(program_id, &indexed));
+    }
+    send(rpc, &instructions, payer, &[&multisig_keypair])?;
+    println!("Multisig: {}", multisig_keypair.pubkey());
+    println!(
+        "Vault: {}",
+        find_vault_address(&multisig_keypair.pubkey(), program_id).0
+    );
+    Ok(())
+}
+
+fn propose(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    multisig: Pubkey,
+    vault: u8,
+    action: ProposalAction,
+) -> CliResult {
+    let options = ProposeOptions {
+        vault,
+        ..ProposeOptions::default()
+    };
+    propose_with_options(rpc, payer, program_id, multisig, action, options)
+}
+
+fn propose_with_options(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    multisig: Pubkey,
+    action: ProposalAction,
+    options: ProposeOptions,
+) -> CliResult {
+    let multisig_state = load::<Multisig>(&rpc.get_account_data(&multisig)?)?;
+    let transaction_keypair = Keypair::new();
+    let space = Transaction::space(&action);
+
+    println!("Action: {action:?}");
+    let instructions = [
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &transaction_keypair.pubkey(),
+            rpc.get_minimum_balance_for_rent_exemption(space)?,
+            space as u64,
+            program_id,
+        ),
+        instruction::propose_and_notify(
+            program_id,
+            &payer.pubkey(),
+            &multisig,
+            &transaction_keypair.pubkey(),
+            action,
+            options,
+            &registered_inboxes(rpc, program_id, &multisig_state, &payer.pubkey())?,
+        ),
+    ];
+    send(rpc, &instructions, payer, &[&transaction_keypair])?;
+    println!("Transaction: {}", transaction_keypair.pubkey());
+    Ok(())
+}
+
+// Owners other than `proposer` who registered an inbox
+fn registered_inboxes(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    multisig: &Multisig,
+    proposer: &Pubkey,
+) -> Result<Vec<Pubkey>, Box<dyn Error>> {
+    let owners: Vec<Pubkey> = multisig
+        .owners
+        .iter()
+        .filter(|owner| *owner != proposer)
+        .copied()
+        .collect();
+    let inboxes: Vec<Pubkey> = owners
+        .iter()
+        .map(|owner| find_inbox_address(owner, program_id).0)
+        .collect();
+    let accounts = rpc.get_multiple_accounts(&inboxes)?;
+    Ok(owners
+        .into_iter()
+        .zip(accounts)
+        .filter(|(_, account)| account.as_ref().is_some_and(|a| a.owner == *program_id))
+        .map(|(owner, _)| owner)
+        .collect())
+}
+
+// Those of `owners` who registered an owner index
+fn registered_owner_indexes(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    owners: &[Pubkey],
+) -> Result<Vec<Pubkey>, Box<dyn Error>> {
+    let indexes: Vec<Pubkey> = owners
+        .iter()
+        .map(|owner| find_owner_index_address(owner, program_id).0)
+        .collect();
+    let accounts = rpc.get_multiple_accounts(&indexes)?;
+    Ok(owners
+        .iter()
+        .zip(accounts)
+        .filter(|(_, account)| account.as_ref().is_some_and(|a| a.owner == *program_id))
+        .map(|(owner, _)| *owner)
+        .collect())
+}
+
+fn inbox(rpc: &RpcClient, payer: &Keypair, program_id: &Pubkey, action: InboxAction) -> CliResult {
+    match action {
+        InboxAction::Register => send(
+            rpc,
+            &[instruction::register_inbox(program_id, &payer.pubkey())],
+            payer,
+            &[],
+        ),
+        InboxAction::Clear => send(
+            rpc,
+            &[instruction::clear_inbox(program_id, &payer.pubkey())],
+            payer,
+            &[],
+        ),
+        InboxAction::Show => {
+            let (address, _) = find_inbox_address(&payer.pubkey(), program_id);
+            let inbox = load::<Inbox>(&rpc.get_account_data(&address)?)?;
+            println!("Inbox: {address}");
+            if inbox.pending {
+                println!(
+                    "Pending: {} (multisig {})",
+                    inbox.transaction, inbox.multisig
+                );
+            } else {
+                println!("Nothing pending");
+            }
+            Ok(())
+        }
+    }
+}
+
+fn receipts(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    destination: &Pubkey,
+) -> CliResult {
+    let (address, _) = find_receipt_ledger_address(multisig, destination, program_id);
+    let Some(account) = rpc
+        .get_account_with_commitment(&address, rpc.commitment())?
+        .value
+    else {
+        println!("No receipts");
+        return Ok(());
+    };
+    let ledger = load::<ReceiptLedger>(&account.data)?;
+    println!(
+        "{} payments, {} in total",
+        ledger.count,
+        display::format_sol(ledger.total)
+    );
+    let addresses: Vec<Pubkey> = (0..ledger.count)
+        .map(|index| find_receipt_address(multisig, destination, index, program_id).0)
+        .collect();
+    // `getMultipleAccounts` takes at most 100 keys
+    for chunk in addresses.chunks(100) {
+        for (address, account) in chunk.iter().zip(rpc.get_multiple_accounts(chunk)?) {
+            let Some(account) = account else {
+                println!("{address}: missing");
+                continue;
+            };
+            let receipt = load::<Receipt>(&account.data)?;
+            let transaction = if receipt.transaction == Pubkey::default() {
+                "direct execute".to_string()
+            } else {
+                format!("recurring payment {}", receipt.transaction)
+            };
+            println!(
+                "#{} {} at {} (slot {}), {transaction}",
+                receipt.index,
+                display::format_sol(receipt.amount),
+                receipt.paid_at,
+                receipt.slot
+            );
+        }
+    }
+    Ok(())
+}
+
+fn show_multisig(rpc: &RpcClient, program_id: &Pubkey, address: &Pubkey) -> CliResult {
+    let multisig = load::<Multisig>(&rpc.get_account_data(address)?)?;
+    let (vault, _) = find_vault_address(address, program_id);
+
+    if let Some(metadata) = fetch::fetch_metadata(rpc, program_id, address)? {
+        println!("Name: {}", metadata.name);
+        if !metadata.uri.is_empty() {
+            println!("URI: {}", metadata.uri);
+        }
+    }
+    println!(
+        "Threshold: {} of {}",
+        multisig.threshold,
+        multisig.owners.len()
+    );
+    for (owner, signed) in multisig.owners.iter().zip(&multisig.signers) {
+        println!("  {owner} {}", if *signed { "signed" } else { "-" });
+    }
+    println!(
+        "Balance: {}",
+        display::format_sol(rpc.get_balance(address)?)
+    );
+    println!(
+        "Vault: {vault} ({})",
+        display::format_sol(rpc.get_balance(&vault)?)
+    );
+    if multisig.paused {
+        println!("Paused");
+    }
+    if let Some(authority) = multisig.veto_authority {
+        println!("Veto authority: {authority}");
+    }
+    if let Some(authority) = multisig.emergency_authority {
+        println!("Emergency authority: {authority}");
+    }
+    if let Some(policy) = multisig.amount_policy {
+        println!(
+            "Above {}: {} approvals",
+            display::format_sol(policy.limit),
+            policy.large_threshold
+        );
+    }
+    if let Some(ttl) = multisig.approval_ttl_seconds {
+        println!("Approval TTL: {ttl} s");
+    }
+    if let Some(policy_signer) = multisig.policy_signer {
+        println!("Policy signer: {policy_signer}");
+    }
+    if multisig.transaction_count > 0 {
+        println!("Indexed proposals: {}", multisig.transaction_count);
+    }
+    if multisig.fee_exempt {
+        println!("Exempt from the protocol fee");
+    }
+    if multisig.allow_zero_amount {
+        println!("Zero-amount transfers allowed");
+    }
+    if multisig.strict_signing {
+        println!("Strict signing: approvals must be signed by owners only");
+    }
+    if multisig.min_transfer > 0 {
+        println!(
+            "Minimum transfer: {}",
+            display::format_sol(multisig.min_transfer)
+        );
+    }
+    if let Some(cap) = multisig.outflow_cap {
+        let now = rpc.get_block_time(rpc.get_slot()?)?;
+        println!(
+            "Outflow cap: {} per {} s, {} left this period",
+            display::format_sol(cap.limit),
+            cap.period_seconds,
+            display::format_sol(multisig.outflow_remaining(now).unwrap_or_default())
+        );
+    }
+    let pending = fetch::fetch_transactions(rpc, program_id, address)?;
+    for (key, proposal) in pending.iter().filter(|(_, proposal)| !proposal.executed) {
+        println!("Proposal {key}: {:?}", proposal.status(&multisig));
+    }
+    Ok(())
+}
+
+fn show(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    transaction: Pubkey,
+    so: Option<PathBuf>,
+) -> CliResult {
+    let proposal = fetch::fetch_transaction(rpc, program_id, &transaction)?;
+    let multisig = load::<Multisig>(&rpc.get_account_data(&proposal.multisig)?)?;
+
+    println!("Multisig: {}", proposal.multisig);
+    println!("Proposer: {}", proposal.proposer);
+    println!("Digest: {}", proposal.digest(program_id, &transaction));
+    println!("Action: {:?}", proposal.action);
+    let unit = display::fetch_display_unit(rpc, program_id, &proposal.multisig)?;
+    if let Some(amount) = display::describe_amount(unit.as_ref(), &proposal.action) {
+        println!("Amount: {amount}");
+    }
+    if let Some(expires_at) = proposal.expires_at {
+        let extended = if proposal.extended { ", extended" } else { "" };
+        println!("Expires at: {expires_at}{extended}");
+    }
+    if proposal.executor_fee > 0 {
+        println!("Executor fee: {} lamports", proposal.executor_fee);
+    }
+    if let Some(attachment) = proposal.attachment_hex() {
+        println!("Attachment (SHA-256): {attachment}");
+    }
+    println!(
+        "Vault: {} ({})",
+        proposal.vault,
+        find_indexed_vault_address(&proposal.multisig, proposal.vault, program_id).0
+    );
+    let status = match multisig.approval_ttl_seconds {
+        Some(ttl) => {
+            println!("Approval TTL: {ttl} s");
+            let now = rpc.get_block_time(rpc.get_slot()?)?;
+            proposal.status_at(&multisig, now)
+        }
+        None => proposal.status(&multisig),
+    };
+    println!("Status: {status:?}");
+    for ((owner, signed), required) in multisig
+        .owners
+        .iter()
+        .zip(&proposal.signers)
+        .zip(&proposal.required_signers)
+    {
+        let required = if *required { " (required)" } else { "" };
+        println!(
+            "  {owner} {}{required}",
+            if *signed { "approved" } else { "-" }
+        );
+    }
+
+    if let ProposalAction::DeployProgram {
+        buffer,
+        buffer_hash: expected,
+        ..
+    }
+    | ProposalAction::UpgradeProgram {
+        buffer,
+        buffer_hash: expected,
+        ..
+    } = &proposal.action
+    {
+        println!("Proposed bytecode hash: {}", hash_to_string(expected));
+        match fetch_buffer_hash(rpc, buffer) {
+            Ok(current) if current == *expected => println!("Buffer: unchanged"),
+            Ok(current) => println!("Buffer: CHANGED to {}", hash_to_string(&current)),
+            Err(err) => println!("Buffer: unavailable ({err})"),
+        }
+        if let Some(path) = so {
+            let local = hash(&std::fs::read(path)?).to_bytes();
+            if local == *expected {
+                println!("Local binary: matches");
+            } else {
+                println!("Local binary: DOES NOT MATCH ({})", hash_to_string(&local));
+            }
+        }
+    }
+    Ok(())
+}
+
+struct ParsedTransaction {
+    slot: u64,
+    block_time: Option<i64>,
+    meta: UiTransactionStatusMeta,
+    message: UiParsedMessage,
+}
+
+fn fetch_parsed_transaction(
+    rpc: &RpcClient,
+    signature: &Signature,
+) -> Result<ParsedTransaction, Box<dyn Error>> {
+    // jsonParsed leaves our instructions as raw data but decodes the system, token,
+    // stake and loader CPIs an execute makes
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::JsonParsed),
+        commitment: Some(rpc.commitment()),
+        max_supported_transaction_version: Some(0),
+    };
+    let confirmed = rpc.get_transaction_with_config(signature, config)?;
+    let meta = confirmed
+        .transaction
+        .meta
+        .ok_or("transaction has no status meta")?;
+    let EncodedTransaction::Json(transaction) = confirmed.transaction.transaction else {
+        return Err("node did not return a JSON transaction".into());
+    };
+    let UiMessage::Parsed(message) = transaction.message else {
+        return Err("node did not return a parsed message".into());
+    };
+    Ok(ParsedTransaction {
+        slot: confirmed.slot,
+        block_time: confirmed.block_time,
+        meta,
+        message,
+    })
+}
+
+fn decode_multisig_instruction(ix: &UiPartiallyDecodedInstruction) -> Option<MultisigInstruction> {
+    let data = solana_sdk::bs58::decode(&ix.data).into_vec().ok()?;
+    MultisigInstruction::decode(&data).ok()
+}
+
+fn inspect(rpc: &RpcClient, program_id: &Pubkey, signature: &Signature) -> CliResult {
+    let ParsedTransaction {
+        slot,
+        block_time,
+        meta,
+        message,
+    } = fetch_parsed_transaction(rpc, signature)?;
+
+    println!("Signature: {signature}");
+    println!("Slot: {slot}");
+    if let Some(block_time) = block_time {
+        println!("Block time: {block_time}");
+    }
+    match &meta.err {
+        None => println!("Status: success"),
+        Some(err) => println!("Status: FAILED ({err})"),
+    }
+    println!("Fee: {} lamports", meta.fee);
+    for key in message.account_keys.iter().filter(|key| key.signer) {
+        println!("Signer: {}", key.pubkey);
+    }
+
+    let mut inner: HashMap<u8, Vec<UiInstruction>> = HashMap::new();
+    if let OptionSerializer::Some(inner_instructions) = meta.inner_instructions {
+        for set in inner_instructions {
+            inner.insert(set.index, set.instructions);
+        }
+    }
+    let program_id = program_id.to_string();
+    for (index, ix) in message.instructions.iter().enumerate() {
+        println!("#{index} {}", describe_instruction(ix, &program_id));
+        if let UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(ix)) = ix {
+            if ix.program_id == program_id {
+                for (position, account) in ix.accounts.iter().enumerate() {
+                    println!("    account {position}: {account}");
+                }
+                for cpi in inner.get(&(index as u8)).into_iter().flatten() {
+                    println!("    -> {}", describe_instruction(cpi, &program_id));
+                }
+            }
+        }
+    }
+
+    if meta.err.is_some() {
+        if let OptionSerializer::Some(logs) = meta.log_messages {
+            println!("Logs:");
+            for line in logs {
+                println!("  {line}");
+            }
+        }
+    }
+    Ok(())
+}
+
+// One line summary: multisig instructions are borsh decoded, other programs the node
+// could parse are shown as `program type {info}`
+fn describe_instruction(ix: &UiInstruction, program_id: &str) -> String {
+    match ix {
+        UiInstruction::Parsed(UiParsedInstruction::Parsed(parsed)) => {
+            let info = &parsed.parsed["info"];
+            match parsed.parsed["type"].as_str() {
+                Some(kind) if info.is_null() => format!("{} {kind}", parsed.program),
+                Some(kind) => format!("{} {kind} {info}", parsed.program),
+                None => format!("{} {}", parsed.program, parsed.parsed),
+            }
+        }
+        UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(ix))
+            if ix.program_id == program_id =>
+        {
+            match decode_multisig_instruction(ix) {
+                Some(decoded) => format!("multisig {decoded:?}"),
+                None => format!("multisig <undecodable data {}>", ix.data),
+            }
+        }
+        UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(ix)) => {
+            format!("{} data {}", ix.program_id, ix.data)
+        }
+        UiInstruction::Compiled(ix) => format!("program #{} data {}", ix.program_id_index, ix.data),
+    }
+}
+
+fn import(
+    rpc: &RpcClient,
+    bundle: PathBuf,
+    signer: Option<Pubkey>,
+    accounts_dir: Option<PathBuf>,
+    compare: bool,
+) -> CliResult {
+    let bundle: serde_json::Value = serde_json::from_slice(&std::fs::read(bundle)?)?;
+    let verified = export::verify(&bundle, signer.as_ref())?;
+
+    println!("Bundle verified, signed by {}", verified.signer);
+    println!("Program: {}", verified.program_id);
+    println!("Multisig: {} (slot {})", verified.multisig, verified.slot);
+    println!(
+        "Threshold: {} of {}",
+        verified.state.threshold,
+        verified.state.owners.len()
+    );
+    for owner in &verified.state.owners {
+        println!("  {owner}");
+    }
+    println!("Accounts: {}", verified.accounts.len());
+
+    if let Some(dir) = accounts_dir {
+        std::fs::create_dir_all(&dir)?;
+        for (address, account) in &verified.accounts {
+            let file = export::test_validator_account(address, account);
+            std::fs::write(
+                dir.join(format!("{address}.json")),
+                serde_json::to_string_pretty(&file)?,
+            )?;
+        }
+        println!("Wrote account files to {}", dir.display());
+    }
+
+    if compare {
+        for (address, account) in &verified.accounts {
+            let current = rpc
+                .get_account_with_commitment(address, rpc.commitment())?
+                .value;
+            let status = match current {
+                None => "GONE",
+                Some(current) if current.data != account.data => "DATA CHANGED",
+                Some(current) if current.lamports != account.lamports => "lamports changed",
+                Some(_) => "unchanged",
+            };
+            println!("  {address} {status}");
+        }
+    }
+    Ok(())
+}
+
+struct Compromise {
+    compromised: Pubkey,
+    replacement: Pubkey,
+    cold_address: Pubkey,
+}
+
+// Each step either runs with the `--keypair` (when it holds the role the step needs) or
+// says who has to act. Safe to re-run, steps that are already done are skipped
+fn respond_compromise(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    compromise: &Compromise,
+    dry_run: bool,
+) -> CliResult {
+    let me = payer.pubkey();
+    let state = load::<Multisig>(&rpc.get_account_data(multisig)?)?;
+    if !state.owners.contains(&compromise.compromised) {
+        return Err(format!("{} is not an owner", compromise.compromised).into());
+    }
+    if state.owners.contains(&compromise.replacement) {
+        return Err(format!("{} already is an owner", compromise.replacement).into());
+    }
+    let pending: Vec<(Pubkey, Transaction)> = fetch::fetch_transactions(rpc, program_id, multisig)?
+        .into_iter()
+        .filter(|(_, transaction)| !transaction.executed)
+        .collect();
+    let run = |instructions: &[Instruction]| -> CliResult {
+        if dry_run {
+            println!("   (dry run, not sent)");
+            return Ok(());
+        }
+        send(rpc, instructions, payer, &[])
+    };
+
+    println!("1. Freeze the wallet");
+    if state.paused {
+        println!("   already paused");
+    } else if state.emergency_authority == Some(me) {
+        println!("   pausing as the emergency authority");
+        run(&[instruction::set_paused(program_id, &me, multisig, true)])?;
+    } else {
+        match state.emergency_authority {
+            Some(authority) => {
+                println!("   ACTION NEEDED: the emergency authority {authority} must pause")
+            }
+            None => println!("   no emergency authority set, pausing takes the owners' approvals"),
+        }
+    }
+
+    println!("2. Cancel pending proposals");
+    if pending.is_empty() {
+        println!("   none pending");
+    } else if state.veto_authority == Some(me) {
+        for (key, transaction) in &pending {
+            println!("   vetoing {key}");
+            run(&[instruction::veto_transaction(
+                program_id,
+                &me,
+                multisig,
+                key,
+                &transaction.proposer,
+            )])?;
+        }
+    } else {
+        for (key, transaction) in &pending {
+            println!("   {key} {:?}", transaction.action);
+        }
+        match state.veto_authority {
+            Some(authority) => {
+                println!("   ACTION NEEDED: the veto authority {authority} must veto these")
+            }
+            None => println!(
+                "   no veto authority set, step 3 at least clears the compromised key's approvals"
+            ),
+        }
+    }
+
+    println!(
+        "3. Replace {} with {}",
+        compromise.compromised, compromise.replacement
+    );
+    let mut approvals = state.num_approvals();
+    match state.owner_index(&me) {
+        None => println!("   not an owner, can't approve"),
+        Some(_) if state.has_signed(&me) => println!("   already approved"),
+        Some(_) if me == compromise.compromised => {
+            println!("   refusing to approve with the compromised key")
+        }
+        Some(_) => {
+            println!("   approving");
+            let sign = match state.strict_signing {
+                true => instruction::sign_strict(program_id, &me, multisig, &me),
+                false => instruction::sign(program_id, &me, multisig),
+            };
+            run(&[sign])?;
+            approvals += 1;
+        }
+    }
+    if approvals >= state.threshold as usize {
+        println!("   threshold reached, swapping the owner");
+        let pending_keys: Vec<Pubkey> = pending.iter().map(|(key, _)| *key).collect();
+        let mut swap = instruction::swap_owner(
+            program_id,
+            multisig,
+            &compromise.compromised,
+            &compromise.replacement,
+            &pending_keys,
+        );
+        let indexed = registered_owner_indexes(
+            rpc,
+            program_id,
+            &[compromise.compromised, compromise.replacement],
+        )?;
+        swap.accounts
+            .extend(instruction::owner_index_accounts(program_id, &indexed));
+        run(&[swap])?;
+    } else {
+        println!(
+            "   ACTION NEEDED: {approvals} of {} approvals, other owners must run this too",
+            state.threshold
+        );
+        println!("   (a pending approval by the compromised key counts too, finish quickly)");
+    }
+
+    println!("4. Sweep to {}", compromise.cold_address);
+    let amount = sweepable_balance(rpc, multisig)?;
+    let (vault, _) = find_vault_address(multisig, program_id);
+    println!("   {amount} lamports held by the multisig account");
+    println!("   after the swap: unpause, collect the owners' approvals again, then run");
+    println!(
+        "   multisig-cli sweep {multisig} --to {}",
+        compromise.cold_address
+    );
+    println!(
+        "   the vault {vault} holds {} lamports, move those and any tokens with proposals",
+        rpc.get_balance(&vault)?
+    );
+    Ok(())
+}
+
+// Lamports of the multisig account above its rent exempt minimum
+fn sweepable_balance(rpc: &RpcClient, multisig: &Pubkey) -> Result<u64, Box<dyn Error>> {
+    let account = rpc.get_account(multisig)?;
+    let rent = rpc.get_minimum_balance_for_rent_exemption(account.data.len())?;
+    Ok(account.lamports.saturating_sub(rent))
+}
+
+struct HistoryEvent {
+    slot: u64,
+    block_time: Option<i64>,
+    signature: String,
+    kind: &'static str,
+    instruction: String,
+    signers: Vec<String>,
+    // set when the transaction landed but failed
+    error: Option<String>,
+}
+
+fn history(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    format: HistoryFormat,
+) -> CliResult {
+    // Approvals and executions of proposals all pass the multisig account, the open
+    // proposals are paged as well for anything that only touched the proposal
+    let mut addresses = vec![*multisig];
+    for (key, _) in fetch::fetch_transactions(rpc, program_id, multisig)? {
+        addresses.push(key);
+    }
+
+    let mut signatures = Vec::new();
+    let mut seen = HashSet::new();
+    for address in &addresses {
+        let mut before = None;
+        loop {
+            let config = GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: None,
+                commitment: Some(rpc.commitment()),
+            };
+            let page = rpc.get_signatures_for_address_with_config(address, config)?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            before = Some(last.signature.parse()?);
+            for status in page {
+                if seen.insert(status.signature.clone()) {
+                    signatures.push((status.slot, status.signature));
+                }
+            }
+        }
+    }
+    // oldest first; the order within a slot isn't exposed by the RPC
+    signatures.sort();
+
+    let program_id = program_id.to_string();
+    let multisig = multisig.to_string();
+    let mut events = Vec::new();
+    for (_, signature) in signatures {
+        let parsed = fetch_parsed_transaction(rpc, &signature.parse()?)?;
+        let signers: Vec<String> = parsed
+            .message
+            .account_keys
+            .iter()
+            .filter(|key| key.signer)
+            .map(|key| key.pubkey.clone())
+            .collect();
+        for ix in &parsed.message.instructions {
+            let UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(ix)) = ix else {
+                continue;
+            };
+            if ix.program_id != program_id || !ix.accounts.contains(&multisig) {
+                continue;
+            }
+            let Some(decoded) = decode_multisig_instruction(ix) else {
+                continue;
+            };
+            events.push(HistoryEvent {
+                slot: parsed.slot,
+                block_time: parsed.block_time,
+                signature: signature.clone(),
+                kind: event_kind(&decoded),
+                instruction: format!("{decoded:?}"),
+                signers: signers.clone(),
+                error: parsed.meta.err.as_ref().map(ToString::to_string),
+            });
+        }
+    }
+
+    match format {
+        HistoryFormat::Text => {
+            for event in &events {
+                let status = match &event.error {
+                    None => String::new(),
+                    Some(err) => format!(" FAILED ({err})"),
+                };
+                println!(
+                    "{} {} {:<9} {}{}",
+                    event.slot,
+                    event
+                        .block_time
+                        .map_or("-".to_string(), |time| time.to_string()),
+                    event.kind,
+                    event.instruction,
+                    status
+                );
+                println!("    {} by {}", event.signature, event.signers.join(", "));
+            }
+        }
+        HistoryFormat::Json => {
+            let events: Vec<serde_json::Value> = events
+                .iter()
+                .map(|event| {
+                    serde_json::json!({
+                        "slot": event.slot,
+                        "block_time": event.block_time,
+                        "signature": event.signature,
+                        "kind": event.kind,
+                        "instruction": event.instruction,
+                        "signers": event.signers,
+                        "error": event.error,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&events)?);
+        }
+        HistoryFormat::Csv => {
+            println!("slot,block_time,signature,kind,instruction,signers,error");
+            for event in &events {
+                let fields = [
+                    event.slot.to_string(),
+                    event
+                        .block_time
+                        .map_or(String::new(), |time| time.to_string()),
+                    event.signature.clone(),
+                    event.kind.to_string(),
+                    event.instruction.clone(),
+                    event.signers.join(" "),
+                    event.error.clone().unwrap_or_default(),
+                ];
+                let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                println!("{}", fields.join(","));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn event_kind(instruction: &MultisigInstruction) -> &'static str {
+    match instruction {
+        MultisigInstruction::Propose { .. }
+        | MultisigInstruction::ProposeIndexed { .. }
+        | MultisigInstruction::ProposeRecovery { .. } => "proposal",
+        MultisigInstruction::Sign
+        | MultisigInstruction::Approve
+        | MultisigInstruction::ApproveRecovery
+        | MultisigInstruction::ApproveAggregated
+        | MultisigInstruction::ApproveWithSignature { .. }
+        | MultisigInstruction::SignAsDelegate
+        | MultisigInstruction::SignAsMultisig
+        | MultisigInstruction::ApproveAsDelegate
+        | MultisigInstruction::CheckApproved { .. } => "approval",
+        MultisigInstruction::Execute { .. }
+        | MultisigInstruction::ExecuteTransaction
+        | MultisigInstruction::ExecuteRecovery
+        | MultisigInstruction::ExecuteRecurring
+        | MultisigInstruction::ClaimDistribution { .. }
+        | MultisigInstruction::ExecuteFromVault { .. }
+        | MultisigInstruction::CrankTransaction => "execution",
+        MultisigInstruction::VetoTransaction | MultisigInstruction::VetoRecovery => "veto",
+        MultisigInstruction::Deposit { .. } => "deposit",
+        MultisigInstruction::Create { .. }
+        | MultisigInstruction::CreateDerived { .. }
+        | MultisigInstruction::CreateTokenAccount { .. }
+        | MultisigInstruction::SwapOwner { .. }
+        | MultisigInstruction::InitGuardians { .. }
+        | MultisigInstruction::InitAggregateKey { .. }
+        | MultisigInstruction::SetVetoAuthority { .. }
+        | MultisigInstruction::SetDestinationPolicy { .. }
+        | MultisigInstruction::AddDestination { .. }
+        | MultisigInstruction::RemoveDestination { .. }
+        | MultisigInstruction::SetDestinationPolicyEnforcement { .. }
+        | MultisigInstruction::RegisterInbox
+        | MultisigInstruction::ClearInbox
+        | MultisigInstruction::RegisterOwnerIndex
+        | MultisigInstruction::SyncOwnerIndex
+        | MultisigInstruction::UnlistMultisig { .. }
+        | MultisigInstruction::ExtendProposal { .. }
+        | MultisigInstruction::SetPaused { .. }
+        | MultisigInstruction::SetEmergencyAuthority { .. }
+        | MultisigInstruction::SetPolicy { .. }
+        | MultisigInstruction::SetApprovalTtl { .. }
+        | MultisigInstruction::SetOutflowCap { .. }
+        | MultisigInstruction::SetPolicySigner { .. }
+        | MultisigInstruction::SetDelegate { .. }
+        | MultisigInstruction::RevokeDelegate
+        | MultisigInstruction::SetDisplayUnit { .. }
+        | MultisigInstruction::SetMetadata { .. }
+        | MultisigInstruction::CreateLookupTable { .. }
+        | MultisigInstruction::ExtendLookupTable { .. }
+        | MultisigInstruction::DeactivateLookupTable
+        | MultisigInstruction::CloseLookupTable
+        | MultisigInstruction::CloseTransaction
+        | MultisigInstruction::InitHistory
+        | MultisigInstruction::ChangeThreshold { .. }
+        | MultisigInstruction::InitProgramConfig { .. }
+        | MultisigInstruction::SetProgramConfig { .. }
+        | MultisigInstruction::SetFeeExempt { .. }
+        | MultisigInstruction::SetTransferMinimum { .. }
+        | MultisigInstruction::SetStrictSigning { .. }
+        | MultisigInstruction::AddOwner { .. }
+        | MultisigInstruction::MigrateToVault => "config",
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Proposal accounts of `multisig`, they start with the multisig key
+fn fetch_buffer_hash(rpc: &RpcClient, buffer: &Pubkey) -> Result<[u8; 32], Box<dyn Error>> {
+    let data = rpc.get_account_data(buffer)?;
+    Ok(buffer_hash(&data)?.to_bytes())
+}
+
+fn hash_to_string(bytes: &[u8; 32]) -> String {
+    solana_program::hash::Hash::new_from_array(*bytes).to_string()
+}
+
+fn send(
+    rpc: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Keypair,
+    extra_signers: &[&Keypair],
+) -> CliResult {
+    let mut signers = vec![payer];
+    signers.extend_from_slice(extra_signers);
+    let transaction = solana_sdk::transaction::Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &signers,
+        rpc.get_latest_blockhash()?,
+    );
+    let signature = rpc
+        .send_and_confirm_transaction(&transaction)
+        .map_err(describe_error)?;
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn print_simulation(simulation: &simulate::ExecuteSimulation) {
+    match &simulation.error {
+        None => println!("Ready to execute"),
+        Some(TransactionError::InstructionError(index, InstructionError::Custom(code))) => {
+            match MultisigError::from_code(*code) {
+                Some(err) => println!("Would fail: instruction {index}: {err} (error {code})"),
+                None => println!("Would fail: instruction {index}: custom error {code}"),
+            }
+        }
+        Some(err) => println!("Would fail: {err}"),
+    }
+    if let Some(units) = simulation.units_consumed {
+        println!("Compute units: {units}");
+    }
+    println!("Accounts:");
+    for meta in &simulation.accounts {
+        let access = if meta.is_writable {
+            "writable"
+        } else {
+            "readonly"
+        };
+        println!("  {} ({access})", meta.pubkey);
+    }
+    if !simulation.balance_changes.is_empty() {
+        println!("Balance changes:");
+        for change in &simulation.balance_changes {
+            println!("  {} {:+} lamports", change.account, change.delta());
+        }
+    }
+    if simulation.error.is_some() {
+        println!("Logs:");
+        for line in &simulation.logs {
+            println!("  {line}");
+        }
+    }
+}
+
+// Spells out the program's own errors, the RPC only reports them as `custom program error: 0x..`
+fn describe_error(err: ClientError) -> Box<dyn Error> {
+    if let Some(TransactionError::InstructionError(index, InstructionError::Custom(code))) =
+        err.get_transaction_error()
+    {
+        if let Some(multisig_error) = MultisigError::from_code(code) {
+            return format!("instruction {index} failed: {multisig_error} (error {code})").into();
+        }
+    }
+    err.into()
+}
+
+fn expand_tilde(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
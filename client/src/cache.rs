@@ -0,0 +1,186 @@
+// Read-through account cache in front of an RPC node (or a pool of lagging read
+// replicas). Every cached account remembers the slot it was observed at, so a caller
+// that already saw slot N (e.g. the slot its own approval landed in) can ask for state
+// that is at least that new instead of silently reading an older snapshot.
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::client_error::ClientError;
+use solana_client::pubsub_client::{PubsubAccountClientSubscription, PubsubClient};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_program::clock::Slot;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use solana_multisig_wallet::state::{load, Multisig, Transaction};
+
+#[derive(Debug)]
+pub enum CacheError {
+    Rpc(Box<ClientError>),
+    Subscribe(String),
+    AccountNotFound(Pubkey),
+    // the node answered, but from a slot older than the one asked for
+    StaleSlot { wanted: Slot, got: Slot },
+    InvalidData(std::io::Error),
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Rpc(err) => write!(f, "rpc error: {err}"),
+            CacheError::Subscribe(err) => write!(f, "subscription failed: {err}"),
+            CacheError::AccountNotFound(key) => write!(f, "account {key} not found"),
+            CacheError::StaleSlot { wanted, got } => {
+                write!(f, "node is at slot {got}, wanted at least {wanted}")
+            }
+            CacheError::InvalidData(err) => write!(f, "invalid account data: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+impl From<ClientError> for CacheError {
+    fn from(err: ClientError) -> Self {
+        CacheError::Rpc(Box::new(err))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CachedAccount {
+    pub slot: Slot,
+    pub account: Account,
+}
+
+pub struct MultisigClient {
+    rpc: RpcClient,
+    cache: Mutex<HashMap<Pubkey, CachedAccount>>,
+}
+
+impl MultisigClient {
+    pub fn new(rpc: RpcClient) -> Self {
+        Self {
+            rpc,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    // Returns the cached copy if it was observed at `min_slot` or later, otherwise asks
+    // the node for a snapshot no older than `min_slot`.
+    pub fn get_account_at_least(
+        &self,
+        key: &Pubkey,
+        min_slot: Slot,
+    ) -> Result<CachedAccount, CacheError> {
+        if let Some(cached) = self.cached(key) {
+            if cached.slot >= min_slot {
+                return Ok(cached);
+            }
+        }
+
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: None,
+            commitment: Some(self.rpc.commitment()),
+            min_context_slot: Some(min_slot),
+        };
+        let response = self.rpc.get_account_with_config(key, config)?;
+        let slot = response.context.slot;
+        if slot < min_slot {
+            return Err(CacheError::StaleSlot {
+                wanted: min_slot,
+                got: slot,
+            });
+        }
+        let account = response.value.ok_or(CacheError::AccountNotFound(*key))?;
+        self.apply_update(key, slot, account.clone());
+
+        // a websocket notification may have raced ahead of the response, or invalidated the entry
+        Ok(self.cached(key).unwrap_or(CachedAccount { slot, account }))
+    }
+
+    pub fn get_account(&self, key: &Pubkey) -> Result<CachedAccount, CacheError> {
+        self.get_account_at_least(key, 0)
+    }
+
+    // Multisig state as of `min_slot` or later, together with the slot it was read at.
+    pub fn get_state_at_least(
+        &self,
+        multisig: &Pubkey,
+        min_slot: Slot,
+    ) -> Result<(Slot, Multisig), CacheError> {
+        let cached = self.get_account_at_least(multisig, min_slot)?;
+        let state = load::<Multisig>(&cached.account.data).map_err(CacheError::InvalidData)?;
+        Ok((cached.slot, state))
+    }
+
+    pub fn get_transaction_at_least(
+        &self,
+        transaction: &Pubkey,
+        min_slot: Slot,
+    ) -> Result<(Slot, Transaction), CacheError> {
+        let cached = self.get_account_at_least(transaction, min_slot)?;
+        let state = load::<Transaction>(&cached.account.data).map_err(CacheError::InvalidData)?;
+        Ok((cached.slot, state))
+    }
+
+    pub fn cached(&self, key: &Pubkey) -> Option<CachedAccount> {
+        self.cache.lock().unwrap().get(key).cloned()
+    }
+
+    // Keeps whichever copy was observed at the later slot, so an answer from a lagging
+    // replica never overwrites newer state.
+    pub fn apply_update(&self, key: &Pubkey, slot: Slot, account: Account) {
+        let mut cache = self.cache.lock().unwrap();
+        match cache.get(key) {
+            Some(cached) if cached.slot > slot => {}
+            _ => {
+                cache.insert(*key, CachedAccount { slot, account });
+            }
+        }
+    }
+
+    pub fn invalidate(&self, key: &Pubkey) {
+        self.cache.lock().unwrap().remove(key);
+    }
+
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    // Keeps `key` fresh from websocket account notifications. The returned handle owns
+    // the subscription; shutting it down (or dropping it) stops the update thread.
+    pub fn subscribe(
+        self: &Arc<Self>,
+        ws_url: &str,
+        key: &Pubkey,
+    ) -> Result<PubsubAccountClientSubscription, CacheError> {
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: None,
+            commitment: Some(self.rpc.commitment()),
+            min_context_slot: None,
+        };
+        let (subscription, receiver) = PubsubClient::account_subscribe(ws_url, key, Some(config))
+            .map_err(|err| CacheError::Subscribe(err.to_string()))?;
+
+        let client = Arc::clone(self);
+        let key = *key;
+        std::thread::spawn(move || {
+            for notification in receiver {
+                match notification.value.decode::<Account>() {
+                    Some(account) => client.apply_update(&key, notification.context.slot, account),
+                    // can't trust what we have any more, refetch on next read
+                    None => client.invalidate(&key),
+                }
+            }
+        });
+        Ok(subscription)
+    }
+}
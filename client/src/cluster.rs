@@ -0,0 +1,144 @@
+// The clusters the program is deployed to, so the same CLI or SDK code targets any of them without
+// recompiling. Public clusters run the deployment at the declared program id; `Custom` covers
+// private validators, RPC providers and deployments at another id. Parses from the monikers
+// `solana -u` accepts, or from an RPC URL.
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cluster {
+    Mainnet,
+    Devnet,
+    Testnet,
+    Localnet,
+    Custom {
+        url: String,
+        ws_url: String,
+        program_id: Pubkey,
+    },
+}
+
+impl Cluster {
+    // A cluster at `url`, running the program at the declared id
+    pub fn custom(url: &str) -> Self {
+        Cluster::Custom {
+            url: url.to_string(),
+            ws_url: websocket_url(url),
+            program_id: solana_multisig_wallet::id(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        match self {
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Testnet => "https://api.testnet.solana.com",
+            Cluster::Localnet => "http://127.0.0.1:8899",
+            Cluster::Custom { url, .. } => url,
+        }
+    }
+
+    // Endpoint for subscriptions, e.g. `cache::MultisigClient::subscribe`
+    pub fn ws_url(&self) -> &str {
+        match self {
+            Cluster::Mainnet => "wss://api.mainnet-beta.solana.com",
+            Cluster::Devnet => "wss://api.devnet.solana.com",
+            Cluster::Testnet => "wss://api.testnet.solana.com",
+            Cluster::Localnet => "ws://127.0.0.1:8900",
+            Cluster::Custom { ws_url, .. } => ws_url,
+        }
+    }
+
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            Cluster::Custom { program_id, .. } => *program_id,
+            _ => solana_multisig_wallet::id(),
+        }
+    }
+
+    // The same endpoints with the deployment at `program_id`
+    pub fn with_program_id(self, program_id: Pubkey) -> Self {
+        Cluster::Custom {
+            url: self.url().to_string(),
+            ws_url: self.ws_url().to_string(),
+            program_id,
+        }
+    }
+
+    pub fn rpc_client(&self, commitment: CommitmentConfig) -> RpcClient {
+        RpcClient::new_with_commitment(self.url().to_string(), commitment)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownCluster(pub String);
+
+impl fmt::Display for UnknownCluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown cluster {}, expected mainnet-beta, devnet, testnet, localnet or a URL",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownCluster {}
+
+impl FromStr for Cluster {
+    type Err = UnknownCluster;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mainnet-beta" | "mainnet" | "m" => Ok(Cluster::Mainnet),
+            "devnet" | "d" => Ok(Cluster::Devnet),
+            "testnet" | "t" => Ok(Cluster::Testnet),
+            "localnet" | "localhost" | "l" => Ok(Cluster::Localnet),
+            url if url.starts_with("http://") || url.starts_with("https://") => {
+                Ok(Cluster::custom(url))
+            }
+            other => Err(UnknownCluster(other.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cluster::Mainnet => write!(f, "mainnet-beta"),
+            Cluster::Devnet => write!(f, "devnet"),
+            Cluster::Testnet => write!(f, "testnet"),
+            Cluster::Localnet => write!(f, "localnet"),
+            Cluster::Custom { url, .. } => write!(f, "{url}"),
+        }
+    }
+}
+
+// The websocket endpoint validators serve next to the RPC one at `url`: same host, `ws(s)`
+// scheme, and the next port up when the port is explicit
+pub fn websocket_url(url: &str) -> String {
+    let (scheme, rest) = match url.split_once("://") {
+        Some(("https", rest)) => ("wss", rest),
+        Some((_, rest)) => ("ws", rest),
+        None => ("ws", url),
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(at) => rest.split_at(at),
+        None => (rest, ""),
+    };
+    let authority = match authority.rsplit_once(':') {
+        Some((host, port)) => match port
+            .parse::<u16>()
+            .ok()
+            .and_then(|port| port.checked_add(1))
+        {
+            Some(port) => format!("{host}:{port}"),
+            None => authority.to_string(),
+        },
+        None => authority.to_string(),
+    };
+    format!("{scheme}://{authority}{path}")
+}
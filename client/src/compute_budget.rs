@@ -0,0 +1,162 @@
+// Compute units for executing a proposal. A transaction gets 200k units by default, a batch
+// proposal runs all its instructions inside one `ExecuteTransaction` and quickly needs more, so
+// the execution asks for a limit that grows with the batch.
+//
+// During congestion a sized limit isn't enough to land: `estimate_budget` simulates the
+// transaction for its actual units and prices them from the fees recently paid to write the same
+// accounts.
+use solana_client::client_error::ClientError;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_multisig_wallet::instruction::ProposalAction;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use std::fmt;
+
+// The multisig's own checks and bookkeeping
+pub const BASE_COMPUTE_UNITS: u32 = 50_000;
+// Room for each batched instruction and the CPI around it
+pub const COMPUTE_UNITS_PER_INSTRUCTION: u32 = 100_000;
+// Most a transaction may request
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+// Limit to request for executing `action`, `None` when the default is enough
+pub fn compute_unit_limit(action: &ProposalAction) -> Option<u32> {
+    match action {
+        ProposalAction::Batch { instructions } => Some(
+            COMPUTE_UNITS_PER_INSTRUCTION
+                .saturating_mul(instructions.len() as u32)
+                .saturating_add(BASE_COMPUTE_UNITS)
+                .min(MAX_COMPUTE_UNITS),
+        ),
+        _ => None,
+    }
+}
+
+// The execution instruction of `action`, preceded by a compute unit limit when it needs one
+pub fn with_compute_budget(action: &ProposalAction, execute: Instruction) -> Vec<Instruction> {
+    let mut instructions: Vec<Instruction> = compute_unit_limit(action)
+        .map(ComputeBudgetInstruction::set_compute_unit_limit)
+        .into_iter()
+        .collect();
+    instructions.push(execute);
+    instructions
+}
+
+// Headroom over the simulated units, the state can change between simulation and landing
+pub const SIMULATION_MARGIN_PERCENT: u64 = 20;
+// Recent fee paid to write the same accounts that `estimate_budget` matches
+pub const PRIORITY_FEE_PERCENTILE: u8 = 75;
+
+// Compute unit limit and price (in micro-lamports per unit) to prepend, either may be left out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComputeBudget {
+    pub unit_limit: Option<u32>,
+    pub unit_price: Option<u64>,
+}
+
+impl ComputeBudget {
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let limit = self
+            .unit_limit
+            .map(ComputeBudgetInstruction::set_compute_unit_limit);
+        let price = self
+            .unit_price
+            .map(ComputeBudgetInstruction::set_compute_unit_price);
+        limit.into_iter().chain(price).collect()
+    }
+
+    // `instructions` preceded by the budget's
+    pub fn prepend(&self, instructions: Vec<Instruction>) -> Vec<Instruction> {
+        let mut budgeted = self.instructions();
+        budgeted.extend(instructions);
+        budgeted
+    }
+}
+
+#[derive(Debug)]
+pub enum EstimateError {
+    Rpc(Box<ClientError>),
+    // the simulation failed, sending it would too
+    Simulation(TransactionError),
+}
+
+impl fmt::Display for EstimateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EstimateError::Rpc(err) => write!(f, "rpc error: {err}"),
+            EstimateError::Simulation(err) => write!(f, "simulation failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for EstimateError {}
+
+impl From<ClientError> for EstimateError {
+    fn from(err: ClientError) -> Self {
+        EstimateError::Rpc(Box::new(err))
+    }
+}
+
+// Limit for a transaction that consumed `units_consumed` in simulation
+pub fn unit_limit_from_simulation(units_consumed: u64) -> u32 {
+    let limit = units_consumed.saturating_mul(100 + SIMULATION_MARGIN_PERCENT) / 100;
+    limit.min(MAX_COMPUTE_UNITS as u64) as u32
+}
+
+// The fee at `percentile` of the recently paid ones, `None` when none was paid
+pub fn priority_fee(fees: &[u64], percentile: u8) -> Option<u64> {
+    let mut fees: Vec<u64> = fees.iter().copied().filter(|&fee| fee > 0).collect();
+    fees.sort_unstable();
+    let last = fees.len().checked_sub(1)?;
+    Some(fees[last * percentile.min(100) as usize / 100])
+}
+
+// Budget for sending `instructions` paid by `payer`: the units a simulation consumes plus
+// `SIMULATION_MARGIN_PERCENT`, priced at `PRIORITY_FEE_PERCENTILE` of the recent fees for
+// writing the same accounts
+pub fn estimate_budget(
+    rpc: &RpcClient,
+    payer: &Pubkey,
+    instructions: &[Instruction],
+) -> Result<ComputeBudget, EstimateError> {
+    // simulated with all the units a transaction may have, so running out doesn't hide the need
+    let simulated = ComputeBudget {
+        unit_limit: Some(MAX_COMPUTE_UNITS),
+        unit_price: None,
+    }
+    .prepend(instructions.to_vec());
+    let transaction = Transaction::new_with_payer(&simulated, Some(payer));
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let simulation = rpc
+        .simulate_transaction_with_config(&transaction, config)?
+        .value;
+    if let Some(err) = simulation.err {
+        return Err(EstimateError::Simulation(err));
+    }
+
+    let mut writable: Vec<Pubkey> = instructions
+        .iter()
+        .flat_map(|ix| &ix.accounts)
+        .filter(|meta| meta.is_writable)
+        .map(|meta| meta.pubkey)
+        .collect();
+    writable.sort();
+    writable.dedup();
+    let fees: Vec<u64> = rpc
+        .get_recent_prioritization_fees(&writable)?
+        .iter()
+        .map(|fee| fee.prioritization_fee)
+        .collect();
+
+    Ok(ComputeBudget {
+        unit_limit: simulation.units_consumed.map(unit_limit_from_simulation),
+        unit_price: priority_fee(&fees, PRIORITY_FEE_PERCENTILE),
+    })
+}
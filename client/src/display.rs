@@ -0,0 +1,67 @@
+// Amounts as approvers should read them. A multisig may pick a unit of account
+// (`SetDisplayUnit`, e.g. USDC) so the CLI, the server and wallets all print token amounts
+// the same way; lamport amounts are always shown in SOL, nothing is converted.
+use solana_client::client_error::ClientError;
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+
+use solana_multisig_wallet::instruction::ProposalAction;
+use solana_multisig_wallet::state::{find_display_unit_address, load, DisplayUnit};
+
+// The multisig's unit of account, `None` when it never set one
+pub fn fetch_display_unit(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+) -> Result<Option<DisplayUnit>, Box<ClientError>> {
+    let (address, _) = find_display_unit_address(multisig, program_id);
+    let account = rpc
+        .get_account_with_commitment(&address, rpc.commitment())?
+        .value;
+    Ok(account
+        .filter(|account| account.owner == *program_id)
+        .and_then(|account| load::<DisplayUnit>(&account.data).ok()))
+}
+
+// `amount` base units with `decimals` decimals, trailing zeros dropped ("1.5", "42")
+pub fn format_amount(amount: u64, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    let digits = format!("{amount:0>width$}", width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    match fraction.trim_end_matches('0') {
+        "" => whole.to_string(),
+        fraction => format!("{whole}.{fraction}"),
+    }
+}
+
+pub fn format_sol(lamports: u64) -> String {
+    format!("{} SOL", format_amount(lamports, 9))
+}
+
+// What the action moves, in the unit of account when it is a transfer of that mint. Other mints'
+// amounts are shown with the decimals the proposal expects
+pub fn describe_amount(unit: Option<&DisplayUnit>, action: &ProposalAction) -> Option<String> {
+    match (unit, action) {
+        (Some(unit), ProposalAction::TransferToken { mint, amount, .. }) if unit.mint == *mint => {
+            Some(format!(
+                "{} {}",
+                format_amount(*amount, unit.decimals),
+                unit.symbol
+            ))
+        }
+        (_, action) if action.lamports() > 0 => Some(format_sol(action.lamports())),
+        (
+            _,
+            ProposalAction::TransferToken {
+                mint,
+                amount,
+                expected_decimals,
+                ..
+            },
+        ) => Some(format!(
+            "{} of {mint}",
+            format_amount(*amount, *expected_decimals)
+        )),
+        _ => None,
+    }
+}
@@ -0,0 +1,61 @@
+// The program's structured events (`solana_multisig_wallet::event`) read back from a transaction's
+// log messages, as RPC, websocket `logsSubscribe` or Geyser plugins deliver them. Only
+// `Program data:` lines logged while the multisig program itself runs are taken, other programs
+// logging the same bytes are skipped
+use base64::Engine;
+use solana_multisig_wallet::event::{DepositEvent, MultisigEvent};
+use solana_program::pubkey::Pubkey;
+
+// The events of the program deployed at `solana_multisig_wallet::id()` the logs record, in order
+pub fn parse_logs(logs: &[String]) -> Vec<MultisigEvent> {
+    parse_program_logs(&solana_multisig_wallet::id(), logs)
+}
+
+// Same for the deployment at `program_id`
+pub fn parse_program_logs(program_id: &Pubkey, logs: &[String]) -> Vec<MultisigEvent> {
+    let program_id = program_id.to_string();
+    // the program running at each invoke depth
+    let mut running: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if running.last() != Some(&program_id.as_str()) {
+                continue;
+            }
+            let fields: Result<Vec<Vec<u8>>, _> = data
+                .split(' ')
+                .map(|field| base64::engine::general_purpose::STANDARD.decode(field))
+                .collect();
+            if let Some(event) = fields
+                .ok()
+                .and_then(|fields| MultisigEvent::from_log_data(&fields))
+            {
+                events.push(event);
+            }
+            continue;
+        }
+        let mut words = rest.split(' ');
+        match (words.next(), words.next()) {
+            (Some(program), Some("invoke")) => running.push(program),
+            (Some(_), Some("success" | "failed:")) => {
+                running.pop();
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+// The deposits into multisigs of `program_id` the logs record, in order
+pub fn deposit_events(program_id: &Pubkey, logs: &[String]) -> Vec<DepositEvent> {
+    parse_program_logs(program_id, logs)
+        .into_iter()
+        .filter_map(|event| match event {
+            MultisigEvent::Deposit(event) => Some(event),
+            _ => None,
+        })
+        .collect()
+}
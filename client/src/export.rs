@@ -0,0 +1,285 @@
+// Disaster-recovery bundle: a signed JSON snapshot of a wallet (multisig account, its PDAs with
+// their seeds, open proposals) that custody procedures can archive and later verify offline.
+//
+// Accounts are stored in the `solana-test-validator --account <ADDRESS> <FILE>` format, so a
+// verified bundle is also enough to rebuild the wallet on a local validator. The exporter signs
+// the serialized `payload`; `verify` re-derives every PDA and re-decodes every account instead
+// of trusting the decoded fields stored next to them.
+use serde_json::{json, Value};
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::client_error::ClientError;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_program::clock::Slot;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::account::Account;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::fetch::multisig_account_filters;
+use solana_multisig_wallet::fixtures;
+use solana_multisig_wallet::state::{
+    load, Multisig, Transaction, DESTINATIONS_SEED, GUARDIANS_SEED, RECOVERY_SEED, VAULT_SEED,
+};
+
+pub const BUNDLE_VERSION: u64 = 1;
+
+// Companion PDAs of a multisig, all derived from `[seed, multisig]`
+const PDA_SEEDS: [(&str, &[u8]); 4] = [
+    ("vault", VAULT_SEED),
+    ("guardians", GUARDIANS_SEED),
+    ("recovery", RECOVERY_SEED),
+    ("destinations", DESTINATIONS_SEED),
+];
+
+#[derive(Debug)]
+pub enum ExportError {
+    Rpc(Box<ClientError>),
+    AccountNotFound(Pubkey),
+    // the bundle is malformed or doesn't match what it claims
+    Invalid(String),
+    BadSignature,
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Rpc(err) => write!(f, "rpc error: {err}"),
+            ExportError::AccountNotFound(key) => write!(f, "account {key} not found"),
+            ExportError::Invalid(err) => write!(f, "invalid bundle: {err}"),
+            ExportError::BadSignature => write!(f, "bundle signature does not verify"),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<ClientError> for ExportError {
+    fn from(err: ClientError) -> Self {
+        ExportError::Rpc(Box::new(err))
+    }
+}
+
+fn invalid(message: impl fmt::Display) -> ExportError {
+    ExportError::Invalid(message.to_string())
+}
+
+// A bundle that passed `verify`
+pub struct VerifiedBundle {
+    pub signer: Pubkey,
+    pub program_id: Pubkey,
+    pub multisig: Pubkey,
+    pub slot: Slot,
+    pub state: Multisig,
+    // every account of the bundle (multisig, existing PDAs, proposals)
+    pub accounts: Vec<(Pubkey, Account)>,
+}
+
+// Snapshot of `multisig` and everything hanging off it, signed by `signer`
+pub fn export(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    signer: &Keypair,
+) -> Result<Value, ExportError> {
+    let slot = rpc.get_slot()?;
+    let multisig_account = rpc
+        .get_account_with_commitment(multisig, rpc.commitment())?
+        .value
+        .ok_or(ExportError::AccountNotFound(*multisig))?;
+    if multisig_account.owner != *program_id {
+        return Err(invalid(format!("{multisig} is not owned by {program_id}")));
+    }
+    let state = load::<Multisig>(&multisig_account.data)
+        .map_err(|err| invalid(format!("not a multisig: {err}")))?;
+
+    let mut pdas = Vec::new();
+    for (name, seed) in PDA_SEEDS {
+        let (address, bump) = Pubkey::find_program_address(&[seed, multisig.as_ref()], program_id);
+        let account = rpc
+            .get_account_with_commitment(&address, rpc.commitment())?
+            .value;
+        pdas.push(json!({
+            "name": name,
+            "seed": String::from_utf8_lossy(seed),
+            "bump": bump,
+            "address": address.to_string(),
+            "account": account.map(|account| encode_account(&address, &account)),
+        }));
+    }
+
+    let config = RpcProgramAccountsConfig {
+        filters: Some(multisig_account_filters(multisig)),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let mut proposals = Vec::new();
+    for (address, account) in rpc.get_program_accounts_with_config(program_id, config)? {
+        let Ok(transaction) = load::<Transaction>(&account.data) else {
+            continue;
+        };
+        proposals.push(json!({
+            "address": address.to_string(),
+            "account": encode_account(&address, &account),
+            "fields": decoded(&fixtures::transaction_json(&transaction)),
+        }));
+    }
+
+    let payload = json!({
+        "version": BUNDLE_VERSION,
+        "program_id": program_id.to_string(),
+        "multisig": multisig.to_string(),
+        "slot": slot,
+        "account": encode_account(multisig, &multisig_account),
+        "fields": decoded(&fixtures::multisig_json(&state)),
+        "pdas": pdas,
+        "proposals": proposals,
+    });
+    let signature = signer.sign_message(&payload_bytes(&payload));
+    Ok(json!({
+        "signer": signer.pubkey().to_string(),
+        "signature": signature.to_string(),
+        "payload": payload,
+    }))
+}
+
+// Checks the signature (and the signer, if one is expected), re-derives the PDAs and decodes
+// every account
+pub fn verify(
+    bundle: &Value,
+    expected_signer: Option<&Pubkey>,
+) -> Result<VerifiedBundle, ExportError> {
+    let signer = pubkey_field(bundle, "signer")?;
+    let signature = Signature::from_str(str_field(bundle, "signature")?)
+        .map_err(|err| invalid(format!("signature: {err}")))?;
+    let payload = &bundle["payload"];
+    if !signature.verify(signer.as_ref(), &payload_bytes(payload)) {
+        return Err(ExportError::BadSignature);
+    }
+    if expected_signer.is_some_and(|expected| *expected != signer) {
+        return Err(invalid(format!("signed by {signer}")));
+    }
+    if payload["version"].as_u64() != Some(BUNDLE_VERSION) {
+        return Err(invalid(format!(
+            "unsupported version {}",
+            payload["version"]
+        )));
+    }
+
+    let program_id = pubkey_field(payload, "program_id")?;
+    let multisig = pubkey_field(payload, "multisig")?;
+    let slot = payload["slot"]
+        .as_u64()
+        .ok_or_else(|| invalid("missing `slot`"))?;
+
+    let multisig_account = decode_account(&payload["account"], &program_id)?;
+    let state = load::<Multisig>(&multisig_account.data)
+        .map_err(|err| invalid(format!("multisig data: {err}")))?;
+    if payload["fields"] != decoded(&fixtures::multisig_json(&state)) {
+        return Err(invalid("multisig fields don't match its data"));
+    }
+    let mut accounts = vec![(multisig, multisig_account)];
+
+    let pdas = payload["pdas"]
+        .as_array()
+        .ok_or_else(|| invalid("missing `pdas`"))?;
+    for (name, seed) in PDA_SEEDS {
+        let pda = pdas
+            .iter()
+            .find(|pda| pda["name"] == name)
+            .ok_or_else(|| invalid(format!("missing {name} PDA")))?;
+        let (address, bump) = Pubkey::find_program_address(&[seed, multisig.as_ref()], &program_id);
+        if pubkey_field(pda, "address")? != address || pda["bump"].as_u64() != Some(bump.into()) {
+            return Err(invalid(format!("{name} PDA doesn't derive from its seeds")));
+        }
+        if !pda["account"].is_null() {
+            // the vault only ever holds lamports, the other PDAs hold program state
+            let owner = if name == "vault" {
+                system_program::id()
+            } else {
+                program_id
+            };
+            accounts.push((address, decode_account(&pda["account"], &owner)?));
+        }
+    }
+
+    let proposals = payload["proposals"]
+        .as_array()
+        .ok_or_else(|| invalid("missing `proposals`"))?;
+    for proposal in proposals {
+        let address = pubkey_field(proposal, "address")?;
+        let account = decode_account(&proposal["account"], &program_id)?;
+        let transaction = load::<Transaction>(&account.data)
+            .map_err(|err| invalid(format!("proposal {address}: {err}")))?;
+        if transaction.multisig != multisig {
+            return Err(invalid(format!(
+                "proposal {address} belongs to another multisig"
+            )));
+        }
+        if proposal["fields"] != decoded(&fixtures::transaction_json(&transaction)) {
+            return Err(invalid(format!(
+                "proposal {address} fields don't match its data"
+            )));
+        }
+        accounts.push((address, account));
+    }
+
+    Ok(VerifiedBundle {
+        signer,
+        program_id,
+        multisig,
+        slot,
+        state,
+        accounts,
+    })
+}
+
+// One `solana-test-validator --account` file
+pub fn test_validator_account(address: &Pubkey, account: &Account) -> Value {
+    json!({
+        "pubkey": address.to_string(),
+        "account": encode_account(address, account),
+    })
+}
+
+// What gets signed; serde_json writes a parsed payload back out byte for byte the same
+fn payload_bytes(payload: &Value) -> Vec<u8> {
+    serde_json::to_vec(payload).expect("JSON values always serialize")
+}
+
+fn encode_account(address: &Pubkey, account: &Account) -> Value {
+    let account = UiAccount::encode(address, account, UiAccountEncoding::Base64, None, None);
+    serde_json::to_value(account).expect("UiAccount always serializes")
+}
+
+fn decode_account(value: &Value, expected_owner: &Pubkey) -> Result<Account, ExportError> {
+    let account: UiAccount =
+        serde_json::from_value(value.clone()).map_err(|err| invalid(format!("account: {err}")))?;
+    let account: Account = account
+        .decode()
+        .ok_or_else(|| invalid("undecodable account data"))?;
+    if account.owner != *expected_owner {
+        return Err(invalid(format!("account owned by {}", account.owner)));
+    }
+    Ok(account)
+}
+
+fn decoded(fields: &str) -> Value {
+    serde_json::from_str(fields).expect("fixtures emit valid JSON")
+}
+
+fn str_field<'a>(value: &'a Value, field: &str) -> Result<&'a str, ExportError> {
+    value[field]
+        .as_str()
+        .ok_or_else(|| invalid(format!("missing `{field}`")))
+}
+
+fn pubkey_field(value: &Value, field: &str) -> Result<Pubkey, ExportError> {
+    Pubkey::from_str(str_field(value, field)?).map_err(|err| invalid(format!("{field}: {err}")))
+}
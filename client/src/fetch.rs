@@ -0,0 +1,262 @@
+// Fetch-and-decode helpers for the program's accounts. Accounts carry no type discriminator:
+// a multisig is recognised by its fixed size (`Multisig::LEN`), the accounts of a multisig
+// (proposals, but also its policy, display unit, distributions...) by its key in their first 32
+// bytes, and proposals among those by decoding as a `Transaction`.
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::client_error::ClientError;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use std::fmt;
+use std::ops::Range;
+
+use solana_multisig_wallet::state::{
+    find_history_address, find_metadata_address, find_owner_index_address,
+    find_transaction_address, load, History, Metadata, Multisig, OwnerIndex, Transaction,
+    MAX_OWNERS,
+};
+
+// most accounts a `getMultipleAccounts` call takes
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Debug)]
+pub enum FetchError {
+    Rpc(Box<ClientError>),
+    AccountNotFound(Pubkey),
+    // the account exists but isn't owned by the program, or doesn't decode
+    InvalidAccount(Pubkey),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Rpc(err) => write!(f, "rpc error: {err}"),
+            FetchError::AccountNotFound(key) => write!(f, "account {key} not found"),
+            FetchError::InvalidAccount(key) => {
+                write!(f, "account {key} is not an account of the program")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+impl From<ClientError> for FetchError {
+    fn from(err: ClientError) -> Self {
+        FetchError::Rpc(Box::new(err))
+    }
+}
+
+pub fn fetch_multisig(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+) -> Result<Multisig, FetchError> {
+    let account = fetch_program_account(rpc, program_id, multisig)?;
+    if account.data.len() != Multisig::LEN {
+        return Err(FetchError::InvalidAccount(*multisig));
+    }
+    load(&account.data).map_err(|_| FetchError::InvalidAccount(*multisig))
+}
+
+pub fn fetch_transaction(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    transaction: &Pubkey,
+) -> Result<Transaction, FetchError> {
+    let account = fetch_program_account(rpc, program_id, transaction)?;
+    load(&account.data).map_err(|_| FetchError::InvalidAccount(*transaction))
+}
+
+// The multisigs listed in the owner index of `owner`, `AccountNotFound` if they never registered
+// one
+pub fn fetch_owner_multisigs(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Vec<Pubkey>, FetchError> {
+    let (address, _) = find_owner_index_address(owner, program_id);
+    let account = fetch_program_account(rpc, program_id, &address)?;
+    let index: OwnerIndex = load(&account.data).map_err(|_| FetchError::InvalidAccount(address))?;
+    Ok(index.multisigs)
+}
+
+// The wallet's name and URI, `None` when it never set them
+pub fn fetch_metadata(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+) -> Result<Option<Metadata>, FetchError> {
+    let (address, _) = find_metadata_address(multisig, program_id);
+    match fetch_program_account(rpc, program_id, &address) {
+        Ok(account) => load(&account.data)
+            .map(Some)
+            .map_err(|_| FetchError::InvalidAccount(address)),
+        Err(FetchError::AccountNotFound(_)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+// The wallet's audit trail, `None` when it never created one
+pub fn fetch_history(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+) -> Result<Option<History>, FetchError> {
+    let (address, _) = find_history_address(multisig, program_id);
+    match fetch_program_account(rpc, program_id, &address) {
+        Ok(account) => load(&account.data)
+            .map(Some)
+            .map_err(|_| FetchError::InvalidAccount(address)),
+        Err(FetchError::AccountNotFound(_)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+// Every proposal of `multisig`, executed ones included
+pub fn fetch_transactions(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+) -> Result<Vec<(Pubkey, Transaction)>, FetchError> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(multisig_account_filters(multisig)),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    Ok(rpc
+        .get_program_accounts_with_config(program_id, config)?
+        .into_iter()
+        .filter_map(|(key, account)| Some((key, load::<Transaction>(&account.data).ok()?)))
+        .collect())
+}
+
+// The proposals made with `ProposeIndexed` numbered in `range` (cut at the multisig's
+// `transaction_count`), in order with their number and address. Derives the addresses instead of
+// scanning the program's accounts; numbers whose account is gone are skipped
+pub fn list_transactions(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    range: Range<u64>,
+) -> Result<Vec<(u64, Pubkey, Transaction)>, FetchError> {
+    let count = fetch_multisig(rpc, program_id, multisig)?.transaction_count;
+    let indexes: Vec<u64> = (range.start..range.end.min(count)).collect();
+    let mut transactions = Vec::with_capacity(indexes.len());
+    for chunk in indexes.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let addresses: Vec<Pubkey> = chunk
+            .iter()
+            .map(|&index| find_transaction_address(multisig, index, program_id).0)
+            .collect();
+        let accounts = rpc.get_multiple_accounts(&addresses)?;
+        for ((&index, address), account) in chunk.iter().zip(addresses).zip(accounts) {
+            let Some(account) = account else {
+                continue;
+            };
+            if account.owner != *program_id {
+                return Err(FetchError::InvalidAccount(address));
+            }
+            let transaction =
+                load(&account.data).map_err(|_| FetchError::InvalidAccount(address))?;
+            transactions.push((index, address, transaction));
+        }
+    }
+    Ok(transactions)
+}
+
+// Proposals of `multisig` that haven't been executed yet
+pub fn fetch_pending_transactions(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+) -> Result<Vec<(Pubkey, Transaction)>, FetchError> {
+    let mut transactions = fetch_transactions(rpc, program_id, multisig)?;
+    transactions.retain(|(_, transaction)| !transaction.executed);
+    Ok(transactions)
+}
+
+// `getProgramAccounts` filters matching the accounts that belong to `multisig`
+pub fn multisig_account_filters(multisig: &Pubkey) -> Vec<RpcFilterType> {
+    vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        0,
+        multisig.as_ref(),
+    ))]
+}
+
+// `getProgramAccounts` filters matching multisig accounts
+pub fn multisig_filters() -> Vec<RpcFilterType> {
+    vec![RpcFilterType::DataSize(Multisig::LEN as u64)]
+}
+
+// Same, for the multisigs needing `threshold` approvals
+pub fn multisig_threshold_filters(threshold: u8) -> Vec<RpcFilterType> {
+    let mut filters = multisig_filters();
+    filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+        Multisig::THRESHOLD_OFFSET,
+        vec![threshold],
+    )));
+    filters
+}
+
+// Same, for the multisigs listing `owner`: one set of filters per owner slot, as the key can sit
+// in any of the `MAX_OWNERS` slots and a memcmp matches at a single offset
+pub fn multisig_owner_filters(owner: &Pubkey) -> Vec<Vec<RpcFilterType>> {
+    (0..MAX_OWNERS)
+        .map(|slot| {
+            let mut filters = multisig_filters();
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                Multisig::OWNERS_OFFSET + 32 * slot,
+                owner.to_bytes().to_vec(),
+            )));
+            filters
+        })
+        .collect()
+}
+
+// The multisigs listing `owner`, scanning the program's accounts (a query per owner slot). For
+// owners without an owner index, `fetch_owner_multisigs` is a single account read
+pub fn fetch_multisigs_with_owner(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Vec<Pubkey>, FetchError> {
+    let mut multisigs = vec![];
+    for filters in multisig_owner_filters(owner) {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                // only the addresses are needed
+                data_slice: Some(UiDataSliceConfig {
+                    offset: 0,
+                    length: 0,
+                }),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let accounts = rpc.get_program_accounts_with_config(program_id, config)?;
+        multisigs.extend(accounts.into_iter().map(|(key, _)| key));
+    }
+    Ok(multisigs)
+}
+
+fn fetch_program_account(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    address: &Pubkey,
+) -> Result<Account, FetchError> {
+    let account = rpc
+        .get_account_with_commitment(address, rpc.commitment())?
+        .value
+        .ok_or(FetchError::AccountNotFound(*address))?;
+    if account.owner != *program_id {
+        return Err(FetchError::InvalidAccount(*address));
+    }
+    Ok(account)
+}
@@ -0,0 +1,14 @@
+// Off-chain helpers for wallets and frontends, not part of the on-chain program
+pub mod cache;
+pub mod cluster;
+pub mod compute_budget;
+pub mod display;
+pub mod events;
+pub mod export;
+pub mod fetch;
+pub mod lookup_table;
+pub mod migrate;
+pub mod nonce;
+pub mod offline;
+pub mod setup;
+pub mod simulate;
@@ -0,0 +1,84 @@
+// v0 messages referencing accounts through the multisig's address lookup tables. A legacy
+// transaction (1232 bytes) runs out of room at a few dozen 32-byte account keys, which proposals
+// executing with many remaining accounts exceed; a v0 message lists the accounts found in a table
+// as 1-byte indexes instead. Tables belong to the main vault and are created, extended and closed
+// through the threshold-gated `*LookupTable` instructions.
+use solana_client::rpc_client::RpcClient;
+use solana_multisig_wallet::{instruction, lookup_table, ProposalAction};
+use solana_program::address_lookup_table_account::AddressLookupTableAccount;
+use solana_program::hash::Hash;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::message::{v0, CompileError, VersionedMessage};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+
+use crate::fetch::FetchError;
+
+// `None` unless `account` is a lookup table
+pub fn lookup_table_from_account(
+    address: Pubkey,
+    account: &Account,
+) -> Option<AddressLookupTableAccount> {
+    if account.owner != lookup_table::ID {
+        return None;
+    }
+    Some(AddressLookupTableAccount {
+        key: address,
+        addresses: lookup_table::lookup_table_addresses(&account.data)?,
+    })
+}
+
+pub fn fetch_lookup_table(
+    rpc: &RpcClient,
+    address: &Pubkey,
+) -> Result<AddressLookupTableAccount, FetchError> {
+    let account = rpc
+        .get_account_with_commitment(address, rpc.commitment())?
+        .value
+        .ok_or(FetchError::AccountNotFound(*address))?;
+    lookup_table_from_account(*address, &account).ok_or(FetchError::InvalidAccount(*address))
+}
+
+// Accounts of `instructions` worth putting in a table, in order of appearance. Signers and the
+// invoked programs have to stay in the message itself
+pub fn lookup_table_candidates(instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut candidates: Vec<Pubkey> = vec![];
+    for ix in instructions {
+        for meta in &ix.accounts {
+            let invoked = instructions.iter().any(|ix| ix.program_id == meta.pubkey);
+            if !meta.is_signer && !invoked && !candidates.contains(&meta.pubkey) {
+                candidates.push(meta.pubkey);
+            }
+        }
+    }
+    candidates
+}
+
+// Accounts missing from `lookup_tables` are listed in full, as in a legacy message
+pub fn v0_message(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    lookup_tables: &[AddressLookupTableAccount],
+    blockhash: Hash,
+) -> Result<VersionedMessage, CompileError> {
+    let message = v0::Message::try_compile(payer, instructions, lookup_tables, blockhash)?;
+    Ok(VersionedMessage::V0(message))
+}
+
+// `remaining_accounts` follow the accounts `instruction::execute_transaction` derives from the
+// action
+#[allow(clippy::too_many_arguments)]
+pub fn execute_transaction_message(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    action: &ProposalAction,
+    remaining_accounts: &[AccountMeta],
+    lookup_tables: &[AddressLookupTableAccount],
+    blockhash: Hash,
+) -> Result<VersionedMessage, CompileError> {
+    let mut ix = instruction::execute_transaction(program_id, multisig, transaction, action);
+    ix.accounts.extend_from_slice(remaining_accounts);
+    v0_message(&[ix], payer, lookup_tables, blockhash)
+}
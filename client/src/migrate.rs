@@ -0,0 +1,453 @@
+// Migration from another treasury program: reads a Squads v3 / v4 multisig or an SPL-Governance
+// governance over RPC and builds the instructions creating a multisig of this program with the
+// same owner set and threshold, funded like the source vault.
+//
+// The source accounts are decoded from their published layouts rather than through the other
+// programs' crates. Squads owners map one to one (v4 members without the vote permission are
+// left out). A governance has no owner list, its members are the token owner records holding
+// deposits of the mint that votes, counted one vote each as in council-run "multisig DAOs", and
+// its yes-vote percentage becomes a threshold over them.
+use borsh::BorshDeserialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::client_error::ClientError;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_program::hash::hash;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey;
+use solana_program::pubkey::Pubkey;
+use std::fmt;
+
+use crate::setup::{create_multisig_instructions, SetupError};
+
+pub const SQUADS_V3_PROGRAM_ID: Pubkey = pubkey!("SMPLecH534NA9acpos4G6x7uf3LWbCAwZQE9e8ZekMu");
+pub const SQUADS_V4_PROGRAM_ID: Pubkey = pubkey!("SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf");
+pub const SPL_GOVERNANCE_PROGRAM_ID: Pubkey =
+    pubkey!("GovER5Lthms3bLBqWub97yVrMmEogzX7xNjdXpPPCVZw");
+
+// Squads v4 member permission bits, only voters become owners
+const SQUADS_V4_VOTE: u8 = 1 << 1;
+
+// SPL-Governance account types (`GovernanceAccountType`)
+const REALM_V2: u8 = 16;
+const TOKEN_OWNER_RECORD_V2: u8 = 17;
+const GOVERNANCE_V2: u8 = 18;
+const TOKEN_GOVERNANCE_V2: u8 = 21;
+// byte offsets into a `TokenOwnerRecordV2`
+const RECORD_REALM_OFFSET: usize = 1;
+const RECORD_MINT_OFFSET: usize = 33;
+const RECORD_OWNER_OFFSET: usize = 65;
+const RECORD_DEPOSIT_OFFSET: usize = 97;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    SquadsV3,
+    SquadsV4,
+    SplGovernance,
+}
+
+impl Source {
+    pub fn program_id(self) -> Pubkey {
+        match self {
+            Source::SquadsV3 => SQUADS_V3_PROGRAM_ID,
+            Source::SquadsV4 => SQUADS_V4_PROGRAM_ID,
+            Source::SplGovernance => SPL_GOVERNANCE_PROGRAM_ID,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MigrateError {
+    Rpc(Box<ClientError>),
+    AccountNotFound(Pubkey),
+    // the account belongs to another program than the source's
+    WrongProgram { account: Pubkey, owner: Pubkey },
+    // the account doesn't decode as the source's, or its configuration has no equivalent here
+    Invalid(String),
+}
+
+impl fmt::Display for MigrateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrateError::Rpc(err) => write!(f, "rpc error: {err}"),
+            MigrateError::AccountNotFound(key) => write!(f, "account {key} not found"),
+            MigrateError::WrongProgram { account, owner } => {
+                write!(
+                    f,
+                    "account {account} is owned by {owner}, not the source program"
+                )
+            }
+            MigrateError::Invalid(err) => write!(f, "can't import: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for MigrateError {}
+
+impl From<ClientError> for MigrateError {
+    fn from(err: ClientError) -> Self {
+        MigrateError::Rpc(Box::new(err))
+    }
+}
+
+fn invalid(message: impl fmt::Display) -> MigrateError {
+    MigrateError::Invalid(message.to_string())
+}
+
+// Owner set, threshold and vault of the source treasury
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceConfig {
+    pub owners: Vec<Pubkey>,
+    pub threshold: u8,
+    // where the source keeps its SOL: the Squads vault or the governance's native treasury
+    pub vault: Pubkey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedTreasury {
+    pub source: Source,
+    pub address: Pubkey,
+    pub config: SourceConfig,
+    pub vault_lamports: u64,
+}
+
+impl ImportedTreasury {
+    // `create_multisig_instructions` for the imported owners and threshold. The payer funds the
+    // new main vault with the source vault's balance when `fund` is set, moving the source's own
+    // funds over takes a proposal on the source
+    pub fn creation_instructions(
+        &self,
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        multisig: &Pubkey,
+        rent_lamports: u64,
+        fund: bool,
+    ) -> Result<Vec<Instruction>, SetupError> {
+        create_multisig_instructions(
+            program_id,
+            payer,
+            multisig,
+            self.config.owners.clone(),
+            self.config.threshold,
+            rent_lamports,
+            if fund { self.vault_lamports } else { 0 },
+        )
+    }
+}
+
+// Reads the treasury at `address`: the multisig account for Squads, the governance for
+// SPL-Governance
+pub fn fetch_treasury(
+    rpc: &RpcClient,
+    source: Source,
+    address: &Pubkey,
+) -> Result<ImportedTreasury, MigrateError> {
+    let data = fetch_source_account(rpc, source, address)?;
+    let config = match source {
+        Source::SquadsV3 => decode_squads_v3(address, &data)?,
+        Source::SquadsV4 => decode_squads_v4(address, &data)?,
+        Source::SplGovernance => {
+            let governance = decode_governance(&data)?;
+            let realm = decode_realm(&fetch_source_account(rpc, source, &governance.realm)?)?;
+            let (percent, mint) = match (governance.community, governance.council) {
+                (Some(percent), _) => (percent, realm.community_mint),
+                (None, Some(percent)) => (
+                    percent,
+                    realm
+                        .council_mint
+                        .ok_or_else(|| invalid("council votes but the realm has no council"))?,
+                ),
+                (None, None) => return Err(invalid("governance has voting disabled")),
+            };
+            let members = fetch_governance_members(rpc, &governance.realm, &mint)?;
+            SourceConfig {
+                threshold: governance_threshold(percent, members.len())?,
+                owners: members,
+                vault: find_native_treasury_address(address),
+            }
+        }
+    };
+    let vault_lamports = rpc.get_balance(&config.vault)?;
+    Ok(ImportedTreasury {
+        source,
+        address: *address,
+        config,
+        vault_lamports,
+    })
+}
+
+fn fetch_source_account(
+    rpc: &RpcClient,
+    source: Source,
+    address: &Pubkey,
+) -> Result<Vec<u8>, MigrateError> {
+    let account = rpc
+        .get_account_with_commitment(address, rpc.commitment())?
+        .value
+        .ok_or(MigrateError::AccountNotFound(*address))?;
+    if account.owner != source.program_id() {
+        return Err(MigrateError::WrongProgram {
+            account: *address,
+            owner: account.owner,
+        });
+    }
+    Ok(account.data)
+}
+
+// Owners of the token owner records of `realm` with a deposit of `mint`
+fn fetch_governance_members(
+    rpc: &RpcClient,
+    realm: &Pubkey,
+    mint: &Pubkey,
+) -> Result<Vec<Pubkey>, MigrateError> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, vec![TOKEN_OWNER_RECORD_V2])),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                RECORD_REALM_OFFSET,
+                realm.to_bytes().to_vec(),
+            )),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                RECORD_MINT_OFFSET,
+                mint.to_bytes().to_vec(),
+            )),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let records = rpc.get_program_accounts_with_config(&SPL_GOVERNANCE_PROGRAM_ID, config)?;
+    let mut members: Vec<Pubkey> = records
+        .iter()
+        .filter_map(|(_, account)| decode_token_owner_record(&account.data))
+        .filter(|(_, deposit)| *deposit > 0)
+        .map(|(owner, _)| owner)
+        .collect();
+    members.sort();
+    members.dedup();
+    Ok(members)
+}
+
+// Anchor account discriminator, the first 8 bytes of a Squads account
+fn anchor_discriminator(name: &str) -> [u8; 8] {
+    let mut discriminator = [0; 8];
+    discriminator.copy_from_slice(&hash(format!("account:{name}").as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+fn anchor_body<'a>(data: &'a [u8], name: &str) -> Result<&'a [u8], MigrateError> {
+    match data.get(..8) {
+        Some(discriminator) if discriminator == anchor_discriminator(name) => Ok(&data[8..]),
+        _ => Err(invalid(format!("not a Squads `{name}` account"))),
+    }
+}
+
+fn squads_threshold(threshold: u16, owners: usize) -> Result<u8, MigrateError> {
+    match u8::try_from(threshold) {
+        Ok(threshold) if threshold > 0 && threshold as usize <= owners => Ok(threshold),
+        _ => Err(invalid(format!("threshold {threshold} of {owners} owners"))),
+    }
+}
+
+#[derive(BorshDeserialize)]
+struct SquadsV3Multisig {
+    threshold: u16,
+    _authority_index: u16,
+    _transaction_index: u32,
+    _ms_change_index: u32,
+    _bump: u8,
+    _create_key: Pubkey,
+    _allow_external_execute: bool,
+    keys: Vec<Pubkey>,
+}
+
+// Squads v3 `Ms` account, its vault is the default authority (index 1)
+pub fn decode_squads_v3(address: &Pubkey, data: &[u8]) -> Result<SourceConfig, MigrateError> {
+    let ms = SquadsV3Multisig::deserialize(&mut anchor_body(data, "Ms")?).map_err(invalid)?;
+    let (vault, _) = Pubkey::find_program_address(
+        &[
+            b"squad",
+            address.as_ref(),
+            &1u32.to_le_bytes(),
+            b"authority",
+        ],
+        &SQUADS_V3_PROGRAM_ID,
+    );
+    Ok(SourceConfig {
+        threshold: squads_threshold(ms.threshold, ms.keys.len())?,
+        owners: ms.keys,
+        vault,
+    })
+}
+
+#[derive(BorshDeserialize)]
+struct SquadsV4Member {
+    key: Pubkey,
+    permissions: u8,
+}
+
+#[derive(BorshDeserialize)]
+struct SquadsV4Multisig {
+    _create_key: Pubkey,
+    _config_authority: Pubkey,
+    threshold: u16,
+    _time_lock: u32,
+    _transaction_index: u64,
+    _stale_transaction_index: u64,
+    _rent_collector: Option<Pubkey>,
+    _bump: u8,
+    members: Vec<SquadsV4Member>,
+}
+
+// Squads v4 `Multisig` account, its vault is the one at index 0
+pub fn decode_squads_v4(address: &Pubkey, data: &[u8]) -> Result<SourceConfig, MigrateError> {
+    let multisig =
+        SquadsV4Multisig::deserialize(&mut anchor_body(data, "Multisig")?).map_err(invalid)?;
+    let owners: Vec<Pubkey> = multisig
+        .members
+        .iter()
+        .filter(|member| member.permissions & SQUADS_V4_VOTE != 0)
+        .map(|member| member.key)
+        .collect();
+    let (vault, _) = Pubkey::find_program_address(
+        &[b"multisig", address.as_ref(), b"vault", &[0]],
+        &SQUADS_V4_PROGRAM_ID,
+    );
+    Ok(SourceConfig {
+        threshold: squads_threshold(multisig.threshold, owners.len())?,
+        owners,
+        vault,
+    })
+}
+
+#[derive(BorshDeserialize)]
+enum VoteThreshold {
+    YesVotePercentage(u8),
+    QuorumPercentage(u8),
+    Disabled,
+}
+
+impl VoteThreshold {
+    fn percent(&self) -> Option<u8> {
+        match self {
+            VoteThreshold::YesVotePercentage(percent)
+            | VoteThreshold::QuorumPercentage(percent) => Some(*percent),
+            VoteThreshold::Disabled => None,
+        }
+    }
+}
+
+#[derive(BorshDeserialize)]
+enum VoteTipping {
+    Strict,
+    Early,
+    Disabled,
+}
+
+#[derive(BorshDeserialize)]
+struct GovernanceV2 {
+    account_type: u8,
+    realm: Pubkey,
+    _governed_account: Pubkey,
+    _reserved: u32,
+    community_vote_threshold: VoteThreshold,
+    _min_community_weight_to_create_proposal: u64,
+    _min_transaction_hold_up_time: u32,
+    _voting_base_time: u32,
+    _community_vote_tipping: VoteTipping,
+    council_vote_threshold: VoteThreshold,
+}
+
+// What a governance account tells: its realm and the vote percentages of each voter population,
+// `None` where that population can't vote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Governance {
+    pub realm: Pubkey,
+    pub community: Option<u8>,
+    pub council: Option<u8>,
+}
+
+// A `GovernanceV2` (or its program, mint and token variants, which share the layout)
+pub fn decode_governance(data: &[u8]) -> Result<Governance, MigrateError> {
+    let governance = GovernanceV2::deserialize(&mut &data[..]).map_err(invalid)?;
+    if !(GOVERNANCE_V2..=TOKEN_GOVERNANCE_V2).contains(&governance.account_type) {
+        return Err(invalid("not a governance account"));
+    }
+    Ok(Governance {
+        realm: governance.realm,
+        community: governance.community_vote_threshold.percent(),
+        council: governance.council_vote_threshold.percent(),
+    })
+}
+
+// `MintMaxVoterWeightSource`, either variant holds a u64
+#[derive(BorshDeserialize)]
+struct MintMaxVoterWeightSource {
+    _variant: u8,
+    _value: u64,
+}
+
+#[derive(BorshDeserialize)]
+struct RealmV2 {
+    account_type: u8,
+    community_mint: Pubkey,
+    _legacy: [u8; 2],
+    _reserved: [u8; 6],
+    _min_community_weight_to_create_governance: u64,
+    _community_mint_max_voter_weight_source: MintMaxVoterWeightSource,
+    council_mint: Option<Pubkey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Realm {
+    pub community_mint: Pubkey,
+    pub council_mint: Option<Pubkey>,
+}
+
+pub fn decode_realm(data: &[u8]) -> Result<Realm, MigrateError> {
+    let realm = RealmV2::deserialize(&mut &data[..]).map_err(invalid)?;
+    if realm.account_type != REALM_V2 {
+        return Err(invalid("not a realm account"));
+    }
+    Ok(Realm {
+        community_mint: realm.community_mint,
+        council_mint: realm.council_mint,
+    })
+}
+
+// Owner and deposit of a `TokenOwnerRecordV2`
+pub fn decode_token_owner_record(data: &[u8]) -> Option<(Pubkey, u64)> {
+    if data.first() != Some(&TOKEN_OWNER_RECORD_V2) {
+        return None;
+    }
+    let owner = data.get(RECORD_OWNER_OFFSET..RECORD_DEPOSIT_OFFSET)?;
+    let deposit = data.get(RECORD_DEPOSIT_OFFSET..RECORD_DEPOSIT_OFFSET + 8)?;
+    Some((
+        Pubkey::try_from(owner).ok()?,
+        u64::from_le_bytes(deposit.try_into().ok()?),
+    ))
+}
+
+// Approvals needed out of `members` for a vote to pass at `percent`, one vote per member
+pub fn governance_threshold(percent: u8, members: usize) -> Result<u8, MigrateError> {
+    if members == 0 {
+        return Err(invalid("the governance has no members with a deposit"));
+    }
+    if percent == 0 || percent > 100 {
+        return Err(invalid(format!("vote threshold of {percent}%")));
+    }
+    let threshold = (members * percent as usize).div_ceil(100);
+    u8::try_from(threshold).map_err(|_| invalid(format!("{members} members")))
+}
+
+// The governance's SOL treasury
+pub fn find_native_treasury_address(governance: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"native-treasury", governance.as_ref()],
+        &SPL_GOVERNANCE_PROGRAM_ID,
+    )
+    .0
+}
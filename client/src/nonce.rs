@@ -0,0 +1,106 @@
+// Durable nonce variants of the instruction builders. Collecting approvals often takes longer than
+// a recent blockhash stays valid (about 2 minutes); a message built on a durable nonce stays valid
+// until the nonce advances. Its first instruction advances it, so the message lands at most once,
+// and the nonce authority has to sign it along with the message's own signers.
+use solana_client::nonce_utils;
+use solana_client::rpc_client::RpcClient;
+use solana_multisig_wallet::{instruction, ProposalAction};
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::message::Message;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+
+use crate::fetch::FetchError;
+
+// A nonce account and the blockhash it currently holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurableNonce {
+    pub account: Pubkey,
+    pub authority: Pubkey,
+    pub blockhash: Hash,
+}
+
+impl DurableNonce {
+    // `None` unless `account` is an initialized nonce account
+    pub fn from_account(address: Pubkey, account: &Account) -> Option<Self> {
+        let data = nonce_utils::data_from_account(account).ok()?;
+        Some(DurableNonce {
+            account: address,
+            authority: data.authority,
+            blockhash: data.blockhash(),
+        })
+    }
+
+    pub fn fetch(rpc: &RpcClient, address: &Pubkey) -> Result<Self, FetchError> {
+        let account = rpc
+            .get_account_with_commitment(address, rpc.commitment())?
+            .value
+            .ok_or(FetchError::AccountNotFound(*address))?;
+        Self::from_account(*address, &account).ok_or(FetchError::InvalidAccount(*address))
+    }
+}
+
+// `instructions` behind the nonce's advance instruction, with the nonce's blockhash
+pub fn nonce_message(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    nonce: &DurableNonce,
+) -> Message {
+    let mut message = Message::new_with_nonce(
+        instructions.to_vec(),
+        Some(payer),
+        &nonce.account,
+        &nonce.authority,
+    );
+    message.recent_blockhash = nonce.blockhash;
+    message
+}
+
+pub fn sign_message(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    owner: &Pubkey,
+    multisig: &Pubkey,
+    nonce: &DurableNonce,
+) -> Message {
+    let ix = instruction::sign(program_id, owner, multisig);
+    nonce_message(&[ix], payer, nonce)
+}
+
+pub fn execute_message(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    amount: u64,
+    destination: &Pubkey,
+    nonce: &DurableNonce,
+) -> Message {
+    let ix = instruction::execute(program_id, multisig, amount, destination);
+    nonce_message(&[ix], payer, nonce)
+}
+
+pub fn approve_message(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    owner: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    action: &ProposalAction,
+    nonce: &DurableNonce,
+) -> Message {
+    let ix = instruction::approve(program_id, owner, multisig, transaction, action);
+    nonce_message(&[ix], payer, nonce)
+}
+
+pub fn execute_transaction_message(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    action: &ProposalAction,
+    nonce: &DurableNonce,
+) -> Message {
+    let ix = instruction::execute_transaction(program_id, multisig, transaction, action);
+    nonce_message(&[ix], payer, nonce)
+}
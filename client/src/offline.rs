@@ -0,0 +1,151 @@
+// Air-gapped signing. An online machine assembles the unsigned transaction, normally on a durable
+// nonce (`nonce`) since collecting signatures by hand outlives any recent blockhash, and hands it
+// over as base64 of its wire format. Each owner signs its message on a machine that never goes
+// online (`sign_encoded`), and `sign_offline` merges the signatures back, checking each one,
+// before the transaction is sent.
+use base64::Engine;
+use solana_multisig_wallet::ProposalAction;
+use solana_program::message::Message;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::sanitize::Sanitize;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use std::fmt;
+
+use crate::nonce::{self, DurableNonce};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OfflineError {
+    // not base64 of a transaction
+    Decode(String),
+    // the key doesn't have to sign the transaction
+    NotASigner(Pubkey),
+    // the signature isn't the key's over this transaction's message
+    BadSignature(Pubkey),
+}
+
+impl fmt::Display for OfflineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OfflineError::Decode(err) => write!(f, "invalid transaction: {err}"),
+            OfflineError::NotASigner(key) => write!(f, "{key} doesn't sign this transaction"),
+            OfflineError::BadSignature(key) => {
+                write!(f, "signature by {key} doesn't match the transaction")
+            }
+        }
+    }
+}
+
+impl std::error::Error for OfflineError {}
+
+pub fn encode_transaction(transaction: &Transaction) -> String {
+    let bytes = bincode::serialize(transaction).expect("transactions serialize");
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+pub fn decode_transaction(encoded: &str) -> Result<Transaction, OfflineError> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|err| OfflineError::Decode(err.to_string()))?;
+    let transaction: Transaction =
+        bincode::deserialize(&bytes).map_err(|err| OfflineError::Decode(err.to_string()))?;
+    if transaction.sanitize().is_err() {
+        return Err(OfflineError::Decode("malformed message".to_string()));
+    }
+    Ok(transaction)
+}
+
+// `message` as a transaction with every signature still blank
+pub fn unsigned_transaction(message: Message) -> String {
+    encode_transaction(&Transaction::new_unsigned(message))
+}
+
+// `Approve` of the proposal at `transaction` by `owner`, on `nonce`
+pub fn approval_transaction(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    owner: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    action: &ProposalAction,
+    nonce: &DurableNonce,
+) -> String {
+    unsigned_transaction(nonce::approve_message(
+        program_id,
+        payer,
+        owner,
+        multisig,
+        transaction,
+        action,
+        nonce,
+    ))
+}
+
+// `ExecuteTransaction` of the proposal at `transaction`, on `nonce`
+pub fn execution_transaction(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    action: &ProposalAction,
+    nonce: &DurableNonce,
+) -> String {
+    unsigned_transaction(nonce::execute_transaction_message(
+        program_id,
+        payer,
+        multisig,
+        transaction,
+        action,
+        nonce,
+    ))
+}
+
+// The keys whose signature the encoded transaction still lacks
+pub fn missing_signers(transaction: &Transaction) -> Vec<Pubkey> {
+    let signers = transaction.message.header.num_required_signatures as usize;
+    transaction.message.account_keys[..signers]
+        .iter()
+        .zip(&transaction.signatures)
+        .filter(|(_, signature)| **signature == Signature::default())
+        .map(|(key, _)| *key)
+        .collect()
+}
+
+// On the cold machine: `signer`'s signature over the encoded transaction's message, to carry back
+pub fn sign_encoded(
+    encoded: &str,
+    signer: &dyn Signer,
+) -> Result<(Pubkey, Signature), OfflineError> {
+    let transaction = decode_transaction(encoded)?;
+    let key = signer.pubkey();
+    if signer_position(&transaction, &key).is_none() {
+        return Err(OfflineError::NotASigner(key));
+    }
+    Ok((key, signer.sign_message(&transaction.message_data())))
+}
+
+// Places externally produced `signatures` in the encoded transaction, each checked against the
+// message. Signatures already there are kept, so owners' signatures can be merged as they come in
+pub fn sign_offline(
+    encoded: &str,
+    signatures: &[(Pubkey, Signature)],
+) -> Result<String, OfflineError> {
+    let mut transaction = decode_transaction(encoded)?;
+    let message = transaction.message_data();
+    for (key, signature) in signatures {
+        let position = signer_position(&transaction, key).ok_or(OfflineError::NotASigner(*key))?;
+        if !signature.verify(key.as_ref(), &message) {
+            return Err(OfflineError::BadSignature(*key));
+        }
+        transaction.signatures[position] = *signature;
+    }
+    Ok(encode_transaction(&transaction))
+}
+
+fn signer_position(transaction: &Transaction, key: &Pubkey) -> Option<usize> {
+    let signers = transaction.message.header.num_required_signatures as usize;
+    transaction.message.account_keys[..signers]
+        .iter()
+        .position(|signer| signer == key)
+}
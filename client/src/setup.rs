@@ -0,0 +1,273 @@
+// Guided wallet setup: create -> owner verification -> policy configuration -> test transfer ->
+// activation. The wizard only builds instructions and checks fetched account state, so every
+// frontend drives the same sequence whatever RPC client / wallet adapter it uses.
+use solana_multisig_wallet::state::{Multisig, MAX_OWNERS};
+use solana_multisig_wallet::{find_vault_address, instruction};
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_sdk::transaction::Transaction;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetupStep {
+    // allocate and initialize the multisig account
+    Create,
+    // every owner submits a `Sign`, proving they control their key
+    VerifyOwners { pending: Vec<Pubkey> },
+    // integrator supplied configuration instructions (may be empty)
+    ConfigurePolicy,
+    // a small `Execute` back to the payer, proves the quorum can move funds (and resets approvals)
+    TestTransfer,
+    // transfer the initial treasury funding
+    Activate,
+    Complete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetupError {
+    NoOwners,
+    TooManyOwners(usize),
+    DuplicateOwner(Pubkey),
+    InvalidThreshold { threshold: u8, owners: usize },
+    // the fetched account doesn't match what this wizard created
+    StateMismatch(&'static str),
+    // the current step isn't finished on-chain yet
+    StepIncomplete(SetupStep),
+    AlreadyComplete,
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::NoOwners => write!(f, "at least one owner is required"),
+            SetupError::TooManyOwners(owners) => {
+                write!(f, "{owners} owners, at most {MAX_OWNERS} are supported")
+            }
+            SetupError::DuplicateOwner(owner) => write!(f, "owner {owner} is listed twice"),
+            SetupError::InvalidThreshold { threshold, owners } => {
+                write!(f, "threshold {threshold} is invalid for {owners} owners")
+            }
+            SetupError::StateMismatch(what) => write!(f, "on-chain multisig mismatch: {what}"),
+            SetupError::StepIncomplete(step) => write!(f, "step not completed yet: {step:?}"),
+            SetupError::AlreadyComplete => write!(f, "setup already complete"),
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+fn check_owners(owners: &[Pubkey], threshold: u8) -> Result<(), SetupError> {
+    if owners.is_empty() {
+        return Err(SetupError::NoOwners);
+    }
+    if owners.len() > MAX_OWNERS {
+        return Err(SetupError::TooManyOwners(owners.len()));
+    }
+    for (index, owner) in owners.iter().enumerate() {
+        if owners[..index].contains(owner) {
+            return Err(SetupError::DuplicateOwner(*owner));
+        }
+    }
+    if threshold == 0 || threshold as usize > owners.len() {
+        return Err(SetupError::InvalidThreshold {
+            threshold,
+            owners: owners.len(),
+        });
+    }
+    Ok(())
+}
+
+// Allocation of the multisig account (`rent_lamports`, the rent-exempt minimum of
+// `Multisig::LEN`), its `Create`, then `initial_funding` lamports to the main vault unless 0. They
+// have to land together: an allocated but uninitialized account can be initialized by anyone
+pub fn create_multisig_instructions(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    owners: Vec<Pubkey>,
+    threshold: u8,
+    rent_lamports: u64,
+    initial_funding: u64,
+) -> Result<Vec<Instruction>, SetupError> {
+    check_owners(&owners, threshold)?;
+    let mut instructions = vec![
+        system_instruction::create_account(
+            payer,
+            multisig,
+            rent_lamports,
+            Multisig::LEN as u64,
+            program_id,
+        ),
+        instruction::create(program_id, multisig, owners, threshold),
+    ];
+    if initial_funding > 0 {
+        let (vault, _) = find_vault_address(multisig, program_id);
+        instructions.push(system_instruction::transfer(payer, &vault, initial_funding));
+    }
+    Ok(instructions)
+}
+
+// Same as one unsigned transaction, the payer and the `multisig` keypair sign it
+pub fn create_multisig_transaction(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    owners: Vec<Pubkey>,
+    threshold: u8,
+    rent_lamports: u64,
+    initial_funding: u64,
+) -> Result<Transaction, SetupError> {
+    let instructions = create_multisig_instructions(
+        program_id,
+        payer,
+        multisig,
+        owners,
+        threshold,
+        rent_lamports,
+        initial_funding,
+    )?;
+    Ok(Transaction::new_with_payer(&instructions, Some(payer)))
+}
+
+pub struct Wizard {
+    program_id: Pubkey,
+    payer: Pubkey,
+    multisig: Pubkey,
+    owners: Vec<Pubkey>,
+    threshold: u8,
+    policy_instructions: Vec<Instruction>,
+    test_transfer_lamports: u64,
+    initial_funding: u64,
+    step: SetupStep,
+}
+
+impl Wizard {
+    // `multisig` is the address of a fresh keypair, it co-signs the `Create` step
+    pub fn new(
+        program_id: Pubkey,
+        payer: Pubkey,
+        multisig: Pubkey,
+        owners: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<Self, SetupError> {
+        check_owners(&owners, threshold)?;
+        Ok(Wizard {
+            program_id,
+            payer,
+            multisig,
+            owners,
+            threshold,
+            policy_instructions: vec![],
+            test_transfer_lamports: 1,
+            initial_funding: 0,
+            step: SetupStep::Create,
+        })
+    }
+
+    pub fn with_policy(mut self, instructions: Vec<Instruction>) -> Self {
+        self.policy_instructions = instructions;
+        self
+    }
+
+    pub fn with_test_transfer(mut self, lamports: u64) -> Self {
+        self.test_transfer_lamports = lamports;
+        self
+    }
+
+    pub fn with_initial_funding(mut self, lamports: u64) -> Self {
+        self.initial_funding = lamports;
+        self
+    }
+
+    pub fn step(&self) -> &SetupStep {
+        &self.step
+    }
+
+    pub fn multisig(&self) -> &Pubkey {
+        &self.multisig
+    }
+
+    pub fn space(&self) -> usize {
+        Multisig::LEN
+    }
+
+    // Instructions for the current step. `rent_lamports` is the rent-exempt minimum for
+    // `space()`, only used by `Create`. During `VerifyOwners` there is one instruction per
+    // pending owner, each signed by that owner.
+    pub fn instructions(&self, rent_lamports: u64) -> Vec<Instruction> {
+        match &self.step {
+            SetupStep::Create => vec![
+                system_instruction::create_account(
+                    &self.payer,
+                    &self.multisig,
+                    // the test transfer is paid from the multisig balance
+                    rent_lamports + self.test_transfer_lamports,
+                    self.space() as u64,
+                    &self.program_id,
+                ),
+                instruction::create(
+                    &self.program_id,
+                    &self.multisig,
+                    self.owners.clone(),
+                    self.threshold,
+                ),
+            ],
+            SetupStep::VerifyOwners { pending } => pending
+                .iter()
+                .map(|owner| instruction::sign(&self.program_id, owner, &self.multisig))
+                .collect(),
+            SetupStep::ConfigurePolicy => self.policy_instructions.clone(),
+            SetupStep::TestTransfer => vec![instruction::execute(
+                &self.program_id,
+                &self.multisig,
+                self.test_transfer_lamports,
+                &self.payer,
+            )],
+            SetupStep::Activate => vec![system_instruction::transfer(
+                &self.payer,
+                &self.multisig,
+                self.initial_funding,
+            )],
+            SetupStep::Complete => vec![],
+        }
+    }
+
+    // Moves to the next step once `multisig_state` (freshly fetched, after the current step's
+    // instructions landed) shows the step is done. Returns the new step.
+    pub fn advance(&mut self, multisig_state: &Multisig) -> Result<&SetupStep, SetupError> {
+        if multisig_state.owners != self.owners {
+            return Err(SetupError::StateMismatch("owners"));
+        }
+        if multisig_state.threshold != self.threshold {
+            return Err(SetupError::StateMismatch("threshold"));
+        }
+
+        let unsigned: Vec<Pubkey> = self
+            .owners
+            .iter()
+            .zip(&multisig_state.signers)
+            .filter(|(_, &signed)| !signed)
+            .map(|(owner, _)| *owner)
+            .collect();
+
+        self.step = match &self.step {
+            SetupStep::Create => SetupStep::VerifyOwners { pending: unsigned },
+            SetupStep::VerifyOwners { .. } if unsigned.is_empty() => SetupStep::ConfigurePolicy,
+            SetupStep::VerifyOwners { .. } => {
+                // refresh who is still missing
+                self.step = SetupStep::VerifyOwners { pending: unsigned };
+                return Err(SetupError::StepIncomplete(self.step.clone()));
+            }
+            SetupStep::ConfigurePolicy => SetupStep::TestTransfer,
+            // a successful `Execute` resets every approval
+            SetupStep::TestTransfer if unsigned.len() == self.owners.len() => SetupStep::Activate,
+            SetupStep::TestTransfer => {
+                return Err(SetupError::StepIncomplete(SetupStep::TestTransfer))
+            }
+            SetupStep::Activate => SetupStep::Complete,
+            SetupStep::Complete => return Err(SetupError::AlreadyComplete),
+        };
+        Ok(&self.step)
+    }
+}
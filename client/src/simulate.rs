@@ -0,0 +1,134 @@
+// Dry run of a proposal's execution with `simulateTransaction`, for UIs to show "ready to execute"
+// from what the cluster would do right now rather than from counting approvals: expiry, pause,
+// policies, balances and the action's own CPIs all play a part.
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+};
+use solana_multisig_wallet::instruction;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+use crate::fetch::{self, FetchError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceChange {
+    pub account: Pubkey,
+    pub before: u64,
+    pub after: u64,
+}
+
+impl BalanceChange {
+    pub fn delta(&self) -> i128 {
+        self.after as i128 - self.before as i128
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecuteSimulation {
+    // why the execution would fail, `None` when it would succeed
+    pub error: Option<TransactionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    // every account the execution passes to the program
+    pub accounts: Vec<AccountMeta>,
+    // writable accounts of the instruction whose lamports would change (fees aside), empty when it
+    // would fail
+    pub balance_changes: Vec<BalanceChange>,
+}
+
+impl ExecuteSimulation {
+    pub fn ready(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+// Pairs up balances before and after (`None` for an account that doesn't exist), unchanged ones
+// are left out
+pub fn balance_changes(
+    accounts: &[Pubkey],
+    before: &[Option<u64>],
+    after: &[Option<u64>],
+) -> Vec<BalanceChange> {
+    accounts
+        .iter()
+        .zip(before.iter().zip(after))
+        .map(|(account, (before, after))| BalanceChange {
+            account: *account,
+            before: before.unwrap_or(0),
+            after: after.unwrap_or(0),
+        })
+        .filter(|change| change.before != change.after)
+        .collect()
+}
+
+// The `ExecuteTransaction` of the proposal at `transaction`, paid by `payer`
+pub fn simulate_execute(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    transaction: &Pubkey,
+) -> Result<ExecuteSimulation, FetchError> {
+    let proposal = fetch::fetch_transaction(rpc, program_id, transaction)?;
+    let ix = instruction::execute_transaction_from_vault(
+        program_id,
+        &proposal.multisig,
+        transaction,
+        proposal.vault,
+        &proposal.action,
+    );
+    simulate_instruction(rpc, payer, ix)
+}
+
+// Any instruction, e.g. an `Execute` or an `ExecuteTransaction` with extra accounts
+pub fn simulate_instruction(
+    rpc: &RpcClient,
+    payer: &Pubkey,
+    ix: Instruction,
+) -> Result<ExecuteSimulation, FetchError> {
+    let writable: Vec<Pubkey> = ix
+        .accounts
+        .iter()
+        .filter(|meta| meta.is_writable)
+        .map(|meta| meta.pubkey)
+        .collect();
+    let before: Vec<Option<u64>> = rpc
+        .get_multiple_accounts(&writable)?
+        .iter()
+        .map(|account| account.as_ref().map(|account| account.lamports))
+        .collect();
+
+    // unsigned, with the latest blockhash filled in by the node
+    let tx = Transaction::new_with_payer(std::slice::from_ref(&ix), Some(payer));
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(rpc.commitment()),
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            addresses: writable.iter().map(|key| key.to_string()).collect(),
+        }),
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let result = rpc.simulate_transaction_with_config(&tx, config)?.value;
+
+    let balance_changes = match (&result.err, &result.accounts) {
+        (None, Some(accounts)) => {
+            let after: Vec<Option<u64>> = accounts
+                .iter()
+                .map(|account| account.as_ref().map(|account| account.lamports))
+                .collect();
+            balance_changes(&writable, &before, &after)
+        }
+        _ => vec![],
+    };
+    Ok(ExecuteSimulation {
+        error: result.err,
+        logs: result.logs.unwrap_or_default(),
+        units_consumed: result.units_consumed,
+        accounts: ix.accounts,
+        balance_changes,
+    })
+}
@@ -0,0 +1,47 @@
+// Cluster monikers, endpoints and deployments
+use multisig_client::cluster::{websocket_url, Cluster, UnknownCluster};
+use solana_program::pubkey::Pubkey;
+
+#[test]
+fn test_parse_cluster() {
+    assert_eq!("mainnet-beta".parse(), Ok(Cluster::Mainnet));
+    assert_eq!("m".parse(), Ok(Cluster::Mainnet));
+    assert_eq!("devnet".parse(), Ok(Cluster::Devnet));
+    assert_eq!("t".parse(), Ok(Cluster::Testnet));
+    assert_eq!("localhost".parse(), Ok(Cluster::Localnet));
+    assert_eq!(
+        "nowhere".parse::<Cluster>(),
+        Err(UnknownCluster("nowhere".to_string()))
+    );
+
+    let custom: Cluster = "https://rpc.example.com/key".parse().unwrap();
+    assert_eq!(custom.url(), "https://rpc.example.com/key");
+    assert_eq!(custom.ws_url(), "wss://rpc.example.com/key");
+    assert_eq!(custom.program_id(), solana_multisig_wallet::id());
+    // monikers and URLs print back as they parse
+    assert_eq!(custom.to_string(), "https://rpc.example.com/key");
+    assert_eq!(Cluster::Mainnet.to_string().parse(), Ok(Cluster::Mainnet));
+}
+
+#[test]
+fn test_cluster_endpoints() {
+    assert_eq!(Cluster::Devnet.url(), "https://api.devnet.solana.com");
+    assert_eq!(
+        Cluster::Localnet.ws_url(),
+        websocket_url(Cluster::Localnet.url())
+    );
+    assert_eq!(websocket_url("http://10.0.0.1:8899"), "ws://10.0.0.1:8900");
+    assert_eq!(websocket_url("http://[::1]:8899"), "ws://[::1]:8900");
+    assert_eq!(
+        websocket_url("https://rpc.example.com:443/x"),
+        "wss://rpc.example.com:444/x"
+    );
+
+    // another deployment keeps the cluster's endpoints
+    let program_id = Pubkey::new_unique();
+    let cluster = Cluster::Devnet.with_program_id(program_id);
+    assert_eq!(cluster.program_id(), program_id);
+    assert_eq!(cluster.url(), Cluster::Devnet.url());
+    assert_eq!(cluster.ws_url(), Cluster::Devnet.ws_url());
+    assert_eq!(Cluster::Testnet.program_id(), solana_multisig_wallet::id());
+}
@@ -0,0 +1,85 @@
+// Compute unit limits requested for executing proposals
+use multisig_client::compute_budget::{
+    compute_unit_limit, priority_fee, unit_limit_from_simulation, with_compute_budget,
+    ComputeBudget, BASE_COMPUTE_UNITS, COMPUTE_UNITS_PER_INSTRUCTION, MAX_COMPUTE_UNITS,
+};
+use solana_multisig_wallet::instruction::{self, ProposalAction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
+
+#[test]
+fn test_compute_unit_limit() {
+    let program_id = solana_multisig_wallet::id();
+    let multisig = Pubkey::new_unique();
+    let transaction = Pubkey::new_unique();
+    let vault = Pubkey::new_unique();
+    let transfer = system_instruction::transfer(&vault, &Pubkey::new_unique(), 1);
+
+    // a single-purpose action fits the default budget
+    let stake = ProposalAction::StakeDeactivate {
+        stake: Pubkey::new_unique(),
+    };
+    assert_eq!(compute_unit_limit(&stake), None);
+    let execute = instruction::execute_transaction(&program_id, &multisig, &transaction, &stake);
+    assert_eq!(with_compute_budget(&stake, execute.clone()), vec![execute]);
+
+    let batch = ProposalAction::Batch {
+        instructions: vec![transfer.clone().into(), transfer.clone().into()],
+    };
+    let limit = BASE_COMPUTE_UNITS + 2 * COMPUTE_UNITS_PER_INSTRUCTION;
+    assert_eq!(compute_unit_limit(&batch), Some(limit));
+    let execute = instruction::execute_transaction(&program_id, &multisig, &transaction, &batch);
+    let instructions = with_compute_budget(&batch, execute.clone());
+    assert_eq!(instructions.len(), 2);
+    assert_eq!(instructions[0].program_id, compute_budget::id());
+    assert_eq!(
+        instructions[0],
+        ComputeBudgetInstruction::set_compute_unit_limit(limit)
+    );
+    assert_eq!(instructions[1], execute);
+
+    // capped at what a transaction may request
+    let huge = ProposalAction::Batch {
+        instructions: vec![transfer.into(); 20],
+    };
+    assert_eq!(compute_unit_limit(&huge), Some(MAX_COMPUTE_UNITS));
+}
+
+#[test]
+fn test_estimated_budget() {
+    // the simulated units with their margin, capped
+    assert_eq!(unit_limit_from_simulation(100_000), 120_000);
+    assert_eq!(unit_limit_from_simulation(0), 0);
+    assert_eq!(
+        unit_limit_from_simulation(MAX_COMPUTE_UNITS as u64),
+        MAX_COMPUTE_UNITS
+    );
+
+    // slots where nobody paid for priority don't pull the price down
+    let fees = [0, 0, 10, 20, 30, 40, 50];
+    assert_eq!(priority_fee(&fees, 0), Some(10));
+    assert_eq!(priority_fee(&fees, 50), Some(30));
+    assert_eq!(priority_fee(&fees, 75), Some(40));
+    assert_eq!(priority_fee(&fees, 100), Some(50));
+    assert_eq!(priority_fee(&[0, 0], 75), None);
+    assert_eq!(priority_fee(&[], 75), None);
+
+    let execute = system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1);
+    let budget = ComputeBudget {
+        unit_limit: Some(120_000),
+        unit_price: Some(40),
+    };
+    assert_eq!(
+        budget.prepend(vec![execute.clone()]),
+        vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(120_000),
+            ComputeBudgetInstruction::set_compute_unit_price(40),
+            execute.clone(),
+        ]
+    );
+    assert_eq!(
+        ComputeBudget::default().prepend(vec![execute.clone()]),
+        vec![execute]
+    );
+}
@@ -0,0 +1,76 @@
+// The one-transaction multisig creation of `setup`
+use multisig_client::setup::{create_multisig_transaction, SetupError};
+use multisig_test_utils::program_test;
+use solana_multisig_wallet::state::load;
+use solana_multisig_wallet::{find_vault_address, Multisig};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn test_create_multisig_transaction() {
+    let program_id = solana_multisig_wallet::id();
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let owners = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let multisig = Keypair::new();
+    let mut tx = create_multisig_transaction(
+        &program_id,
+        &payer.pubkey(),
+        &multisig.pubkey(),
+        owners.clone(),
+        2,
+        rent.minimum_balance(Multisig::LEN),
+        50_000_000,
+    )
+    .unwrap();
+    tx.sign(&[&payer, &multisig], context.last_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(multisig.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let state: Multisig = load(&account.data).unwrap();
+    assert_eq!(state.owners, owners);
+    assert_eq!(state.threshold, 2);
+    assert_eq!(account.lamports, rent.minimum_balance(Multisig::LEN));
+    let (vault, _) = find_vault_address(&multisig.pubkey(), &program_id);
+    let vault_balance = context.banks_client.get_balance(vault).await.unwrap();
+    assert_eq!(vault_balance, 50_000_000);
+
+    // no funding, no transfer
+    let tx = create_multisig_transaction(
+        &program_id,
+        &payer.pubkey(),
+        &multisig.pubkey(),
+        owners.clone(),
+        2,
+        rent.minimum_balance(Multisig::LEN),
+        0,
+    )
+    .unwrap();
+    assert_eq!(tx.message.instructions.len(), 2);
+
+    // mistakes are caught before anything is sent
+    let result = create_multisig_transaction(
+        &program_id,
+        &payer.pubkey(),
+        &multisig.pubkey(),
+        owners,
+        3,
+        rent.minimum_balance(Multisig::LEN),
+        0,
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        SetupError::InvalidThreshold {
+            threshold: 3,
+            owners: 2
+        }
+    );
+}
@@ -0,0 +1,198 @@
+// End-to-end run of the everyday flow against a live cluster, to validate releases against real
+// rent, fees and blockhash expiry rather than ProgramTest's. Ignored by default:
+//
+//   cargo test -p multisig-client --test test_devnet_smoke -- --ignored --nocapture
+//
+// MULTISIG_SMOKE_URL picks the cluster, a moniker like `testnet` or an RPC URL (devnet by
+// default), and MULTISIG_SMOKE_PROGRAM_ID the deployed program (the cluster's by default). The
+// payer is a throwaway key funded by airdrop, or MULTISIG_SMOKE_PAYER, a keypair file, when the
+// faucet is rate limiting.
+use multisig_client::cluster::Cluster;
+use multisig_client::fetch;
+use solana_client::rpc_client::RpcClient;
+use solana_multisig_wallet::{find_vault_address, instruction, Multisig, ProposalAction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use std::env;
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::Duration;
+
+const VAULT_FUNDING: u64 = LAMPORTS_PER_SOL / 10;
+const AMOUNT: u64 = LAMPORTS_PER_SOL / 40;
+
+#[test]
+#[ignore = "needs a live cluster, see the top of the file"]
+fn test_devnet_smoke() {
+    let mut cluster = env::var("MULTISIG_SMOKE_URL")
+        .map(|url| Cluster::from_str(&url).expect("MULTISIG_SMOKE_URL"))
+        .unwrap_or(Cluster::Devnet);
+    if let Ok(id) = env::var("MULTISIG_SMOKE_PROGRAM_ID") {
+        cluster =
+            cluster.with_program_id(Pubkey::from_str(&id).expect("MULTISIG_SMOKE_PROGRAM_ID"));
+    }
+    let program_id = cluster.program_id();
+    let rpc = cluster.rpc_client(CommitmentConfig::confirmed());
+    let payer = match env::var("MULTISIG_SMOKE_PAYER") {
+        Ok(path) => read_keypair_file(path).expect("MULTISIG_SMOKE_PAYER"),
+        Err(_) => airdropped_payer(&rpc),
+    };
+
+    // create a 2-of-2 multisig and fund its vault
+    let owners = [Keypair::new(), Keypair::new()];
+    let multisig = Keypair::new();
+    let rent = rpc
+        .get_minimum_balance_for_rent_exemption(Multisig::LEN)
+        .unwrap();
+    let (vault, _) = find_vault_address(&multisig.pubkey(), &program_id);
+    send(
+        &rpc,
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &multisig.pubkey(),
+                rent,
+                Multisig::LEN as u64,
+                &program_id,
+            ),
+            instruction::create(
+                &program_id,
+                &multisig.pubkey(),
+                owners.iter().map(|owner| owner.pubkey()).collect(),
+                2,
+            ),
+            system_instruction::transfer(&payer.pubkey(), &vault, VAULT_FUNDING),
+        ],
+        &payer,
+        &[&multisig],
+    );
+    let state = fetch::fetch_multisig(&rpc, &program_id, &multisig.pubkey()).unwrap();
+    assert_eq!(state.threshold, 2);
+    assert_eq!(rpc.get_balance(&vault).unwrap(), VAULT_FUNDING);
+
+    // a recipient with a wrapped SOL account
+    let native_mint = spl_token::native_mint::id();
+    let recipient = Pubkey::new_unique();
+    let recipient_token_account =
+        spl_associated_token_account::get_associated_token_address(&recipient, &native_mint);
+    send(
+        &rpc,
+        &[
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer.pubkey(),
+                &recipient,
+                &native_mint,
+                &spl_token::id(),
+            ),
+        ],
+        &payer,
+        &[],
+    );
+
+    // the first owner proposes (and approves), the second approves
+    let action = ProposalAction::TransferWrappedSol {
+        destination: recipient_token_account,
+        amount: AMOUNT,
+    };
+    let transaction = Keypair::new();
+    let space = solana_multisig_wallet::Transaction::space(&action);
+    send(
+        &rpc,
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &transaction.pubkey(),
+                rpc.get_minimum_balance_for_rent_exemption(space).unwrap(),
+                space as u64,
+                &program_id,
+            ),
+            instruction::propose(
+                &program_id,
+                &owners[0].pubkey(),
+                &multisig.pubkey(),
+                &transaction.pubkey(),
+                action.clone(),
+            ),
+        ],
+        &payer,
+        &[&owners[0], &transaction],
+    );
+
+    // the approval costs the payer the cluster's fee for it and nothing else
+    let approve = [instruction::approve(
+        &program_id,
+        &owners[1].pubkey(),
+        &multisig.pubkey(),
+        &transaction.pubkey(),
+        &action,
+    )];
+    let payer_before = rpc.get_balance(&payer.pubkey()).unwrap();
+    let message = solana_sdk::message::Message::new(&approve, Some(&payer.pubkey()));
+    let fee = rpc.get_fee_for_message(&message).unwrap();
+    send(&rpc, &approve, &payer, &[&owners[1]]);
+    assert_eq!(
+        rpc.get_balance(&payer.pubkey()).unwrap(),
+        payer_before - fee
+    );
+    let proposal = fetch::fetch_transaction(&rpc, &program_id, &transaction.pubkey()).unwrap();
+    assert!(proposal.signers.iter().all(|&signed| signed));
+
+    send(
+        &rpc,
+        &[instruction::execute_transaction(
+            &program_id,
+            &multisig.pubkey(),
+            &transaction.pubkey(),
+            &action,
+        )],
+        &payer,
+        &[],
+    );
+    let account = rpc.get_account(&recipient_token_account).unwrap();
+    let token_account = spl_token::state::Account::unpack(&account.data).unwrap();
+    assert_eq!(token_account.amount, AMOUNT);
+    assert_eq!(rpc.get_balance(&vault).unwrap(), VAULT_FUNDING - AMOUNT);
+    let proposal = fetch::fetch_transaction(&rpc, &program_id, &transaction.pubkey()).unwrap();
+    assert!(proposal.executed);
+}
+
+fn airdropped_payer(rpc: &RpcClient) -> Keypair {
+    let payer = Keypair::new();
+    let signature = rpc
+        .request_airdrop(&payer.pubkey(), LAMPORTS_PER_SOL)
+        .expect("airdrop, set MULTISIG_SMOKE_PAYER if the faucet is rate limiting");
+    wait_for(rpc, &signature);
+    payer
+}
+
+fn wait_for(rpc: &RpcClient, signature: &Signature) {
+    for _ in 0..60 {
+        if rpc.confirm_transaction(signature).unwrap() {
+            return;
+        }
+        sleep(Duration::from_millis(500));
+    }
+    panic!("{signature} wasn't confirmed within 30s");
+}
+
+// Signs with the latest blockhash, `send_and_confirm_transaction` resends until the transaction
+// lands or that blockhash expires
+fn send(rpc: &RpcClient, instructions: &[Instruction], payer: &Keypair, signers: &[&Keypair]) {
+    let blockhash = rpc.get_latest_blockhash().unwrap();
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    rpc.send_and_confirm_transaction(&transaction).unwrap();
+}
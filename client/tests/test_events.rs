@@ -0,0 +1,140 @@
+// Deposits made with `Deposit`, read back from transaction logs. The program test runs the program
+// natively, where `sol_log_data` prints to stdout instead of the logs, so the logs are written
+// here the way the runtime writes them
+use base64::Engine;
+use borsh::BorshSerialize;
+use multisig_client::events::{deposit_events, parse_logs};
+use multisig_test_utils::{process, program_test, setup_multisig};
+use solana_multisig_wallet::event::{
+    DepositEvent, ExecutedEvent, MultisigEvent, ProposedEvent, DEPOSIT_EVENT, EVENT_VERSION,
+};
+use solana_multisig_wallet::{find_vault_address, instruction};
+use solana_program::hash::hash;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+#[tokio::test]
+async fn test_deposit() {
+    let program_id = solana_multisig_wallet::id();
+    let mut context = program_test().start_with_context().await;
+    let owners = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let multisig = setup_multisig(&mut context, &owners, 2, 0).await;
+    let (vault, _) = find_vault_address(&multisig, &program_id);
+    let depositor = context.payer.pubkey();
+
+    let deposit = instruction::deposit(&program_id, &depositor, &multisig, 250_000_000);
+    process(&mut context, deposit, &[]).await.unwrap();
+    let balance = context.banks_client.get_balance(vault).await.unwrap();
+    assert_eq!(balance, 250_000_000);
+
+    // only into the multisig's own vault
+    let mut elsewhere = instruction::deposit(&program_id, &depositor, &multisig, 1);
+    elsewhere.accounts[2].pubkey = Pubkey::new_unique();
+    let err = process(&mut context, elsewhere, &[])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+}
+
+#[test]
+fn test_deposit_events() {
+    let program_id = solana_multisig_wallet::id();
+    let other = Pubkey::new_unique();
+    let event = DepositEvent {
+        multisig: Pubkey::new_unique(),
+        vault: Pubkey::new_unique(),
+        depositor: Pubkey::new_unique(),
+        amount: 250_000_000,
+    };
+    let encode = |bytes: &[u8]| base64::engine::general_purpose::STANDARD.encode(bytes);
+    let data_line = format!(
+        "Program data: {}",
+        encode(&MultisigEvent::Deposit(event).to_log_data())
+    );
+
+    let logs = [
+        format!("Program {program_id} invoke [1]"),
+        "Program 11111111111111111111111111111111 invoke [2]".to_string(),
+        "Program 11111111111111111111111111111111 success".to_string(),
+        data_line.clone(),
+        format!("Program {program_id} success"),
+    ];
+    assert_eq!(deposit_events(&program_id, &logs), [event]);
+
+    // the same bytes logged by another program, even one that called the multisig program,
+    // aren't a deposit
+    let forged = [
+        format!("Program {other} invoke [1]"),
+        data_line.clone(),
+        format!("Program {program_id} invoke [2]"),
+        format!("Program {program_id} success"),
+        data_line.clone(),
+        format!("Program {other} success"),
+    ];
+    assert!(deposit_events(&program_id, &forged).is_empty());
+
+    // deposits logged before discriminators, as the name and the event
+    let legacy = [
+        format!("Program {program_id} invoke [1]"),
+        format!(
+            "Program data: {} {}",
+            encode(DEPOSIT_EVENT),
+            encode(&event.try_to_vec().unwrap())
+        ),
+        format!("Program {program_id} success"),
+    ];
+    assert_eq!(deposit_events(&program_id, &legacy), [event]);
+}
+
+#[test]
+fn test_parse_logs() {
+    let program_id = solana_multisig_wallet::id();
+    let multisig = Pubkey::new_unique();
+    let transaction = Pubkey::new_unique();
+    let events = [
+        MultisigEvent::Proposed(ProposedEvent {
+            multisig,
+            transaction,
+            proposer: Pubkey::new_unique(),
+        }),
+        MultisigEvent::Executed(ExecutedEvent {
+            multisig,
+            transaction,
+            executed_by: None,
+        }),
+    ];
+    let encode = |bytes: &[u8]| base64::engine::general_purpose::STANDARD.encode(bytes);
+    let mut logs = vec![format!("Program {program_id} invoke [1]")];
+    for event in &events {
+        logs.push(format!("Program data: {}", encode(&event.to_log_data())));
+    }
+    // an unknown discriminator, e.g. an event added after this decoder was built
+    logs.push(format!("Program data: {}", encode(&[7; 40])));
+    logs.push(format!("Program {program_id} success"));
+    assert_eq!(parse_logs(&logs), events);
+
+    // fields a later version appends are skipped
+    let mut newer = events[0].to_log_data();
+    newer[8] = EVENT_VERSION + 1;
+    newer.extend([1, 2, 3]);
+    assert_eq!(MultisigEvent::from_log_data(&[newer]), Some(events[0]));
+}
+
+#[test]
+fn test_event_discriminators() {
+    // the first 8 bytes of `sha256("event:<Name>")`, stable across versions
+    for (name, discriminator) in [
+        ("Deposit", DepositEvent::DISCRIMINATOR),
+        ("Proposed", ProposedEvent::DISCRIMINATOR),
+        ("Executed", ExecutedEvent::DISCRIMINATOR),
+    ] {
+        let hash = hash(format!("event:{name}").as_bytes());
+        assert_eq!(discriminator, hash.to_bytes()[..8]);
+    }
+}
@@ -0,0 +1,47 @@
+// `getProgramAccounts` filters, checked against encoded accounts the way the RPC node applies them
+use borsh::BorshDeserialize;
+use multisig_client::fetch::{multisig_owner_filters, multisig_threshold_filters};
+use solana_multisig_wallet::{fixtures, Multisig};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::AccountSharedData;
+
+fn multisig_account() -> (Multisig, AccountSharedData) {
+    let fixture = fixtures::all()
+        .into_iter()
+        .find(|fixture| fixture.kind == "multisig")
+        .unwrap();
+    let mut account = AccountSharedData::new(1, fixture.data.len(), &solana_multisig_wallet::id());
+    account.set_data(fixture.data.clone());
+    (Multisig::try_from_slice(&fixture.data).unwrap(), account)
+}
+
+#[test]
+fn test_threshold_filters() {
+    let (multisig, account) = multisig_account();
+    let matches = |threshold| {
+        multisig_threshold_filters(threshold)
+            .iter()
+            .all(|filter| filter.allows(&account))
+    };
+    assert!(matches(multisig.threshold));
+    assert!(!matches(multisig.threshold + 1));
+}
+
+#[test]
+fn test_owner_filters() {
+    let (multisig, account) = multisig_account();
+    // exactly the owner's slot matches
+    for (slot, owner) in multisig.owners.iter().enumerate() {
+        let matching: Vec<usize> = multisig_owner_filters(owner)
+            .iter()
+            .enumerate()
+            .filter(|(_, filters)| filters.iter().all(|filter| filter.allows(&account)))
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(matching, vec![slot]);
+    }
+    let stranger = multisig_owner_filters(&Pubkey::new_unique());
+    assert!(stranger
+        .iter()
+        .all(|filters| !filters.iter().all(|filter| filter.allows(&account))));
+}
@@ -0,0 +1,115 @@
+// v0 messages through a lookup table of the multisig's vault
+use multisig_client::lookup_table::{
+    lookup_table_candidates, lookup_table_from_account, v0_message,
+};
+use multisig_test_utils::{next_blockhash, process, MultisigHarness};
+use solana_multisig_wallet::{find_vault_address, instruction, lookup_table};
+use solana_program::address_lookup_table_account::AddressLookupTableAccount;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::{Message, VersionedMessage};
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+
+#[tokio::test]
+async fn test_execute_through_lookup_table() {
+    let program_id = solana_multisig_wallet::id();
+    let mut harness = MultisigHarness::new(2, 2, 100_000_000).await;
+    let multisig = harness.multisig;
+    let payer = harness.context.payer.insecure_clone();
+    let (vault, _) = find_vault_address(&multisig, &program_id);
+    let destination = Pubkey::new_unique();
+    let execute = instruction::execute(&program_id, &multisig, 10_000_000, &destination);
+
+    // a table holding the accounts of the execute instruction
+    harness.context.warp_to_slot(10).unwrap();
+    let recent_slot = 9;
+    let (table, _) = lookup_table::find_lookup_table_address(&vault, recent_slot);
+    harness.sign_as(0).await.unwrap();
+    harness.sign_as(1).await.unwrap();
+    let ix = instruction::create_lookup_table(&program_id, &payer.pubkey(), &multisig, recent_slot);
+    process(&mut harness.context, ix, &[]).await.unwrap();
+    let addresses = lookup_table_candidates(std::slice::from_ref(&execute));
+    assert!(!addresses.contains(&program_id));
+    next_blockhash(&mut harness.context).await;
+    harness.sign_as(0).await.unwrap();
+    harness.sign_as(1).await.unwrap();
+    let ix = instruction::extend_lookup_table(
+        &program_id,
+        &payer.pubkey(),
+        &multisig,
+        &table,
+        addresses.clone(),
+    );
+    process(&mut harness.context, ix, &[]).await.unwrap();
+
+    // extended addresses can be looked up from the next slot on
+    harness.context.warp_to_slot(11).unwrap();
+    let account = harness
+        .context
+        .banks_client
+        .get_account(table)
+        .await
+        .unwrap()
+        .unwrap();
+    let table_account = lookup_table_from_account(table, &account).unwrap();
+    assert_eq!(table_account.addresses, addresses);
+
+    next_blockhash(&mut harness.context).await;
+    harness.sign_as(0).await.unwrap();
+    harness.sign_as(1).await.unwrap();
+    let blockhash = harness
+        .context
+        .banks_client
+        .get_latest_blockhash()
+        .await
+        .unwrap();
+    let message = v0_message(&[execute], &payer.pubkey(), &[table_account], blockhash).unwrap();
+    let VersionedMessage::V0(v0) = &message else {
+        panic!("expected a v0 message");
+    };
+    assert_eq!(v0.address_table_lookups.len(), 1);
+    assert!(!v0.account_keys.contains(&destination));
+    let tx = VersionedTransaction::try_new(message, &[&payer]).unwrap();
+    // the banks server of program-test 1.16 queues transactions as legacy ones, so a v0 one only
+    // lands when executed directly on the bank
+    harness
+        .context
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(harness.balance(&destination).await, 10_000_000);
+}
+
+// An instruction with more accounts than a legacy transaction holds fits a v0 one
+#[test]
+fn test_v0_message_size() {
+    let payer = Keypair::new();
+    let accounts = (0..64)
+        .map(|_| AccountMeta::new_readonly(Pubkey::new_unique(), false))
+        .collect();
+    let ixs = [Instruction::new_with_bytes(
+        solana_multisig_wallet::id(),
+        &[],
+        accounts,
+    )];
+
+    // one signature: its count and 64 bytes
+    let signature_size = 1 + 64;
+    let legacy = Message::new(&ixs, Some(&payer.pubkey()));
+    assert!(legacy.serialize().len() + signature_size > PACKET_DATA_SIZE);
+
+    let table = AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: lookup_table_candidates(&ixs),
+    };
+    let message = v0_message(&ixs, &payer.pubkey(), &[table], Hash::default()).unwrap();
+    assert!(message.serialize().len() + signature_size <= PACKET_DATA_SIZE);
+    VersionedTransaction::try_new(message, &[&payer]).unwrap();
+}
@@ -0,0 +1,194 @@
+// Decoding of the treasuries `migrate` imports from
+use borsh::BorshSerialize;
+use multisig_client::migrate::{
+    decode_governance, decode_squads_v3, decode_squads_v4, decode_token_owner_record,
+    governance_threshold, Governance, ImportedTreasury, MigrateError, Source, SQUADS_V3_PROGRAM_ID,
+    SQUADS_V4_PROGRAM_ID,
+};
+use solana_program::hash::hash;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+
+fn anchor_account(name: &str, body: impl BorshSerialize) -> Vec<u8> {
+    let mut data = hash(format!("account:{name}").as_bytes()).to_bytes()[..8].to_vec();
+    data.extend(body.try_to_vec().unwrap());
+    // Anchor accounts are allocated with room to spare
+    data.extend([0; 64]);
+    data
+}
+
+#[test]
+fn test_decode_squads() {
+    let address = Pubkey::new_unique();
+    let keys = vec![
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+
+    let v3 = anchor_account(
+        "Ms",
+        (
+            2u16,
+            1u16,
+            7u32,
+            0u32,
+            255u8,
+            Pubkey::new_unique(),
+            false,
+            keys.clone(),
+        ),
+    );
+    let config = decode_squads_v3(&address, &v3).unwrap();
+    assert_eq!(config.owners, keys);
+    assert_eq!(config.threshold, 2);
+    let (vault, _) = Pubkey::find_program_address(
+        &[
+            b"squad",
+            address.as_ref(),
+            &1u32.to_le_bytes(),
+            b"authority",
+        ],
+        &SQUADS_V3_PROGRAM_ID,
+    );
+    assert_eq!(config.vault, vault);
+    // a v4 account isn't a v3 one
+    let err = decode_squads_v3(&address, &anchor_account("Multisig", 0u8)).unwrap_err();
+    assert!(matches!(err, MigrateError::Invalid(_)));
+
+    // members that can only propose or execute aren't owners
+    let members: Vec<(Pubkey, u8)> = vec![(keys[0], 7), (keys[1], 1), (keys[2], 2)];
+    let v4 = anchor_account(
+        "Multisig",
+        (
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            2u16,
+            0u32,
+            3u64,
+            0u64,
+            None::<Pubkey>,
+            255u8,
+            members,
+        ),
+    );
+    let config = decode_squads_v4(&address, &v4).unwrap();
+    assert_eq!(config.owners, vec![keys[0], keys[2]]);
+    assert_eq!(config.threshold, 2);
+    let (vault, _) = Pubkey::find_program_address(
+        &[b"multisig", address.as_ref(), b"vault", &[0]],
+        &SQUADS_V4_PROGRAM_ID,
+    );
+    assert_eq!(config.vault, vault);
+
+    // a threshold above the voters has no equivalent
+    let members: Vec<(Pubkey, u8)> = vec![(keys[0], 7), (keys[1], 1)];
+    let v4 = anchor_account(
+        "Multisig",
+        (
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            2u16,
+            0u32,
+            3u64,
+            0u64,
+            Some(Pubkey::new_unique()),
+            255u8,
+            members,
+        ),
+    );
+    let err = decode_squads_v4(&address, &v4).unwrap_err();
+    assert!(matches!(err, MigrateError::Invalid(_)));
+}
+
+#[test]
+fn test_decode_governance() {
+    let realm = Pubkey::new_unique();
+    // community votes at 60%, the council can't vote
+    let governance = (
+        18u8,
+        realm,
+        Pubkey::new_unique(),
+        0u32,
+        (0u8, 60u8),
+        1u64,
+        0u32,
+        259_200u32,
+        0u8,
+        2u8,
+    )
+        .try_to_vec()
+        .unwrap();
+    assert_eq!(
+        decode_governance(&governance).unwrap(),
+        Governance {
+            realm,
+            community: Some(60),
+            council: None,
+        }
+    );
+    let mut realm_account = governance;
+    realm_account[0] = 16;
+    assert!(decode_governance(&realm_account).is_err());
+
+    let owner = Pubkey::new_unique();
+    let record = (
+        17u8,
+        realm,
+        Pubkey::new_unique(),
+        owner,
+        1_000u64,
+        [0u8; 32],
+    )
+        .try_to_vec()
+        .unwrap();
+    assert_eq!(decode_token_owner_record(&record), Some((owner, 1_000)));
+    assert_eq!(decode_token_owner_record(&record[..100]), None);
+
+    // one vote per member, rounded up
+    assert_eq!(governance_threshold(60, 5).unwrap(), 3);
+    assert_eq!(governance_threshold(51, 4).unwrap(), 3);
+    assert_eq!(governance_threshold(100, 3).unwrap(), 3);
+    assert_eq!(governance_threshold(1, 3).unwrap(), 1);
+    assert!(governance_threshold(60, 0).is_err());
+    assert!(governance_threshold(101, 3).is_err());
+}
+
+#[test]
+fn test_creation_instructions() {
+    let program_id = solana_multisig_wallet::id();
+    let payer = Pubkey::new_unique();
+    let multisig = Pubkey::new_unique();
+    let treasury = ImportedTreasury {
+        source: Source::SquadsV3,
+        address: Pubkey::new_unique(),
+        config: decode_squads_v3(
+            &Pubkey::new_unique(),
+            &anchor_account(
+                "Ms",
+                (
+                    1u16,
+                    1u16,
+                    0u32,
+                    0u32,
+                    255u8,
+                    Pubkey::new_unique(),
+                    false,
+                    vec![Pubkey::new_unique()],
+                ),
+            ),
+        )
+        .unwrap(),
+        vault_lamports: 5_000_000,
+    };
+
+    let unfunded = treasury
+        .creation_instructions(&program_id, &payer, &multisig, 1_000, false)
+        .unwrap();
+    assert_eq!(unfunded.len(), 2);
+    let funded = treasury
+        .creation_instructions(&program_id, &payer, &multisig, 1_000, true)
+        .unwrap();
+    assert_eq!(funded.len(), 3);
+    assert_eq!(funded[2].program_id, system_program::id());
+}
@@ -0,0 +1,79 @@
+// Durable nonce messages on ProgramTest. Its banks server can't land a nonce transaction (it looks
+// the blockhash up among the recent ones), so they're simulated
+use multisig_client::nonce::{sign_message, DurableNonce};
+use multisig_test_utils::MultisigHarness;
+use solana_program::system_program;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+
+#[tokio::test]
+async fn test_nonce_messages() {
+    let program_id = solana_multisig_wallet::id();
+    let mut harness = MultisigHarness::new(2, 2, 1_000_000_000).await;
+    let multisig = harness.multisig;
+    let context = &mut harness.context;
+    let payer = context.payer.insecure_clone();
+
+    // the payer is the nonce authority
+    let nonce_account = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &system_instruction::create_nonce_account(
+            &payer.pubkey(),
+            &nonce_account.pubkey(),
+            &payer.pubkey(),
+            rent.minimum_balance(solana_sdk::nonce::State::size()),
+        ),
+        Some(&payer.pubkey()),
+        &[&payer, &nonce_account],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let account = context
+        .banks_client
+        .get_account(nonce_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let nonce = DurableNonce::from_account(nonce_account.pubkey(), &account).unwrap();
+    assert_eq!(nonce.authority, payer.pubkey());
+    assert!(DurableNonce::from_account(multisig, &Default::default()).is_none());
+
+    // the nonce's blockhash, and its advance ahead of the multisig instruction
+    let owner = &harness.owners[0];
+    let message = sign_message(
+        &program_id,
+        &payer.pubkey(),
+        &owner.pubkey(),
+        &multisig,
+        &nonce,
+    );
+    assert_eq!(message.recent_blockhash, nonce.blockhash);
+    assert_eq!(message.instructions.len(), 2);
+    let advance = &message.instructions[0];
+    assert_eq!(
+        message.account_keys[advance.program_id_index as usize],
+        system_program::id()
+    );
+    assert_eq!(
+        advance.data,
+        system_instruction::advance_nonce_account(&nonce_account.pubkey(), &payer.pubkey()).data
+    );
+    assert_eq!(
+        message.account_keys[advance.accounts[0] as usize],
+        nonce_account.pubkey()
+    );
+    let sign = &message.instructions[1];
+    assert_eq!(
+        message.account_keys[sign.program_id_index as usize],
+        program_id
+    );
+
+    // valid once the cluster moved past the slot the nonce was stored in
+    context.warp_to_slot(1_000).unwrap();
+    let tx = Transaction::new(&[&payer, owner], message, nonce.blockhash);
+    let simulation = context.banks_client.simulate_transaction(tx).await.unwrap();
+    simulation.result.unwrap().unwrap();
+}
@@ -0,0 +1,79 @@
+// Assembling transactions online and signing them on air-gapped machines
+use multisig_client::nonce::DurableNonce;
+use multisig_client::offline::{
+    approval_transaction, decode_transaction, missing_signers, sign_encoded, sign_offline,
+    OfflineError,
+};
+use solana_multisig_wallet::ProposalAction;
+use solana_program::hash::Hash;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+
+#[test]
+fn test_offline_signing() {
+    let program_id = solana_multisig_wallet::id();
+    let payer = Keypair::new();
+    let owner = Keypair::new();
+    let nonce_authority = Keypair::new();
+    let nonce = DurableNonce {
+        account: Pubkey::new_unique(),
+        authority: nonce_authority.pubkey(),
+        blockhash: Hash::new_unique(),
+    };
+    let action = ProposalAction::StakeDeactivate {
+        stake: Pubkey::new_unique(),
+    };
+    let unsigned = approval_transaction(
+        &program_id,
+        &payer.pubkey(),
+        &owner.pubkey(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &action,
+        &nonce,
+    );
+    let transaction = decode_transaction(&unsigned).unwrap();
+    assert_eq!(transaction.message.recent_blockhash, nonce.blockhash);
+    let mut expected = vec![payer.pubkey(), owner.pubkey(), nonce_authority.pubkey()];
+    let mut missing = missing_signers(&transaction);
+    expected.sort();
+    missing.sort();
+    assert_eq!(missing, expected);
+
+    // each key signs on its own machine, signatures merge as they arrive
+    let owner_signature = sign_encoded(&unsigned, &owner).unwrap();
+    let partial = sign_offline(&unsigned, &[owner_signature]).unwrap();
+    let transaction = decode_transaction(&partial).unwrap();
+    assert!(!missing_signers(&transaction).contains(&owner.pubkey()));
+    assert!(transaction.verify().is_err());
+
+    let rest = [
+        sign_encoded(&unsigned, &payer).unwrap(),
+        sign_encoded(&unsigned, &nonce_authority).unwrap(),
+    ];
+    let signed = sign_offline(&partial, &rest).unwrap();
+    let transaction = decode_transaction(&signed).unwrap();
+    assert!(missing_signers(&transaction).is_empty());
+    transaction.verify().unwrap();
+
+    // keys that don't sign, and signatures over something else, are refused
+    let stranger = Keypair::new();
+    assert_eq!(
+        sign_encoded(&unsigned, &stranger).unwrap_err(),
+        OfflineError::NotASigner(stranger.pubkey())
+    );
+    let forged = (owner.pubkey(), owner.sign_message(b"something else"));
+    assert_eq!(
+        sign_offline(&unsigned, &[forged]).unwrap_err(),
+        OfflineError::BadSignature(owner.pubkey())
+    );
+    assert_eq!(
+        sign_offline(&unsigned, &[(stranger.pubkey(), Signature::default())]).unwrap_err(),
+        OfflineError::NotASigner(stranger.pubkey())
+    );
+    assert!(matches!(
+        decode_transaction("not a transaction"),
+        Err(OfflineError::Decode(_))
+    ));
+}
@@ -0,0 +1,57 @@
+// The balance bookkeeping of `simulate`; the simulation itself needs an RPC node
+use multisig_client::simulate::{balance_changes, BalanceChange, ExecuteSimulation};
+use solana_program::instruction::InstructionError;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+
+#[test]
+fn test_balance_changes() {
+    let vault = Pubkey::new_unique();
+    let untouched = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    let closed = Pubkey::new_unique();
+    let changes = balance_changes(
+        &[vault, untouched, destination, closed],
+        &[Some(100), Some(5), None, Some(7)],
+        &[Some(60), Some(5), Some(40), None],
+    );
+    assert_eq!(
+        changes,
+        vec![
+            BalanceChange {
+                account: vault,
+                before: 100,
+                after: 60
+            },
+            BalanceChange {
+                account: destination,
+                before: 0,
+                after: 40
+            },
+            BalanceChange {
+                account: closed,
+                before: 7,
+                after: 0
+            },
+        ]
+    );
+    let deltas: Vec<i128> = changes.iter().map(BalanceChange::delta).collect();
+    assert_eq!(deltas, vec![-40, 40, -7]);
+}
+
+#[test]
+fn test_ready() {
+    let mut simulation = ExecuteSimulation {
+        error: None,
+        logs: vec![],
+        units_consumed: Some(1_000),
+        accounts: vec![],
+        balance_changes: vec![],
+    };
+    assert!(simulation.ready());
+    simulation.error = Some(TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(1),
+    ));
+    assert!(!simulation.ready());
+}
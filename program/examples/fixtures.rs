@@ -0,0 +1,13 @@
+// Regenerates the SDK test vectors in `fixtures/` (or the directory given as argument):
+//   cargo run --example fixtures [-- <dir>]
+use std::path::PathBuf;
+
+fn main() -> std::io::Result<()> {
+    let dir = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures"));
+    solana_multisig_wallet::fixtures::write_all(&dir)?;
+    println!("wrote fixtures to {}", dir.display());
+    Ok(())
+}
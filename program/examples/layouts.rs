@@ -0,0 +1,14 @@
+// Regenerates the account and instruction layouts for other languages in `layouts/` (or the
+// directory given as argument):
+//   cargo run --example layouts [-- <dir>]
+use std::path::PathBuf;
+
+fn main() -> std::io::Result<()> {
+    let dir = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("layouts"));
+    solana_multisig_wallet::layouts::write_all(&dir)?;
+    println!("wrote layouts to {}", dir.display());
+    Ok(())
+}
@@ -0,0 +1,230 @@
+// Generated by `cargo run --example layouts`, do not edit
+
+export type Field = {
+  type: string;
+  from: "start" | "action_end";
+  offset: number;
+  len: number;
+};
+
+export const MULTISIG_LEN = 835;
+
+export const MULTISIG_LAYOUT: Record<string, Field> = {
+  owners_len: { type: "u8", from: "start", offset: 0, len: 1 },
+  owners: { type: "[pubkey; 16]", from: "start", offset: 1, len: 512 },
+  threshold: { type: "u8", from: "start", offset: 513, len: 1 },
+  signers: { type: "[bool; 16]", from: "start", offset: 514, len: 16 },
+  veto_authority: { type: "option<pubkey>", from: "start", offset: 530, len: 33 },
+  paused: { type: "bool", from: "start", offset: 563, len: 1 },
+  emergency_authority: { type: "option<pubkey>", from: "start", offset: 564, len: 33 },
+  amount_policy: { type: "option<AmountPolicy>", from: "start", offset: 597, len: 10 },
+  approval_ttl_seconds: { type: "option<i64>", from: "start", offset: 607, len: 9 },
+  signed_at: { type: "[i64; 16]", from: "start", offset: 616, len: 128 },
+  owner_set_seqno: { type: "u32", from: "start", offset: 744, len: 4 },
+  outflow_cap: { type: "option<OutflowCap>", from: "start", offset: 748, len: 17 },
+  spent_this_period: { type: "u64", from: "start", offset: 765, len: 8 },
+  period_start: { type: "i64", from: "start", offset: 773, len: 8 },
+  policy_signer: { type: "option<pubkey>", from: "start", offset: 781, len: 33 },
+  transaction_count: { type: "u64", from: "start", offset: 814, len: 8 },
+  fee_exempt: { type: "bool", from: "start", offset: 822, len: 1 },
+  allow_zero_amount: { type: "bool", from: "start", offset: 823, len: 1 },
+  min_transfer: { type: "u64", from: "start", offset: 824, len: 8 },
+  strict_signing: { type: "bool", from: "start", offset: 832, len: 1 },
+  delegate_signers: { type: "u16", from: "start", offset: 833, len: 2 },
+};
+
+export const TRANSACTION_LEN = 331;
+
+export const TRANSACTION_LAYOUT: Record<string, Field> = {
+  multisig: { type: "pubkey", from: "start", offset: 0, len: 32 },
+  proposer: { type: "pubkey", from: "start", offset: 32, len: 32 },
+  action: { type: "ProposalAction", from: "start", offset: 64, len: 0 },
+  signers_len: { type: "u8", from: "action_end", offset: 0, len: 1 },
+  signers: { type: "[bool; 16]", from: "action_end", offset: 1, len: 16 },
+  executed: { type: "bool", from: "action_end", offset: 17, len: 1 },
+  vault: { type: "u8", from: "action_end", offset: 18, len: 1 },
+  expires_at: { type: "option<i64>", from: "action_end", offset: 19, len: 9 },
+  extended: { type: "bool", from: "action_end", offset: 28, len: 1 },
+  required_signers: { type: "[bool; 16]", from: "action_end", offset: 29, len: 16 },
+  approved_at: { type: "[i64; 16]", from: "action_end", offset: 45, len: 128 },
+  executor_fee: { type: "u64", from: "action_end", offset: 173, len: 8 },
+  owner_set_seqno: { type: "u32", from: "action_end", offset: 181, len: 4 },
+  executed_at: { type: "i64", from: "action_end", offset: 185, len: 8 },
+  executed_slot: { type: "u64", from: "action_end", offset: 193, len: 8 },
+  executed_by: { type: "option<pubkey>", from: "action_end", offset: 201, len: 33 },
+  attachment: { type: "option<[u8; 32]>", from: "action_end", offset: 234, len: 33 },
+};
+
+export const MAX_INSTRUCTION_TAG = 127;
+export const RESERVED_TAGS = [128, 254] as const;
+export const PACKED_FORMAT_TAG = 255;
+export const PACKED_SIGN = 1;
+export const PACKED_EXECUTE = 2;
+
+export enum MultisigInstructionTag {
+  Create = 0,
+  Sign = 1,
+  Execute = 2,
+  CreateTokenAccount = 3,
+  Propose = 4,
+  Approve = 5,
+  ExecuteTransaction = 6,
+  SwapOwner = 7,
+  InitGuardians = 8,
+  ProposeRecovery = 9,
+  ApproveRecovery = 10,
+  VetoRecovery = 11,
+  ExecuteRecovery = 12,
+  SetVetoAuthority = 13,
+  VetoTransaction = 14,
+  SetDestinationPolicy = 15,
+  AddDestination = 16,
+  RemoveDestination = 17,
+  SetDestinationPolicyEnforcement = 18,
+  SetPaused = 19,
+  SetEmergencyAuthority = 20,
+  ExecuteRecurring = 21,
+  InitAggregateKey = 22,
+  ApproveAggregated = 23,
+  RegisterInbox = 24,
+  ClearInbox = 25,
+  ExtendProposal = 26,
+  SetPolicy = 27,
+  SetDisplayUnit = 28,
+  SetApprovalTtl = 29,
+  ClaimDistribution = 30,
+  CreateLookupTable = 31,
+  ExtendLookupTable = 32,
+  DeactivateLookupTable = 33,
+  CloseLookupTable = 34,
+  CheckApproved = 35,
+  ExecuteFromVault = 36,
+  MigrateToVault = 37,
+  CrankTransaction = 38,
+  RegisterOwnerIndex = 39,
+  SyncOwnerIndex = 40,
+  UnlistMultisig = 41,
+  SetMetadata = 42,
+  ApproveWithSignature = 43,
+  SetOutflowCap = 44,
+  SetDelegate = 45,
+  RevokeDelegate = 46,
+  SignAsDelegate = 47,
+  ApproveAsDelegate = 48,
+  SetPolicySigner = 49,
+  ProposeIndexed = 50,
+  CloseTransaction = 51,
+  InitHistory = 52,
+  ChangeThreshold = 53,
+  AddOwner = 54,
+  CreateDerived = 55,
+  Deposit = 56,
+  InitProgramConfig = 57,
+  SetProgramConfig = 58,
+  SetFeeExempt = 59,
+  SetTransferMinimum = 60,
+  SetStrictSigning = 61,
+  SignAsMultisig = 62,
+}
+
+export enum MultisigErrorCode {
+  InvalidThreshold = 0,
+  TooManyOwners = 1,
+  OwnerNotFound = 2,
+  DuplicateOwner = 3,
+  InsufficientSignatures = 4,
+  RequiredSignerMissing = 5,
+  InsufficientBalance = 6,
+  BelowRentFloor = 7,
+  InvalidDestination = 8,
+  AccountMismatch = 9,
+  WrongMultisig = 10,
+  InvalidAction = 11,
+  InvalidExpiry = 12,
+  ProposalExpired = 13,
+  AlreadyExecuted = 14,
+  StaleProposal = 15,
+  NotExtendable = 16,
+  Paused = 17,
+  PolicyViolation = 18,
+  DuplicateDestination = 19,
+  DestinationNotFound = 20,
+  VetoAuthorityIsOwner = 21,
+  NotVetoAuthority = 22,
+  InvalidConfig = 23,
+  NotGuardian = 24,
+  RecoveryPending = 25,
+  RecoveryNotReady = 26,
+  OwnerSetChanged = 27,
+  AlreadyClaimed = 28,
+  InvalidProof = 29,
+  DistributionCapExceeded = 30,
+  PaymentNotDue = 31,
+  TransferFeeTooHigh = 32,
+  InvalidBufferAuthority = 33,
+  BufferHashMismatch = 34,
+  OwnerIndexFull = 35,
+  InvalidTransition = 36,
+  OutflowCapExceeded = 37,
+  DelegateExpired = 38,
+  DelegateNotPermitted = 39,
+  PolicySignerMissing = 40,
+  DecimalsMismatch = 41,
+  ProposalNotClosable = 42,
+  ArithmeticOverflow = 43,
+  ZeroAmount = 44,
+  BelowMinimumTransfer = 45,
+  NonOwnerSigner = 46,
+  AlreadySigned = 47,
+}
+
+export const MULTISIG_ERROR_MESSAGES: Record<MultisigErrorCode, string> = {
+  [MultisigErrorCode.InvalidThreshold]: "threshold must be between 1 and the owner count",
+  [MultisigErrorCode.TooManyOwners]: "too many owners",
+  [MultisigErrorCode.OwnerNotFound]: "not an owner of the multisig",
+  [MultisigErrorCode.DuplicateOwner]: "already an owner of the multisig",
+  [MultisigErrorCode.InsufficientSignatures]: "not enough approvals",
+  [MultisigErrorCode.RequiredSignerMissing]: "a required signer hasn't approved",
+  [MultisigErrorCode.InsufficientBalance]: "insufficient balance",
+  [MultisigErrorCode.BelowRentFloor]: "payment would leave the multisig below rent",
+  [MultisigErrorCode.InvalidDestination]: "destination can't receive funds",
+  [MultisigErrorCode.AccountMismatch]: "account doesn't match the instruction",
+  [MultisigErrorCode.WrongMultisig]: "account belongs to another multisig",
+  [MultisigErrorCode.InvalidAction]: "invalid proposal action",
+  [MultisigErrorCode.InvalidExpiry]: "invalid proposal expiry",
+  [MultisigErrorCode.ProposalExpired]: "proposal expired",
+  [MultisigErrorCode.AlreadyExecuted]: "proposal already executed",
+  [MultisigErrorCode.StaleProposal]: "proposal predates the current owner set",
+  [MultisigErrorCode.NotExtendable]: "proposal can't be extended",
+  [MultisigErrorCode.Paused]: "multisig is paused",
+  [MultisigErrorCode.PolicyViolation]: "rejected by the destination policy",
+  [MultisigErrorCode.DuplicateDestination]: "destination already in the policy",
+  [MultisigErrorCode.DestinationNotFound]: "destination not in the policy",
+  [MultisigErrorCode.VetoAuthorityIsOwner]: "the veto authority can't be an owner",
+  [MultisigErrorCode.NotVetoAuthority]: "not the veto authority",
+  [MultisigErrorCode.InvalidConfig]: "setting out of range",
+  [MultisigErrorCode.NotGuardian]: "not a guardian of the multisig",
+  [MultisigErrorCode.RecoveryPending]: "a recovery is already pending",
+  [MultisigErrorCode.RecoveryNotReady]: "recovery delay hasn't elapsed yet",
+  [MultisigErrorCode.OwnerSetChanged]: "owners changed since the aggregate key was set",
+  [MultisigErrorCode.AlreadyClaimed]: "already claimed",
+  [MultisigErrorCode.InvalidProof]: "invalid merkle proof",
+  [MultisigErrorCode.DistributionCapExceeded]: "claim exceeds the distribution cap",
+  [MultisigErrorCode.PaymentNotDue]: "payment not due yet",
+  [MultisigErrorCode.TransferFeeTooHigh]: "transfer fee above the approved maximum",
+  [MultisigErrorCode.InvalidBufferAuthority]: "buffer authority must be the vault",
+  [MultisigErrorCode.BufferHashMismatch]: "buffer doesn't hold the approved bytes",
+  [MultisigErrorCode.OwnerIndexFull]: "owner index is full",
+  [MultisigErrorCode.InvalidTransition]: "proposal can't move to that state",
+  [MultisigErrorCode.OutflowCapExceeded]: "outflow cap for the period exceeded",
+  [MultisigErrorCode.DelegateExpired]: "delegate key expired",
+  [MultisigErrorCode.DelegateNotPermitted]: "delegate key lacks the permission",
+  [MultisigErrorCode.PolicySignerMissing]: "policy signer must co-sign",
+  [MultisigErrorCode.DecimalsMismatch]: "mint decimals differ from the proposal's",
+  [MultisigErrorCode.ProposalNotClosable]: "proposal can't be closed yet",
+  [MultisigErrorCode.ArithmeticOverflow]: "arithmetic overflow",
+  [MultisigErrorCode.ZeroAmount]: "zero-amount transfers are disabled",
+  [MultisigErrorCode.BelowMinimumTransfer]: "transfer below the minimum amount",
+  [MultisigErrorCode.NonOwnerSigner]: "transaction signed by a key that isn't an owner",
+  [MultisigErrorCode.AlreadySigned]: "owner already approved",
+};
@@ -0,0 +1,213 @@
+use solana_program::program_error::ProgramError;
+use std::fmt;
+
+// Failures specific to the multisig, returned as `ProgramError::Custom(code)`. The codes are part of
+// the API, clients map them to messages: a variant keeps its number for good and new ones go at the
+// end. Failures the runtime already names (missing signature, account owned by another program,
+// wrong PDA seeds, malformed instruction data, read-only account...) keep their `ProgramError`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultisigError {
+    // zero, or above the number of owners
+    InvalidThreshold = 0,
+    TooManyOwners = 1,
+    // the key isn't one of the multisig's owners
+    OwnerNotFound = 2,
+    DuplicateOwner = 3,
+    // fewer approvals than the threshold (or a guardian supermajority)
+    InsufficientSignatures = 4,
+    // an owner the proposal names in `required_signers` hasn't approved
+    RequiredSignerMissing = 5,
+    InsufficientBalance = 6,
+    // the payment would leave the multisig account below its rent-exempt minimum
+    BelowRentFloor = 7,
+    // the destination is the multisig, its proposal or the program
+    InvalidDestination = 8,
+    // an account isn't the one the instruction, proposal or schedule names
+    AccountMismatch = 9,
+    // the proposal (schedule, distribution...) belongs to another multisig
+    WrongMultisig = 10,
+    // the proposed action's parameters can't be executed
+    InvalidAction = 11,
+    InvalidExpiry = 12,
+    ProposalExpired = 13,
+    AlreadyExecuted = 14,
+    // proposed before a recovery changed the number of owners
+    StaleProposal = 15,
+    // the proposal has no expiry or was extended already
+    NotExtendable = 16,
+    Paused = 17,
+    PolicyViolation = 18,
+    DuplicateDestination = 19,
+    DestinationNotFound = 20,
+    VetoAuthorityIsOwner = 21,
+    NotVetoAuthority = 22,
+    // an out of range setting: approval TTL, display symbol, guardian set, extension
+    InvalidConfig = 23,
+    NotGuardian = 24,
+    RecoveryPending = 25,
+    // the recovery delay hasn't elapsed yet
+    RecoveryNotReady = 26,
+    // the owners changed since the aggregate key was registered
+    OwnerSetChanged = 27,
+    AlreadyClaimed = 28,
+    InvalidProof = 29,
+    DistributionCapExceeded = 30,
+    PaymentNotDue = 31,
+    TransferFeeTooHigh = 32,
+    // the buffer's authority isn't the vault
+    InvalidBufferAuthority = 33,
+    // the buffer's bytes aren't the ones approved
+    BufferHashMismatch = 34,
+    // the owner index lists `MAX_INDEXED_MULTISIGS` multisigs already
+    OwnerIndexFull = 35,
+    // the instruction doesn't apply to the proposal in its current state
+    InvalidTransition = 36,
+    // the payout would take the period's outflow past the multisig's cap
+    OutflowCapExceeded = 37,
+    // the owner's delegate key is past its expiry
+    DelegateExpired = 38,
+    // the delegate key wasn't granted the permission
+    DelegateNotPermitted = 39,
+    // the multisig's policy signer didn't co-sign the payout
+    PolicySignerMissing = 40,
+    // the mint's decimals aren't the ones the proposal was made with
+    DecimalsMismatch = 41,
+    // the proposal isn't executed, cancelled or expired, or its grace period hasn't passed
+    ProposalNotClosable = 42,
+    // an amount, counter or timestamp would overflow (or go below zero)
+    ArithmeticOverflow = 43,
+    // a transfer of zero, and the multisig doesn't allow them
+    ZeroAmount = 44,
+    // a lamport transfer below the multisig's `min_transfer`
+    BelowMinimumTransfer = 45,
+    // in strict mode, a transaction carrying a `Sign` is also signed by a key that isn't an owner
+    NonOwnerSigner = 46,
+    // `Sign` by an owner whose approval is already pending and, with an approval TTL, still live
+    AlreadySigned = 47,
+}
+
+impl MultisigError {
+    pub const ALL: [MultisigError; 48] = [
+        MultisigError::InvalidThreshold,
+        MultisigError::TooManyOwners,
+        MultisigError::OwnerNotFound,
+        MultisigError::DuplicateOwner,
+        MultisigError::InsufficientSignatures,
+        MultisigError::RequiredSignerMissing,
+        MultisigError::InsufficientBalance,
+        MultisigError::BelowRentFloor,
+        MultisigError::InvalidDestination,
+        MultisigError::AccountMismatch,
+        MultisigError::WrongMultisig,
+        MultisigError::InvalidAction,
+        MultisigError::InvalidExpiry,
+        MultisigError::ProposalExpired,
+        MultisigError::AlreadyExecuted,
+        MultisigError::StaleProposal,
+        MultisigError::NotExtendable,
+        MultisigError::Paused,
+        MultisigError::PolicyViolation,
+        MultisigError::DuplicateDestination,
+        MultisigError::DestinationNotFound,
+        MultisigError::VetoAuthorityIsOwner,
+        MultisigError::NotVetoAuthority,
+        MultisigError::InvalidConfig,
+        MultisigError::NotGuardian,
+        MultisigError::RecoveryPending,
+        MultisigError::RecoveryNotReady,
+        MultisigError::OwnerSetChanged,
+        MultisigError::AlreadyClaimed,
+        MultisigError::InvalidProof,
+        MultisigError::DistributionCapExceeded,
+        MultisigError::PaymentNotDue,
+        MultisigError::TransferFeeTooHigh,
+        MultisigError::InvalidBufferAuthority,
+        MultisigError::BufferHashMismatch,
+        MultisigError::OwnerIndexFull,
+        MultisigError::InvalidTransition,
+        MultisigError::OutflowCapExceeded,
+        MultisigError::DelegateExpired,
+        MultisigError::DelegateNotPermitted,
+        MultisigError::PolicySignerMissing,
+        MultisigError::DecimalsMismatch,
+        MultisigError::ProposalNotClosable,
+        MultisigError::ArithmeticOverflow,
+        MultisigError::ZeroAmount,
+        MultisigError::BelowMinimumTransfer,
+        MultisigError::NonOwnerSigner,
+        MultisigError::AlreadySigned,
+    ];
+
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    // The error behind a `ProgramError::Custom` code returned by the program
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
+}
+
+impl From<MultisigError> for ProgramError {
+    fn from(err: MultisigError) -> Self {
+        ProgramError::Custom(err.code())
+    }
+}
+
+impl fmt::Display for MultisigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            MultisigError::InvalidThreshold => "threshold must be between 1 and the owner count",
+            MultisigError::TooManyOwners => "too many owners",
+            MultisigError::OwnerNotFound => "not an owner of the multisig",
+            MultisigError::DuplicateOwner => "already an owner of the multisig",
+            MultisigError::InsufficientSignatures => "not enough approvals",
+            MultisigError::RequiredSignerMissing => "a required signer hasn't approved",
+            MultisigError::InsufficientBalance => "insufficient balance",
+            MultisigError::BelowRentFloor => "payment would leave the multisig below rent",
+            MultisigError::InvalidDestination => "destination can't receive funds",
+            MultisigError::AccountMismatch => "account doesn't match the instruction",
+            MultisigError::WrongMultisig => "account belongs to another multisig",
+            MultisigError::InvalidAction => "invalid proposal action",
+            MultisigError::InvalidExpiry => "invalid proposal expiry",
+            MultisigError::ProposalExpired => "proposal expired",
+            MultisigError::AlreadyExecuted => "proposal already executed",
+            MultisigError::StaleProposal => "proposal predates the current owner set",
+            MultisigError::NotExtendable => "proposal can't be extended",
+            MultisigError::Paused => "multisig is paused",
+            MultisigError::PolicyViolation => "rejected by the destination policy",
+            MultisigError::DuplicateDestination => "destination already in the policy",
+            MultisigError::DestinationNotFound => "destination not in the policy",
+            MultisigError::VetoAuthorityIsOwner => "the veto authority can't be an owner",
+            MultisigError::NotVetoAuthority => "not the veto authority",
+            MultisigError::InvalidConfig => "setting out of range",
+            MultisigError::NotGuardian => "not a guardian of the multisig",
+            MultisigError::RecoveryPending => "a recovery is already pending",
+            MultisigError::RecoveryNotReady => "recovery delay hasn't elapsed yet",
+            MultisigError::OwnerSetChanged => "owners changed since the aggregate key was set",
+            MultisigError::AlreadyClaimed => "already claimed",
+            MultisigError::InvalidProof => "invalid merkle proof",
+            MultisigError::DistributionCapExceeded => "claim exceeds the distribution cap",
+            MultisigError::PaymentNotDue => "payment not due yet",
+            MultisigError::TransferFeeTooHigh => "transfer fee above the approved maximum",
+            MultisigError::InvalidBufferAuthority => "buffer authority must be the vault",
+            MultisigError::BufferHashMismatch => "buffer doesn't hold the approved bytes",
+            MultisigError::OwnerIndexFull => "owner index is full",
+            MultisigError::InvalidTransition => "proposal can't move to that state",
+            MultisigError::OutflowCapExceeded => "outflow cap for the period exceeded",
+            MultisigError::DelegateExpired => "delegate key expired",
+            MultisigError::DelegateNotPermitted => "delegate key lacks the permission",
+            MultisigError::PolicySignerMissing => "policy signer must co-sign",
+            MultisigError::DecimalsMismatch => "mint decimals differ from the proposal's",
+            MultisigError::ProposalNotClosable => "proposal can't be closed yet",
+            MultisigError::ArithmeticOverflow => "arithmetic overflow",
+            MultisigError::ZeroAmount => "zero-amount transfers are disabled",
+            MultisigError::BelowMinimumTransfer => "transfer below the minimum amount",
+            MultisigError::NonOwnerSigner => "transaction signed by a key that isn't an owner",
+            MultisigError::AlreadySigned => "owner already approved",
+        };
+        write!(f, "{message}")
+    }
+}
+
+impl std::error::Error for MultisigError {}
@@ -0,0 +1,134 @@
+// Structured events the program logs with `sol_log_data`, shown as `Program data: <base64>` lines.
+// Each line is one field: the event's 8-byte discriminator, the schema version, then the event's
+// Borsh encoding. Indexers and UIs read deposits, proposals and executions from these instead of
+// diffing balances or parsing `msg!` text; only lines logged while the multisig program itself
+// runs count, any program can log the same bytes.
+//
+// Discriminators are the first 8 bytes of `sha256("event:<Name>")` and never change. New fields
+// only ever go at the end of an event, under a bumped `EVENT_VERSION`, so older decoders read the
+// fields they know and skip the rest.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::log::sol_log_data;
+use solana_program::pubkey::Pubkey;
+
+pub const EVENT_VERSION: u8 = 1;
+
+// Name field of the deposit lines logged before discriminators, still decoded for old transactions
+pub const DEPOSIT_EVENT: &[u8] = b"deposit";
+
+// `Deposit` of `amount` lamports by `depositor` into the main vault of `multisig`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepositEvent {
+    pub multisig: Pubkey,
+    pub vault: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+}
+
+impl DepositEvent {
+    pub const DISCRIMINATOR: [u8; 8] = [0x3e, 0xcd, 0xf2, 0xaf, 0xf4, 0xa9, 0x88, 0x34];
+
+    pub fn emit(&self) {
+        MultisigEvent::Deposit(*self).emit();
+    }
+
+    // The event from the decoded fields of a `Program data:` line, `None` for other events
+    pub fn from_log_data(fields: &[Vec<u8>]) -> Option<Self> {
+        match MultisigEvent::from_log_data(fields)? {
+            MultisigEvent::Deposit(event) => Some(event),
+            _ => None,
+        }
+    }
+}
+
+// `Propose` or `ProposeIndexed` created the proposal `transaction`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProposedEvent {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub proposer: Pubkey,
+}
+
+impl ProposedEvent {
+    pub const DISCRIMINATOR: [u8; 8] = [0xd8, 0x25, 0x8a, 0x8d, 0x82, 0xd0, 0xb4, 0x99];
+}
+
+// The proposal `transaction` was executed, `executed_by` is the `CrankTransaction` signer
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutedEvent {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub executed_by: Option<Pubkey>,
+}
+
+impl ExecutedEvent {
+    pub const DISCRIMINATOR: [u8; 8] = [0x08, 0xe8, 0x8b, 0x84, 0xc5, 0x2d, 0x1d, 0xa4];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MultisigEvent {
+    Deposit(DepositEvent),
+    Proposed(ProposedEvent),
+    Executed(ExecutedEvent),
+}
+
+impl MultisigEvent {
+    pub fn discriminator(&self) -> [u8; 8] {
+        match self {
+            MultisigEvent::Deposit(_) => DepositEvent::DISCRIMINATOR,
+            MultisigEvent::Proposed(_) => ProposedEvent::DISCRIMINATOR,
+            MultisigEvent::Executed(_) => ExecutedEvent::DISCRIMINATOR,
+        }
+    }
+
+    // The single field of the event's `Program data:` line
+    pub fn to_log_data(&self) -> Vec<u8> {
+        let mut data = self.discriminator().to_vec();
+        data.push(EVENT_VERSION);
+        let encoded = match self {
+            MultisigEvent::Deposit(event) => event.try_to_vec(),
+            MultisigEvent::Proposed(event) => event.try_to_vec(),
+            MultisigEvent::Executed(event) => event.try_to_vec(),
+        };
+        data.extend(encoded.unwrap());
+        data
+    }
+
+    pub fn emit(&self) {
+        sol_log_data(&[&self.to_log_data()]);
+    }
+
+    // The event from the decoded fields of a `Program data:` line, `None` for lines that aren't
+    // one of these events
+    pub fn from_log_data(fields: &[Vec<u8>]) -> Option<Self> {
+        match fields {
+            [data] => {
+                let (discriminator, rest) = (data.get(..8)?, data.get(8..)?);
+                let (&version, mut encoded) = rest.split_first()?;
+                if version == 0 {
+                    return None;
+                }
+                match <[u8; 8]>::try_from(discriminator).ok()? {
+                    DepositEvent::DISCRIMINATOR => DepositEvent::deserialize(&mut encoded)
+                        .ok()
+                        .map(Self::Deposit),
+                    ProposedEvent::DISCRIMINATOR => ProposedEvent::deserialize(&mut encoded)
+                        .ok()
+                        .map(Self::Proposed),
+                    ExecutedEvent::DISCRIMINATOR => ExecutedEvent::deserialize(&mut encoded)
+                        .ok()
+                        .map(Self::Executed),
+                    _ => None,
+                }
+            }
+            [name, data] if name == DEPOSIT_EVENT => {
+                DepositEvent::try_from_slice(data).ok().map(Self::Deposit)
+            }
+            _ => None,
+        }
+    }
+}
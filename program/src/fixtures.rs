@@ -0,0 +1,626 @@
+// Canonical account bytes for SDK decoder tests (TypeScript, Python, ...) so they can be
+// checked against what the program actually stores without running a validator. All
+// keys are fixed byte patterns, so the output is the same on every run.
+//
+// The generated files live in `fixtures/` (regenerate with `cargo run --example fixtures`),
+// `tests/test_fixtures.rs` fails whenever they drift from the current encoding.
+use borsh::BorshSerialize;
+use solana_program::hash::{hash, Hash};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+use crate::instruction::{ProposalAction, ProposedInstruction, VoteAuthority};
+use crate::state::{AmountPolicy, Multisig, OutflowCap, Transaction};
+
+pub struct Fixture {
+    pub name: &'static str,
+    // "multisig" or "transaction"
+    pub kind: &'static str,
+    pub description: &'static str,
+    pub data: Vec<u8>,
+    // decoded fields as a JSON object
+    pub fields: String,
+}
+
+// Deterministic stand-in key, every byte set to `n`
+pub fn key(n: u8) -> Pubkey {
+    Pubkey::new_from_array([n; 32])
+}
+
+pub fn all() -> Vec<Fixture> {
+    let owners = vec![key(1), key(2), key(3)];
+    let multisig_key = key(10);
+
+    let multisig = |signers: [bool; 3]| Multisig {
+        owners: owners.clone(),
+        threshold: 2,
+        signers: signers.to_vec(),
+        veto_authority: None,
+        paused: false,
+        emergency_authority: None,
+        amount_policy: None,
+        approval_ttl_seconds: None,
+        signed_at: vec![0; 3],
+        owner_set_seqno: 0,
+        outflow_cap: None,
+        spent_this_period: 0,
+        period_start: 0,
+        policy_signer: None,
+        transaction_count: 0,
+        fee_exempt: false,
+        allow_zero_amount: false,
+        min_transfer: 0,
+        strict_signing: false,
+        delegate_signers: 0,
+    };
+    let upgrade = ProposalAction::UpgradeProgram {
+        program: key(20),
+        buffer: key(21),
+        buffer_hash: [7; 32],
+    };
+    let transaction = |action: &ProposalAction, signers: [bool; 3], executed| Transaction {
+        multisig: multisig_key,
+        proposer: owners[0],
+        action: action.clone(),
+        signers: signers.to_vec(),
+        executed,
+        vault: 0,
+        expires_at: None,
+        extended: false,
+        required_signers: vec![false; 3],
+        approved_at: vec![0; 3],
+        executor_fee: 0,
+        owner_set_seqno: 0,
+        executed_at: 0,
+        executed_slot: 0,
+        executed_by: None,
+        attachment: None,
+    };
+
+    vec![
+        multisig_fixture(
+            "multisig_2_of_3_unsigned",
+            "2-of-3 multisig, no pending signatures",
+            multisig([false, false, false]),
+        ),
+        multisig_fixture(
+            "multisig_2_of_3_one_signed",
+            "2-of-3 multisig, second owner signed",
+            multisig([false, true, false]),
+        ),
+        multisig_fixture(
+            "multisig_2_of_3_ready",
+            "2-of-3 multisig, threshold reached",
+            multisig([true, false, true]),
+        ),
+        multisig_fixture(
+            "multisig_2_of_3_veto_authority",
+            "2-of-3 multisig with a veto authority",
+            Multisig {
+                veto_authority: Some(key(40)),
+                ..multisig([false, false, false])
+            },
+        ),
+        multisig_fixture(
+            "multisig_2_of_3_paused",
+            "2-of-3 multisig paused by its emergency authority",
+            Multisig {
+                paused: true,
+                emergency_authority: Some(key(41)),
+                ..multisig([false, true, false])
+            },
+        ),
+        multisig_fixture(
+            "multisig_2_of_3_approval_ttl",
+            "2-of-3 multisig, approvals count for a day, two owners signed",
+            Multisig {
+                approval_ttl_seconds: Some(86_400),
+                signed_at: vec![1_700_000_000, 0, 1_700_000_600],
+                ..multisig([true, false, true])
+            },
+        ),
+        multisig_fixture(
+            "multisig_2_of_3_amount_policy",
+            "2-of-3 multisig, transfers above 1 SOL need all three owners",
+            Multisig {
+                amount_policy: Some(AmountPolicy {
+                    limit: 1_000_000_000,
+                    large_threshold: 3,
+                }),
+                ..multisig([false, false, false])
+            },
+        ),
+        multisig_fixture(
+            "multisig_2_of_3_outflow_cap",
+            "2-of-3 multisig, at most 10 SOL out per day, 2.5 SOL paid out so far",
+            Multisig {
+                outflow_cap: Some(OutflowCap {
+                    limit: 10_000_000_000,
+                    period_seconds: 86_400,
+                }),
+                spent_this_period: 2_500_000_000,
+                period_start: 1_700_000_000,
+                ..multisig([false, false, false])
+            },
+        ),
+        multisig_fixture(
+            "multisig_2_of_3_owner_swapped",
+            "2-of-3 multisig after one owner swap, older proposals are stale",
+            Multisig {
+                owner_set_seqno: 1,
+                ..multisig([false, false, false])
+            },
+        ),
+        transaction_fixture(
+            "proposal_upgrade_proposed",
+            "upgrade proposal right after Propose (proposer approved)",
+            transaction(&upgrade, [true, false, false], false),
+        ),
+        transaction_fixture(
+            "proposal_upgrade_ready",
+            "upgrade proposal with enough approvals to execute",
+            transaction(&upgrade, [true, true, false], false),
+        ),
+        transaction_fixture(
+            "proposal_upgrade_executed",
+            "executed upgrade proposal",
+            transaction(&upgrade, [true, true, false], true),
+        ),
+        transaction_fixture(
+            "proposal_deploy_proposed",
+            "deploy proposal right after Propose",
+            transaction(
+                &ProposalAction::DeployProgram {
+                    program: key(22),
+                    buffer: key(23),
+                    max_data_len: 200_000,
+                    buffer_hash: [8; 32],
+                },
+                [true, false, false],
+                false,
+            ),
+        ),
+        transaction_fixture(
+            "proposal_transfer_token_proposed",
+            "token transfer proposal right after Propose",
+            transaction(
+                &ProposalAction::TransferToken {
+                    token_program: spl_token::id(),
+                    mint: key(30),
+                    destination: key(31),
+                    amount: 1_000_000,
+                    max_fee: 0,
+                    expected_decimals: 6,
+                },
+                [true, false, false],
+                false,
+            ),
+        ),
+        transaction_fixture(
+            "proposal_recurring_payment_proposed",
+            "monthly payment proposal right after Propose",
+            transaction(
+                &ProposalAction::CreateRecurringPayment {
+                    destination: key(32),
+                    amount: 5_000_000_000,
+                    interval_seconds: 30 * 24 * 60 * 60,
+                    count: 12,
+                },
+                [true, false, false],
+                false,
+            ),
+        ),
+        transaction_fixture(
+            "proposal_transfer_wrapped_sol_vault_2",
+            "wrapped SOL payment proposal drawing from vault 2",
+            Transaction {
+                vault: 2,
+                ..transaction(
+                    &ProposalAction::TransferWrappedSol {
+                        destination: key(33),
+                        amount: 250_000_000,
+                    },
+                    [true, false, false],
+                    false,
+                )
+            },
+        ),
+        transaction_fixture(
+            "proposal_stake_deactivate_extended",
+            "stake deactivation proposal whose expiry was extended",
+            Transaction {
+                expires_at: Some(1_700_000_000),
+                extended: true,
+                ..transaction(
+                    &ProposalAction::StakeDeactivate { stake: key(34) },
+                    [true, true, false],
+                    false,
+                )
+            },
+        ),
+        transaction_fixture(
+            "proposal_transfer_token_cfo_required",
+            "token transfer proposal that needs the third owner's approval",
+            Transaction {
+                required_signers: vec![false, false, true],
+                ..transaction(
+                    &ProposalAction::TransferToken {
+                        token_program: spl_token::id(),
+                        mint: key(30),
+                        destination: key(35),
+                        amount: 2_000_000,
+                        max_fee: 0,
+                        expected_decimals: 6,
+                    },
+                    [true, true, false],
+                    false,
+                )
+            },
+        ),
+        transaction_fixture(
+            "proposal_swap_owner_proposed",
+            "owner swap proposal right after Propose",
+            transaction(
+                &ProposalAction::SwapOwner {
+                    old_owner: key(3),
+                    new_owner: key(4),
+                },
+                [true, false, false],
+                false,
+            ),
+        ),
+        transaction_fixture(
+            "proposal_distribution_ready",
+            "grant round of up to 50 SOL with enough approvals to execute",
+            transaction(
+                &ProposalAction::CreateDistribution {
+                    merkle_root: [9; 32],
+                    total_cap: 50_000_000_000,
+                },
+                [true, true, false],
+                false,
+            ),
+        ),
+        transaction_fixture(
+            "proposal_set_upgrade_authority_none_ready",
+            "proposal making a program immutable, ready to execute",
+            transaction(
+                &ProposalAction::SetUpgradeAuthority {
+                    program: key(20),
+                    new_authority: None,
+                },
+                [false, true, true],
+                false,
+            ),
+        ),
+        transaction_fixture(
+            "proposal_stake_delegate_executor_fee",
+            "stake delegation paying its executor 0.001 SOL, ready to execute",
+            Transaction {
+                executor_fee: 1_000_000,
+                ..transaction(
+                    &ProposalAction::StakeDelegate {
+                        stake: key(36),
+                        vote: key(37),
+                    },
+                    [true, false, true],
+                    false,
+                )
+            },
+        ),
+        transaction_fixture(
+            "proposal_batch_transfers",
+            "batch of two vault transfers (0.001 and 0.002 SOL), proposer approved",
+            transaction(
+                &ProposalAction::Batch {
+                    instructions: vec![
+                        system_instruction::transfer(&key(11), &key(38), 1_000_000).into(),
+                        system_instruction::transfer(&key(11), &key(39), 2_000_000).into(),
+                    ],
+                },
+                [true, false, false],
+                false,
+            ),
+        ),
+        transaction_fixture(
+            "proposal_vote_authorize_withdrawer",
+            "proposal handing a vote account's withdrawer authority on, proposer approved",
+            transaction(
+                &ProposalAction::VoteAuthorize {
+                    vote: key(40),
+                    new_authority: key(41),
+                    authority: VoteAuthority::Withdrawer,
+                },
+                [true, false, false],
+                false,
+            ),
+        ),
+        transaction_fixture(
+            "proposal_transfer_with_attachment",
+            "vault transfer of 0.5 SOL committed to an invoice by its SHA-256, proposer approved",
+            Transaction {
+                attachment: Some(hash(b"invoice #42").to_bytes()),
+                ..transaction(
+                    &ProposalAction::Batch {
+                        instructions: vec![system_instruction::transfer(
+                            &key(11),
+                            &key(42),
+                            500_000_000,
+                        )
+                        .into()],
+                    },
+                    [true, false, false],
+                    false,
+                )
+            },
+        ),
+    ]
+}
+
+// `manifest.json`: every fixture with its file name, description and decoded fields
+pub fn manifest(fixtures: &[Fixture]) -> String {
+    let mut out = String::from("[\n");
+    for (i, fixture) in fixtures.iter().enumerate() {
+        let separator = if i + 1 == fixtures.len() { "" } else { "," };
+        writeln!(
+            out,
+            "  {{\"name\": \"{}\", \"kind\": \"{}\", \"file\": \"{}.bin\", \"len\": {}, \"description\": \"{}\", \"fields\": {}}}{}",
+            fixture.name,
+            fixture.kind,
+            fixture.name,
+            fixture.data.len(),
+            fixture.description,
+            fixture.fields,
+            separator
+        )
+        .unwrap();
+    }
+    out.push_str("]\n");
+    out
+}
+
+// Writes `<name>.bin` for every fixture plus `manifest.json` into `dir`
+pub fn write_all(dir: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let fixtures = all();
+    for fixture in &fixtures {
+        std::fs::write(dir.join(format!("{}.bin", fixture.name)), &fixture.data)?;
+    }
+    std::fs::write(dir.join("manifest.json"), manifest(&fixtures))
+}
+
+// Decoded multisig as a JSON object, the `fields` of a multisig fixture
+pub fn multisig_json(multisig: &Multisig) -> String {
+    format!(
+        "{{\"owners\": {}, \"threshold\": {}, \"signers\": {}, \"veto_authority\": {}, \"paused\": {}, \"emergency_authority\": {}, \"amount_policy\": {}, \"approval_ttl_seconds\": {}, \"signed_at\": {}, \"owner_set_seqno\": {}, \"outflow_cap\": {}, \"spent_this_period\": {}, \"period_start\": {}, \"policy_signer\": {}, \"transaction_count\": {}, \"fee_exempt\": {}, \"allow_zero_amount\": {}, \"min_transfer\": {}, \"strict_signing\": {}, \"delegate_signers\": {}}}",
+        keys_json(&multisig.owners),
+        multisig.threshold,
+        bools_json(&multisig.signers),
+        optional_key_json(&multisig.veto_authority),
+        multisig.paused,
+        optional_key_json(&multisig.emergency_authority),
+        multisig.amount_policy.map_or("null".to_string(), |policy| format!(
+            "{{\"limit\": {}, \"large_threshold\": {}}}",
+            policy.limit, policy.large_threshold
+        )),
+        multisig
+            .approval_ttl_seconds
+            .map_or("null".to_string(), |ttl| ttl.to_string()),
+        numbers_json(&multisig.signed_at),
+        multisig.owner_set_seqno,
+        multisig.outflow_cap.map_or("null".to_string(), |cap| format!(
+            "{{\"limit\": {}, \"period_seconds\": {}}}",
+            cap.limit, cap.period_seconds
+        )),
+        multisig.spent_this_period,
+        multisig.period_start,
+        optional_key_json(&multisig.policy_signer),
+        multisig.transaction_count,
+        multisig.fee_exempt,
+        multisig.allow_zero_amount,
+        multisig.min_transfer,
+        multisig.strict_signing,
+        multisig.delegate_signers
+    )
+}
+
+// Decoded proposal as a JSON object, the action is tagged with its `variant` name
+pub fn transaction_json(transaction: &Transaction) -> String {
+    format!(
+        "{{\"multisig\": \"{}\", \"proposer\": \"{}\", \"action\": {}, \"signers\": {}, \"executed\": {}, \"vault\": {}, \"expires_at\": {}, \"extended\": {}, \"required_signers\": {}, \"approved_at\": {}, \"executor_fee\": {}, \"owner_set_seqno\": {}, \"executed_at\": {}, \"executed_slot\": {}, \"executed_by\": {}, \"attachment\": {}}}",
+        transaction.multisig,
+        transaction.proposer,
+        action_json(&transaction.action),
+        bools_json(&transaction.signers),
+        transaction.executed,
+        transaction.vault,
+        transaction
+            .expires_at
+            .map_or("null".to_string(), |expires_at| expires_at.to_string()),
+        transaction.extended,
+        bools_json(&transaction.required_signers),
+        numbers_json(&transaction.approved_at),
+        transaction.executor_fee,
+        transaction.owner_set_seqno,
+        transaction.executed_at,
+        transaction.executed_slot,
+        transaction
+            .executed_by
+            .map_or("null".to_string(), |executor| format!("\"{executor}\"")),
+        transaction
+            .attachment_hex()
+            .map_or("null".to_string(), |hex| format!("\"{hex}\""))
+    )
+}
+
+fn multisig_fixture(name: &'static str, description: &'static str, multisig: Multisig) -> Fixture {
+    Fixture {
+        name,
+        kind: "multisig",
+        description,
+        data: multisig.try_to_vec().unwrap(),
+        fields: multisig_json(&multisig),
+    }
+}
+
+fn transaction_fixture(
+    name: &'static str,
+    description: &'static str,
+    transaction: Transaction,
+) -> Fixture {
+    Fixture {
+        name,
+        kind: "transaction",
+        description,
+        data: transaction.try_to_vec().unwrap(),
+        fields: transaction_json(&transaction),
+    }
+}
+
+fn action_json(action: &ProposalAction) -> String {
+    match action {
+        ProposalAction::DeployProgram {
+            program,
+            buffer,
+            max_data_len,
+            buffer_hash,
+        } => format!(
+            "{{\"variant\": \"DeployProgram\", \"program\": \"{program}\", \"buffer\": \"{buffer}\", \"max_data_len\": {max_data_len}, \"buffer_hash\": \"{}\"}}",
+            Hash::new_from_array(*buffer_hash)
+        ),
+        ProposalAction::UpgradeProgram {
+            program,
+            buffer,
+            buffer_hash,
+        } => format!(
+            "{{\"variant\": \"UpgradeProgram\", \"program\": \"{program}\", \"buffer\": \"{buffer}\", \"buffer_hash\": \"{}\"}}",
+            Hash::new_from_array(*buffer_hash)
+        ),
+        ProposalAction::TransferToken {
+            token_program,
+            mint,
+            destination,
+            amount,
+            max_fee,
+            expected_decimals,
+        } => format!(
+            "{{\"variant\": \"TransferToken\", \"token_program\": \"{token_program}\", \"mint\": \"{mint}\", \"destination\": \"{destination}\", \"amount\": {amount}, \"max_fee\": {max_fee}, \"expected_decimals\": {expected_decimals}}}"
+        ),
+        ProposalAction::StakeDelegate { stake, vote } => format!(
+            "{{\"variant\": \"StakeDelegate\", \"stake\": \"{stake}\", \"vote\": \"{vote}\"}}"
+        ),
+        ProposalAction::StakeDeactivate { stake } => {
+            format!("{{\"variant\": \"StakeDeactivate\", \"stake\": \"{stake}\"}}")
+        }
+        ProposalAction::StakeSplit {
+            stake,
+            split_stake,
+            lamports,
+        } => format!(
+            "{{\"variant\": \"StakeSplit\", \"stake\": \"{stake}\", \"split_stake\": \"{split_stake}\", \"lamports\": {lamports}}}"
+        ),
+        ProposalAction::StakeWithdraw {
+            stake,
+            destination,
+            lamports,
+        } => format!(
+            "{{\"variant\": \"StakeWithdraw\", \"stake\": \"{stake}\", \"destination\": \"{destination}\", \"lamports\": {lamports}}}"
+        ),
+        ProposalAction::TransferWrappedSol {
+            destination,
+            amount,
+        } => format!(
+            "{{\"variant\": \"TransferWrappedSol\", \"destination\": \"{destination}\", \"amount\": {amount}}}"
+        ),
+        ProposalAction::SetUpgradeAuthority {
+            program,
+            new_authority,
+        } => format!(
+            "{{\"variant\": \"SetUpgradeAuthority\", \"program\": \"{program}\", \"new_authority\": {}}}",
+            optional_key_json(new_authority)
+        ),
+        ProposalAction::CreateRecurringPayment {
+            destination,
+            amount,
+            interval_seconds,
+            count,
+        } => format!(
+            "{{\"variant\": \"CreateRecurringPayment\", \"destination\": \"{destination}\", \"amount\": {amount}, \"interval_seconds\": {interval_seconds}, \"count\": {count}}}"
+        ),
+        ProposalAction::SwapOwner {
+            old_owner,
+            new_owner,
+        } => format!(
+            "{{\"variant\": \"SwapOwner\", \"old_owner\": \"{old_owner}\", \"new_owner\": \"{new_owner}\"}}"
+        ),        ProposalAction::CreateDistribution {
+            merkle_root,
+            total_cap,
+        } => format!(
+            "{{\"variant\": \"CreateDistribution\", \"merkle_root\": \"{}\", \"total_cap\": {total_cap}}}",
+            Hash::new_from_array(*merkle_root)
+        ),
+        ProposalAction::Batch { instructions } => {
+            let instructions: Vec<String> = instructions.iter().map(proposed_instruction_json).collect();
+            format!(
+                "{{\"variant\": \"Batch\", \"instructions\": [{}]}}",
+                instructions.join(", ")
+            )
+        }
+        ProposalAction::VoteAuthorize {
+            vote,
+            new_authority,
+            authority,
+        } => format!(
+            "{{\"variant\": \"VoteAuthorize\", \"vote\": \"{vote}\", \"new_authority\": \"{new_authority}\", \"authority\": \"{authority:?}\"}}"
+        ),
+        ProposalAction::VoteWithdraw {
+            vote,
+            destination,
+            lamports,
+        } => format!(
+            "{{\"variant\": \"VoteWithdraw\", \"vote\": \"{vote}\", \"destination\": \"{destination}\", \"lamports\": {lamports}}}"
+        ),
+    }
+}
+
+fn proposed_instruction_json(instruction: &ProposedInstruction) -> String {
+    let accounts: Vec<String> = instruction
+        .accounts
+        .iter()
+        .map(|account| {
+            format!(
+                "{{\"pubkey\": \"{}\", \"is_signer\": {}, \"is_writable\": {}}}",
+                account.pubkey, account.is_signer, account.is_writable
+            )
+        })
+        .collect();
+    let data: Vec<String> = instruction.data.iter().map(u8::to_string).collect();
+    format!(
+        "{{\"program_id\": \"{}\", \"accounts\": [{}], \"data\": [{}]}}",
+        instruction.program_id,
+        accounts.join(", "),
+        data.join(", ")
+    )
+}
+
+fn keys_json(keys: &[Pubkey]) -> String {
+    let keys: Vec<String> = keys.iter().map(|key| format!("\"{key}\"")).collect();
+    format!("[{}]", keys.join(", "))
+}
+
+fn optional_key_json(key: &Option<Pubkey>) -> String {
+    key.map_or("null".to_string(), |key| format!("\"{key}\""))
+}
+
+fn bools_json(values: &[bool]) -> String {
+    let values: Vec<String> = values.iter().map(bool::to_string).collect();
+    format!("[{}]", values.join(", "))
+}
+
+fn numbers_json(values: &[i64]) -> String {
+    let values: Vec<String> = values.iter().map(i64::to_string).collect();
+    format!("[{}]", values.join(", "))
+}
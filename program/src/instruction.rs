@@ -0,0 +1,2803 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::bpf_loader_upgradeable;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::{ed25519_program, stake, system_instruction, system_program, sysvar, vote};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+};
+use std::ops::RangeInclusive;
+
+use crate::lookup_table::{self, find_lookup_table_address};
+use crate::state::{
+    aggregated_approval_message, find_aggregate_key_address, find_claim_address,
+    find_delegate_address, find_derived_multisig_address, find_destination_policy_address,
+    find_display_unit_address, find_distribution_address, find_guardians_address,
+    find_history_address, find_inbox_address, find_indexed_vault_address, find_metadata_address,
+    find_owner_index_address, find_program_config_address, find_receipt_address,
+    find_receipt_ledger_address, find_recovery_address, find_recurring_payment_address,
+    find_transaction_address, find_vault_address, AmountPolicy, DestinationMode, OutflowCap,
+    PolicyEnforcement, ProgramConfig, Transaction,
+};
+use crate::token_metadata::{self, TokenDelegateRole};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub enum MultisigInstruction {
+    // owner indexes passed after the multisig account get the new multisig listed
+    Create {
+        owners: Vec<Pubkey>,
+        threshold: u8,
+    },
+    Sign,
+    // threshold-gated transfer from the multisig account; a payer, the destination's receipt
+    // ledger and its next receipt passed after the accounts record the payment
+    Execute {
+        amount: u64,
+        destination: Pubkey,
+    },
+    // creates the associated token account of the vault PDA for `mint`, consumes the current approvals
+    CreateTokenAccount {
+        mint: Pubkey,
+    },
+    // initializes a proposal account, the proposer (an owner) approves it right away; inboxes of
+    // other owners passed after the accounts get flagged. `vault` is the index of the vault the
+    // action draws from, `None` for `expires_at` keeps the proposal open until executed.
+    // `ExecuteTransaction` also waits for the approval of every owner in `required_signers`.
+    // `attachment` commits the proposal to an off-chain document by its hash
+    Propose {
+        action: ProposalAction,
+        vault: u8,
+        expires_at: Option<i64>,
+        required_signers: Vec<Pubkey>,
+        executor_fee: u64,
+        attachment: Option<[u8; 32]>,
+    },
+    // an owner approves a proposal
+    Approve,
+    // runs a proposal's action once it has enough approvals, the proposal's vault PDA signs
+    ExecuteTransaction,
+    // replaces `old_owner` with `new_owner` at the same index, consumes the current approvals;
+    // pending proposals passed after the multisig account drop the old key's approval, the owner
+    // indexes of either key among them are updated (so for `SwapOwner` proposals)
+    SwapOwner {
+        old_owner: Pubkey,
+        new_owner: Pubkey,
+    },
+    // writes the guardian set (`Guardians` PDA) of a multisig, the multisig keypair must sign,
+    // so this belongs in the creation transaction
+    InitGuardians {
+        guardians: Vec<Pubkey>,
+        recovery_delay: i64,
+    },
+    // a guardian proposes replacing the owner set (`Recovery` PDA) and approves it right away
+    ProposeRecovery {
+        new_owners: Vec<Pubkey>,
+        new_threshold: u8,
+    },
+    ApproveRecovery,
+    // any single owner cancels the pending recovery
+    VetoRecovery,
+    // replaces the owner set once a guardian supermajority approved and the recovery delay
+    // elapsed, pending proposals passed after the fixed accounts lose their approvals
+    ExecuteRecovery,
+    // threshold-gated, `None` removes the veto authority
+    SetVetoAuthority {
+        veto_authority: Option<Pubkey>,
+    },
+    // the veto authority cancels a proposal that hasn't been executed yet, its account is
+    // closed and the rent goes back to the proposer
+    VetoTransaction,
+    // threshold-gated, (re)writes the destination policy checked by `Execute`
+    SetDestinationPolicy {
+        mode: DestinationMode,
+        destinations: Vec<Pubkey>,
+    },
+    // threshold-gated, edit a single entry of an existing destination policy
+    AddDestination {
+        destination: Pubkey,
+    },
+    RemoveDestination {
+        destination: Pubkey,
+    },
+    // threshold-gated, `Shadow` only logs the destinations the policy would refuse
+    SetDestinationPolicyEnforcement {
+        enforcement: PolicyEnforcement,
+    },
+    // threshold-gated; the emergency authority may also pause (but not unpause) on its own.
+    // `Sign` keeps working while paused, the executes don't
+    SetPaused {
+        paused: bool,
+    },
+    // threshold-gated, `None` removes the emergency authority
+    SetEmergencyAuthority {
+        emergency_authority: Option<Pubkey>,
+    },
+    // permissionless crank of a recurring payment that is due. Like `Execute` it may be followed
+    // by a payer, the destination's receipt ledger and its next receipt to record the payment
+    ExecuteRecurring,
+    // Experimental, only processed when built with the `aggregated-approval` feature.
+    // Registers the owners' group key (`AggregateKey` PDA), the multisig keypair must sign, so
+    // like `InitGuardians` this belongs in the creation transaction
+    InitAggregateKey {
+        aggregate_key: Pubkey,
+    },
+    // Experimental. Approves a proposal for every owner; the instruction right before it must be
+    // an Ed25519 program instruction checking the group key's signature over
+    // `aggregated_approval_message`. Needs no signer
+    ApproveAggregated,
+    // creates the signing owner's `Inbox` PDA
+    RegisterInbox,
+    // the owner lowers the `pending` flag of their inbox
+    ClearInbox,
+    // pushes an open proposal's expiry back by up to `MAX_PROPOSAL_EXTENSION` seconds, once.
+    // Signed by threshold minus one owners, or by the proposer and one other owner, passed after
+    // the fixed accounts
+    ExtendProposal {
+        seconds: i64,
+    },
+    // threshold-gated, `None` removes the amount policy. The large threshold must lie between the
+    // threshold and the owner count
+    SetPolicy {
+        policy: Option<AmountPolicy>,
+    },
+    // threshold-gated, (re)writes the `DisplayUnit` clients show amounts in. `symbol` is at most
+    // `MAX_DISPLAY_SYMBOL_LEN` bytes
+    SetDisplayUnit {
+        mint: Pubkey,
+        decimals: u8,
+        symbol: String,
+    },
+    // threshold-gated, approvals (`Sign`, `Approve`) older than `ttl_seconds` stop counting toward
+    // thresholds; `None` lets them count until consumed
+    SetApprovalTtl {
+        ttl_seconds: Option<i64>,
+    },
+    // permissionless claim of `amount` lamports for the recipient of a distribution, `proof` are
+    // the sibling hashes from its leaf up to the merkle root. The payer funds the claim marker
+    ClaimDistribution {
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    },
+    // threshold-gated, creates an address lookup table with the main vault as its authority, the
+    // payer funds it. `recent_slot` has to be in the `SlotHashes` sysvar and, with the vault,
+    // derives the table address. Execute transactions too large for a legacy message reference
+    // their accounts through it from a v0 message
+    CreateLookupTable {
+        recent_slot: u64,
+    },
+    // threshold-gated, appends `addresses` to a lookup table of the vault, the payer funds the
+    // extra space. They become usable in the slot after
+    ExtendLookupTable {
+        addresses: Vec<Pubkey>,
+    },
+    // threshold-gated; the table can still be read until the deactivation slot leaves
+    // `SlotHashes` (about 513 slots later), then `CloseLookupTable` may close it
+    DeactivateLookupTable,
+    // threshold-gated, closes a deactivated lookup table, its rent goes to the vault
+    CloseLookupTable,
+    // fails unless the multisig holds enough live `Sign` approvals for `context`, changes nothing.
+    // For other programs to CPI into before acting on the multisig's behalf (`assert_approved`)
+    CheckApproved {
+        context: ThresholdContext,
+    },
+    // `Execute` paid by the main vault rather than the multisig account, which then only holds
+    // configuration and its rent. The vault can't be left with less than its rent-exempt minimum
+    // unless emptied. Takes the same optional receipt accounts
+    ExecuteFromVault {
+        amount: u64,
+        destination: Pubkey,
+    },
+    // permissionless, moves the multisig account's lamports above its rent-exempt minimum to the
+    // main vault: wallets created before vaults kept their funds in the state account
+    MigrateToVault,
+    // `ExecuteTransaction` by any signer, who then receives the proposal's `executor_fee` from
+    // its vault, so keepers can run approved proposals while the owners are away. The
+    // `ExecuteTransaction` accounts follow the executor and the system program
+    CrankTransaction,
+    // creates the owner's index (`OwnerIndex` PDA), listing the multisigs passed after the
+    // accounts that have the owner in their owner set
+    RegisterOwnerIndex,
+    // permissionless, lists the multisig in the owner's index if the owner belongs to it and
+    // drops it otherwise, e.g. after a recovery replaced the owner set
+    SyncOwnerIndex,
+    // the index owner drops a multisig from their index: anyone can create a multisig naming
+    // them as an owner
+    UnlistMultisig {
+        multisig: Pubkey,
+    },
+    // threshold-gated, (re)writes the wallet's `Metadata`, the payer covers its rent. `name` and
+    // `uri` are at most `MAX_METADATA_NAME_LEN` and `MAX_METADATA_URI_LEN` bytes
+    SetMetadata {
+        name: String,
+        uri: String,
+    },
+    // `Approve` for `owner` without their signature on the transaction: the instruction right
+    // before it must be an Ed25519 program instruction checking the owner's signature over the
+    // proposal's `Transaction::digest`, so a relayer can submit (and pay for) approvals signed
+    // offline. Does nothing for an owner who approved already, a replay doesn't renew the approval
+    ApproveWithSignature {
+        owner: Pubkey,
+    },
+    // threshold-gated, caps the lamports `Execute`, `ExecuteFromVault` and executed proposals
+    // move out per period. `None` lifts the cap and clears the period's count
+    SetOutflowCap {
+        cap: Option<OutflowCap>,
+    },
+    // the signing owner (re)writes their `Delegate` record for the multisig and pays its rent.
+    // `expires_at` must lie ahead, `permissions` is a non-empty mask of the `DELEGATE_*` bits
+    SetDelegate {
+        delegate: Pubkey,
+        expires_at: i64,
+        permissions: u8,
+    },
+    // the signing owner closes their `Delegate` record, the rent goes back to them
+    RevokeDelegate,
+    // `Sign` for the owner of the `Delegate` record, signed by its delegate key
+    SignAsDelegate,
+    // `Approve` for the owner of the `Delegate` record, signed by its delegate key. Takes the
+    // buffer of a deploy or upgrade proposal last, like `Approve`
+    ApproveAsDelegate,
+    // threshold-gated, `Some` makes `Execute` and `ExecuteFromVault` require the key's signature
+    // (`with_policy_signer`), `None` turns the requirement off
+    SetPolicySigner {
+        policy_signer: Option<Pubkey>,
+    },
+    // `Propose` into the PDA `find_transaction_address(multisig, transaction_count)`, created here
+    // and paid by the proposer, then bumps the count so proposals can be listed in order
+    ProposeIndexed {
+        action: ProposalAction,
+        vault: u8,
+        expires_at: Option<i64>,
+        required_signers: Vec<Pubkey>,
+        executor_fee: u64,
+        attachment: Option<[u8; 32]>,
+    },
+    // anyone may close a proposal `CLOSE_GRACE_PERIOD` after it was executed, went stale or
+    // expired, the rent goes back to its proposer
+    CloseTransaction,
+    // creates the multisig's `History` account, paid by the payer
+    InitHistory,
+    // sets the multisig's threshold, signed by its main vault: only an executed proposal can do
+    // that, as a `Batch` instruction calling back into the program (`propose_change_threshold`).
+    // Approvals are consumed and the proposals made before go stale
+    ChangeThreshold {
+        threshold: u8,
+    },
+    // adds `owner` to the owner set, signed by the main vault like `ChangeThreshold`
+    // (`propose_add_owner`)
+    AddOwner {
+        owner: Pubkey,
+    },
+    // `Create` at the PDA `find_derived_multisig_address(creator, seed)`, created here and paid by
+    // the payer. The creator signs, a program creating wallets signs with one of its PDAs. Owner
+    // indexes follow the accounts, like for `Create`
+    CreateDerived {
+        seed: [u8; 32],
+        owners: Vec<Pubkey>,
+        threshold: u8,
+    },
+    // the depositor pays `amount` lamports into the multisig's main vault, logged as a
+    // `DepositEvent` so deposits show up with who made them
+    Deposit {
+        amount: u64,
+    },
+    // creates the `ProgramConfig`, signed by the program's upgrade authority, paid by the payer.
+    // `governance` is the multisig changing it from then on. The pause and the fee only bind every
+    // payout in builds with the `program-config` feature, elsewhere the account is optional
+    InitProgramConfig {
+        governance: Pubkey,
+        fee_destination: Pubkey,
+        protocol_fee_bps: u16,
+    },
+    // rewrites the `ProgramConfig`, signed by the main vault of its governance multisig like
+    // `ChangeThreshold` (`propose_program_paused`). `paused` stops the payouts of every wallet
+    SetProgramConfig {
+        governance: Pubkey,
+        fee_destination: Pubkey,
+        protocol_fee_bps: u16,
+        paused: bool,
+    },
+    // exempts a multisig from (or subjects it again to) the protocol fee, signed by the main vault
+    // of the program config's governance multisig (`propose_fee_exempt`)
+    SetFeeExempt {
+        exempt: bool,
+    },
+    // threshold-gated, transfers of zero are refused unless `allow_zero_amount` is set (e.g. for
+    // 0-lamport signal proposals) and lamport transfers below `min_transfer` always are; checked
+    // by `Execute`, `ExecuteFromVault` and `Propose`
+    SetTransferMinimum {
+        allow_zero_amount: bool,
+        min_transfer: u64,
+    },
+    // threshold-gated, in strict mode `Sign` takes the instructions sysvar and the fee payer (see
+    // `sign_strict`) and fails if the payer or a signer of any instruction isn't an owner
+    SetStrictSigning {
+        strict: bool,
+    },
+    // `Sign` for an owner that is another multisig of this program, i.e. its main vault PDA. The
+    // child's own approvals, checked the way `CheckApproved` does, stand in for the PDA's signature
+    // and are consumed like a threshold-gated instruction's. One of the child's owners has to sign
+    // it, so the child's approvals aren't spent on a parent they weren't meant for. For
+    // hierarchies, e.g. department wallets owning the treasury wallet
+    SignAsMultisig,
+}
+
+impl MultisigInstruction {
+    // Variant names indexed by tag
+    pub const NAMES: [&'static str; 63] = [
+        "Create",
+        "Sign",
+        "Execute",
+        "CreateTokenAccount",
+        "Propose",
+        "Approve",
+        "ExecuteTransaction",
+        "SwapOwner",
+        "InitGuardians",
+        "ProposeRecovery",
+        "ApproveRecovery",
+        "VetoRecovery",
+        "ExecuteRecovery",
+        "SetVetoAuthority",
+        "VetoTransaction",
+        "SetDestinationPolicy",
+        "AddDestination",
+        "RemoveDestination",
+        "SetDestinationPolicyEnforcement",
+        "SetPaused",
+        "SetEmergencyAuthority",
+        "ExecuteRecurring",
+        "InitAggregateKey",
+        "ApproveAggregated",
+        "RegisterInbox",
+        "ClearInbox",
+        "ExtendProposal",
+        "SetPolicy",
+        "SetDisplayUnit",
+        "SetApprovalTtl",
+        "ClaimDistribution",
+        "CreateLookupTable",
+        "ExtendLookupTable",
+        "DeactivateLookupTable",
+        "CloseLookupTable",
+        "CheckApproved",
+        "ExecuteFromVault",
+        "MigrateToVault",
+        "CrankTransaction",
+        "RegisterOwnerIndex",
+        "SyncOwnerIndex",
+        "UnlistMultisig",
+        "SetMetadata",
+        "ApproveWithSignature",
+        "SetOutflowCap",
+        "SetDelegate",
+        "RevokeDelegate",
+        "SignAsDelegate",
+        "ApproveAsDelegate",
+        "SetPolicySigner",
+        "ProposeIndexed",
+        "CloseTransaction",
+        "InitHistory",
+        "ChangeThreshold",
+        "AddOwner",
+        "CreateDerived",
+        "Deposit",
+        "InitProgramConfig",
+        "SetProgramConfig",
+        "SetFeeExempt",
+        "SetTransferMinimum",
+        "SetStrictSigning",
+        "SignAsMultisig",
+    ];
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[self.tag() as usize]
+    }
+
+    // The variant's tag on the wire, pinned here so reordering the enum can't renumber it
+    pub fn tag(&self) -> u8 {
+        match self {
+            MultisigInstruction::Create { .. } => 0,
+            MultisigInstruction::Sign => 1,
+            MultisigInstruction::Execute { .. } => 2,
+            MultisigInstruction::CreateTokenAccount { .. } => 3,
+            MultisigInstruction::Propose { .. } => 4,
+            MultisigInstruction::Approve => 5,
+            MultisigInstruction::ExecuteTransaction => 6,
+            MultisigInstruction::SwapOwner { .. } => 7,
+            MultisigInstruction::InitGuardians { .. } => 8,
+            MultisigInstruction::ProposeRecovery { .. } => 9,
+            MultisigInstruction::ApproveRecovery => 10,
+            MultisigInstruction::VetoRecovery => 11,
+            MultisigInstruction::ExecuteRecovery => 12,
+            MultisigInstruction::SetVetoAuthority { .. } => 13,
+            MultisigInstruction::VetoTransaction => 14,
+            MultisigInstruction::SetDestinationPolicy { .. } => 15,
+            MultisigInstruction::AddDestination { .. } => 16,
+            MultisigInstruction::RemoveDestination { .. } => 17,
+            MultisigInstruction::SetDestinationPolicyEnforcement { .. } => 18,
+            MultisigInstruction::SetPaused { .. } => 19,
+            MultisigInstruction::SetEmergencyAuthority { .. } => 20,
+            MultisigInstruction::ExecuteRecurring => 21,
+            MultisigInstruction::InitAggregateKey { .. } => 22,
+            MultisigInstruction::ApproveAggregated => 23,
+            MultisigInstruction::RegisterInbox => 24,
+            MultisigInstruction::ClearInbox => 25,
+            MultisigInstruction::ExtendProposal { .. } => 26,
+            MultisigInstruction::SetPolicy { .. } => 27,
+            MultisigInstruction::SetDisplayUnit { .. } => 28,
+            MultisigInstruction::SetApprovalTtl { .. } => 29,
+            MultisigInstruction::ClaimDistribution { .. } => 30,
+            MultisigInstruction::CreateLookupTable { .. } => 31,
+            MultisigInstruction::ExtendLookupTable { .. } => 32,
+            MultisigInstruction::DeactivateLookupTable => 33,
+            MultisigInstruction::CloseLookupTable => 34,
+            MultisigInstruction::CheckApproved { .. } => 35,
+            MultisigInstruction::ExecuteFromVault { .. } => 36,
+            MultisigInstruction::MigrateToVault => 37,
+            MultisigInstruction::CrankTransaction => 38,
+            MultisigInstruction::RegisterOwnerIndex => 39,
+            MultisigInstruction::SyncOwnerIndex => 40,
+            MultisigInstruction::UnlistMultisig { .. } => 41,
+            MultisigInstruction::SetMetadata { .. } => 42,
+            MultisigInstruction::ApproveWithSignature { .. } => 43,
+            MultisigInstruction::SetOutflowCap { .. } => 44,
+            MultisigInstruction::SetDelegate { .. } => 45,
+            MultisigInstruction::RevokeDelegate => 46,
+            MultisigInstruction::SignAsDelegate => 47,
+            MultisigInstruction::ApproveAsDelegate => 48,
+            MultisigInstruction::SetPolicySigner { .. } => 49,
+            MultisigInstruction::ProposeIndexed { .. } => 50,
+            MultisigInstruction::CloseTransaction => 51,
+            MultisigInstruction::InitHistory => 52,
+            MultisigInstruction::ChangeThreshold { .. } => 53,
+            MultisigInstruction::AddOwner { .. } => 54,
+            MultisigInstruction::CreateDerived { .. } => 55,
+            MultisigInstruction::Deposit { .. } => 56,
+            MultisigInstruction::InitProgramConfig { .. } => 57,
+            MultisigInstruction::SetProgramConfig { .. } => 58,
+            MultisigInstruction::SetFeeExempt { .. } => 59,
+            MultisigInstruction::SetTransferMinimum { .. } => 60,
+            MultisigInstruction::SetStrictSigning { .. } => 61,
+            MultisigInstruction::SignAsMultisig => 62,
+        }
+    }
+
+    // Decodes the Borsh encoding (tags up to `MAX_INSTRUCTION_TAG`), ignoring trailing bytes. The
+    // packed encoding is decoded by the processor, which also needs the accounts
+    pub fn decode(data: &[u8]) -> Result<Self, ProgramError> {
+        match data.first() {
+            Some(&tag) if tag <= MAX_INSTRUCTION_TAG => {
+                Self::deserialize(&mut &data[..]).map_err(|_| ProgramError::InvalidInstructionData)
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+
+const _: () = assert!(MAX_INSTRUCTION_TAG < *RESERVED_TAGS.start());
+const _: () = assert!(*RESERVED_TAGS.end() < PACKED_FORMAT_TAG);
+
+// The approvals `CheckApproved` asks for
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThresholdContext {
+    // the multisig's threshold
+    Threshold,
+    // the threshold for moving `lamports` out of the wallet, see `AmountPolicy`
+    Amount(u64),
+    // the threshold, or this many approvals if that is more
+    AtLeast(u8),
+}
+
+// How a payout moves its lamports. `Execute` debits the multisig account directly, which only
+// works because the program owns it; `ExecuteFromVault` pays from the system-owned vault PDA with
+// a System Program transfer the program signs for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionStrategy {
+    DirectLamports,
+    SystemTransfer,
+}
+
+// Instruction data starts with a tag byte:
+//   0x00..=0x7f  Borsh-encoded `MultisigInstruction`, the tag is the variant index
+//                (`MultisigInstruction::tag`). Tags never move, new instructions take the next one
+//   0x80..=0xfe  reserved for future encodings, rejected for now
+//   0xff         packed encoding of the hottest instructions
+// Bytes after an instruction's fields are ignored, so a later version can append optional fields
+// without breaking the clients that don't send them.
+//
+// The packed encoding is easy to build without Borsh: `PACKED_FORMAT_TAG`, then the instruction's
+// variant index, then its fields as little-endian integers.
+//   Sign:    [0xff, 1]
+//   Execute: [0xff, 2, amount: u64], the destination is the second account rather than data
+pub const PACKED_FORMAT_TAG: u8 = 0xff;
+pub const PACKED_SIGN: u8 = 1;
+pub const PACKED_EXECUTE: u8 = 2;
+pub const MAX_INSTRUCTION_TAG: u8 = 0x7f;
+pub const RESERVED_TAGS: RangeInclusive<u8> = 0x80..=0xfe;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProposalAction {
+    // deploys the program from `buffer` with the vault PDA as upgrade authority,
+    // the program account is created during execution (program keypair must sign)
+    DeployProgram {
+        program: Pubkey,
+        buffer: Pubkey,
+        max_data_len: u64,
+        buffer_hash: [u8; 32],
+    },
+    // upgrades `program` (upgrade authority must be the vault PDA) from `buffer`
+    UpgradeProgram {
+        program: Pubkey,
+        buffer: Pubkey,
+        buffer_hash: [u8; 32],
+    },
+    // transfers `amount` tokens (fee included) from the vault's associated token account to the
+    // `destination` token account with `transfer_checked`, `token_program` is SPL Token or
+    // Token-2022; execution fails if the mint's current transfer fee is above `max_fee`, or if the
+    // mint's decimals aren't `expected_decimals`, the ones `amount` was worked out with
+    TransferToken {
+        token_program: Pubkey,
+        mint: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+        max_fee: u64,
+        expected_decimals: u8,
+    },
+    // stake accounts whose staker/withdrawer authority is the vault PDA
+    StakeDelegate {
+        stake: Pubkey,
+        vote: Pubkey,
+    },
+    StakeDeactivate {
+        stake: Pubkey,
+    },
+    // `split_stake` must be a fresh system account, its keypair signs the execution
+    StakeSplit {
+        stake: Pubkey,
+        split_stake: Pubkey,
+        lamports: u64,
+    },
+    StakeWithdraw {
+        stake: Pubkey,
+        destination: Pubkey,
+        lamports: u64,
+    },
+    // pays `amount` lamports from the vault as wrapped SOL to the `destination` token account,
+    // the vault's WSOL associated token account is created, funded and closed again in the process
+    TransferWrappedSol {
+        destination: Pubkey,
+        amount: u64,
+    },
+    // hands the upgrade authority of `program` (currently the vault PDA) to `new_authority`,
+    // `None` makes the program immutable
+    SetUpgradeAuthority {
+        program: Pubkey,
+        new_authority: Option<Pubkey>,
+    },
+    // executing it starts a schedule (`RecurringPayment`) paying `amount` lamports from the vault
+    // to `destination` every `interval_seconds`, `count` times; the first payment is due one
+    // interval after execution
+    CreateRecurringPayment {
+        destination: Pubkey,
+        amount: u64,
+        interval_seconds: i64,
+        count: u32,
+    },
+    // replaces `old_owner` with `new_owner` at the same index in one step, the owner count and
+    // threshold stay as they are. Pending proposals passed after the fixed accounts drop the old
+    // key's approval
+    SwapOwner {
+        old_owner: Pubkey,
+        new_owner: Pubkey,
+    },
+    // approves a whole grant round at once: recipients of the (recipient, amount) merkle tree
+    // under `merkle_root` claim from the vault themselves (`ClaimDistribution`), `total_cap`
+    // lamports at most. The vault pays the `Distribution` account's rent
+    CreateDistribution {
+        merkle_root: [u8; 32],
+        total_cap: u64,
+    },
+    // arbitrary instructions run one after the other under the one approval, the vault PDA signs
+    // wherever it is listed as a signer. Can't call back into the multisig program
+    Batch {
+        instructions: Vec<ProposedInstruction>,
+    },
+    // hands the voter or withdrawer authority of the vote account `vote` (currently the vault PDA)
+    // to `new_authority`
+    VoteAuthorize {
+        vote: Pubkey,
+        new_authority: Pubkey,
+        authority: VoteAuthority,
+    },
+    // withdraws `lamports` of a vote account whose withdrawer authority is the vault PDA
+    VoteWithdraw {
+        vote: Pubkey,
+        destination: Pubkey,
+        lamports: u64,
+    },
+}
+
+// Which authority of a vote account `VoteAuthorize` moves
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VoteAuthority {
+    Voter,
+    Withdrawer,
+}
+
+// One instruction of a `Batch` proposal, kept as proposed so approvers review the exact program,
+// accounts and data that will run
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProposedInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<ProposedAccount>,
+    pub data: Vec<u8>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProposedAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl ProposedInstruction {
+    // Lamports a System Program `Transfer` or `TransferWithSeed` moves, 0 for anything else. Both
+    // encode the amount right after the u32 variant tag
+    pub fn transferred_lamports(&self) -> u64 {
+        const TRANSFER: u32 = 2;
+        const TRANSFER_WITH_SEED: u32 = 11;
+        if self.program_id != system_program::id() || self.data.len() < 12 {
+            return 0;
+        }
+        let tag = u32::from_le_bytes(self.data[..4].try_into().unwrap());
+        match tag {
+            TRANSFER | TRANSFER_WITH_SEED => {
+                u64::from_le_bytes(self.data[4..12].try_into().unwrap())
+            }
+            _ => 0,
+        }
+    }
+
+    // Whether this is a `ChangeThreshold`, `AddOwner`, `SetProgramConfig` or `SetFeeExempt` of
+    // `multisig`, the only instructions of the program itself a `Batch` may carry
+    pub fn is_config_change(&self, program_id: &Pubkey, multisig: &Pubkey) -> bool {
+        self.program_id == *program_id
+            && self
+                .accounts
+                .first()
+                .is_some_and(|account| account.pubkey == *multisig)
+            && matches!(
+                MultisigInstruction::decode(&self.data),
+                Ok(MultisigInstruction::ChangeThreshold { .. }
+                    | MultisigInstruction::AddOwner { .. }
+                    | MultisigInstruction::SetProgramConfig { .. }
+                    | MultisigInstruction::SetFeeExempt { .. })
+            )
+    }
+}
+
+impl From<Instruction> for ProposedInstruction {
+    fn from(instruction: Instruction) -> Self {
+        ProposedInstruction {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .into_iter()
+                .map(|meta| ProposedAccount {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: instruction.data,
+        }
+    }
+}
+
+impl From<&ProposedInstruction> for Instruction {
+    fn from(instruction: &ProposedInstruction) -> Self {
+        Instruction {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: account.pubkey,
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data: instruction.data.clone(),
+        }
+    }
+}
+
+impl ProposalAction {
+    // Lamports the action moves out of the vault, what the amount policy is checked against. Token
+    // amounts only count for wrapped SOL, a recurring payment counts with all its payments
+    pub fn lamports(&self) -> u64 {
+        match self {
+            ProposalAction::StakeWithdraw { lamports, .. }
+            | ProposalAction::VoteWithdraw { lamports, .. } => *lamports,
+            ProposalAction::TransferWrappedSol { amount, .. } => *amount,
+            ProposalAction::TransferToken { mint, amount, .. }
+                if *mint == spl_token::native_mint::id() =>
+            {
+                *amount
+            }
+            ProposalAction::CreateRecurringPayment { amount, count, .. } => {
+                amount.saturating_mul(*count as u64)
+            }
+            ProposalAction::CreateDistribution { total_cap, .. } => *total_cap,
+            // only plain lamport transfers are recognised inside a batch
+            ProposalAction::Batch { instructions } => instructions
+                .iter()
+                .map(ProposedInstruction::transferred_lamports)
+                .fold(0, u64::saturating_add),
+            _ => 0,
+        }
+    }
+
+    // The amount a transfer action moves (each payment of a recurring one) and whether it is in
+    // lamports rather than a token's base units, `None` for actions that aren't transfers
+    pub fn transfer_amount(&self) -> Option<(u64, bool)> {
+        match self {
+            ProposalAction::StakeWithdraw { lamports, .. }
+            | ProposalAction::VoteWithdraw { lamports, .. } => Some((*lamports, true)),
+            ProposalAction::TransferWrappedSol { amount, .. }
+            | ProposalAction::CreateRecurringPayment { amount, .. } => Some((*amount, true)),
+            ProposalAction::TransferToken { mint, amount, .. } => {
+                Some((*amount, *mint == spl_token::native_mint::id()))
+            }
+            _ => None,
+        }
+    }
+
+    // Where the action pays out to, `None` for actions without a single destination
+    pub fn destination(&self) -> Option<Pubkey> {
+        match self {
+            ProposalAction::TransferToken { destination, .. }
+            | ProposalAction::StakeWithdraw { destination, .. }
+            | ProposalAction::VoteWithdraw { destination, .. }
+            | ProposalAction::TransferWrappedSol { destination, .. }
+            | ProposalAction::CreateRecurringPayment { destination, .. } => Some(*destination),
+            _ => None,
+        }
+    }
+}
+
+// Instructions a stake, vote, wrapped SOL, upgrade authority or batch proposal runs with the vault PDA as
+// signer, `None` for other actions. Shared by the processor and `execute_transaction` so both see the same accounts
+pub(crate) fn vault_cpi_instructions(
+    action: &ProposalAction,
+    vault: &Pubkey,
+) -> Option<Vec<Instruction>> {
+    let instructions = match action {
+        ProposalAction::StakeDelegate {
+            stake: stake_account,
+            vote,
+        } => {
+            vec![stake::instruction::delegate_stake(
+                stake_account,
+                vault,
+                vote,
+            )]
+        }
+        ProposalAction::StakeDeactivate {
+            stake: stake_account,
+        } => {
+            vec![stake::instruction::deactivate_stake(stake_account, vault)]
+        }
+        ProposalAction::StakeSplit {
+            stake: stake_account,
+            split_stake,
+            lamports,
+        } => stake::instruction::split(stake_account, vault, *lamports, split_stake),
+        ProposalAction::StakeWithdraw {
+            stake: stake_account,
+            destination,
+            lamports,
+        } => vec![stake::instruction::withdraw(
+            stake_account,
+            vault,
+            destination,
+            *lamports,
+            None,
+        )],
+        ProposalAction::VoteAuthorize {
+            vote: vote_account,
+            new_authority,
+            authority,
+        } => {
+            let authority = match authority {
+                VoteAuthority::Voter => vote::state::VoteAuthorize::Voter,
+                VoteAuthority::Withdrawer => vote::state::VoteAuthorize::Withdrawer,
+            };
+            vec![vote::instruction::authorize(
+                vote_account,
+                vault,
+                new_authority,
+                authority,
+            )]
+        }
+        ProposalAction::VoteWithdraw {
+            vote: vote_account,
+            destination,
+            lamports,
+        } => vec![vote::instruction::withdraw(
+            vote_account,
+            vault,
+            *lamports,
+            destination,
+        )],
+        ProposalAction::SetUpgradeAuthority {
+            program,
+            new_authority,
+        } => vec![bpf_loader_upgradeable::set_upgrade_authority(
+            program,
+            vault,
+            new_authority.as_ref(),
+        )],
+        ProposalAction::TransferWrappedSol {
+            destination,
+            amount,
+        } => {
+            let native_mint = spl_token::native_mint::id();
+            let wrapped_sol_account = get_associated_token_address(vault, &native_mint);
+            vec![
+                create_associated_token_account_idempotent(
+                    vault,
+                    vault,
+                    &native_mint,
+                    &spl_token::id(),
+                ),
+                system_instruction::transfer(vault, &wrapped_sol_account, *amount),
+                spl_token::instruction::sync_native(&spl_token::id(), &wrapped_sol_account)
+                    .unwrap(),
+                spl_token::instruction::transfer_checked(
+                    &spl_token::id(),
+                    &wrapped_sol_account,
+                    &native_mint,
+                    destination,
+                    vault,
+                    &[],
+                    *amount,
+                    spl_token::native_mint::DECIMALS,
+                )
+                .unwrap(),
+                // unwraps whatever is left (the rent) back into the vault
+                spl_token::instruction::close_account(
+                    &spl_token::id(),
+                    &wrapped_sol_account,
+                    vault,
+                    vault,
+                    &[],
+                )
+                .unwrap(),
+            ]
+        }
+        ProposalAction::Batch { instructions } => {
+            instructions.iter().map(Instruction::from).collect()
+        }
+        _ => return None,
+    };
+    Some(instructions)
+}
+
+// Builds a `Create` instruction, `multisig` must already be allocated and owned by the program
+pub fn create(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    owners: Vec<Pubkey>,
+    threshold: u8,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::Create { owners, threshold }
+            .try_to_vec()
+            .unwrap(),
+        vec![AccountMeta::new(*multisig, false)],
+    )
+}
+
+// `Create` at the PDA of `creator` and `seed` (`find_derived_multisig_address`)
+pub fn create_derived(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    creator: &Pubkey,
+    seed: [u8; 32],
+    owners: Vec<Pubkey>,
+    threshold: u8,
+) -> Instruction {
+    let (multisig, _) = find_derived_multisig_address(creator, &seed, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::CreateDerived {
+            seed,
+            owners,
+            threshold,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*creator, true),
+            AccountMeta::new(multisig, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+// `depositor` pays `amount` lamports into the main vault of `multisig`
+pub fn deposit(
+    program_id: &Pubkey,
+    depositor: &Pubkey,
+    multisig: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let (vault, _) = find_vault_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::Deposit { amount }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*depositor, true),
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn sign(program_id: &Pubkey, owner: &Pubkey, multisig: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::Sign.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(*multisig, false),
+        ],
+    )
+}
+
+// `child` approves `multisig` as the owner `find_vault_address(child)`, spending its approvals,
+// at the request of `child_owner`, one of its owners
+pub fn sign_as_multisig(
+    program_id: &Pubkey,
+    child_owner: &Pubkey,
+    child: &Pubkey,
+    multisig: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SignAsMultisig.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*child_owner, true),
+            AccountMeta::new(*child, false),
+            AccountMeta::new(*multisig, false),
+        ],
+    )
+}
+
+// `sign` for a multisig in strict mode, with the instructions sysvar it checks the transaction's
+// signers against and the transaction's fee payer, named so that it is checked too
+pub fn sign_strict(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    multisig: &Pubkey,
+    fee_payer: &Pubkey,
+) -> Instruction {
+    let mut ix = sign(program_id, owner, multisig);
+    ix.accounts.extend([
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(*fee_payer, true),
+    ]);
+    ix
+}
+
+// `sign` in the packed encoding
+pub fn sign_packed(program_id: &Pubkey, owner: &Pubkey, multisig: &Pubkey) -> Instruction {
+    let mut ix = sign(program_id, owner, multisig);
+    ix.data = vec![PACKED_FORMAT_TAG, PACKED_SIGN];
+    ix
+}
+
+// `execute` in the packed encoding, 10 bytes of data instead of 41
+pub fn execute_packed(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    amount: u64,
+    destination: &Pubkey,
+) -> Instruction {
+    let mut ix = execute(program_id, multisig, amount, destination);
+    ix.data = vec![PACKED_FORMAT_TAG, PACKED_EXECUTE];
+    ix.data.extend_from_slice(&amount.to_le_bytes());
+    ix
+}
+
+pub fn execute(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    amount: u64,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::Execute {
+            amount,
+            destination: *destination,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(
+                find_destination_policy_address(multisig, program_id).0,
+                false,
+            ),
+        ],
+    )
+}
+
+// `execute` that also records a receipt for the destination, `index` is the destination's
+// current receipt count (`ReceiptLedger::count`, 0 before its first payment)
+pub fn execute_with_receipt(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    amount: u64,
+    destination: &Pubkey,
+    index: u64,
+) -> Instruction {
+    let mut ix = execute(program_id, multisig, amount, destination);
+    append_receipt_accounts(&mut ix, payer, multisig, destination, index);
+    ix
+}
+
+// Adds the multisig's policy signer (`Multisig::policy_signer`) to an `execute` or
+// `execute_from_vault` instruction, after the destination policy account and before any receipt
+// accounts
+pub fn with_policy_signer(mut ix: Instruction, policy_signer: &Pubkey) -> Instruction {
+    let (policy, _) = find_destination_policy_address(&ix.accounts[0].pubkey, &ix.program_id);
+    let index = ix
+        .accounts
+        .iter()
+        .position(|meta| meta.pubkey == policy)
+        .map_or(ix.accounts.len(), |index| index + 1);
+    ix.accounts
+        .insert(index, AccountMeta::new_readonly(*policy_signer, true));
+    ix
+}
+
+pub fn init_history(program_id: &Pubkey, payer: &Pubkey, multisig: &Pubkey) -> Instruction {
+    let (history, _) = find_history_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::InitHistory.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(history, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+// `ChangeThreshold` for `multisig`, the instruction a proposal's `Batch` carries
+pub fn change_threshold(program_id: &Pubkey, multisig: &Pubkey, threshold: u8) -> Instruction {
+    vault_governed(
+        program_id,
+        multisig,
+        MultisigInstruction::ChangeThreshold { threshold },
+    )
+}
+
+// `AddOwner` for `multisig`, the instruction a proposal's `Batch` carries
+pub fn add_owner(program_id: &Pubkey, multisig: &Pubkey, owner: &Pubkey) -> Instruction {
+    vault_governed(
+        program_id,
+        multisig,
+        MultisigInstruction::AddOwner { owner: *owner },
+    )
+}
+
+fn vault_governed(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    instruction: MultisigInstruction,
+) -> Instruction {
+    let (vault, _) = find_vault_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &instruction.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new_readonly(vault, true),
+        ],
+    )
+}
+
+// Proposes `instructions` of the program itself (`change_threshold`, `add_owner`) as a `Batch`,
+// executed from the main vault so they go through the same approvals as any other proposal
+pub fn propose_config_change(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    instructions: Vec<Instruction>,
+) -> Instruction {
+    let action = ProposalAction::Batch {
+        instructions: instructions.into_iter().map(Into::into).collect(),
+    };
+    propose(program_id, proposer, multisig, transaction, action)
+}
+
+pub fn propose_change_threshold(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    threshold: u8,
+) -> Instruction {
+    let change = change_threshold(program_id, multisig, threshold);
+    propose_config_change(program_id, proposer, multisig, transaction, vec![change])
+}
+
+pub fn propose_add_owner(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    let add = add_owner(program_id, multisig, owner);
+    propose_config_change(program_id, proposer, multisig, transaction, vec![add])
+}
+
+pub fn init_program_config(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    upgrade_authority: &Pubkey,
+    governance: &Pubkey,
+    fee_destination: &Pubkey,
+    protocol_fee_bps: u16,
+) -> Instruction {
+    let (programdata, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    let (config, _) = find_program_config_address(program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::InitProgramConfig {
+            governance: *governance,
+            fee_destination: *fee_destination,
+            protocol_fee_bps,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*upgrade_authority, true),
+            AccountMeta::new_readonly(programdata, false),
+            AccountMeta::new(config, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+// `SetProgramConfig` as the governance multisig's proposals carry it, signed by its main vault
+pub fn set_program_config(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    new_governance: &Pubkey,
+    fee_destination: &Pubkey,
+    protocol_fee_bps: u16,
+    paused: bool,
+) -> Instruction {
+    let (vault, _) = find_vault_address(governance, program_id);
+    let (config, _) = find_program_config_address(program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetProgramConfig {
+            governance: *new_governance,
+            fee_destination: *fee_destination,
+            protocol_fee_bps,
+            paused,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*governance, false),
+            AccountMeta::new_readonly(vault, true),
+            AccountMeta::new(config, false),
+        ],
+    )
+}
+
+// Proposes pausing or unpausing every wallet of the program, keeping the other settings
+pub fn propose_program_paused(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    transaction: &Pubkey,
+    config: &ProgramConfig,
+    paused: bool,
+) -> Instruction {
+    let set = set_program_config(
+        program_id,
+        &config.governance,
+        &config.governance,
+        &config.fee_destination,
+        config.protocol_fee_bps,
+        paused,
+    );
+    propose_config_change(
+        program_id,
+        proposer,
+        &config.governance,
+        transaction,
+        vec![set],
+    )
+}
+
+// `SetFeeExempt` of `multisig` as the governance multisig's proposals carry it, signed by its main
+// vault
+pub fn set_fee_exempt(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    multisig: &Pubkey,
+    exempt: bool,
+) -> Instruction {
+    let (vault, _) = find_vault_address(governance, program_id);
+    let (config, _) = find_program_config_address(program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetFeeExempt { exempt }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*governance, false),
+            AccountMeta::new_readonly(vault, true),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(*multisig, false),
+        ],
+    )
+}
+
+// Proposes exempting `multisig` from the protocol fee, or ending its exemption
+pub fn propose_fee_exempt(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    transaction: &Pubkey,
+    config: &ProgramConfig,
+    multisig: &Pubkey,
+    exempt: bool,
+) -> Instruction {
+    let set = set_fee_exempt(program_id, &config.governance, multisig, exempt);
+    propose_config_change(
+        program_id,
+        proposer,
+        &config.governance,
+        transaction,
+        vec![set],
+    )
+}
+
+// Passes the `ProgramConfig` along, so the instruction is refused while the program is paused.
+// Required on payouts by `program-config` builds only. Goes before `with_history`, the history
+// account stays last
+pub fn with_program_config(mut ix: Instruction) -> Instruction {
+    let (config, _) = find_program_config_address(&ix.program_id);
+    ix.accounts.push(AccountMeta::new_readonly(config, false));
+    ix
+}
+
+// `with_program_config` for `Execute` and `ExecuteFromVault` under a config charging a protocol
+// fee, which pays it to the config's `fee_destination` passed before the config
+pub fn with_protocol_fee(mut ix: Instruction, config: &ProgramConfig) -> Instruction {
+    ix.accounts
+        .push(AccountMeta::new(config.fee_destination, false));
+    with_program_config(ix)
+}
+
+// Appends the `History` account of `multisig` to any instruction of the program, which records
+// itself there once it succeeded
+pub fn with_history(mut ix: Instruction, multisig: &Pubkey) -> Instruction {
+    let (history, _) = find_history_address(multisig, &ix.program_id);
+    ix.accounts.push(AccountMeta::new(history, false));
+    ix
+}
+
+fn append_receipt_accounts(
+    ix: &mut Instruction,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    destination: &Pubkey,
+    index: u64,
+) {
+    let (ledger, _) = find_receipt_ledger_address(multisig, destination, &ix.program_id);
+    let (receipt, _) = find_receipt_address(multisig, destination, index, &ix.program_id);
+    ix.accounts.extend([
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(ledger, false),
+        AccountMeta::new(receipt, false),
+    ]);
+}
+
+pub fn set_destination_policy(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    mode: DestinationMode,
+    destinations: Vec<Pubkey>,
+) -> Instruction {
+    destination_policy_instruction(
+        program_id,
+        payer,
+        multisig,
+        MultisigInstruction::SetDestinationPolicy { mode, destinations },
+    )
+}
+
+pub fn add_destination(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    destination_policy_instruction(
+        program_id,
+        payer,
+        multisig,
+        MultisigInstruction::AddDestination {
+            destination: *destination,
+        },
+    )
+}
+
+pub fn remove_destination(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    destination_policy_instruction(
+        program_id,
+        payer,
+        multisig,
+        MultisigInstruction::RemoveDestination {
+            destination: *destination,
+        },
+    )
+}
+
+pub fn set_destination_policy_enforcement(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    enforcement: PolicyEnforcement,
+) -> Instruction {
+    destination_policy_instruction(
+        program_id,
+        payer,
+        multisig,
+        MultisigInstruction::SetDestinationPolicyEnforcement { enforcement },
+    )
+}
+
+fn destination_policy_instruction(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    instruction: MultisigInstruction,
+) -> Instruction {
+    let (policy_key, _) = find_destination_policy_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &instruction.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(policy_key, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+// `pending_transactions`: open proposals of this multisig on which `old_owner`'s approval must
+// not carry over to the new key
+pub fn swap_owner(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    old_owner: &Pubkey,
+    new_owner: &Pubkey,
+    pending_transactions: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(*multisig, false)];
+    accounts.extend(
+        pending_transactions
+            .iter()
+            .map(|transaction| AccountMeta::new(*transaction, false)),
+    );
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SwapOwner {
+            old_owner: *old_owner,
+            new_owner: *new_owner,
+        }
+        .try_to_vec()
+        .unwrap(),
+        accounts,
+    )
+}
+
+pub fn set_veto_authority(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    veto_authority: Option<Pubkey>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetVetoAuthority { veto_authority }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+// `authority` is the emergency authority, or any fee payer when owners approved
+pub fn set_paused(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    multisig: &Pubkey,
+    paused: bool,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetPaused { paused }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+pub fn set_emergency_authority(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    emergency_authority: Option<Pubkey>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetEmergencyAuthority {
+            emergency_authority,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn set_policy(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    policy: Option<AmountPolicy>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetPolicy { policy }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn set_approval_ttl(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    ttl_seconds: Option<i64>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetApprovalTtl { ttl_seconds }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn set_outflow_cap(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    cap: Option<OutflowCap>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetOutflowCap { cap }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn set_transfer_minimum(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    allow_zero_amount: bool,
+    min_transfer: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetTransferMinimum {
+            allow_zero_amount,
+            min_transfer,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn set_strict_signing(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    strict: bool,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetStrictSigning { strict }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn set_policy_signer(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    policy_signer: Option<Pubkey>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetPolicySigner { policy_signer }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn set_display_unit(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    mint: &Pubkey,
+    decimals: u8,
+    symbol: &str,
+) -> Instruction {
+    let (display_unit, _) = find_display_unit_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetDisplayUnit {
+            mint: *mint,
+            decimals,
+            symbol: symbol.to_string(),
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(display_unit, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn veto_transaction(
+    program_id: &Pubkey,
+    veto_authority: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    proposer: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::VetoTransaction.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*veto_authority, true),
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(*transaction, false),
+            AccountMeta::new(*proposer, false),
+        ],
+    )
+}
+
+pub fn close_transaction(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    proposer: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::CloseTransaction.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(*transaction, false),
+            AccountMeta::new(*proposer, false),
+        ],
+    )
+}
+
+pub fn init_guardians(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    guardians: Vec<Pubkey>,
+    recovery_delay: i64,
+) -> Instruction {
+    let (guardians_key, _) = find_guardians_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::InitGuardians {
+            guardians,
+            recovery_delay,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*multisig, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(guardians_key, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn init_aggregate_key(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    aggregate_key: &Pubkey,
+) -> Instruction {
+    let (aggregate_key_address, _) = find_aggregate_key_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::InitAggregateKey {
+            aggregate_key: *aggregate_key,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*multisig, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(aggregate_key_address, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+// Ed25519 program instruction checking one signature, with the key, signature and message all
+// stored in the instruction itself
+pub fn ed25519_verify(pubkey: &Pubkey, signature: &[u8; 64], message: &[u8]) -> Instruction {
+    // 2 byte header, 7 u16 offsets, then the key, signature and message
+    const PUBKEY_OFFSET: u16 = 2 + 14;
+    const SIGNATURE_OFFSET: u16 = PUBKEY_OFFSET + 32;
+    const MESSAGE_OFFSET: u16 = SIGNATURE_OFFSET + 64;
+    let offsets = [
+        SIGNATURE_OFFSET,
+        u16::MAX,
+        PUBKEY_OFFSET,
+        u16::MAX,
+        MESSAGE_OFFSET,
+        message.len() as u16,
+        u16::MAX,
+    ];
+    let mut data = vec![1, 0];
+    for offset in offsets {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    Instruction::new_with_bytes(ed25519_program::id(), &data, vec![])
+}
+
+// The signature check followed by `ApproveAggregated`, in this order in one transaction.
+// `signature` is the group key's signature over `aggregated_approval_message`
+pub fn approve_aggregated(
+    program_id: &Pubkey,
+    aggregate_key: &Pubkey,
+    signature: &[u8; 64],
+    transaction_key: &Pubkey,
+    transaction: &Transaction,
+) -> Vec<Instruction> {
+    let (aggregate_key_address, _) = find_aggregate_key_address(&transaction.multisig, program_id);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(transaction.multisig, false),
+        AccountMeta::new(*transaction_key, false),
+        AccountMeta::new_readonly(aggregate_key_address, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+    match &transaction.action {
+        ProposalAction::DeployProgram { buffer, .. }
+        | ProposalAction::UpgradeProgram { buffer, .. } => {
+            accounts.push(AccountMeta::new_readonly(*buffer, false));
+        }
+        _ => {}
+    }
+    let message = aggregated_approval_message(transaction_key, transaction);
+    vec![
+        ed25519_verify(aggregate_key, signature, message.as_ref()),
+        Instruction::new_with_bytes(
+            *program_id,
+            &MultisigInstruction::ApproveAggregated.try_to_vec().unwrap(),
+            accounts,
+        ),
+    ]
+}
+
+pub fn propose_recovery(
+    program_id: &Pubkey,
+    guardian: &Pubkey,
+    multisig: &Pubkey,
+    new_owners: Vec<Pubkey>,
+    new_threshold: u8,
+) -> Instruction {
+    let (guardians_key, _) = find_guardians_address(multisig, program_id);
+    let (recovery_key, _) = find_recovery_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::ProposeRecovery {
+            new_owners,
+            new_threshold,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*guardian, true),
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new_readonly(guardians_key, false),
+            AccountMeta::new(recovery_key, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn approve_recovery(program_id: &Pubkey, guardian: &Pubkey, multisig: &Pubkey) -> Instruction {
+    let (guardians_key, _) = find_guardians_address(multisig, program_id);
+    let (recovery_key, _) = find_recovery_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::ApproveRecovery.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*guardian, true),
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new_readonly(guardians_key, false),
+            AccountMeta::new(recovery_key, false),
+        ],
+    )
+}
+
+// `proposer` is the guardian who opened the recovery, it gets the rent back
+pub fn veto_recovery(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    multisig: &Pubkey,
+    proposer: &Pubkey,
+) -> Instruction {
+    let (recovery_key, _) = find_recovery_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::VetoRecovery.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(recovery_key, false),
+            AccountMeta::new(*proposer, false),
+        ],
+    )
+}
+
+pub fn execute_recovery(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    proposer: &Pubkey,
+    pending_transactions: &[Pubkey],
+) -> Instruction {
+    let (guardians_key, _) = find_guardians_address(multisig, program_id);
+    let (recovery_key, _) = find_recovery_address(multisig, program_id);
+    let mut accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*multisig, false),
+        AccountMeta::new_readonly(guardians_key, false),
+        AccountMeta::new(recovery_key, false),
+        AccountMeta::new(*proposer, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(
+        pending_transactions
+            .iter()
+            .map(|transaction| AccountMeta::new(*transaction, false)),
+    );
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::ExecuteRecovery.try_to_vec().unwrap(),
+        accounts,
+    )
+}
+
+// Builds a `Propose` instruction, `transaction` must be an empty account owned by the program.
+// The action draws from the main vault
+pub fn propose(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    action: ProposalAction,
+) -> Instruction {
+    propose_from_vault(program_id, proposer, multisig, transaction, 0, action)
+}
+
+// `propose` with the action drawing from vault `vault` (`find_indexed_vault_address`)
+pub fn propose_from_vault(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    vault: u8,
+    action: ProposalAction,
+) -> Instruction {
+    let options = ProposeOptions {
+        vault,
+        ..ProposeOptions::default()
+    };
+    propose_with_options(program_id, proposer, multisig, transaction, action, options)
+}
+
+// Optional settings of a proposal, the default draws from the main vault, never expires and
+// needs no approval in particular
+#[derive(Debug, Clone, Default)]
+pub struct ProposeOptions {
+    pub vault: u8,
+    // the proposal can't be approved or executed after this unix timestamp
+    pub expires_at: Option<i64>,
+    // owners who must be among the approvers
+    pub required_signers: Vec<Pubkey>,
+    // lamports paid to whoever executes the proposal through `CrankTransaction`
+    pub executor_fee: u64,
+    // hash of the document the proposal is about, e.g. the SHA-256 of an invoice PDF
+    pub attachment: Option<[u8; 32]>,
+}
+
+pub fn propose_with_options(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    action: ProposalAction,
+    options: ProposeOptions,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*proposer, true),
+        AccountMeta::new_readonly(*multisig, false),
+        AccountMeta::new(*transaction, false),
+    ];
+    push_buffer(&mut accounts, &action);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::Propose {
+            action,
+            vault: options.vault,
+            expires_at: options.expires_at,
+            required_signers: options.required_signers,
+            executor_fee: options.executor_fee,
+            attachment: options.attachment,
+        }
+        .try_to_vec()
+        .unwrap(),
+        accounts,
+    )
+}
+
+// `propose_with_options` into the proposal PDA number `index` of the multisig, which must be its
+// current `transaction_count`
+pub fn propose_indexed(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    index: u64,
+    action: ProposalAction,
+    options: ProposeOptions,
+) -> Instruction {
+    let (transaction, _) = find_transaction_address(multisig, index, program_id);
+    let mut accounts = vec![
+        AccountMeta::new(*proposer, true),
+        AccountMeta::new(*multisig, false),
+        AccountMeta::new(transaction, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    push_buffer(&mut accounts, &action);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::ProposeIndexed {
+            action,
+            vault: options.vault,
+            expires_at: options.expires_at,
+            required_signers: options.required_signers,
+            executor_fee: options.executor_fee,
+            attachment: options.attachment,
+        }
+        .try_to_vec()
+        .unwrap(),
+        accounts,
+    )
+}
+
+// the buffer a deploy or upgrade proposal is checked against
+fn push_buffer(accounts: &mut Vec<AccountMeta>, action: &ProposalAction) {
+    match action {
+        ProposalAction::DeployProgram { buffer, .. }
+        | ProposalAction::UpgradeProgram { buffer, .. } => {
+            accounts.push(AccountMeta::new_readonly(*buffer, false));
+        }
+        _ => {}
+    }
+}
+
+// `propose_with_options` that also flags the inboxes of `notify` (owners who registered one)
+pub fn propose_and_notify(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    action: ProposalAction,
+    options: ProposeOptions,
+    notify: &[Pubkey],
+) -> Instruction {
+    let mut ix = propose_with_options(program_id, proposer, multisig, transaction, action, options);
+    ix.accounts.extend(notify.iter().map(|owner| {
+        let (inbox, _) = find_inbox_address(owner, program_id);
+        AccountMeta::new(inbox, false)
+    }));
+    ix
+}
+
+pub fn register_inbox(program_id: &Pubkey, owner: &Pubkey) -> Instruction {
+    let (inbox, _) = find_inbox_address(owner, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::RegisterInbox.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(inbox, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn clear_inbox(program_id: &Pubkey, owner: &Pubkey) -> Instruction {
+    let (inbox, _) = find_inbox_address(owner, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::ClearInbox.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(inbox, false),
+        ],
+    )
+}
+
+// `owners` sign together, see `ExtendProposal` for who may
+pub fn extend_proposal(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    owners: &[Pubkey],
+    seconds: i64,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*multisig, false),
+        AccountMeta::new(*transaction, false),
+    ];
+    accounts.extend(
+        owners
+            .iter()
+            .map(|owner| AccountMeta::new_readonly(*owner, true)),
+    );
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::ExtendProposal { seconds }
+            .try_to_vec()
+            .unwrap(),
+        accounts,
+    )
+}
+
+// Builds an `Approve` instruction, deploy/upgrade proposals also pass the buffer so its hash
+// can be checked again
+// Proposes upgrading `program` (upgrade authority must be the vault) from `buffer`, the buffer
+// authority must be the vault as well and `buffer_hash` must match its current content
+pub fn propose_upgrade(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    program: &Pubkey,
+    buffer: &Pubkey,
+    buffer_hash: [u8; 32],
+) -> Instruction {
+    let action = ProposalAction::UpgradeProgram {
+        program: *program,
+        buffer: *buffer,
+        buffer_hash,
+    };
+    propose(program_id, proposer, multisig, transaction, action)
+}
+
+pub fn propose_set_upgrade_authority(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    program: &Pubkey,
+    new_authority: Option<&Pubkey>,
+) -> Instruction {
+    let action = ProposalAction::SetUpgradeAuthority {
+        program: *program,
+        new_authority: new_authority.copied(),
+    };
+    propose(program_id, proposer, multisig, transaction, action)
+}
+
+pub fn propose_swap_owner(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    old_owner: &Pubkey,
+    new_owner: &Pubkey,
+) -> Instruction {
+    let action = ProposalAction::SwapOwner {
+        old_owner: *old_owner,
+        new_owner: *new_owner,
+    };
+    propose(program_id, proposer, multisig, transaction, action)
+}
+
+pub fn propose_vote_authorize(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    vote: &Pubkey,
+    new_authority: &Pubkey,
+    authority: VoteAuthority,
+) -> Instruction {
+    let action = ProposalAction::VoteAuthorize {
+        vote: *vote,
+        new_authority: *new_authority,
+        authority,
+    };
+    propose(program_id, proposer, multisig, transaction, action)
+}
+
+pub fn propose_vote_withdraw(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    vote: &Pubkey,
+    destination: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    let action = ProposalAction::VoteWithdraw {
+        vote: *vote,
+        destination: *destination,
+        lamports,
+    };
+    propose(program_id, proposer, multisig, transaction, action)
+}
+
+// Proposes moving the NFT `mint` out of the vault's associated token account to `destination_owner`
+// as a one-instruction `Batch`, the vault signs and pays the destination accounts. Programmable
+// NFTs (`programmable`) pass their token records and `authorization_rules`
+#[allow(clippy::too_many_arguments)]
+pub fn propose_nft_transfer(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    mint: &Pubkey,
+    destination_owner: &Pubkey,
+    programmable: bool,
+    authorization_rules: Option<&Pubkey>,
+) -> Instruction {
+    let (vault, _) = find_vault_address(multisig, program_id);
+    let transfer = token_metadata::transfer(
+        mint,
+        &vault,
+        destination_owner,
+        &vault,
+        programmable,
+        authorization_rules,
+    );
+    let action = ProposalAction::Batch {
+        instructions: vec![transfer.into()],
+    };
+    propose(program_id, proposer, multisig, transaction, action)
+}
+
+// Proposes revoking the `role` token delegate `delegate` of the NFT `mint` held by the vault
+#[allow(clippy::too_many_arguments)]
+pub fn propose_nft_revoke(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    mint: &Pubkey,
+    delegate: &Pubkey,
+    role: TokenDelegateRole,
+    authorization_rules: Option<&Pubkey>,
+) -> Instruction {
+    let (vault, _) = find_vault_address(multisig, program_id);
+    let revoke = token_metadata::revoke(mint, &vault, delegate, &vault, role, authorization_rules);
+    let action = ProposalAction::Batch {
+        instructions: vec![revoke.into()],
+    };
+    propose(program_id, proposer, multisig, transaction, action)
+}
+
+pub fn approve(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    action: &ProposalAction,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(*multisig, false),
+        AccountMeta::new(*transaction, false),
+    ];
+    match action {
+        ProposalAction::DeployProgram { buffer, .. }
+        | ProposalAction::UpgradeProgram { buffer, .. } => {
+            accounts.push(AccountMeta::new_readonly(*buffer, false));
+        }
+        _ => {}
+    }
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::Approve.try_to_vec().unwrap(),
+        accounts,
+    )
+}
+
+// Builds an `ExecuteTransaction` instruction with the accounts `action` needs. Accounts required by
+// a Token-2022 transfer hook must be appended by the caller, they are passed through to the CPI;
+// so must the pending proposals of an owner swap
+pub fn execute_transaction(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    action: &ProposalAction,
+) -> Instruction {
+    execute_transaction_from_vault(program_id, multisig, transaction, 0, action)
+}
+
+// `execute_transaction` for a proposal drawing from vault `vault` (`Transaction::vault`)
+pub fn execute_transaction_from_vault(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    vault: u8,
+    action: &ProposalAction,
+) -> Instruction {
+    let (vault, _) = find_indexed_vault_address(multisig, vault, program_id);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*multisig, false),
+        AccountMeta::new(*transaction, false),
+        AccountMeta::new(vault, false),
+    ];
+    // payouts count against the multisig's outflow cap
+    accounts[0].is_writable = action.lamports() > 0;
+    match action {
+        ProposalAction::DeployProgram {
+            program, buffer, ..
+        } => {
+            let (programdata, _) =
+                Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::id());
+            accounts.extend([
+                // the program keypair signs, the account is created during execution
+                AccountMeta::new(*program, true),
+                AccountMeta::new(programdata, false),
+                AccountMeta::new(*buffer, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(bpf_loader_upgradeable::id(), false),
+            ]);
+        }
+        ProposalAction::UpgradeProgram {
+            program, buffer, ..
+        } => {
+            let (programdata, _) =
+                Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::id());
+            accounts.extend([
+                AccountMeta::new(*program, false),
+                AccountMeta::new(programdata, false),
+                AccountMeta::new(*buffer, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(bpf_loader_upgradeable::id(), false),
+            ]);
+        }
+        ProposalAction::TransferToken {
+            token_program,
+            mint,
+            destination,
+            ..
+        } => {
+            let source = get_associated_token_address_with_program_id(&vault, mint, token_program);
+            accounts.extend([
+                AccountMeta::new(source, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(*destination, false),
+                AccountMeta::new_readonly(*token_program, false),
+            ]);
+        }
+        ProposalAction::CreateRecurringPayment { .. } => {
+            let (recurring, _) = find_recurring_payment_address(transaction, program_id);
+            accounts.extend([
+                AccountMeta::new(recurring, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ]);
+        }
+        ProposalAction::CreateDistribution { .. } => {
+            let (distribution, _) = find_distribution_address(transaction, program_id);
+            accounts.extend([
+                AccountMeta::new(distribution, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ]);
+        }
+        ProposalAction::SwapOwner { .. } => accounts[0].is_writable = true,
+        _ => {
+            // the CPI accounts (and their programs) with the vault left as the PDA signer
+            for cpi_instruction in vault_cpi_instructions(action, &vault).unwrap_or_default() {
+                let metas =
+                    std::iter::once(AccountMeta::new_readonly(cpi_instruction.program_id, false))
+                        .chain(cpi_instruction.accounts);
+                for meta in metas.filter(|meta| meta.pubkey != vault) {
+                    match accounts
+                        .iter_mut()
+                        .find(|account| account.pubkey == meta.pubkey)
+                    {
+                        Some(account) => {
+                            account.is_signer |= meta.is_signer;
+                            account.is_writable |= meta.is_writable;
+                        }
+                        None => accounts.push(meta),
+                    }
+                }
+            }
+        }
+    }
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::ExecuteTransaction
+            .try_to_vec()
+            .unwrap(),
+        accounts,
+    )
+}
+
+// Cranks the recurring payment started by the executed proposal `transaction`, `vault` is the
+// proposal's vault
+pub fn execute_recurring(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    vault: u8,
+    destination: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_indexed_vault_address(multisig, vault, program_id);
+    let (recurring, _) = find_recurring_payment_address(transaction, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::ExecuteRecurring.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(recurring, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn execute_recurring_with_receipt(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    vault: u8,
+    destination: &Pubkey,
+    index: u64,
+) -> Instruction {
+    let mut ix = execute_recurring(program_id, multisig, transaction, vault, destination);
+    append_receipt_accounts(&mut ix, payer, multisig, destination, index);
+    ix
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn claim_distribution(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    vault: u8,
+    recipient: &Pubkey,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let (distribution, _) = find_distribution_address(transaction, program_id);
+    let (claim, _) = find_claim_address(&distribution, recipient, program_id);
+    let (vault, _) = find_indexed_vault_address(multisig, vault, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::ClaimDistribution { amount, proof }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(distribution, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new(claim, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+// `lookup_table` is at `lookup_table::find_lookup_table_address(&vault, recent_slot)`, vault 0
+pub fn create_lookup_table(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    recent_slot: u64,
+) -> Instruction {
+    let (vault, _) = find_vault_address(multisig, program_id);
+    let (lookup_table, _) = find_lookup_table_address(&vault, recent_slot);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::CreateLookupTable { recent_slot }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(vault, false),
+            AccountMeta::new(lookup_table, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(lookup_table::ID, false),
+        ],
+    )
+}
+
+pub fn extend_lookup_table(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    lookup_table: &Pubkey,
+    addresses: Vec<Pubkey>,
+) -> Instruction {
+    let (vault, _) = find_vault_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::ExtendLookupTable { addresses }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(vault, false),
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(lookup_table::ID, false),
+        ],
+    )
+}
+
+pub fn deactivate_lookup_table(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    lookup_table: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_vault_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::DeactivateLookupTable
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new_readonly(vault, false),
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(lookup_table::ID, false),
+        ],
+    )
+}
+
+pub fn close_lookup_table(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    lookup_table: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_vault_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::CloseLookupTable.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(lookup_table::ID, false),
+        ],
+    )
+}
+
+pub fn check_approved(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    context: ThresholdContext,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::CheckApproved { context }
+            .try_to_vec()
+            .unwrap(),
+        vec![AccountMeta::new_readonly(*multisig, false)],
+    )
+}
+
+// `execute` paid by the main vault
+pub fn execute_from_vault(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    amount: u64,
+    destination: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_vault_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::ExecuteFromVault {
+            amount,
+            destination: *destination,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(
+                find_destination_policy_address(multisig, program_id).0,
+                false,
+            ),
+        ],
+    )
+}
+
+// `execute` or `execute_from_vault`, whichever pays the way `strategy` says
+pub fn execute_with_strategy(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    amount: u64,
+    destination: &Pubkey,
+    strategy: ExecutionStrategy,
+) -> Instruction {
+    match strategy {
+        ExecutionStrategy::DirectLamports => execute(program_id, multisig, amount, destination),
+        ExecutionStrategy::SystemTransfer => {
+            execute_from_vault(program_id, multisig, amount, destination)
+        }
+    }
+}
+
+pub fn migrate_to_vault(program_id: &Pubkey, multisig: &Pubkey) -> Instruction {
+    let (vault, _) = find_vault_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::MigrateToVault.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(vault, false),
+        ],
+    )
+}
+
+// `execute_transaction_from_vault` by `executor`, who collects the proposal's executor fee
+pub fn crank_transaction(
+    program_id: &Pubkey,
+    executor: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    vault: u8,
+    action: &ProposalAction,
+) -> Instruction {
+    let execute = execute_transaction_from_vault(program_id, multisig, transaction, vault, action);
+    let mut accounts = vec![
+        AccountMeta::new(*executor, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(execute.accounts);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::CrankTransaction.try_to_vec().unwrap(),
+        accounts,
+    )
+}
+
+// Owner indexes of `owners` (those who registered one), to append to `create`, `swap_owner` or
+// the `execute_transaction` of a `SwapOwner` proposal
+pub fn owner_index_accounts(program_id: &Pubkey, owners: &[Pubkey]) -> Vec<AccountMeta> {
+    owners
+        .iter()
+        .map(|owner| AccountMeta::new(find_owner_index_address(owner, program_id).0, false))
+        .collect()
+}
+
+// `multisigs` the owner already belongs to are listed right away
+pub fn register_owner_index(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    multisigs: &[Pubkey],
+) -> Instruction {
+    let (index, _) = find_owner_index_address(owner, program_id);
+    let mut accounts = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(index, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(
+        multisigs
+            .iter()
+            .map(|multisig| AccountMeta::new_readonly(*multisig, false)),
+    );
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::RegisterOwnerIndex
+            .try_to_vec()
+            .unwrap(),
+        accounts,
+    )
+}
+
+pub fn sync_owner_index(program_id: &Pubkey, owner: &Pubkey, multisig: &Pubkey) -> Instruction {
+    let (index, _) = find_owner_index_address(owner, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SyncOwnerIndex.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(index, false),
+            AccountMeta::new_readonly(*multisig, false),
+        ],
+    )
+}
+
+pub fn unlist_multisig(program_id: &Pubkey, owner: &Pubkey, multisig: &Pubkey) -> Instruction {
+    let (index, _) = find_owner_index_address(owner, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::UnlistMultisig {
+            multisig: *multisig,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(index, false),
+        ],
+    )
+}
+
+pub fn set_metadata(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    name: &str,
+    uri: &str,
+) -> Instruction {
+    let (metadata, _) = find_metadata_address(multisig, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetMetadata {
+            name: name.to_string(),
+            uri: uri.to_string(),
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(metadata, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+// The owner's signature check followed by `ApproveWithSignature`, in this order in one
+// transaction. `signature` is the owner's signature over `Transaction::digest`; whoever sends
+// the transaction pays for it
+pub fn approve_with_signature(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    signature: &[u8; 64],
+    transaction_key: &Pubkey,
+    transaction: &Transaction,
+) -> Vec<Instruction> {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(transaction.multisig, false),
+        AccountMeta::new(*transaction_key, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+    match &transaction.action {
+        ProposalAction::DeployProgram { buffer, .. }
+        | ProposalAction::UpgradeProgram { buffer, .. } => {
+            accounts.push(AccountMeta::new_readonly(*buffer, false));
+        }
+        _ => {}
+    }
+    let digest = transaction.digest(program_id, transaction_key);
+    vec![
+        ed25519_verify(owner, signature, digest.as_ref()),
+        Instruction::new_with_bytes(
+            *program_id,
+            &MultisigInstruction::ApproveWithSignature { owner: *owner }
+                .try_to_vec()
+                .unwrap(),
+            accounts,
+        ),
+    ]
+}
+
+pub fn set_delegate(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    multisig: &Pubkey,
+    delegate: &Pubkey,
+    expires_at: i64,
+    permissions: u8,
+) -> Instruction {
+    let (record, _) = find_delegate_address(multisig, owner, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SetDelegate {
+            delegate: *delegate,
+            expires_at,
+            permissions,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(record, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn revoke_delegate(program_id: &Pubkey, owner: &Pubkey, multisig: &Pubkey) -> Instruction {
+    let (record, _) = find_delegate_address(multisig, owner, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::RevokeDelegate.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(record, false),
+        ],
+    )
+}
+
+// `owner` is the owner the delegate signs for
+pub fn sign_as_delegate(
+    program_id: &Pubkey,
+    delegate: &Pubkey,
+    owner: &Pubkey,
+    multisig: &Pubkey,
+) -> Instruction {
+    let (record, _) = find_delegate_address(multisig, owner, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::SignAsDelegate.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*delegate, true),
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new_readonly(record, false),
+        ],
+    )
+}
+
+pub fn approve_as_delegate(
+    program_id: &Pubkey,
+    delegate: &Pubkey,
+    owner: &Pubkey,
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    action: &ProposalAction,
+) -> Instruction {
+    let (record, _) = find_delegate_address(multisig, owner, program_id);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*delegate, true),
+        AccountMeta::new_readonly(*multisig, false),
+        AccountMeta::new(*transaction, false),
+        AccountMeta::new_readonly(record, false),
+    ];
+    match action {
+        ProposalAction::DeployProgram { buffer, .. }
+        | ProposalAction::UpgradeProgram { buffer, .. } => {
+            accounts.push(AccountMeta::new_readonly(*buffer, false));
+        }
+        _ => {}
+    }
+    Instruction::new_with_bytes(
+        *program_id,
+        &MultisigInstruction::ApproveAsDelegate.try_to_vec().unwrap(),
+        accounts,
+    )
+}
@@ -0,0 +1,284 @@
+// Byte layouts of the accounts and the instruction encoding for clients in other languages (the
+// web client decodes accounts from these instead of mirroring the Rust types by hand). Sizes come
+// from the program's own constants, `tests/test_layouts.rs` reads every field back out of real
+// encodings and fails whenever the files in `layouts/` drift from the current code.
+//
+// Regenerate with `cargo run --example layouts [-- <dir>]`.
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+use crate::error::MultisigError;
+use crate::instruction::{
+    MultisigInstruction, MAX_INSTRUCTION_TAG, PACKED_EXECUTE, PACKED_FORMAT_TAG, PACKED_SIGN,
+    RESERVED_TAGS,
+};
+use crate::state::{Multisig, Transaction, MAX_OWNERS};
+
+// Where a field's offset counts from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base {
+    Start,
+    // the end of the proposal's variable-size action
+    ActionEnd,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: &'static str,
+    // "u8", "u32", "u64", "i64", "bool", "pubkey", fixed arrays "[T; N]" and fixed-size options
+    // "option<T>" (a tag byte, then T or zeros); "ProposalAction" is Borsh and variable-size
+    pub ty: String,
+    pub base: Base,
+    pub offset: usize,
+    pub len: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountLayout {
+    pub name: &'static str,
+    // size of every account of the kind, for proposals the size without the action
+    pub len: usize,
+    pub fields: Vec<Field>,
+}
+
+struct Fields {
+    fields: Vec<Field>,
+    base: Base,
+    offset: usize,
+}
+
+impl Fields {
+    fn new() -> Self {
+        Fields {
+            fields: Vec::new(),
+            base: Base::Start,
+            offset: 0,
+        }
+    }
+
+    fn add(mut self, name: &'static str, ty: impl Into<String>, len: usize) -> Self {
+        self.fields.push(Field {
+            name,
+            ty: ty.into(),
+            base: self.base,
+            offset: self.offset,
+            len,
+        });
+        self.offset += len;
+        self
+    }
+
+    fn array(self, name: &'static str, ty: &str, item_len: usize) -> Self {
+        self.add(name, format!("[{ty}; {MAX_OWNERS}]"), item_len * MAX_OWNERS)
+    }
+
+    // the variable-size action, the following offsets count from its end
+    fn action(mut self) -> Self {
+        self = self.add("action", "ProposalAction", 0);
+        self.base = Base::ActionEnd;
+        self.offset = 0;
+        self
+    }
+}
+
+pub fn accounts() -> Vec<AccountLayout> {
+    let multisig = Fields::new()
+        .add("owners_len", "u8", 1)
+        .array("owners", "pubkey", 32)
+        .add("threshold", "u8", 1)
+        .array("signers", "bool", 1)
+        .add("veto_authority", "option<pubkey>", 33)
+        .add("paused", "bool", 1)
+        .add("emergency_authority", "option<pubkey>", 33)
+        .add("amount_policy", "option<AmountPolicy>", 10)
+        .add("approval_ttl_seconds", "option<i64>", 9)
+        .array("signed_at", "i64", 8)
+        .add("owner_set_seqno", "u32", 4)
+        .add("outflow_cap", "option<OutflowCap>", 17)
+        .add("spent_this_period", "u64", 8)
+        .add("period_start", "i64", 8)
+        .add("policy_signer", "option<pubkey>", 33)
+        .add("transaction_count", "u64", 8)
+        .add("fee_exempt", "bool", 1)
+        .add("allow_zero_amount", "bool", 1)
+        .add("min_transfer", "u64", 8)
+        .add("strict_signing", "bool", 1)
+        .add("delegate_signers", "u16", 2);
+    let transaction = Fields::new()
+        .add("multisig", "pubkey", 32)
+        .add("proposer", "pubkey", 32)
+        .action()
+        .add("signers_len", "u8", 1)
+        .array("signers", "bool", 1)
+        .add("executed", "bool", 1)
+        .add("vault", "u8", 1)
+        .add("expires_at", "option<i64>", 9)
+        .add("extended", "bool", 1)
+        .array("required_signers", "bool", 1)
+        .array("approved_at", "i64", 8)
+        .add("executor_fee", "u64", 8)
+        .add("owner_set_seqno", "u32", 4)
+        .add("executed_at", "i64", 8)
+        .add("executed_slot", "u64", 8)
+        .add("executed_by", "option<pubkey>", 33)
+        .add("attachment", "option<[u8; 32]>", 33);
+    vec![
+        AccountLayout {
+            name: "Multisig",
+            len: Multisig::LEN,
+            fields: multisig.fields,
+        },
+        AccountLayout {
+            name: "Transaction",
+            len: Transaction::FIXED_LEN,
+            fields: transaction.fields,
+        },
+    ]
+}
+
+// `layouts.json`: account layouts, instruction tags and error codes
+pub fn json() -> String {
+    let mut out = String::from("{\n  \"accounts\": [\n");
+    let accounts = accounts();
+    for (i, account) in accounts.iter().enumerate() {
+        let fields: Vec<String> = account
+            .fields
+            .iter()
+            .map(|field| {
+                format!(
+                    "{{\"name\": \"{}\", \"type\": \"{}\", \"from\": \"{}\", \"offset\": {}, \"len\": {}}}",
+                    field.name,
+                    field.ty,
+                    base_name(field.base),
+                    field.offset,
+                    field.len
+                )
+            })
+            .collect();
+        writeln!(
+            out,
+            "    {{\"name\": \"{}\", \"len\": {}, \"fields\": [\n      {}\n    ]}}{}",
+            account.name,
+            account.len,
+            fields.join(",\n      "),
+            separator(i, accounts.len())
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "  ],\n  \"instructions\": {{\"max_tag\": {MAX_INSTRUCTION_TAG}, \"reserved_tags\": [{}, {}], \"packed_format_tag\": {PACKED_FORMAT_TAG}, \"packed\": {{\"Sign\": {PACKED_SIGN}, \"Execute\": {PACKED_EXECUTE}}}, \"tags\": [",
+        RESERVED_TAGS.start(),
+        RESERVED_TAGS.end()
+    )
+    .unwrap();
+    for (tag, name) in MultisigInstruction::NAMES.iter().enumerate() {
+        let separator = separator(tag, MultisigInstruction::NAMES.len());
+        writeln!(
+            out,
+            "    {{\"name\": \"{name}\", \"tag\": {tag}}}{separator}"
+        )
+        .unwrap();
+    }
+    out.push_str("  ]},\n  \"errors\": [\n");
+    for (i, err) in MultisigError::ALL.iter().enumerate() {
+        writeln!(
+            out,
+            "    {{\"name\": \"{err:?}\", \"code\": {}, \"message\": {:?}}}{}",
+            err.code(),
+            err.to_string(),
+            separator(i, MultisigError::ALL.len())
+        )
+        .unwrap();
+    }
+    out.push_str("  ]\n}\n");
+    out
+}
+
+// `layouts.ts`: the same as TypeScript constants
+pub fn typescript() -> String {
+    let mut out = String::from("// Generated by `cargo run --example layouts`, do not edit\n\n");
+    out.push_str(
+        "export type Field = {\n  type: string;\n  from: \"start\" | \"action_end\";\n  offset: number;\n  len: number;\n};\n",
+    );
+    for account in accounts() {
+        let constant = screaming_snake(account.name);
+        writeln!(
+            out,
+            "\nexport const {constant}_LEN = {};\n\nexport const {constant}_LAYOUT: Record<string, Field> = {{",
+            account.len
+        )
+        .unwrap();
+        for field in &account.fields {
+            writeln!(
+                out,
+                "  {}: {{ type: \"{}\", from: \"{}\", offset: {}, len: {} }},",
+                field.name,
+                field.ty,
+                base_name(field.base),
+                field.offset,
+                field.len
+            )
+            .unwrap();
+        }
+        out.push_str("};\n");
+    }
+
+    writeln!(
+        out,
+        "\nexport const MAX_INSTRUCTION_TAG = {MAX_INSTRUCTION_TAG};\nexport const RESERVED_TAGS = [{}, {}] as const;\nexport const PACKED_FORMAT_TAG = {PACKED_FORMAT_TAG};\nexport const PACKED_SIGN = {PACKED_SIGN};\nexport const PACKED_EXECUTE = {PACKED_EXECUTE};\n\nexport enum MultisigInstructionTag {{",
+        RESERVED_TAGS.start(),
+        RESERVED_TAGS.end()
+    )
+    .unwrap();
+    for (tag, name) in MultisigInstruction::NAMES.iter().enumerate() {
+        writeln!(out, "  {name} = {tag},").unwrap();
+    }
+    out.push_str("}\n\nexport enum MultisigErrorCode {\n");
+    for err in MultisigError::ALL {
+        writeln!(out, "  {err:?} = {},", err.code()).unwrap();
+    }
+    out.push_str(
+        "}\n\nexport const MULTISIG_ERROR_MESSAGES: Record<MultisigErrorCode, string> = {\n",
+    );
+    for err in MultisigError::ALL {
+        writeln!(out, "  [MultisigErrorCode.{err:?}]: {:?},", err.to_string()).unwrap();
+    }
+    out.push_str("};\n");
+    out
+}
+
+// Writes `layouts.json` and `layouts.ts` into `dir`
+pub fn write_all(dir: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join("layouts.json"), json())?;
+    std::fs::write(dir.join("layouts.ts"), typescript())
+}
+
+fn base_name(base: Base) -> &'static str {
+    match base {
+        Base::Start => "start",
+        Base::ActionEnd => "action_end",
+    }
+}
+
+fn separator(index: usize, len: usize) -> &'static str {
+    if index + 1 == len {
+        ""
+    } else {
+        ","
+    }
+}
+
+// "Multisig" -> "MULTISIG", "OwnerIndex" -> "OWNER_INDEX"
+fn screaming_snake(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_uppercase());
+    }
+    out
+}
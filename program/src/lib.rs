@@ -0,0 +1,46 @@
+// host-only, SDK test vectors
+pub mod error;
+pub mod event;
+#[cfg(not(target_os = "solana"))]
+pub mod fixtures;
+pub mod instruction;
+// host-only, layouts for clients in other languages
+#[cfg(not(target_os = "solana"))]
+pub mod layouts;
+pub mod lookup_table;
+pub mod merkle;
+pub mod multisig;
+pub mod processor;
+pub mod state;
+pub mod token_metadata;
+mod validation;
+
+use solana_program::{declare_id, entrypoint};
+
+pub use error::MultisigError;
+pub use instruction::{
+    ExecutionStrategy, MultisigInstruction, ProposalAction, ProposedAccount, ProposedInstruction,
+    ThresholdContext, VoteAuthority,
+};
+pub use processor::{assert_approved, process_instruction};
+pub use state::{
+    aggregated_approval_message, buffer_authority, buffer_hash, find_aggregate_key_address,
+    find_claim_address, find_delegate_address, find_derived_multisig_address,
+    find_destination_policy_address, find_display_unit_address, find_distribution_address,
+    find_guardians_address, find_history_address, find_inbox_address, find_indexed_vault_address,
+    find_metadata_address, find_owner_index_address, find_program_config_address,
+    find_receipt_address, find_receipt_ledger_address, find_recovery_address,
+    find_recurring_payment_address, find_transaction_address, find_vault_address,
+    programdata_upgrade_authority, AggregateKey, AmountPolicy, Claim, Delegate, DestinationMode,
+    DestinationPolicy, DisplayUnit, Distribution, Guardians, History, HistoryEntry, Inbox,
+    Metadata, Multisig, OutflowCap, OwnerIndex, PolicyEnforcement, ProgramConfig, ProposalState,
+    ProposalStatus, Receipt, ReceiptLedger, Recovery, RecurringPayment, Transaction,
+    CLOSE_GRACE_PERIOD, DELEGATE_APPROVE, DELEGATE_PERMISSIONS, DELEGATE_SIGN, HISTORY_CAPACITY,
+    MAX_DISPLAY_SYMBOL_LEN, MAX_INDEXED_MULTISIGS, MAX_METADATA_NAME_LEN, MAX_METADATA_URI_LEN,
+    MAX_OWNERS, MAX_PROPOSAL_EXTENSION, MAX_PROTOCOL_FEE_BPS, PROPOSAL_DIGEST_DOMAIN,
+    PROPOSAL_DIGEST_VERSION, TRANSACTION_SEED, VAULT_SEED,
+};
+
+// program's public key (after generating keypair)
+declare_id!("mw45AnZJJU8iUMkRNgytM11J7b4VAi6ptzViWHJ9mbD");
+entrypoint!(process_instruction);
@@ -0,0 +1,112 @@
+// Instructions of the address lookup table program the multisig signs as table authority, and
+// reading table accounts. Encoded by hand (bincode: `u32` variant index, `u64` vector length, all
+// little-endian) to keep the table program's crate and bincode out of the program
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::{pubkey, system_program};
+
+pub const ID: Pubkey = pubkey!("AddressLookupTab1e1111111111111111111111111");
+
+// `ProgramState` tag and `LookupTableMeta` ahead of the addresses in a table account
+pub const LOOKUP_TABLE_META_SIZE: usize = 56;
+const LOOKUP_TABLE_TAG: u32 = 1;
+
+// instruction variant indexes
+const CREATE: u32 = 0;
+const EXTEND: u32 = 2;
+const DEACTIVATE: u32 = 3;
+const CLOSE: u32 = 4;
+
+// A table is addressed by its authority and the (recent) slot it was created in
+pub fn find_lookup_table_address(authority: &Pubkey, recent_slot: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[authority.as_ref(), &recent_slot.to_le_bytes()], &ID)
+}
+
+fn data(variant: u32, fields: &[u8]) -> Vec<u8> {
+    [&variant.to_le_bytes()[..], fields].concat()
+}
+
+// The authority signs, `recent_slot` must still be in the `SlotHashes` sysvar
+pub fn create_lookup_table(
+    lookup_table: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    recent_slot: u64,
+    bump: u8,
+) -> Instruction {
+    let fields = [&recent_slot.to_le_bytes()[..], &[bump]].concat();
+    Instruction::new_with_bytes(
+        ID,
+        &data(CREATE, &fields),
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+// The payer tops the table up to the rent of its new size
+pub fn extend_lookup_table(
+    lookup_table: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    addresses: &[Pubkey],
+) -> Instruction {
+    let mut fields = (addresses.len() as u64).to_le_bytes().to_vec();
+    for address in addresses {
+        fields.extend_from_slice(address.as_ref());
+    }
+    Instruction::new_with_bytes(
+        ID,
+        &data(EXTEND, &fields),
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn deactivate_lookup_table(lookup_table: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &data(DEACTIVATE, &[]),
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+// Only once the deactivation slot has left `SlotHashes`, the rent goes to `recipient`
+pub fn close_lookup_table(
+    lookup_table: &Pubkey,
+    authority: &Pubkey,
+    recipient: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &data(CLOSE, &[]),
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*recipient, false),
+        ],
+    )
+}
+
+// The addresses a table account holds, `None` unless it is an initialized table
+pub fn lookup_table_addresses(data: &[u8]) -> Option<Vec<Pubkey>> {
+    let tag = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+    let addresses = data.get(LOOKUP_TABLE_META_SIZE..)?;
+    if tag != LOOKUP_TABLE_TAG || addresses.len() % 32 != 0 {
+        return None;
+    }
+    addresses
+        .chunks(32)
+        .map(|key| Pubkey::try_from(key).ok())
+        .collect()
+}
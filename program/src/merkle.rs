@@ -0,0 +1,95 @@
+// Merkle trees of (recipient, amount) grants as `CreateDistribution` commits to them. The program
+// verifies claims with `verify`; off-chain, `MerkleTree` builds the root to propose and the proof
+// each recipient claims with, so both sides always hash the same way.
+//
+// Leaves and inner nodes are hashed with different prefixes so an inner node can't pass for a
+// leaf; siblings are sorted, a proof needs no left/right flags
+use solana_program::hash::hashv;
+use solana_program::pubkey::Pubkey;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+pub fn leaf(recipient: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, recipient.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if left <= right {
+        (left, right)
+    } else {
+        (right, left)
+    };
+    hashv(&[NODE_PREFIX, first, second]).to_bytes()
+}
+
+// Whether `proof` leads from `leaf` up to `root`
+pub fn verify(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |node, sibling| hash_pair(&node, sibling));
+    computed == *root
+}
+
+// Host-only: the whole tree, leaves in the order the grants were given. A node without a sibling
+// moves up a level unchanged, so a proof may be shorter than the tree is deep
+#[cfg(not(target_os = "solana"))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleTree {
+    // levels[0] are the leaves, the last level holds the root alone
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+#[cfg(not(target_os = "solana"))]
+impl MerkleTree {
+    // `None` without grants, there is nothing to commit to
+    pub fn new(grants: &[(Pubkey, u64)]) -> Option<Self> {
+        if grants.is_empty() {
+            return None;
+        }
+        let mut levels = vec![grants
+            .iter()
+            .map(|(recipient, amount)| leaf(recipient, *amount))
+            .collect::<Vec<_>>()];
+        while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+            let next = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_pair(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        Some(Self { levels })
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    // Proof for the grant at `index`, `None` when out of range
+    pub fn proof(&self, index: usize) -> Option<Vec<[u8; 32]>> {
+        if index >= self.len() {
+            return None;
+        }
+        let mut proof = Vec::new();
+        let mut index = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        Some(proof)
+    }
+}
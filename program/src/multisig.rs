@@ -0,0 +1,104 @@
+// State transitions of a multisig on plain values, without accounts, sysvars or CPIs: the
+// processors load the account, read the clock, call these and save the result, so the rules can
+// be unit tested and fuzzed off-chain. `Sign` is the hot path and patches its two fields in the
+// account data instead of decoding it (`apply_sign_in_place`), with the same effect as
+// `apply_sign`.
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+use crate::error::MultisigError;
+use crate::state::{Multisig, MAX_OWNERS};
+
+// `Sign` by `signer` at `now`, or by their delegate for them (`by_delegate`). A pending approval
+// can't be given again, unless it has outlived the approval TTL, when signing renews it, or the
+// owner replaces their delegate's approval with their own
+pub fn apply_sign(
+    multisig: &mut Multisig,
+    signer: &Pubkey,
+    now: i64,
+    by_delegate: bool,
+) -> Result<(), MultisigError> {
+    let index = multisig
+        .owner_index(signer)
+        .ok_or(MultisigError::OwnerNotFound)?;
+    let upgrade = multisig.delegate_signers & (1 << index) != 0 && !by_delegate;
+    if multisig.signers[index]
+        && multisig.approval_is_live(multisig.signed_at[index], now)
+        && !upgrade
+    {
+        return Err(MultisigError::AlreadySigned);
+    }
+    multisig.signers[index] = true;
+    multisig.signed_at[index] = now;
+    match by_delegate {
+        true => multisig.delegate_signers |= 1 << index,
+        false => multisig.delegate_signers &= !(1 << index),
+    }
+    Ok(())
+}
+
+// `apply_sign` on the serialized multisig
+pub fn apply_sign_in_place(
+    data: &mut [u8],
+    signer: &Pubkey,
+    now: i64,
+    by_delegate: bool,
+) -> Result<(), ProgramError> {
+    if data.len() < Multisig::LEN || data[0] as usize > MAX_OWNERS {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let owners = &data[Multisig::OWNERS_OFFSET..][..32 * data[0] as usize];
+    let index = owners
+        .chunks_exact(32)
+        .position(|key| key == signer.as_ref())
+        .ok_or(MultisigError::OwnerNotFound)?;
+    let delegates = &data[Multisig::DELEGATE_SIGNERS_OFFSET..][..2];
+    let mut bits = u16::from_le_bytes([delegates[0], delegates[1]]);
+    let upgrade = bits & (1 << index) != 0 && !by_delegate;
+    if data[Multisig::SIGNERS_OFFSET + index] == 1 && !upgrade {
+        let signed_at = read_i64(data, Multisig::SIGNED_AT_OFFSET + 8 * index);
+        let live = match data[Multisig::APPROVAL_TTL_OFFSET] {
+            1 => now.saturating_sub(signed_at) <= read_i64(data, Multisig::APPROVAL_TTL_OFFSET + 1),
+            _ => true,
+        };
+        if live {
+            return Err(MultisigError::AlreadySigned.into());
+        }
+    }
+    data[Multisig::SIGNERS_OFFSET + index] = 1;
+    data[Multisig::SIGNED_AT_OFFSET + 8 * index..][..8].copy_from_slice(&now.to_le_bytes());
+    match by_delegate {
+        true => bits |= 1 << index,
+        false => bits &= !(1 << index),
+    }
+    data[Multisig::DELEGATE_SIGNERS_OFFSET..][..2].copy_from_slice(&bits.to_le_bytes());
+    Ok(())
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&data[offset..][..8]);
+    i64::from_le_bytes(bytes)
+}
+
+// `Execute` or `ExecuteFromVault` of `amount` lamports at `now`: refused while paused, without
+// the approvals the amount's tier needs, or for amounts the transfer settings rule out. The amount
+// is counted against the outflow cap and the approvals are consumed. `now` only matters with an
+// approval TTL or an outflow cap
+pub fn apply_execute(multisig: &mut Multisig, amount: u64, now: i64) -> Result<(), MultisigError> {
+    if multisig.paused {
+        return Err(MultisigError::Paused);
+    }
+    if multisig.live_approvals(now) < multisig.threshold_for(amount) as usize {
+        return Err(MultisigError::InsufficientSignatures);
+    }
+    multisig.check_transfer_amount(amount, true)?;
+    multisig.record_outflow(amount, now)?;
+    multisig.signers = vec![false; multisig.owners.len()];
+    Ok(())
+}
+
+// Whether `apply_execute` needs the clock for `multisig`, the processors skip the sysvar otherwise
+pub fn execute_needs_clock(multisig: &Multisig) -> bool {
+    multisig.approval_ttl_seconds.is_some() || multisig.outflow_cap.is_some()
+}
@@ -0,0 +1,4506 @@
+use crate::error::MultisigError;
+use crate::event::{DepositEvent, ExecutedEvent, MultisigEvent, ProposedEvent};
+use crate::instruction::{
+    vault_cpi_instructions, ExecutionStrategy, MultisigInstruction, ProposalAction,
+    ThresholdContext, PACKED_EXECUTE, PACKED_FORMAT_TAG, PACKED_SIGN,
+};
+use crate::lookup_table;
+use crate::merkle;
+use crate::multisig::{apply_execute, apply_sign_in_place, execute_needs_clock};
+use crate::state::{
+    buffer_authority, buffer_hash, find_claim_address, find_delegate_address,
+    find_derived_multisig_address, find_destination_policy_address, find_display_unit_address,
+    find_distribution_address, find_guardians_address, find_history_address, find_inbox_address,
+    find_indexed_vault_address, find_metadata_address, find_owner_index_address,
+    find_program_config_address, find_receipt_address, find_receipt_ledger_address,
+    find_recovery_address, find_recurring_payment_address, find_transaction_address,
+    find_vault_address, load, programdata_upgrade_authority, save, vault_seeds, AmountPolicy,
+    Claim, Delegate, DestinationMode, DestinationPolicy, DisplayUnit, Distribution, Guardians,
+    History, HistoryEntry, Inbox, Metadata, Multisig, OutflowCap, OwnerIndex, PolicyEnforcement,
+    ProgramConfig, ProposalState, Receipt, ReceiptLedger, Recovery, RecurringPayment, Transaction,
+    CLAIM_SEED, CLOSE_GRACE_PERIOD, DELEGATE_APPROVE, DELEGATE_PERMISSIONS, DELEGATE_SEED,
+    DELEGATE_SIGN, DERIVED_MULTISIG_SEED, DESTINATIONS_SEED, DISPLAY_SEED, DISTRIBUTION_SEED,
+    GUARDIANS_SEED, HISTORY_SEED, INBOX_SEED, MAX_DISPLAY_SYMBOL_LEN, MAX_INDEXED_MULTISIGS,
+    MAX_METADATA_NAME_LEN, MAX_METADATA_URI_LEN, MAX_OWNERS, MAX_PROPOSAL_EXTENSION,
+    MAX_PROTOCOL_FEE_BPS, METADATA_SEED, OWNER_INDEX_SEED, PROGRAM_CONFIG_SEED,
+    RECEIPT_LEDGER_SEED, RECEIPT_SEED, RECOVERY_SEED, RECURRING_SEED, TRANSACTION_SEED,
+};
+use crate::validation::{
+    validate_accounts, CreateAccounts, DepositAccounts, SetterAccounts, SignAccounts,
+    SignAsMultisigAccounts,
+};
+#[cfg(not(feature = "custom-program-id"))]
+use crate::ID;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use solana_program::clock::Clock;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::loader_upgradeable_instruction::UpgradeableLoaderInstruction;
+use solana_program::program::{invoke, invoke_signed};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::sysvar::Sysvar;
+use solana_program::{ed25519_program, msg, system_instruction, system_program, sysvar};
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use std::slice::Iter;
+
+#[cfg(feature = "aggregated-approval")]
+use crate::state::{
+    aggregated_approval_message, find_aggregate_key_address, owner_set_hash, AggregateKey,
+    AGGREGATE_SEED,
+};
+
+// Binary size: `{:?}` / `format!` and panicking calls (`unwrap`, slice indexing that can go out of
+// bounds) each pull formatting code into the `.so`, which `tests/test_binary_size.rs` holds to a
+// budget. On instruction paths log with `msg!` and plain arguments and return errors instead;
+// verbose dumps go through `debug_log!`, compiled in only with the `debug-logs` feature.
+macro_rules! debug_log {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "debug-logs")]
+        msg!($($arg)*);
+    };
+}
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    // `ID` comes from the `declare_id!` macro. When we use `declare_id!`, it creates a static constant called `ID`.
+    // Forks deployed under their own key build with `custom-program-id` instead
+    #[cfg(not(feature = "custom-program-id"))]
+    if program_id != &ID {
+        msg!("Invalid program ID");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let (accounts, history) = split_history(program_id, accounts)?;
+    let instruction = match instruction_data.split_first() {
+        Some((&PACKED_FORMAT_TAG, packed)) => unpack_instruction(packed, accounts)?,
+        _ => MultisigInstruction::decode(instruction_data)?,
+    };
+    // only payouts take the config, `SetProgramConfig` itself ends with it
+    let mut protocol_fee = None;
+    let accounts = match instruction {
+        MultisigInstruction::Execute { .. } | MultisigInstruction::ExecuteFromVault { .. } => {
+            let (accounts, program_config) = split_program_config(program_id, accounts)?;
+            check_program_not_paused(program_config, accounts.first())?;
+            let (accounts, fee) = split_fee_destination(accounts, program_config)?;
+            protocol_fee = fee;
+            accounts
+        }
+        MultisigInstruction::ExecuteRecurring | MultisigInstruction::ClaimDistribution { .. } => {
+            let (accounts, program_config) = split_program_config(program_id, accounts)?;
+            check_program_not_paused(program_config, accounts.first())?;
+            accounts
+        }
+        MultisigInstruction::ExecuteTransaction | MultisigInstruction::CrankTransaction => {
+            let (accounts, program_config) = split_program_config(program_id, accounts)?;
+            // the cranker comes before the multisig
+            let multisig_index = if matches!(instruction, MultisigInstruction::CrankTransaction) {
+                2
+            } else {
+                0
+            };
+            check_program_not_paused(program_config, accounts.get(multisig_index))?;
+            accounts
+        }
+        _ => accounts,
+    };
+    validate_accounts(program_id, &instruction, accounts)?;
+    let tag = instruction.tag();
+    let account_info_iter = &mut accounts.iter();
+
+    let result = match instruction {
+        MultisigInstruction::Create { owners, threshold } => process_create(
+            program_id,
+            CreateAccounts::from_accounts(program_id, accounts)?,
+            owners,
+            threshold,
+        ),
+        MultisigInstruction::Sign => {
+            process_sign(SignAccounts::from_accounts(program_id, accounts)?)
+        }
+        MultisigInstruction::Execute {
+            amount,
+            destination,
+        } => process_execute(
+            program_id,
+            account_info_iter,
+            amount,
+            destination,
+            ExecutionStrategy::DirectLamports,
+            protocol_fee,
+        ),
+        MultisigInstruction::CreateTokenAccount { mint } => {
+            process_create_token_account(program_id, account_info_iter, mint)
+        }
+        MultisigInstruction::Propose {
+            action,
+            vault,
+            expires_at,
+            required_signers,
+            executor_fee,
+            attachment,
+        } => process_propose(
+            program_id,
+            account_info_iter,
+            action,
+            vault,
+            expires_at,
+            &required_signers,
+            executor_fee,
+            attachment,
+        ),
+        MultisigInstruction::Approve => process_approve(program_id, account_info_iter),
+        MultisigInstruction::ExecuteTransaction => {
+            process_execute_transaction(program_id, account_info_iter, None)
+        }
+        MultisigInstruction::SwapOwner {
+            old_owner,
+            new_owner,
+        } => process_swap_owner(program_id, account_info_iter, old_owner, new_owner),
+        MultisigInstruction::InitGuardians {
+            guardians,
+            recovery_delay,
+        } => process_init_guardians(program_id, account_info_iter, guardians, recovery_delay),
+        MultisigInstruction::ProposeRecovery {
+            new_owners,
+            new_threshold,
+        } => process_propose_recovery(program_id, account_info_iter, new_owners, new_threshold),
+        MultisigInstruction::ApproveRecovery => {
+            process_approve_recovery(program_id, account_info_iter)
+        }
+        MultisigInstruction::VetoRecovery => process_veto_recovery(program_id, account_info_iter),
+        MultisigInstruction::ExecuteRecovery => {
+            process_execute_recovery(program_id, account_info_iter)
+        }
+        MultisigInstruction::SetVetoAuthority { veto_authority } => process_set_veto_authority(
+            SetterAccounts::from_accounts(program_id, accounts)?,
+            veto_authority,
+        ),
+        MultisigInstruction::CloseTransaction => {
+            process_close_transaction(program_id, account_info_iter)
+        }
+        MultisigInstruction::VetoTransaction => {
+            process_veto_transaction(program_id, account_info_iter)
+        }
+        MultisigInstruction::SetDestinationPolicy { mode, destinations } => {
+            process_update_destination_policy(program_id, account_info_iter, |policy| {
+                policy.mode = mode;
+                policy.destinations = destinations;
+                Ok(())
+            })
+        }
+        MultisigInstruction::AddDestination { destination } => {
+            process_update_destination_policy(program_id, account_info_iter, |policy| {
+                if policy.destinations.contains(&destination) {
+                    return Err(MultisigError::DuplicateDestination.into());
+                }
+                policy.destinations.push(destination);
+                Ok(())
+            })
+        }
+        MultisigInstruction::RemoveDestination { destination } => {
+            process_update_destination_policy(program_id, account_info_iter, |policy| {
+                let index = policy
+                    .destinations
+                    .iter()
+                    .position(|key| key == &destination)
+                    .ok_or(MultisigError::DestinationNotFound)?;
+                policy.destinations.remove(index);
+                Ok(())
+            })
+        }
+        MultisigInstruction::SetDestinationPolicyEnforcement { enforcement } => {
+            process_update_destination_policy(program_id, account_info_iter, |policy| {
+                policy.enforcement = enforcement;
+                Ok(())
+            })
+        }
+        MultisigInstruction::SetPaused { paused } => {
+            process_set_paused(program_id, account_info_iter, paused)
+        }
+        MultisigInstruction::SetEmergencyAuthority {
+            emergency_authority,
+        } => process_set_emergency_authority(
+            SetterAccounts::from_accounts(program_id, accounts)?,
+            emergency_authority,
+        ),
+        MultisigInstruction::ExecuteRecurring => {
+            process_execute_recurring(program_id, account_info_iter)
+        }
+        MultisigInstruction::RegisterInbox => process_register_inbox(program_id, account_info_iter),
+        MultisigInstruction::ClearInbox => process_clear_inbox(program_id, account_info_iter),
+        MultisigInstruction::ExtendProposal { seconds } => {
+            process_extend_proposal(program_id, account_info_iter, seconds)
+        }
+        MultisigInstruction::SetPolicy { policy } => {
+            process_set_policy(SetterAccounts::from_accounts(program_id, accounts)?, policy)
+        }
+        MultisigInstruction::SetApprovalTtl { ttl_seconds } => process_set_approval_ttl(
+            SetterAccounts::from_accounts(program_id, accounts)?,
+            ttl_seconds,
+        ),
+        MultisigInstruction::ClaimDistribution { amount, proof } => {
+            process_claim_distribution(program_id, account_info_iter, amount, proof)
+        }
+        MultisigInstruction::SetDisplayUnit {
+            mint,
+            decimals,
+            symbol,
+        } => process_set_display_unit(program_id, account_info_iter, mint, decimals, symbol),
+        #[cfg(feature = "aggregated-approval")]
+        MultisigInstruction::InitAggregateKey { aggregate_key } => {
+            process_init_aggregate_key(program_id, account_info_iter, aggregate_key)
+        }
+        #[cfg(feature = "aggregated-approval")]
+        MultisigInstruction::ApproveAggregated => {
+            process_approve_aggregated(program_id, account_info_iter)
+        }
+        #[cfg(not(feature = "aggregated-approval"))]
+        MultisigInstruction::InitAggregateKey { .. } | MultisigInstruction::ApproveAggregated => {
+            msg!("Built without the aggregated-approval feature");
+            Err(ProgramError::InvalidInstructionData)
+        }
+        MultisigInstruction::CreateLookupTable { recent_slot } => {
+            process_create_lookup_table(program_id, account_info_iter, recent_slot)
+        }
+        MultisigInstruction::ExtendLookupTable { addresses } => {
+            process_extend_lookup_table(program_id, account_info_iter, &addresses)
+        }
+        MultisigInstruction::DeactivateLookupTable => {
+            process_deactivate_lookup_table(program_id, account_info_iter)
+        }
+        MultisigInstruction::CloseLookupTable => {
+            process_close_lookup_table(program_id, account_info_iter)
+        }
+        MultisigInstruction::CheckApproved { context } => {
+            assert_approved(program_id, next_account_info(account_info_iter)?, context)
+        }
+        MultisigInstruction::ExecuteFromVault {
+            amount,
+            destination,
+        } => process_execute(
+            program_id,
+            account_info_iter,
+            amount,
+            destination,
+            ExecutionStrategy::SystemTransfer,
+            protocol_fee,
+        ),
+        MultisigInstruction::MigrateToVault => {
+            process_migrate_to_vault(program_id, account_info_iter)
+        }
+        MultisigInstruction::CrankTransaction => {
+            process_crank_transaction(program_id, account_info_iter)
+        }
+        MultisigInstruction::RegisterOwnerIndex => {
+            process_register_owner_index(program_id, account_info_iter)
+        }
+        MultisigInstruction::SyncOwnerIndex => {
+            process_sync_owner_index(program_id, account_info_iter)
+        }
+        MultisigInstruction::UnlistMultisig { multisig } => {
+            process_unlist_multisig(program_id, account_info_iter, &multisig)
+        }
+        MultisigInstruction::SetDelegate {
+            delegate,
+            expires_at,
+            permissions,
+        } => process_set_delegate(
+            program_id,
+            account_info_iter,
+            delegate,
+            expires_at,
+            permissions,
+        ),
+        MultisigInstruction::RevokeDelegate => {
+            process_revoke_delegate(program_id, account_info_iter)
+        }
+        MultisigInstruction::SignAsDelegate => {
+            process_sign_as_delegate(program_id, account_info_iter)
+        }
+        MultisigInstruction::ApproveAsDelegate => {
+            process_approve_as_delegate(program_id, account_info_iter)
+        }
+        MultisigInstruction::SetPolicySigner { policy_signer } => process_set_policy_signer(
+            SetterAccounts::from_accounts(program_id, accounts)?,
+            policy_signer,
+        ),
+        MultisigInstruction::ProposeIndexed {
+            action,
+            vault,
+            expires_at,
+            required_signers,
+            executor_fee,
+            attachment,
+        } => process_propose_indexed(
+            program_id,
+            account_info_iter,
+            action,
+            vault,
+            expires_at,
+            &required_signers,
+            executor_fee,
+            attachment,
+        ),
+        MultisigInstruction::SetOutflowCap { cap } => {
+            process_set_outflow_cap(SetterAccounts::from_accounts(program_id, accounts)?, cap)
+        }
+        MultisigInstruction::ApproveWithSignature { owner } => {
+            process_approve_with_signature(program_id, account_info_iter, &owner)
+        }
+        MultisigInstruction::SetMetadata { name, uri } => {
+            process_set_metadata(program_id, account_info_iter, name, uri)
+        }
+        MultisigInstruction::InitHistory => process_init_history(program_id, account_info_iter),
+        MultisigInstruction::ChangeThreshold { threshold } => {
+            process_change_threshold(program_id, account_info_iter, threshold)
+        }
+        MultisigInstruction::AddOwner { owner } => {
+            process_add_owner(program_id, account_info_iter, owner)
+        }
+        MultisigInstruction::CreateDerived {
+            seed,
+            owners,
+            threshold,
+        } => process_create_derived(program_id, account_info_iter, seed, owners, threshold),
+        MultisigInstruction::Deposit { amount } => process_deposit(
+            program_id,
+            DepositAccounts::from_accounts(program_id, accounts)?,
+            amount,
+        ),
+        MultisigInstruction::InitProgramConfig {
+            governance,
+            fee_destination,
+            protocol_fee_bps,
+        } => process_init_program_config(
+            program_id,
+            account_info_iter,
+            ProgramConfig {
+                governance,
+                fee_destination,
+                protocol_fee_bps,
+                paused: false,
+                bump: 0,
+            },
+        ),
+        MultisigInstruction::SetProgramConfig {
+            governance,
+            fee_destination,
+            protocol_fee_bps,
+            paused,
+        } => process_set_program_config(
+            program_id,
+            account_info_iter,
+            governance,
+            fee_destination,
+            protocol_fee_bps,
+            paused,
+        ),
+        MultisigInstruction::SetFeeExempt { exempt } => {
+            process_set_fee_exempt(program_id, account_info_iter, exempt)
+        }
+        MultisigInstruction::SetTransferMinimum {
+            allow_zero_amount,
+            min_transfer,
+        } => process_set_transfer_minimum(
+            SetterAccounts::from_accounts(program_id, accounts)?,
+            allow_zero_amount,
+            min_transfer,
+        ),
+        MultisigInstruction::SetStrictSigning { strict } => {
+            process_set_strict_signing(SetterAccounts::from_accounts(program_id, accounts)?, strict)
+        }
+        MultisigInstruction::SignAsMultisig => process_sign_as_multisig(
+            program_id,
+            SignAsMultisigAccounts::from_accounts(program_id, accounts)?,
+        ),
+    };
+    result?;
+
+    match history {
+        Some(history_account) => record_history(history_account, tag, accounts),
+        None => Ok(()),
+    }
+}
+
+// Takes the multisig's `History` account off the end of the accounts when it was passed
+// (`instruction::with_history`), the instruction itself never sees it
+fn split_history<'a, 'b>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<(&'a [AccountInfo<'b>], Option<&'a AccountInfo<'b>>), ProgramError> {
+    let Some((last, rest)) = accounts.split_last() else {
+        return Ok((accounts, None));
+    };
+    if last.owner != program_id || last.data_len() != History::SPACE {
+        return Ok((accounts, None));
+    }
+    // any other account of the program that happens to have the size isn't at the history's
+    // address
+    let Ok(history) = load::<History>(&last.data.borrow()) else {
+        return Ok((accounts, None));
+    };
+    let address = Pubkey::create_program_address(
+        &[HISTORY_SEED, history.multisig.as_ref(), &[history.bump]],
+        program_id,
+    );
+    if address.as_ref() != Ok(last.key) {
+        return Ok((accounts, None));
+    }
+    if !last.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    // an instruction is only recorded in the history of a multisig it involves
+    if !rest.iter().any(|account| account.key == &history.multisig) {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    Ok((rest, Some(last)))
+}
+
+// Takes the `ProgramConfig` off the end of the accounts when it was passed
+// (`instruction::with_program_config`), like `split_history`
+fn split_program_config<'a, 'b>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<(&'a [AccountInfo<'b>], Option<&'a AccountInfo<'b>>), ProgramError> {
+    let Some((last, rest)) = accounts.split_last() else {
+        return Ok((accounts, None));
+    };
+    if last.owner != program_id || last.data_len() != ProgramConfig::LEN {
+        return Ok((accounts, None));
+    }
+    let Ok(config) = load::<ProgramConfig>(&last.data.borrow()) else {
+        return Ok((accounts, None));
+    };
+    let address =
+        Pubkey::create_program_address(&[PROGRAM_CONFIG_SEED, &[config.bump]], program_id);
+    if address.as_ref() != Ok(last.key) {
+        return Ok((accounts, None));
+    }
+    Ok((rest, Some(last)))
+}
+
+// Payouts stop while the program's governance has paused it, except the governance multisig's own
+// so it can still unpause. Builds with `program-config` don't let them go ahead without the config
+// account
+fn check_program_not_paused(
+    program_config: Option<&AccountInfo>,
+    multisig_account: Option<&AccountInfo>,
+) -> ProgramResult {
+    let Some(config_account) = program_config else {
+        if cfg!(feature = "program-config") {
+            msg!("The program config account must be passed (`with_program_config`)");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        return Ok(());
+    };
+    let config = load::<ProgramConfig>(&config_account.data.borrow())?;
+    if config.paused && multisig_account.map(|account| account.key) != Some(&config.governance) {
+        msg!("The program is paused by its governance");
+        return Err(MultisigError::Paused.into());
+    }
+    Ok(())
+}
+
+// The fee destination of a payout and the config charging the fee
+type ProtocolFee<'a, 'b> = (&'a AccountInfo<'b>, ProgramConfig);
+
+// Takes the fee destination off the end of a payout's accounts when the config charges a protocol
+// fee (`instruction::with_protocol_fee`), it must be the config's `fee_destination`
+fn split_fee_destination<'a, 'b>(
+    accounts: &'a [AccountInfo<'b>],
+    program_config: Option<&AccountInfo>,
+) -> Result<(&'a [AccountInfo<'b>], Option<ProtocolFee<'a, 'b>>), ProgramError> {
+    let Some(config_account) = program_config else {
+        return Ok((accounts, None));
+    };
+    let config = load::<ProgramConfig>(&config_account.data.borrow())?;
+    if config.protocol_fee_bps == 0 {
+        return Ok((accounts, None));
+    }
+    match accounts.split_last() {
+        Some((last, rest)) if last.key == &config.fee_destination => {
+            Ok((rest, Some((last, config))))
+        }
+        _ => {
+            msg!(
+                "The protocol fee goes to {}, passed before the config",
+                config.fee_destination
+            );
+            Err(MultisigError::AccountMismatch.into())
+        }
+    }
+}
+
+fn record_history(
+    history_account: &AccountInfo,
+    instruction: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let mut history = load::<History>(&history_account.data.borrow())?;
+    let actor = accounts
+        .iter()
+        .find(|account| account.is_signer)
+        .map_or(Pubkey::default(), |account| *account.key);
+    history.record(HistoryEntry {
+        instruction,
+        actor,
+        slot: Clock::get()?.slot,
+    })?;
+    save(&history, &mut history_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+// Decodes the packed encoding (`PACKED_FORMAT_TAG`), `data` is what follows the tag
+fn unpack_instruction(
+    data: &[u8],
+    accounts: &[AccountInfo],
+) -> Result<MultisigInstruction, ProgramError> {
+    match data {
+        [PACKED_SIGN] => Ok(MultisigInstruction::Sign),
+        [PACKED_EXECUTE, amount @ ..] => {
+            let amount =
+                <[u8; 8]>::try_from(amount).map_err(|_| ProgramError::InvalidInstructionData)?;
+            let destination = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            Ok(MultisigInstruction::Execute {
+                amount: u64::from_le_bytes(amount),
+                destination: *destination.key,
+            })
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn process_create(
+    program_id: &Pubkey,
+    accounts: CreateAccounts,
+    owners: Vec<Pubkey>,
+    threshold: u8,
+) -> ProgramResult {
+    let multisig_account = accounts.multisig;
+    // a multisig can only be initialized once, its owner set changes through its own approvals
+    if multisig_account.data.borrow().iter().any(|&byte| byte != 0) {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    check_owner_set(&owners, threshold)?;
+
+    // Create the multisig structure
+    let multisig = Multisig {
+        owners: owners.clone(),
+        threshold,
+        signers: vec![false; owners.len()],
+        veto_authority: None,
+        paused: false,
+        emergency_authority: None,
+        amount_policy: None,
+        approval_ttl_seconds: None,
+        signed_at: vec![0; owners.len()],
+        owner_set_seqno: 0,
+        outflow_cap: None,
+        spent_this_period: 0,
+        period_start: 0,
+        policy_signer: None,
+        transaction_count: 0,
+        fee_exempt: false,
+        allow_zero_amount: false,
+        min_transfer: 0,
+        strict_signing: false,
+        delegate_signers: 0,
+    };
+
+    // Serialize the multisig structure into the account data, clearing whatever follows it
+    let mut data = multisig_account.try_borrow_mut_data()?;
+    save(&multisig, &mut data)?;
+
+    // remaining accounts at an owner's index address are indexes to list the multisig in, any
+    // others (older clients pass the system program) are ignored
+    if accounts.remaining.is_empty() {
+        return Ok(());
+    }
+    let index_addresses: Vec<Pubkey> = owners
+        .iter()
+        .map(|owner| find_owner_index_address(owner, program_id).0)
+        .collect();
+    for index_account in accounts.remaining {
+        if index_addresses.contains(index_account.key) {
+            list_in_owner_index(program_id, index_account, multisig_account.key, &owners)?;
+        }
+    }
+    Ok(())
+}
+
+fn process_create_derived(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    seed: [u8; 32],
+    owners: Vec<Pubkey>,
+    threshold: u8,
+) -> ProgramResult {
+    let payer = next_account_info(account_info_iter)?;
+    let creator = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !payer.is_signer || !creator.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (multisig_key, bump) = find_derived_multisig_address(creator.key, &seed, program_id);
+    if multisig_account.key != &multisig_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if multisig_account.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        program_id,
+        payer,
+        multisig_account,
+        system_program,
+        Multisig::LEN,
+        &[&[DERIVED_MULTISIG_SEED, creator.key.as_ref(), &seed, &[bump]]],
+    )?;
+
+    // the rest is `Create`, with the owner indexes following the system program
+    let accounts: Vec<AccountInfo> = std::iter::once(multisig_account)
+        .chain(account_info_iter)
+        .cloned()
+        .collect();
+    process_create(
+        program_id,
+        CreateAccounts::from_accounts(program_id, &accounts)?,
+        owners,
+        threshold,
+    )
+}
+
+fn process_deposit(program_id: &Pubkey, accounts: DepositAccounts, amount: u64) -> ProgramResult {
+    let DepositAccounts {
+        depositor,
+        multisig: multisig_account,
+        vault,
+        system_program,
+        ..
+    } = accounts;
+
+    let (vault_key, _) = find_vault_address(multisig_account.key, program_id);
+    if vault.key != &vault_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    invoke(
+        &system_instruction::transfer(depositor.key, vault.key, amount),
+        &[depositor.clone(), vault.clone(), system_program.clone()],
+    )?;
+    DepositEvent {
+        multisig: *multisig_account.key,
+        vault: vault_key,
+        depositor: *depositor.key,
+        amount,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_sign(accounts: SignAccounts) -> ProgramResult {
+    let SignAccounts {
+        owner: signer,
+        multisig: multisig_account,
+        remaining,
+    } = accounts;
+
+    // strict mode is the one flag read here, the rest of the account stays undecoded
+    let strict = multisig_account
+        .data
+        .borrow()
+        .get(Multisig::STRICT_SIGNING_OFFSET)
+        == Some(&1);
+    if strict {
+        let [instructions_sysvar, fee_payer, ..] = remaining else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        // being a signer of this instruction, the payer is held to the owner check below
+        if !fee_payer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        check_signers_are_owners(multisig_account, instructions_sysvar)?;
+    }
+
+    record_sign(
+        multisig_account,
+        signer.key,
+        Clock::get()?.unix_timestamp,
+        false,
+    )
+}
+
+// The child multisig's vault PDA can't sign outside the child's own CPIs, which can't call back
+// into this program. Instead its approval is read off the child account, as `CheckApproved` would
+fn process_sign_as_multisig(
+    program_id: &Pubkey,
+    accounts: SignAsMultisigAccounts,
+) -> ProgramResult {
+    let SignAsMultisigAccounts {
+        child_owner,
+        child: child_account,
+        multisig: multisig_account,
+        ..
+    } = accounts;
+
+    // a multisig owning itself would approve with the approvals it just spent
+    if child_account.key == multisig_account.key {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    assert_approved(program_id, child_account, ThresholdContext::Threshold)?;
+
+    let mut child = load::<Multisig>(&child_account.data.borrow())?;
+    // the approvals are the child's owners' to spend, not whoever passes the accounts
+    if child.owner_index(child_owner.key).is_none() {
+        return Err(MultisigError::OwnerNotFound.into());
+    }
+    // Approvals are consumed, same as after `Execute`
+    child.signers = vec![false; child.owners.len()];
+    save(&child, &mut child_account.try_borrow_mut_data()?)?;
+
+    let (owner, _) = find_vault_address(child_account.key, program_id);
+    record_sign(
+        multisig_account,
+        &owner,
+        Clock::get()?.unix_timestamp,
+        false,
+    )
+}
+
+// Every signer of every instruction in the transaction must be an owner. The sysvar only lists
+// the keys instructions name, not the message's fee payer, hence strict `Sign` names the payer
+fn check_signers_are_owners(
+    multisig_account: &AccountInfo,
+    instructions_sysvar: &AccountInfo,
+) -> ProgramResult {
+    if instructions_sysvar.key != &sysvar::instructions::id() {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    // the sysvar starts with the number of instructions
+    let count = {
+        let data = instructions_sysvar.try_borrow_data()?;
+        match data.get(..2) {
+            Some(bytes) => u16::from_le_bytes([bytes[0], bytes[1]]),
+            None => return Err(ProgramError::InvalidAccountData),
+        }
+    };
+    for index in 0..count {
+        let ix =
+            sysvar::instructions::load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        if let Some(meta) = ix
+            .accounts
+            .iter()
+            .find(|meta| meta.is_signer && multisig.owner_index(&meta.pubkey).is_none())
+        {
+            msg!("{} signs the transaction but isn't an owner", meta.pubkey);
+            return Err(MultisigError::NonOwnerSigner.into());
+        }
+    }
+    Ok(())
+}
+
+// Sets `owner`'s `Sign` flag and timestamp in place instead of decoding and re-encoding the whole
+// account (the hot path, see `tests/test_compute_budget.rs`)
+fn record_sign(
+    multisig_account: &AccountInfo,
+    owner: &Pubkey,
+    now: i64,
+    by_delegate: bool,
+) -> ProgramResult {
+    apply_sign_in_place(
+        &mut multisig_account.try_borrow_mut_data()?,
+        owner,
+        now,
+        by_delegate,
+    )
+}
+
+// `SystemTransfer`: paid by the main vault (`ExecuteFromVault`), passed after the multisig account
+fn process_execute<'a>(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo<'a>>,
+    amount: u64,
+    destination: Pubkey,
+    strategy: ExecutionStrategy,
+    protocol_fee: Option<ProtocolFee<'_, 'a>>,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let vault = match strategy {
+        ExecutionStrategy::SystemTransfer => {
+            let vault = next_account_info(account_info_iter)?;
+            let (vault_key, vault_bump) = find_vault_address(multisig_account.key, program_id);
+            if vault.key != &vault_key {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            Some((vault, vault_bump))
+        }
+        ExecutionStrategy::DirectLamports => None,
+    };
+    let destination_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let policy_account = next_account_info(account_info_iter)?;
+
+    debug_log!(
+        "In process_execute - Account data length: {}",
+        multisig_account.data.borrow().len()
+    );
+    debug_log!(
+        "In process_execute - Account is_writable: {}",
+        multisig_account.is_writable
+    );
+    debug_log!("Execute amount: {}, destination: {}", amount, destination);
+
+    // Verify accounts
+    if !multisig_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if destination_account.key != &destination {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    check_destination(program_id, multisig_account.key, None, &destination)?;
+
+    if system_program.key != &system_program::ID {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+
+    // Read the current multisig state
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    debug_log!("Current multisig state: {:?}", multisig);
+
+    // The Clock is only read when the approvals or the cap depend on it
+    let now = match execute_needs_clock(&multisig) {
+        true => Clock::get()?.unix_timestamp,
+        false => 0,
+    };
+    // consumes the approvals, the account is saved before any CPI so nothing invoked from here
+    // can spend them again
+    if let Err(err) = apply_execute(&mut multisig, amount, now) {
+        msg!("Execute refused: {}", err);
+        return Err(err.into());
+    }
+    // the protocol fee comes out of the payout, exempt multisigs pay none
+    let fee = match protocol_fee {
+        Some((_, config)) if !multisig.fee_exempt => config.protocol_fee(amount),
+        _ => 0,
+    };
+    // then the co-signature, which comes before any receipt accounts
+    if let Some(policy_signer) = multisig.policy_signer {
+        let cosigner = account_info_iter.next();
+        if !cosigner.is_some_and(|cosigner| cosigner.key == &policy_signer && cosigner.is_signer) {
+            msg!("Payouts need the signature of {}", policy_signer);
+            return Err(MultisigError::PolicySignerMissing.into());
+        }
+    }
+
+    if let Some(policy) = load_destination_policy(program_id, multisig_account.key, policy_account)?
+    {
+        if !policy.allows(&destination) {
+            policy_violation(
+                policy.enforcement,
+                &format!("destination {destination} is not allowed by the policy"),
+            )?;
+        }
+    }
+
+    // Check if the paying account has enough funds, the multisig keeps enough to stay rent exempt
+    let source = vault.map_or(multisig_account, |(vault, _)| vault);
+    if source.lamports() < amount {
+        return Err(MultisigError::InsufficientBalance.into());
+    }
+    if strategy == ExecutionStrategy::DirectLamports {
+        let rent_floor = Rent::get()?.minimum_balance(multisig_account.data_len());
+        multisig_account
+            .lamports()
+            .checked_sub(amount)
+            .filter(|&left| left >= rent_floor)
+            .ok_or(MultisigError::BelowRentFloor)?;
+    }
+
+    debug_log!("Updated multisig state after reset: {:?}", multisig);
+
+    save(&multisig, &mut multisig_account.try_borrow_mut_data()?)?;
+
+    record_receipt(
+        program_id,
+        account_info_iter,
+        system_program,
+        multisig_account.key,
+        &destination,
+        amount,
+        Pubkey::default(),
+    )?;
+
+    // Transfer funds
+    let payout = amount
+        .checked_sub(fee)
+        .ok_or(MultisigError::ArithmeticOverflow)?;
+    let vault_index = [0];
+    let vault_bump = [vault.map_or(0, |(_, bump)| bump)];
+    let mut signer_seeds = vault_seeds(multisig_account.key, &vault_index);
+    signer_seeds.push(&vault_bump);
+    transfer_lamports(
+        strategy,
+        source,
+        destination_account,
+        system_program,
+        &signer_seeds,
+        payout,
+    )?;
+    match protocol_fee {
+        Some((fee_destination, _)) if fee > 0 => {
+            msg!("Protocol fee: {}", fee);
+            transfer_lamports(
+                strategy,
+                source,
+                fee_destination,
+                system_program,
+                &signer_seeds,
+                fee,
+            )
+        }
+        _ => Ok(()),
+    }
+}
+
+// Pays `amount` lamports from `source` to `destination`. `DirectLamports` needs a source the
+// program owns, `SystemTransfer` a system-owned one `signer_seeds` sign for
+fn transfer_lamports<'a>(
+    strategy: ExecutionStrategy,
+    source: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    signer_seeds: &[&[u8]],
+    amount: u64,
+) -> ProgramResult {
+    match strategy {
+        ExecutionStrategy::DirectLamports => move_lamports(source, destination, amount),
+        ExecutionStrategy::SystemTransfer => invoke_signed(
+            &system_instruction::transfer(source.key, destination.key, amount),
+            &[source.clone(), destination.clone(), system_program.clone()],
+            &[signer_seeds],
+        ),
+    }
+}
+
+// Debits `amount` lamports from `source` and credits them to `destination`, both accounts the
+// runtime lets the program change the balance of
+fn move_lamports(source: &AccountInfo, destination: &AccountInfo, amount: u64) -> ProgramResult {
+    if source.key == destination.key {
+        return Ok(());
+    }
+    let source_lamports = source
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(MultisigError::ArithmeticOverflow)?;
+    let destination_lamports = destination
+        .lamports()
+        .checked_add(amount)
+        .ok_or(MultisigError::ArithmeticOverflow)?;
+    **source.try_borrow_mut_lamports()? = source_lamports;
+    **destination.try_borrow_mut_lamports()? = destination_lamports;
+    Ok(())
+}
+
+// Adds a payment to the destination's receipt ledger when the optional receipt accounts (payer,
+// ledger, receipt) follow the instruction's accounts, the payer funds the new accounts
+fn record_receipt<'a>(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo<'a>>,
+    system_program: &AccountInfo<'a>,
+    multisig: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+    transaction: Pubkey,
+) -> ProgramResult {
+    let Some(payer) = account_info_iter.next() else {
+        return Ok(());
+    };
+    let ledger_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (ledger_key, ledger_bump) = find_receipt_ledger_address(multisig, destination, program_id);
+    if ledger_account.key != &ledger_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let mut ledger = if ledger_account.owner == program_id {
+        load::<ReceiptLedger>(&ledger_account.data.borrow())?
+    } else {
+        create_pda_account(
+            program_id,
+            payer,
+            ledger_account,
+            system_program,
+            ReceiptLedger::SPACE,
+            &[&[
+                RECEIPT_LEDGER_SEED,
+                multisig.as_ref(),
+                destination.as_ref(),
+                &[ledger_bump],
+            ]],
+        )?;
+        ReceiptLedger {
+            multisig: *multisig,
+            destination: *destination,
+            count: 0,
+            total: 0,
+        }
+    };
+
+    let index = ledger.count;
+    let (receipt_key, receipt_bump) =
+        find_receipt_address(multisig, destination, index, program_id);
+    if receipt_account.key != &receipt_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_pda_account(
+        program_id,
+        payer,
+        receipt_account,
+        system_program,
+        Receipt::SPACE,
+        &[&[
+            RECEIPT_SEED,
+            multisig.as_ref(),
+            destination.as_ref(),
+            &index.to_le_bytes(),
+            &[receipt_bump],
+        ]],
+    )?;
+    let clock = Clock::get()?;
+    let receipt = Receipt {
+        multisig: *multisig,
+        destination: *destination,
+        index,
+        amount,
+        transaction,
+        paid_at: clock.unix_timestamp,
+        slot: clock.slot,
+    };
+    save(&receipt, &mut receipt_account.try_borrow_mut_data()?)?;
+
+    ledger.count = ledger
+        .count
+        .checked_add(1)
+        .ok_or(MultisigError::ArithmeticOverflow)?;
+    ledger.total = ledger.total.saturating_add(amount);
+    save(&ledger, &mut ledger_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+// Verifies that enough owners have signed for a threshold-gated instruction
+fn check_threshold(multisig: &Multisig) -> ProgramResult {
+    check_threshold_for(multisig, 0)
+}
+
+// Same for an instruction moving `lamports` out of the wallet, large amounts may need more
+// approvals (`AmountPolicy`)
+fn check_threshold_for(multisig: &Multisig, lamports: u64) -> ProgramResult {
+    check_approvals(multisig, multisig.threshold_for(lamports))
+}
+
+// Same for a change of the multisig's configuration, which delegates' approvals don't count toward
+fn check_config_threshold(multisig: &Multisig) -> ProgramResult {
+    let signature_count = multisig.live_owner_approvals(approval_clock(multisig)?);
+    msg!(
+        "Owner signature count: {}, Required threshold: {}",
+        signature_count,
+        multisig.threshold
+    );
+
+    if signature_count < multisig.threshold as usize {
+        return Err(MultisigError::InsufficientSignatures.into());
+    }
+    Ok(())
+}
+
+fn check_approvals(multisig: &Multisig, threshold: u8) -> ProgramResult {
+    // Count the number of signatures that haven't outlived the approval TTL
+    let signature_count = multisig.live_approvals(approval_clock(multisig)?);
+    msg!(
+        "Signature count: {}, Required threshold: {}",
+        signature_count,
+        threshold
+    );
+
+    if signature_count < threshold as usize {
+        return Err(MultisigError::InsufficientSignatures.into());
+    }
+    Ok(())
+}
+
+// For other programs gating their own instructions on the multisig's approval, e.g. a lending
+// program's parameter changes: linked in (`no-entrypoint`) with the multisig program's id, or
+// through a `CheckApproved` CPI, whose failure fails their transaction. Nothing is consumed,
+// approvals stay until a threshold-gated instruction of the multisig spends them
+pub fn assert_approved(
+    program_id: &Pubkey,
+    multisig_account: &AccountInfo,
+    context: ThresholdContext,
+) -> ProgramResult {
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_not_paused(&multisig)?;
+    let threshold = match context {
+        ThresholdContext::Threshold => multisig.threshold,
+        ThresholdContext::Amount(lamports) => multisig.threshold_for(lamports),
+        ThresholdContext::AtLeast(count) => multisig.threshold.max(count),
+    };
+    check_approvals(&multisig, threshold)
+}
+
+// Unix time approvals are aged against. The Clock is only read when approvals can expire
+fn approval_clock(multisig: &Multisig) -> Result<i64, ProgramError> {
+    match multisig.approval_ttl_seconds {
+        Some(_) => Ok(Clock::get()?.unix_timestamp),
+        None => Ok(0),
+    }
+}
+
+// Funds sent to the multisig account itself, a proposal account or the program would be stuck
+// there (or, for `Execute`, go nowhere while still consuming the approvals)
+fn check_destination(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    transaction: Option<&Pubkey>,
+    destination: &Pubkey,
+) -> ProgramResult {
+    if destination == multisig || destination == program_id || transaction == Some(destination) {
+        msg!(
+            "InvalidDestination: {} is the multisig, its proposal or the program",
+            destination
+        );
+        return Err(MultisigError::InvalidDestination.into());
+    }
+    Ok(())
+}
+
+fn check_owner_set(owners: &[Pubkey], threshold: u8) -> ProgramResult {
+    if owners.len() > MAX_OWNERS {
+        return Err(MultisigError::TooManyOwners.into());
+    }
+    // a key listed twice would count toward the threshold once, possibly leaving it out of reach
+    for (index, owner) in owners.iter().enumerate() {
+        if owners[..index].contains(owner) {
+            return Err(MultisigError::DuplicateOwner.into());
+        }
+    }
+    if threshold == 0 || threshold as usize > owners.len() {
+        return Err(MultisigError::InvalidThreshold.into());
+    }
+    Ok(())
+}
+
+// Where the proposal is in its lifecycle, the clock is only read when the expiry or the approval
+// TTL depends on it
+fn proposal_state(
+    transaction: &Transaction,
+    multisig: &Multisig,
+) -> Result<ProposalState, ProgramError> {
+    let now = match transaction.expires_at {
+        Some(_) => Clock::get()?.unix_timestamp,
+        None => approval_clock(multisig)?,
+    };
+    let state = transaction.state_at(multisig, now);
+    if let (ProposalState::Expired, Some(expires_at)) = (state, transaction.expires_at) {
+        msg!("Proposal expired at {}", expires_at);
+    }
+    Ok(state)
+}
+
+// The instruction may move the proposal to `next` from where it is now
+fn check_transition(
+    transaction: &Transaction,
+    multisig: &Multisig,
+    next: ProposalState,
+) -> ProgramResult {
+    Ok(proposal_state(transaction, multisig)?.check_transition(next)?)
+}
+
+fn check_not_paused(multisig: &Multisig) -> ProgramResult {
+    if multisig.paused {
+        msg!("Multisig is paused");
+        return Err(MultisigError::Paused.into());
+    }
+    Ok(())
+}
+
+fn process_create_token_account(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    mint: Pubkey,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+    let token_account = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let associated_token_program = next_account_info(account_info_iter)?;
+
+    // Verify accounts
+    if !multisig_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if mint_account.key != &mint {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+
+    let (vault_key, _) = find_vault_address(multisig_account.key, program_id);
+    if vault.key != &vault_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let expected_token_account =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &vault_key,
+            &mint,
+            token_program.key,
+        );
+    if token_account.key != &expected_token_account {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_threshold(&multisig)?;
+
+    // Create the ATA, the payer funds the rent, the vault PDA becomes the wallet owner
+    invoke(
+        &spl_associated_token_account::instruction::create_associated_token_account(
+            payer.key,
+            &vault_key,
+            &mint,
+            token_program.key,
+        ),
+        &[
+            payer.clone(),
+            token_account.clone(),
+            vault.clone(),
+            mint_account.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ],
+    )?;
+
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    let mut data = multisig_account.try_borrow_mut_data()?;
+    save(&multisig, &mut data)?;
+
+    Ok(())
+}
+
+fn process_swap_owner(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    old_owner: Pubkey,
+    new_owner: Pubkey,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+
+    if !multisig_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_config_threshold(&multisig)?;
+
+    let index = multisig
+        .owners
+        .iter()
+        .position(|owner| owner == &old_owner)
+        .ok_or(MultisigError::OwnerNotFound)?;
+    if multisig.owners.contains(&new_owner) {
+        return Err(MultisigError::DuplicateOwner.into());
+    }
+
+    multisig.owners[index] = new_owner;
+    multisig.owner_set_changed();
+    update_swapped_owner(
+        program_id,
+        multisig_account.key,
+        &multisig.owners,
+        index,
+        &old_owner,
+        account_info_iter,
+    )?;
+
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    let mut data = multisig_account.try_borrow_mut_data()?;
+    save(&multisig, &mut data)?;
+
+    Ok(())
+}
+
+// After `old_owner` at `index` was replaced (`owners` is the new set): the owner indexes of both
+// keys among `accounts` are updated, the other accounts are pending proposals
+fn update_swapped_owner(
+    program_id: &Pubkey,
+    multisig_key: &Pubkey,
+    owners: &[Pubkey],
+    index: usize,
+    old_owner: &Pubkey,
+    accounts: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let (old_index_key, _) = find_owner_index_address(old_owner, program_id);
+    let (new_index_key, _) = find_owner_index_address(&owners[index], program_id);
+    for account in accounts {
+        if account.key == &old_index_key {
+            unlist_from_owner_index(program_id, account, multisig_key)?;
+        } else if account.key == &new_index_key {
+            list_in_owner_index(program_id, account, multisig_key, owners)?;
+        } else {
+            revoke_pending_approvals(program_id, multisig_key, index, account)?;
+        }
+    }
+    Ok(())
+}
+
+// The multisig of a vault-signed instruction (`ChangeThreshold`, `AddOwner`, `SetProgramConfig`)
+// with its main vault signing: the program only signs for the vault when executing an approved
+// proposal
+fn check_vault_governed(
+    program_id: &Pubkey,
+    multisig_account: &AccountInfo,
+    vault: &AccountInfo,
+) -> ProgramResult {
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (vault_key, _) = find_vault_address(multisig_account.key, program_id);
+    if vault.key != &vault_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !vault.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+fn process_change_threshold(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    threshold: u8,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+
+    if !multisig_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    check_vault_governed(program_id, multisig_account, vault)?;
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_owner_set(&multisig.owners, threshold)?;
+    // the large-transfer threshold can't end up below the regular one
+    if let Some(policy) = &multisig.amount_policy {
+        if policy.large_threshold < threshold {
+            msg!(
+                "Threshold {} is above the large threshold {}",
+                threshold,
+                policy.large_threshold
+            );
+            return Err(MultisigError::InvalidThreshold.into());
+        }
+    }
+
+    multisig.threshold = threshold;
+    multisig.owner_set_changed();
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    save(&multisig, &mut multisig_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+fn process_add_owner(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    owner: Pubkey,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+
+    if !multisig_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    check_vault_governed(program_id, multisig_account, vault)?;
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+
+    multisig.owners.push(owner);
+    multisig.signed_at.push(0);
+    check_owner_set(&multisig.owners, multisig.threshold)?;
+    multisig.owner_set_changed();
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    save(&multisig, &mut multisig_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+fn process_init_program_config(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    mut config: ProgramConfig,
+) -> ProgramResult {
+    let payer = next_account_info(account_info_iter)?;
+    let upgrade_authority = next_account_info(account_info_iter)?;
+    let programdata_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !upgrade_authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    // only whoever can upgrade the program already has the power the config hands out
+    let (programdata_key, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if programdata_account.key != &programdata_key
+        || programdata_account.owner != &bpf_loader_upgradeable::id()
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if programdata_upgrade_authority(&programdata_account.data.borrow())?
+        != Some(*upgrade_authority.key)
+    {
+        msg!("Only the program's upgrade authority can create its config");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if config.protocol_fee_bps > MAX_PROTOCOL_FEE_BPS {
+        return Err(MultisigError::InvalidConfig.into());
+    }
+
+    let (config_key, bump) = find_program_config_address(program_id);
+    if config_account.key != &config_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if config_account.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        program_id,
+        payer,
+        config_account,
+        system_program,
+        ProgramConfig::LEN,
+        &[&[PROGRAM_CONFIG_SEED, &[bump]]],
+    )?;
+    config.bump = bump;
+    save(&config, &mut config_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+fn process_set_program_config(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    governance: Pubkey,
+    fee_destination: Pubkey,
+    protocol_fee_bps: u16,
+    paused: bool,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+
+    if !config_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut config = load_governed_config(program_id, multisig_account, vault, config_account)?;
+    if protocol_fee_bps > MAX_PROTOCOL_FEE_BPS {
+        return Err(MultisigError::InvalidConfig.into());
+    }
+
+    config.governance = governance;
+    config.fee_destination = fee_destination;
+    config.protocol_fee_bps = protocol_fee_bps;
+    config.paused = paused;
+    save(&config, &mut config_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+fn process_set_fee_exempt(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    exempt: bool,
+) -> ProgramResult {
+    let governance_account = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+
+    load_governed_config(program_id, governance_account, vault, config_account)?;
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !multisig_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    multisig.fee_exempt = exempt;
+    save(&multisig, &mut multisig_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+// The `ProgramConfig`, for an instruction signed by the main vault of its governance multisig
+fn load_governed_config(
+    program_id: &Pubkey,
+    multisig_account: &AccountInfo,
+    vault: &AccountInfo,
+    config_account: &AccountInfo,
+) -> Result<ProgramConfig, ProgramError> {
+    check_vault_governed(program_id, multisig_account, vault)?;
+    if config_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (config_key, _) = find_program_config_address(program_id);
+    if config_account.key != &config_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let config = load::<ProgramConfig>(&config_account.data.borrow())?;
+    if config.governance != *multisig_account.key {
+        msg!("The program config is governed by {}", config.governance);
+        return Err(MultisigError::WrongMultisig.into());
+    }
+    Ok(config)
+}
+
+// The approval vectors are indexed by owner, when the owner at `index` is replaced the approvals
+// it gave on the pending proposal would otherwise count for the new key
+fn revoke_pending_approvals(
+    program_id: &Pubkey,
+    multisig_key: &Pubkey,
+    index: usize,
+    transaction_account: &AccountInfo,
+) -> ProgramResult {
+    if transaction_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !transaction_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut transaction = load::<Transaction>(&transaction_account.data.borrow())?;
+    if transaction.multisig != *multisig_key {
+        return Err(MultisigError::WrongMultisig.into());
+    }
+    if transaction.executed {
+        return Ok(());
+    }
+    transaction.signers[index] = false;
+    let mut data = transaction_account.try_borrow_mut_data()?;
+    save(&transaction, &mut data)?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_propose(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    action: ProposalAction,
+    vault: u8,
+    expires_at: Option<i64>,
+    required_signers: &[Pubkey],
+    executor_fee: u64,
+    attachment: Option<[u8; 32]>,
+) -> ProgramResult {
+    let proposer = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+    let transaction_account = next_account_info(account_info_iter)?;
+
+    if !proposer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if multisig_account.owner != program_id || transaction_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !transaction_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    // a proposal account can only be initialized once
+    if transaction_account
+        .data
+        .borrow()
+        .iter()
+        .any(|&byte| byte != 0)
+    {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    let proposer_index = multisig
+        .owners
+        .iter()
+        .position(|owner| owner == proposer.key)
+        .ok_or(MultisigError::OwnerNotFound)?;
+
+    if let Some(destination) = action.destination() {
+        check_destination(
+            program_id,
+            multisig_account.key,
+            Some(transaction_account.key),
+            &destination,
+        )?;
+    }
+    if let Some((amount, lamports)) = action.transfer_amount() {
+        multisig.check_transfer_amount(amount, lamports)?;
+    }
+
+    match &action {
+        ProposalAction::DeployProgram {
+            buffer,
+            buffer_hash: expected_hash,
+            ..
+        }
+        | ProposalAction::UpgradeProgram {
+            buffer,
+            buffer_hash: expected_hash,
+            ..
+        } => {
+            // approvers must see the hash of the bytes actually sitting in the buffer
+            let buffer_account = next_account_info(account_info_iter)?;
+            if buffer_account.key != buffer {
+                return Err(MultisigError::AccountMismatch.into());
+            }
+            check_buffer_hash(buffer_account, expected_hash)?;
+
+            // only the vault may write to the buffer from now on, so the approved bytes stay put
+            let (vault_key, _) =
+                find_indexed_vault_address(multisig_account.key, vault, program_id);
+            if buffer_authority(&buffer_account.data.borrow())? != Some(vault_key) {
+                msg!("Buffer authority must be the vault {}", vault_key);
+                return Err(MultisigError::InvalidBufferAuthority.into());
+            }
+        }
+        ProposalAction::TransferToken { token_program, .. } if !is_token_program(token_program) => {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        ProposalAction::CreateRecurringPayment {
+            amount,
+            interval_seconds,
+            count,
+            ..
+        } if *amount == 0 || *interval_seconds <= 0 || *count == 0 => {
+            return Err(MultisigError::InvalidAction.into());
+        }
+        ProposalAction::SwapOwner {
+            old_owner,
+            new_owner,
+        } if !multisig.owners.contains(old_owner) || multisig.owners.contains(new_owner) => {
+            return Err(MultisigError::InvalidAction.into());
+        }
+        ProposalAction::CreateDistribution { total_cap: 0, .. } => {
+            return Err(MultisigError::InvalidAction.into());
+        }
+        ProposalAction::Batch { instructions }
+            if instructions.is_empty()
+                || instructions.iter().any(|ix| {
+                    ix.program_id == *program_id
+                        && !ix.is_config_change(program_id, multisig_account.key)
+                }) =>
+        {
+            msg!(
+                "A batch needs at least one instruction and can only call the multisig program \
+                 to change its own threshold or owners"
+            );
+            return Err(MultisigError::InvalidAction.into());
+        }
+        _ => {}
+    }
+
+    if let Some(expires_at) = expires_at {
+        if expires_at <= Clock::get()?.unix_timestamp {
+            msg!("Proposal would be expired already");
+            return Err(MultisigError::InvalidExpiry.into());
+        }
+    }
+
+    let mut required = vec![false; multisig.owners.len()];
+    for required_signer in required_signers {
+        let index = multisig
+            .owner_index(required_signer)
+            .ok_or(MultisigError::OwnerNotFound)?;
+        required[index] = true;
+    }
+
+    let mut signers = vec![false; multisig.owners.len()];
+    signers[proposer_index] = true;
+    let mut approved_at = vec![0; multisig.owners.len()];
+    approved_at[proposer_index] = Clock::get()?.unix_timestamp;
+    let transaction = Transaction {
+        multisig: *multisig_account.key,
+        proposer: *proposer.key,
+        action,
+        signers,
+        executed: false,
+        vault,
+        expires_at,
+        extended: false,
+        required_signers: required,
+        approved_at,
+        executor_fee,
+        owner_set_seqno: multisig.owner_set_seqno,
+        executed_at: 0,
+        executed_slot: 0,
+        executed_by: None,
+        attachment,
+    };
+
+    let mut data = transaction_account.try_borrow_mut_data()?;
+    save(&transaction, &mut data)?;
+    MultisigEvent::Proposed(ProposedEvent {
+        multisig: *multisig_account.key,
+        transaction: *transaction_account.key,
+        proposer: *proposer.key,
+    })
+    .emit();
+
+    // the remaining accounts are inboxes of owners to notify
+    for inbox_account in account_info_iter {
+        let mut inbox = load_inbox(program_id, inbox_account)?;
+        if !multisig.owners.contains(&inbox.owner) {
+            return Err(MultisigError::OwnerNotFound.into());
+        }
+        inbox.pending = true;
+        inbox.multisig = *multisig_account.key;
+        inbox.transaction = *transaction_account.key;
+        save(&inbox, &mut inbox_account.try_borrow_mut_data()?)?;
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_propose_indexed(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    action: ProposalAction,
+    vault: u8,
+    expires_at: Option<i64>,
+    required_signers: &[Pubkey],
+    executor_fee: u64,
+    attachment: Option<[u8; 32]>,
+) -> ProgramResult {
+    let proposer = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+    let transaction_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    let index = multisig.transaction_count;
+    let (transaction_key, bump) = find_transaction_address(multisig_account.key, index, program_id);
+    if transaction_account.key != &transaction_key {
+        msg!("Proposal account must be number {}", index);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_pda_account(
+        program_id,
+        proposer,
+        transaction_account,
+        system_program,
+        Transaction::space(&action),
+        &[&[
+            TRANSACTION_SEED,
+            multisig_account.key.as_ref(),
+            &index.to_le_bytes(),
+            &[bump],
+        ]],
+    )?;
+
+    // the rest is `Propose`, with the buffer and inboxes following the proposal account
+    let accounts: Vec<AccountInfo> = [proposer, multisig_account, transaction_account]
+        .into_iter()
+        .chain(account_info_iter)
+        .cloned()
+        .collect();
+    process_propose(
+        program_id,
+        &mut accounts.iter(),
+        action,
+        vault,
+        expires_at,
+        required_signers,
+        executor_fee,
+        attachment,
+    )?;
+
+    multisig.transaction_count = multisig
+        .transaction_count
+        .checked_add(1)
+        .ok_or(MultisigError::ArithmeticOverflow)?;
+    save(&multisig, &mut multisig_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+fn process_init_history(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let payer = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+    let history_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    load::<Multisig>(&multisig_account.data.borrow())?;
+    let (history_key, bump) = find_history_address(multisig_account.key, program_id);
+    if history_account.key != &history_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if history_account.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        program_id,
+        payer,
+        history_account,
+        system_program,
+        History::SPACE,
+        &[&[HISTORY_SEED, multisig_account.key.as_ref(), &[bump]]],
+    )?;
+    let history = History::new(*multisig_account.key, bump);
+    save(&history, &mut history_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+fn process_register_inbox(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let owner = next_account_info(account_info_iter)?;
+    let inbox_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (inbox_key, bump) = find_inbox_address(owner.key, program_id);
+    if inbox_account.key != &inbox_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if inbox_account.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let inbox = Inbox {
+        owner: *owner.key,
+        pending: false,
+        multisig: Pubkey::default(),
+        transaction: Pubkey::default(),
+    };
+    create_pda_account(
+        program_id,
+        owner,
+        inbox_account,
+        system_program,
+        Inbox::SPACE,
+        &[&[INBOX_SEED, owner.key.as_ref(), &[bump]]],
+    )?;
+    save(&inbox, &mut inbox_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+fn process_clear_inbox(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let owner = next_account_info(account_info_iter)?;
+    let inbox_account = next_account_info(account_info_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut inbox = load_inbox(program_id, inbox_account)?;
+    if inbox.owner != *owner.key {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    inbox.pending = false;
+    save(&inbox, &mut inbox_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+fn process_set_delegate(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    delegate: Pubkey,
+    expires_at: i64,
+    permissions: u8,
+) -> ProgramResult {
+    let owner = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+    let record_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    if multisig.owner_index(owner.key).is_none() {
+        return Err(MultisigError::OwnerNotFound.into());
+    }
+    if permissions == 0
+        || permissions & !DELEGATE_PERMISSIONS != 0
+        || expires_at <= Clock::get()?.unix_timestamp
+    {
+        return Err(MultisigError::InvalidConfig.into());
+    }
+    let (record_key, bump) = find_delegate_address(multisig_account.key, owner.key, program_id);
+    if record_account.key != &record_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if record_account.owner != program_id {
+        create_pda_account(
+            program_id,
+            owner,
+            record_account,
+            system_program,
+            Delegate::SPACE,
+            &[&[
+                DELEGATE_SEED,
+                multisig_account.key.as_ref(),
+                owner.key.as_ref(),
+                &[bump],
+            ]],
+        )?;
+    }
+
+    let record = Delegate {
+        multisig: *multisig_account.key,
+        owner: *owner.key,
+        delegate,
+        expires_at,
+        permissions,
+    };
+    save(&record, &mut record_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+fn process_revoke_delegate(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let owner = next_account_info(account_info_iter)?;
+    let record_account = next_account_info(account_info_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let record = load_delegate(program_id, record_account)?;
+    if record.owner != *owner.key {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    close_account(record_account, owner)
+}
+
+fn process_sign_as_delegate(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let delegate = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+    let record_account = next_account_info(account_info_iter)?;
+
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let record = check_delegate(
+        program_id,
+        delegate,
+        multisig_account,
+        record_account,
+        DELEGATE_SIGN,
+        now,
+    )?;
+    msg!("Sign by {} for {}", delegate.key, record.owner);
+    record_sign(multisig_account, &record.owner, now, true)
+}
+
+fn process_approve_as_delegate(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let delegate = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+    let transaction_account = next_account_info(account_info_iter)?;
+    let record_account = next_account_info(account_info_iter)?;
+
+    if multisig_account.owner != program_id || transaction_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let record = check_delegate(
+        program_id,
+        delegate,
+        multisig_account,
+        record_account,
+        DELEGATE_APPROVE,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    let transaction = load::<Transaction>(&transaction_account.data.borrow())?;
+    if transaction.multisig != *multisig_account.key {
+        return Err(MultisigError::WrongMultisig.into());
+    }
+    // changing the owner set or the multisig's settings is configuration, it takes the owners'
+    // own keys
+    let is_config_change = match &transaction.action {
+        ProposalAction::SwapOwner { .. } => true,
+        ProposalAction::Batch { instructions } => instructions
+            .iter()
+            .any(|ix| ix.is_config_change(program_id, multisig_account.key)),
+        _ => false,
+    };
+    if is_config_change {
+        return Err(MultisigError::DelegateNotPermitted.into());
+    }
+    check_transition(&transaction, &multisig, ProposalState::Active)?;
+
+    let owner_index = multisig
+        .owner_index(&record.owner)
+        .ok_or(MultisigError::OwnerNotFound)?;
+    msg!("Approval by {} for {}", delegate.key, record.owner);
+    record_approval(
+        account_info_iter,
+        transaction_account,
+        transaction,
+        owner_index,
+    )
+}
+
+// The delegate record of `multisig_account`, checked against its seeds, the signing `delegate`
+// and its expiry and permissions at `now`
+fn check_delegate(
+    program_id: &Pubkey,
+    delegate: &AccountInfo,
+    multisig_account: &AccountInfo,
+    record_account: &AccountInfo,
+    permission: u8,
+    now: i64,
+) -> Result<Delegate, ProgramError> {
+    if !delegate.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let record = load_delegate(program_id, record_account)?;
+    if record.multisig != *multisig_account.key || record.delegate != *delegate.key {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    record.check(permission, now)?;
+    Ok(record)
+}
+
+fn load_delegate(
+    program_id: &Pubkey,
+    record_account: &AccountInfo,
+) -> Result<Delegate, ProgramError> {
+    if record_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let record = load::<Delegate>(&record_account.data.borrow())?;
+    let (record_key, _) = find_delegate_address(&record.multisig, &record.owner, program_id);
+    if record_account.key != &record_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(record)
+}
+
+// A registered inbox, checked against its seeds
+fn process_register_owner_index(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let owner = next_account_info(account_info_iter)?;
+    let index_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (index_key, bump) = find_owner_index_address(owner.key, program_id);
+    if index_account.key != &index_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if index_account.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let mut index = OwnerIndex {
+        owner: *owner.key,
+        multisigs: vec![],
+    };
+    // the remaining accounts are multisigs the owner already belongs to
+    for multisig_account in account_info_iter {
+        if multisig_account.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+        if !multisig.owners.contains(owner.key) {
+            return Err(MultisigError::OwnerNotFound.into());
+        }
+        list_multisig(&mut index, multisig_account.key)?;
+    }
+    create_pda_account(
+        program_id,
+        owner,
+        index_account,
+        system_program,
+        OwnerIndex::SPACE,
+        &[&[OWNER_INDEX_SEED, owner.key.as_ref(), &[bump]]],
+    )?;
+    save(&index, &mut index_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+fn process_sync_owner_index(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let index_account = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+
+    let mut index = load_owner_index(program_id, index_account)?;
+    // a closed multisig account counts as one the owner left
+    let is_owner = multisig_account.owner == program_id
+        && load::<Multisig>(&multisig_account.data.borrow())?
+            .owners
+            .contains(&index.owner);
+    if is_owner {
+        list_multisig(&mut index, multisig_account.key)?;
+    } else {
+        index
+            .multisigs
+            .retain(|multisig| multisig != multisig_account.key);
+    }
+    save(&index, &mut index_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+fn process_unlist_multisig(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    multisig: &Pubkey,
+) -> ProgramResult {
+    let owner = next_account_info(account_info_iter)?;
+    let index_account = next_account_info(account_info_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let index = load_owner_index(program_id, index_account)?;
+    if index.owner != *owner.key {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    unlist_from_owner_index(program_id, index_account, multisig)
+}
+
+// Adds `multisig` (whose owner set is `owners`) to the index in `index_account`
+fn list_in_owner_index(
+    program_id: &Pubkey,
+    index_account: &AccountInfo,
+    multisig: &Pubkey,
+    owners: &[Pubkey],
+) -> ProgramResult {
+    let mut index = load_owner_index(program_id, index_account)?;
+    if !owners.contains(&index.owner) {
+        return Err(MultisigError::OwnerNotFound.into());
+    }
+    list_multisig(&mut index, multisig)?;
+    save(&index, &mut index_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+fn unlist_from_owner_index(
+    program_id: &Pubkey,
+    index_account: &AccountInfo,
+    multisig: &Pubkey,
+) -> ProgramResult {
+    let mut index = load_owner_index(program_id, index_account)?;
+    index.multisigs.retain(|listed| listed != multisig);
+    save(&index, &mut index_account.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+fn list_multisig(index: &mut OwnerIndex, multisig: &Pubkey) -> ProgramResult {
+    if index.multisigs.contains(multisig) {
+        return Ok(());
+    }
+    if index.multisigs.len() >= MAX_INDEXED_MULTISIGS {
+        return Err(MultisigError::OwnerIndexFull.into());
+    }
+    index.multisigs.push(*multisig);
+    Ok(())
+}
+
+fn load_owner_index(
+    program_id: &Pubkey,
+    index_account: &AccountInfo,
+) -> Result<OwnerIndex, ProgramError> {
+    if index_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !index_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let index = load::<OwnerIndex>(&index_account.data.borrow())?;
+    let (index_key, _) = find_owner_index_address(&index.owner, program_id);
+    if index_account.key != &index_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(index)
+}
+
+fn load_inbox(program_id: &Pubkey, inbox_account: &AccountInfo) -> Result<Inbox, ProgramError> {
+    if inbox_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let inbox = load::<Inbox>(&inbox_account.data.borrow())?;
+    let (inbox_key, _) = find_inbox_address(&inbox.owner, program_id);
+    if inbox_account.key != &inbox_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(inbox)
+}
+
+fn process_approve(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let owner = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+    let transaction_account = next_account_info(account_info_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if multisig_account.owner != program_id || transaction_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    let transaction = load::<Transaction>(&transaction_account.data.borrow())?;
+    if transaction.multisig != *multisig_account.key {
+        return Err(MultisigError::WrongMultisig.into());
+    }
+    check_transition(&transaction, &multisig, ProposalState::Active)?;
+
+    let owner_index = multisig
+        .owners
+        .iter()
+        .position(|key| key == owner.key)
+        .ok_or(MultisigError::OwnerNotFound)?;
+
+    record_approval(
+        account_info_iter,
+        transaction_account,
+        transaction,
+        owner_index,
+    )
+}
+
+// Approves as `owner`, who proved it by signing the proposal's digest off-chain; the Ed25519
+// check comes right before this instruction. Needs no signer, so anyone can replay the signature:
+// the digest carries no time, and a replay must not renew an approval the TTL lets expire
+fn process_approve_with_signature(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    owner: &Pubkey,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let transaction_account = next_account_info(account_info_iter)?;
+    let instructions_sysvar = next_account_info(account_info_iter)?;
+
+    if multisig_account.owner != program_id || transaction_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    let transaction = load::<Transaction>(&transaction_account.data.borrow())?;
+    if transaction.multisig != *multisig_account.key {
+        return Err(MultisigError::WrongMultisig.into());
+    }
+    check_transition(&transaction, &multisig, ProposalState::Active)?;
+
+    let owner_index = multisig
+        .owners
+        .iter()
+        .position(|key| key == owner)
+        .ok_or(MultisigError::OwnerNotFound)?;
+
+    let digest = transaction.digest(program_id, transaction_account.key);
+    check_ed25519_signature(instructions_sysvar, owner, digest.as_ref())?;
+
+    if transaction.signers[owner_index] {
+        msg!("{} approved already, its approval time is kept", owner);
+        return Ok(());
+    }
+    msg!("Approval signed off-chain by {}", owner);
+    record_approval(
+        account_info_iter,
+        transaction_account,
+        transaction,
+        owner_index,
+    )
+}
+
+// Records the approval of owner `owner_index`. Deploy and upgrade proposals take their buffer as
+// the next account and lose every approval instead when its bytes changed
+fn record_approval(
+    account_info_iter: &mut Iter<AccountInfo>,
+    transaction_account: &AccountInfo,
+    mut transaction: Transaction,
+    owner_index: usize,
+) -> ProgramResult {
+    match &transaction.action {
+        ProposalAction::DeployProgram {
+            buffer,
+            buffer_hash: expected_hash,
+            ..
+        }
+        | ProposalAction::UpgradeProgram {
+            buffer,
+            buffer_hash: expected_hash,
+            ..
+        } => {
+            let buffer_account = next_account_info(account_info_iter)?;
+            if buffer_account.key != buffer {
+                return Err(MultisigError::AccountMismatch.into());
+            }
+            // Approvals were given for other bytes, they don't count anymore. Returning an error
+            // would roll the reset back, so this one succeeds without recording the approval
+            if check_buffer_hash(buffer_account, expected_hash).is_err() {
+                msg!("Buffer changed since the proposal was created, approvals are reset");
+                transaction.signers = vec![false; transaction.signers.len()];
+                let mut data = transaction_account.try_borrow_mut_data()?;
+                save(&transaction, &mut data)?;
+                return Ok(());
+            }
+        }
+        _ => {}
+    }
+
+    transaction.signers[owner_index] = true;
+    transaction.approved_at[owner_index] = Clock::get()?.unix_timestamp;
+
+    let mut data = transaction_account.try_borrow_mut_data()?;
+    save(&transaction, &mut data)?;
+
+    Ok(())
+}
+
+fn process_extend_proposal(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    seconds: i64,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let transaction_account = next_account_info(account_info_iter)?;
+
+    if multisig_account.owner != program_id || transaction_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !(1..=MAX_PROPOSAL_EXTENSION).contains(&seconds) {
+        return Err(MultisigError::InvalidExpiry.into());
+    }
+
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    let mut transaction = load::<Transaction>(&transaction_account.data.borrow())?;
+    if transaction.multisig != *multisig_account.key {
+        return Err(MultisigError::WrongMultisig.into());
+    }
+    check_transition(&transaction, &multisig, ProposalState::Active)?;
+    let expires_at = match transaction.expires_at {
+        Some(expires_at) if !transaction.extended => expires_at,
+        _ => {
+            msg!("Proposal has no expiry or was extended already");
+            return Err(MultisigError::NotExtendable.into());
+        }
+    };
+
+    // the remaining accounts are the owners agreeing to the extension
+    let mut extenders = vec![false; multisig.owners.len()];
+    for owner in account_info_iter {
+        if !owner.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let index = multisig
+            .owner_index(owner.key)
+            .ok_or(MultisigError::OwnerNotFound)?;
+        extenders[index] = true;
+    }
+    let count = extenders.iter().filter(|&&extender| extender).count();
+    let with_proposer = multisig
+        .owner_index(&transaction.proposer)
+        .is_some_and(|index| extenders[index]);
+    // one short of the threshold, or the proposer backed by another owner
+    if count < (multisig.threshold as usize).saturating_sub(1).max(1)
+        && !(with_proposer && count >= 2)
+    {
+        return Err(MultisigError::InsufficientSignatures.into());
+    }
+
+    let expires_at = expires_at
+        .checked_add(seconds)
+        .ok_or(MultisigError::ArithmeticOverflow)?;
+    transaction.expires_at = Some(expires_at);
+    transaction.extended = true;
+    save(
+        &transaction,
+        &mut transaction_account.try_borrow_mut_data()?,
+    )?;
+    msg!("Proposal now expires at {}", expires_at);
+    Ok(())
+}
+
+fn process_execute_transaction(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    executor: Option<&Pubkey>,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let transaction_account = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+
+    if multisig_account.owner != program_id || transaction_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !transaction_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    let mut transaction = load::<Transaction>(&transaction_account.data.borrow())?;
+    if transaction.multisig != *multisig_account.key {
+        return Err(MultisigError::WrongMultisig.into());
+    }
+    let (vault_key, vault_bump) =
+        find_indexed_vault_address(multisig_account.key, transaction.vault, program_id);
+    if vault.key != &vault_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let state = proposal_state(&transaction, &multisig)?;
+    // an under-approved proposal gets the detailed diagnostics below instead
+    if state != ProposalState::Active {
+        state.check_transition(ProposalState::Executed)?;
+    }
+    check_not_paused(&multisig)?;
+
+    // approvals older than the approval TTL don't count
+    let live_signers = transaction.live_signers(&multisig, approval_clock(&multisig)?);
+    let approvals = live_signers.iter().filter(|&&signed| signed).count();
+    let lamports = transaction.action.lamports();
+    let threshold = multisig.threshold_for(lamports);
+    msg!(
+        "Approvals: {}, Required threshold: {}",
+        approvals,
+        threshold
+    );
+    if approvals < threshold as usize {
+        return Err(MultisigError::InsufficientSignatures.into());
+    }
+    let mut missing_required_signers = multisig
+        .owners
+        .iter()
+        .zip(transaction.required_signers.iter().zip(&live_signers))
+        .filter(|(_, (&required, &signed))| required && !signed)
+        .peekable();
+    if missing_required_signers.peek().is_some() {
+        for (owner, _) in missing_required_signers {
+            msg!("Required signer {} hasn't approved", owner);
+        }
+        return Err(MultisigError::RequiredSignerMissing.into());
+    }
+
+    // the payout counts against the outflow cap, the multisig must be writable to record it
+    if multisig.outflow_cap.is_some() && lamports > 0 {
+        if !multisig_account.is_writable {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        multisig.record_outflow(lamports, Clock::get()?.unix_timestamp)?;
+        save(&multisig, &mut multisig_account.try_borrow_mut_data()?)?;
+    }
+
+    // Mark as executed, and by whom, before any CPI
+    let clock = Clock::get()?;
+    transaction.executed = true;
+    transaction.executed_at = clock.unix_timestamp;
+    transaction.executed_slot = clock.slot;
+    transaction.executed_by = executor.copied();
+    save(
+        &transaction,
+        &mut transaction_account.try_borrow_mut_data()?,
+    )?;
+    // the document the owners approved along with the action
+    if let Some(attachment) = transaction.attachment_hex() {
+        msg!("Attachment: {}", attachment);
+    }
+    MultisigEvent::Executed(ExecutedEvent {
+        multisig: *multisig_account.key,
+        transaction: *transaction_account.key,
+        executed_by: transaction.executed_by,
+    })
+    .emit();
+
+    let vault_index = [transaction.vault];
+    let vault_bump = [vault_bump];
+    let mut signer_seeds = vault_seeds(multisig_account.key, &vault_index);
+    signer_seeds.push(&vault_bump);
+    let vault_seeds = signer_seeds.as_slice();
+
+    let vault_signer = VaultSigner {
+        account: vault,
+        key: vault_key,
+        seeds: vault_seeds,
+    };
+    match transaction.action {
+        ProposalAction::DeployProgram {
+            program,
+            buffer,
+            max_data_len,
+            buffer_hash: expected_hash,
+        } => execute_deploy(
+            account_info_iter,
+            &vault_signer,
+            program,
+            buffer,
+            max_data_len,
+            expected_hash,
+        )?,
+        ProposalAction::UpgradeProgram {
+            program,
+            buffer,
+            buffer_hash: expected_hash,
+        } => execute_upgrade(
+            account_info_iter,
+            &vault_signer,
+            program,
+            buffer,
+            expected_hash,
+        )?,
+        ProposalAction::TransferToken {
+            token_program,
+            mint,
+            destination,
+            amount,
+            max_fee,
+            expected_decimals,
+        } => execute_token_transfer(
+            account_info_iter,
+            &vault_signer,
+            token_program,
+            mint,
+            destination,
+            amount,
+            max_fee,
+            expected_decimals,
+        )?,
+        ProposalAction::CreateRecurringPayment {
+            destination,
+            amount,
+            interval_seconds,
+            count,
+        } => {
+            let recurring_account = next_account_info(account_info_iter)?;
+            let system_program_account = next_account_info(account_info_iter)?;
+
+            let (recurring_key, recurring_bump) =
+                find_recurring_payment_address(transaction_account.key, program_id);
+            if recurring_account.key != &recurring_key {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            // the vault pays the rent and gets it back after the last payment
+            create_pda_account(
+                program_id,
+                vault,
+                recurring_account,
+                system_program_account,
+                RecurringPayment::SPACE,
+                &[
+                    vault_seeds,
+                    &[
+                        RECURRING_SEED,
+                        transaction_account.key.as_ref(),
+                        &[recurring_bump],
+                    ],
+                ],
+            )?;
+            let recurring = RecurringPayment {
+                multisig: *multisig_account.key,
+                transaction: *transaction_account.key,
+                destination,
+                amount,
+                interval_seconds,
+                remaining: count,
+                next_payment_at: Clock::get()?
+                    .unix_timestamp
+                    .checked_add(interval_seconds)
+                    .ok_or(MultisigError::ArithmeticOverflow)?,
+                vault: transaction.vault,
+            };
+            save(&recurring, &mut recurring_account.try_borrow_mut_data()?)?;
+        }
+        ProposalAction::CreateDistribution {
+            merkle_root,
+            total_cap,
+        } => {
+            let distribution_account = next_account_info(account_info_iter)?;
+            let system_program_account = next_account_info(account_info_iter)?;
+
+            let (distribution_key, distribution_bump) =
+                find_distribution_address(transaction_account.key, program_id);
+            if distribution_account.key != &distribution_key {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            create_pda_account(
+                program_id,
+                vault,
+                distribution_account,
+                system_program_account,
+                Distribution::SPACE,
+                &[
+                    vault_seeds,
+                    &[
+                        DISTRIBUTION_SEED,
+                        transaction_account.key.as_ref(),
+                        &[distribution_bump],
+                    ],
+                ],
+            )?;
+            let distribution = Distribution {
+                multisig: *multisig_account.key,
+                transaction: *transaction_account.key,
+                vault: transaction.vault,
+                merkle_root,
+                total_cap,
+                claimed: 0,
+            };
+            save(
+                &distribution,
+                &mut distribution_account.try_borrow_mut_data()?,
+            )?;
+        }
+        ProposalAction::SwapOwner {
+            old_owner,
+            new_owner,
+        } => {
+            if !multisig_account.is_writable {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            // the owner set may have changed since the proposal was made
+            let mut multisig = multisig;
+            let index = multisig
+                .owner_index(&old_owner)
+                .ok_or(MultisigError::OwnerNotFound)?;
+            if multisig.owners.contains(&new_owner) {
+                return Err(MultisigError::DuplicateOwner.into());
+            }
+            multisig.owners[index] = new_owner;
+            multisig.signers[index] = false;
+            multisig.owner_set_changed();
+            update_swapped_owner(
+                program_id,
+                multisig_account.key,
+                &multisig.owners,
+                index,
+                &old_owner,
+                account_info_iter,
+            )?;
+            save(&multisig, &mut multisig_account.try_borrow_mut_data()?)?;
+        }
+        ProposalAction::StakeDelegate { .. }
+        | ProposalAction::StakeDeactivate { .. }
+        | ProposalAction::StakeSplit { .. }
+        | ProposalAction::StakeWithdraw { .. }
+        | ProposalAction::TransferWrappedSol { .. }
+        | ProposalAction::SetUpgradeAuthority { .. }
+        | ProposalAction::Batch { .. }
+        | ProposalAction::VoteAuthorize { .. }
+        | ProposalAction::VoteWithdraw { .. } => execute_vault_cpis(
+            multisig_account,
+            account_info_iter,
+            &vault_signer,
+            &transaction.action,
+        )?,
+    }
+
+    Ok(())
+}
+
+// `ExecuteTransaction`, then the vault pays the executor the proposal's fee
+fn process_crank_transaction(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let executor = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    if !executor.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut execute_accounts = account_info_iter.clone();
+    let multisig_account = next_account_info(&mut execute_accounts)?;
+    let transaction_account = next_account_info(&mut execute_accounts)?;
+    let vault = next_account_info(&mut execute_accounts)?;
+    process_execute_transaction(program_id, account_info_iter, Some(executor.key))?;
+
+    let transaction = load::<Transaction>(&transaction_account.data.borrow())?;
+    if transaction.executor_fee == 0 {
+        return Ok(());
+    }
+    msg!("Executor fee: {}", transaction.executor_fee);
+    let (_, vault_bump) =
+        find_indexed_vault_address(multisig_account.key, transaction.vault, program_id);
+    let vault_index = [transaction.vault];
+    let vault_bump = [vault_bump];
+    let mut signer_seeds = vault_seeds(multisig_account.key, &vault_index);
+    signer_seeds.push(&vault_bump);
+    invoke_signed(
+        &system_instruction::transfer(vault.key, executor.key, transaction.executor_fee),
+        &[
+            vault.clone(),
+            executor.clone(),
+            system_program_account.clone(),
+        ],
+        &[&signer_seeds],
+    )
+}
+
+// The vault a proposal executes from, with the seeds its CPIs are signed with
+struct VaultSigner<'a, 'b> {
+    account: &'a AccountInfo<'b>,
+    key: Pubkey,
+    seeds: &'a [&'a [u8]],
+}
+
+// The CPI heavy actions run in their own frames: SBF gives every call frame 4 KB of stack, and
+// kept inline the instructions, account lists and token state of all arms would share
+// `process_execute_transaction`'s frame whichever action runs
+#[inline(never)]
+fn execute_deploy<'a>(
+    account_info_iter: &mut Iter<AccountInfo<'a>>,
+    vault: &VaultSigner<'_, 'a>,
+    program: Pubkey,
+    buffer: Pubkey,
+    max_data_len: u64,
+    expected_hash: [u8; 32],
+) -> ProgramResult {
+    let program_account = next_account_info(account_info_iter)?;
+    let programdata_account = next_account_info(account_info_iter)?;
+    let buffer_account = next_account_info(account_info_iter)?;
+    let rent_sysvar = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let loader_program = next_account_info(account_info_iter)?;
+
+    if program_account.key != &program || buffer_account.key != &buffer {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    check_buffer_hash(buffer_account, &expected_hash)?;
+
+    // The program account must be created in the same transaction as the deploy,
+    // otherwise someone else could initialize it
+    let program_len = UpgradeableLoaderState::size_of_program();
+    invoke_signed(
+        &system_instruction::create_account(
+            &vault.key,
+            &program,
+            Rent::get()?.minimum_balance(program_len),
+            program_len as u64,
+            &bpf_loader_upgradeable::id(),
+        ),
+        &[
+            vault.account.clone(),
+            program_account.clone(),
+            system_program_account.clone(),
+        ],
+        &[vault.seeds],
+    )?;
+
+    let deploy_instruction = Instruction::new_with_bincode(
+        bpf_loader_upgradeable::id(),
+        &UpgradeableLoaderInstruction::DeployWithMaxDataLen {
+            max_data_len: max_data_len as usize,
+        },
+        vec![
+            AccountMeta::new(vault.key, true),
+            AccountMeta::new(*programdata_account.key, false),
+            AccountMeta::new(program, false),
+            AccountMeta::new(buffer, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(vault.key, true),
+        ],
+    );
+    invoke_signed(
+        &deploy_instruction,
+        &[
+            vault.account.clone(),
+            programdata_account.clone(),
+            program_account.clone(),
+            buffer_account.clone(),
+            rent_sysvar.clone(),
+            clock_sysvar.clone(),
+            system_program_account.clone(),
+            loader_program.clone(),
+        ],
+        &[vault.seeds],
+    )?;
+    Ok(())
+}
+
+#[inline(never)]
+fn execute_upgrade<'a>(
+    account_info_iter: &mut Iter<AccountInfo<'a>>,
+    vault: &VaultSigner<'_, 'a>,
+    program: Pubkey,
+    buffer: Pubkey,
+    expected_hash: [u8; 32],
+) -> ProgramResult {
+    let program_account = next_account_info(account_info_iter)?;
+    let programdata_account = next_account_info(account_info_iter)?;
+    let buffer_account = next_account_info(account_info_iter)?;
+    let rent_sysvar = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+    let loader_program = next_account_info(account_info_iter)?;
+
+    if program_account.key != &program || buffer_account.key != &buffer {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    check_buffer_hash(buffer_account, &expected_hash)?;
+
+    // buffer lamports are refunded ("spilled") to the vault
+    invoke_signed(
+        &bpf_loader_upgradeable::upgrade(&program, &buffer, &vault.key, &vault.key),
+        &[
+            programdata_account.clone(),
+            program_account.clone(),
+            buffer_account.clone(),
+            vault.account.clone(),
+            rent_sysvar.clone(),
+            clock_sysvar.clone(),
+            loader_program.clone(),
+        ],
+        &[vault.seeds],
+    )?;
+    Ok(())
+}
+
+#[inline(never)]
+#[allow(clippy::too_many_arguments)]
+fn execute_token_transfer<'a>(
+    account_info_iter: &mut Iter<AccountInfo<'a>>,
+    vault: &VaultSigner<'_, 'a>,
+    token_program: Pubkey,
+    mint: Pubkey,
+    destination: Pubkey,
+    amount: u64,
+    max_fee: u64,
+    expected_decimals: u8,
+) -> ProgramResult {
+    let source_account = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+    let destination_account = next_account_info(account_info_iter)?;
+    let token_program_account = next_account_info(account_info_iter)?;
+    // anything left is for the mint's transfer hook
+    let hook_accounts: Vec<AccountInfo> = account_info_iter.cloned().collect();
+
+    if token_program_account.key != &token_program || !is_token_program(&token_program) {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if mint_account.key != &mint
+        || mint_account.owner != &token_program
+        || destination_account.key != &destination
+    {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+
+    let (decimals, fee) = {
+        let mint_data = mint_account.data.borrow();
+        let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+        let fee = match mint_state.get_extension::<TransferFeeConfig>() {
+            Ok(fee_config) => Some(
+                fee_config
+                    .calculate_epoch_fee(Clock::get()?.epoch, amount)
+                    .ok_or(MultisigError::ArithmeticOverflow)?,
+            ),
+            Err(_) => None,
+        };
+        (mint_state.base.decimals, fee)
+    };
+    // an amount worked out for other decimals would move orders of magnitude more or less
+    if decimals != expected_decimals {
+        msg!(
+            "Mint has {} decimals, the proposal expects {}",
+            decimals,
+            expected_decimals
+        );
+        return Err(MultisigError::DecimalsMismatch.into());
+    }
+    let received = amount
+        .checked_sub(fee.unwrap_or(0))
+        .ok_or(MultisigError::ArithmeticOverflow)?;
+    msg!(
+        "Token transfer amount: {}, fee: {}, received: {}",
+        amount,
+        fee.unwrap_or(0),
+        received
+    );
+    if fee.unwrap_or(0) > max_fee {
+        msg!("Transfer fee above the approved maximum of {}", max_fee);
+        return Err(MultisigError::TransferFeeTooHigh.into());
+    }
+
+    // the fee is withheld from `amount`, so the vault needs exactly `amount`
+    let balance = {
+        let source_data = source_account.data.borrow();
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&source_data)?
+            .base
+            .amount
+    };
+    if balance < amount {
+        return Err(MultisigError::InsufficientBalance.into());
+    }
+
+    let mut transfer_instruction = match fee {
+        Some(fee) => {
+            spl_token_2022::extension::transfer_fee::instruction::transfer_checked_with_fee(
+                &token_program,
+                source_account.key,
+                &mint,
+                &destination,
+                &vault.key,
+                &[],
+                amount,
+                decimals,
+                fee,
+            )?
+        }
+        None => spl_token_2022::instruction::transfer_checked(
+            &token_program,
+            source_account.key,
+            &mint,
+            &destination,
+            &vault.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+    };
+    transfer_instruction
+        .accounts
+        .extend(hook_accounts.iter().map(|account| AccountMeta {
+            pubkey: *account.key,
+            is_signer: account.is_signer,
+            is_writable: account.is_writable,
+        }));
+
+    let mut transfer_accounts = vec![
+        source_account.clone(),
+        mint_account.clone(),
+        destination_account.clone(),
+        vault.account.clone(),
+        token_program_account.clone(),
+    ];
+    transfer_accounts.extend(hook_accounts);
+    invoke_signed(&transfer_instruction, &transfer_accounts, &[vault.seeds])?;
+    Ok(())
+}
+
+#[inline(never)]
+fn execute_vault_cpis<'a>(
+    multisig_account: &AccountInfo<'a>,
+    account_info_iter: &mut Iter<AccountInfo<'a>>,
+    vault: &VaultSigner<'_, 'a>,
+    action: &ProposalAction,
+) -> ProgramResult {
+    // the CPIs are fully determined by the approved action, the remaining accounts only
+    // need to contain what they reference. The multisig is there for a `Batch` changing it
+    let mut cpi_accounts = vec![vault.account.clone(), multisig_account.clone()];
+    cpi_accounts.extend(account_info_iter.cloned());
+    let cpi_instructions = vault_cpi_instructions(action, &vault.key).unwrap_or_default();
+    for (index, cpi_instruction) in cpi_instructions.iter().enumerate() {
+        check_cpi_accounts(index, cpi_instruction, &vault.key, &cpi_accounts)?;
+        invoke_signed(cpi_instruction, &cpi_accounts, &[vault.seeds])?;
+    }
+    Ok(())
+}
+
+// Every account the instruction lists is passed with the privileges it asks for (the vault signs
+// through its seeds), so a missing one is named instead of failing somewhere inside the CPI
+fn check_cpi_accounts(
+    index: usize,
+    instruction: &Instruction,
+    vault: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let find = |key: &Pubkey| accounts.iter().find(|account| account.key == key);
+    if find(&instruction.program_id).is_none() {
+        msg!(
+            "Instruction {} needs program {}",
+            index,
+            instruction.program_id
+        );
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    for meta in &instruction.accounts {
+        let passed = find(&meta.pubkey).is_some_and(|account| {
+            (account.is_writable || !meta.is_writable)
+                && (account.is_signer || !meta.is_signer || meta.pubkey == *vault)
+        });
+        if !passed {
+            msg!(
+                "Instruction {} needs account {} (signer: {}, writable: {})",
+                index,
+                meta.pubkey,
+                meta.is_signer,
+                meta.is_writable
+            );
+            return Err(MultisigError::AccountMismatch.into());
+        }
+    }
+    Ok(())
+}
+
+fn process_execute_recurring(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let recurring_account = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+    let destination_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if multisig_account.owner != program_id || recurring_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !recurring_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut recurring = load::<RecurringPayment>(&recurring_account.data.borrow())?;
+    if recurring.multisig != *multisig_account.key {
+        return Err(MultisigError::WrongMultisig.into());
+    }
+    if destination_account.key != &recurring.destination {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    let (vault_key, vault_bump) =
+        find_indexed_vault_address(multisig_account.key, recurring.vault, program_id);
+    if vault.key != &vault_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_not_paused(&multisig)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    if now < recurring.next_payment_at {
+        msg!("Next payment is due at {}", recurring.next_payment_at);
+        return Err(MultisigError::PaymentNotDue.into());
+    }
+
+    // keeps the cadence, missed payments can be cranked one after the other. Counted before the
+    // payment so it can't be made twice
+    recurring.remaining = recurring
+        .remaining
+        .checked_sub(1)
+        .ok_or(MultisigError::ArithmeticOverflow)?;
+    recurring.next_payment_at = recurring
+        .next_payment_at
+        .checked_add(recurring.interval_seconds)
+        .ok_or(MultisigError::ArithmeticOverflow)?;
+    save(&recurring, &mut recurring_account.try_borrow_mut_data()?)?;
+
+    let vault_index = [recurring.vault];
+    let vault_bump = [vault_bump];
+    let mut signer_seeds = vault_seeds(multisig_account.key, &vault_index);
+    signer_seeds.push(&vault_bump);
+    invoke_signed(
+        &system_instruction::transfer(&vault_key, destination_account.key, recurring.amount),
+        &[
+            vault.clone(),
+            destination_account.clone(),
+            system_program_account.clone(),
+        ],
+        &[&signer_seeds],
+    )?;
+
+    record_receipt(
+        program_id,
+        account_info_iter,
+        system_program_account,
+        multisig_account.key,
+        &recurring.destination,
+        recurring.amount,
+        recurring.transaction,
+    )?;
+
+    msg!("Recurring payment made, {} remaining", recurring.remaining);
+    if recurring.remaining == 0 {
+        return close_account(recurring_account, vault);
+    }
+    Ok(())
+}
+
+fn process_claim_distribution(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let distribution_account = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+    let recipient = next_account_info(account_info_iter)?;
+    let claim_account = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if multisig_account.owner != program_id || distribution_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut distribution = load::<Distribution>(&distribution_account.data.borrow())?;
+    if distribution.multisig != *multisig_account.key {
+        return Err(MultisigError::WrongMultisig.into());
+    }
+    let (vault_key, vault_bump) =
+        find_indexed_vault_address(multisig_account.key, distribution.vault, program_id);
+    if vault.key != &vault_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let (claim_key, claim_bump) =
+        find_claim_address(distribution_account.key, recipient.key, program_id);
+    if claim_account.key != &claim_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if claim_account.owner == program_id {
+        msg!("{} already claimed", recipient.key);
+        return Err(MultisigError::AlreadyClaimed.into());
+    }
+
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_not_paused(&multisig)?;
+
+    let leaf = merkle::leaf(recipient.key, amount);
+    if !merkle::verify(&distribution.merkle_root, leaf, &proof) {
+        msg!("Invalid proof for {} lamports to {}", amount, recipient.key);
+        return Err(MultisigError::InvalidProof.into());
+    }
+    let claimed = distribution
+        .claimed
+        .checked_add(amount)
+        .filter(|claimed| *claimed <= distribution.total_cap)
+        .ok_or_else(|| {
+            msg!("Claim would exceed the distribution cap");
+            MultisigError::DistributionCapExceeded
+        })?;
+
+    create_pda_account(
+        program_id,
+        payer,
+        claim_account,
+        system_program_account,
+        Claim::SPACE,
+        &[&[
+            CLAIM_SEED,
+            distribution_account.key.as_ref(),
+            recipient.key.as_ref(),
+            &[claim_bump],
+        ]],
+    )?;
+    let claim = Claim {
+        distribution: *distribution_account.key,
+        recipient: *recipient.key,
+        amount,
+    };
+    save(&claim, &mut claim_account.try_borrow_mut_data()?)?;
+
+    let vault_index = [distribution.vault];
+    let vault_bump = [vault_bump];
+    let mut signer_seeds = vault_seeds(multisig_account.key, &vault_index);
+    signer_seeds.push(&vault_bump);
+    invoke_signed(
+        &system_instruction::transfer(&vault_key, recipient.key, amount),
+        &[
+            vault.clone(),
+            recipient.clone(),
+            system_program_account.clone(),
+        ],
+        &[&signer_seeds],
+    )?;
+
+    distribution.claimed = claimed;
+    save(
+        &distribution,
+        &mut distribution_account.try_borrow_mut_data()?,
+    )?;
+    Ok(())
+}
+
+fn process_set_veto_authority(
+    accounts: SetterAccounts,
+    veto_authority: Option<Pubkey>,
+) -> ProgramResult {
+    let SetterAccounts {
+        multisig: multisig_account,
+        payer,
+        system_program,
+        ..
+    } = accounts;
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_config_threshold(&multisig)?;
+
+    // an owner holding the veto as well could approve, which the role is meant to rule out
+    if let Some(authority) = &veto_authority {
+        if multisig.owners.contains(authority) {
+            return Err(MultisigError::VetoAuthorityIsOwner.into());
+        }
+    }
+
+    multisig.veto_authority = veto_authority;
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    save_resized(multisig_account, payer, system_program, &multisig)
+}
+
+fn process_set_paused(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    paused: bool,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+
+    if !multisig_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    // the emergency key can only freeze, lifting the freeze always takes the owners
+    if paused && multisig.emergency_authority == Some(*authority.key) {
+        msg!("Paused by the emergency authority");
+    } else {
+        check_config_threshold(&multisig)?;
+        // Approvals are consumed, same as after `Execute`
+        multisig.signers = vec![false; multisig.owners.len()];
+    }
+    multisig.paused = paused;
+
+    let mut data = multisig_account.try_borrow_mut_data()?;
+    save(&multisig, &mut data)?;
+    Ok(())
+}
+
+fn process_set_emergency_authority(
+    accounts: SetterAccounts,
+    emergency_authority: Option<Pubkey>,
+) -> ProgramResult {
+    let SetterAccounts {
+        multisig: multisig_account,
+        payer,
+        system_program,
+        ..
+    } = accounts;
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_config_threshold(&multisig)?;
+
+    multisig.emergency_authority = emergency_authority;
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    save_resized(multisig_account, payer, system_program, &multisig)
+}
+
+fn process_set_policy(accounts: SetterAccounts, policy: Option<AmountPolicy>) -> ProgramResult {
+    let SetterAccounts {
+        multisig: multisig_account,
+        payer,
+        system_program,
+        ..
+    } = accounts;
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_config_threshold(&multisig)?;
+    if let Some(policy) = policy {
+        if policy.large_threshold < multisig.threshold
+            || policy.large_threshold as usize > multisig.owners.len()
+        {
+            msg!(
+                "Large threshold {} must be between {} and {}",
+                policy.large_threshold,
+                multisig.threshold,
+                multisig.owners.len()
+            );
+            return Err(MultisigError::InvalidThreshold.into());
+        }
+    }
+
+    multisig.amount_policy = policy;
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    save_resized(multisig_account, payer, system_program, &multisig)
+}
+
+fn process_set_approval_ttl(accounts: SetterAccounts, ttl_seconds: Option<i64>) -> ProgramResult {
+    let SetterAccounts {
+        multisig: multisig_account,
+        payer,
+        system_program,
+        ..
+    } = accounts;
+    if ttl_seconds.is_some_and(|ttl| ttl <= 0) {
+        return Err(MultisigError::InvalidConfig.into());
+    }
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_config_threshold(&multisig)?;
+
+    multisig.approval_ttl_seconds = ttl_seconds;
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    save_resized(multisig_account, payer, system_program, &multisig)
+}
+
+fn process_set_policy_signer(
+    accounts: SetterAccounts,
+    policy_signer: Option<Pubkey>,
+) -> ProgramResult {
+    let SetterAccounts {
+        multisig: multisig_account,
+        payer,
+        system_program,
+        ..
+    } = accounts;
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_config_threshold(&multisig)?;
+
+    multisig.policy_signer = policy_signer;
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    save_resized(multisig_account, payer, system_program, &multisig)
+}
+
+fn process_set_outflow_cap(accounts: SetterAccounts, cap: Option<OutflowCap>) -> ProgramResult {
+    let SetterAccounts {
+        multisig: multisig_account,
+        payer,
+        system_program,
+        ..
+    } = accounts;
+    if cap.is_some_and(|cap| cap.period_seconds <= 0) {
+        return Err(MultisigError::InvalidConfig.into());
+    }
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_config_threshold(&multisig)?;
+
+    // a changed cap applies to the running period, only lifting it clears the count
+    multisig.outflow_cap = cap;
+    if cap.is_none() {
+        multisig.spent_this_period = 0;
+        multisig.period_start = 0;
+    }
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    save_resized(multisig_account, payer, system_program, &multisig)
+}
+
+fn process_set_transfer_minimum(
+    accounts: SetterAccounts,
+    allow_zero_amount: bool,
+    min_transfer: u64,
+) -> ProgramResult {
+    let SetterAccounts {
+        multisig: multisig_account,
+        payer,
+        system_program,
+        ..
+    } = accounts;
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_config_threshold(&multisig)?;
+
+    multisig.allow_zero_amount = allow_zero_amount;
+    multisig.min_transfer = min_transfer;
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    save_resized(multisig_account, payer, system_program, &multisig)
+}
+
+fn process_set_strict_signing(accounts: SetterAccounts, strict: bool) -> ProgramResult {
+    let SetterAccounts {
+        multisig: multisig_account,
+        payer,
+        system_program,
+        ..
+    } = accounts;
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_config_threshold(&multisig)?;
+
+    multisig.strict_signing = strict;
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    save_resized(multisig_account, payer, system_program, &multisig)
+}
+
+fn process_set_display_unit(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    mint: Pubkey,
+    decimals: u8,
+    symbol: String,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let display_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !multisig_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if symbol.len() > MAX_DISPLAY_SYMBOL_LEN {
+        return Err(MultisigError::InvalidConfig.into());
+    }
+    let (display_key, bump) = find_display_unit_address(multisig_account.key, program_id);
+    if display_account.key != &display_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_config_threshold(&multisig)?;
+
+    let display_unit = DisplayUnit {
+        multisig: *multisig_account.key,
+        mint,
+        decimals,
+        symbol,
+    };
+    if display_account.owner != program_id {
+        create_pda_account(
+            program_id,
+            payer,
+            display_account,
+            system_program,
+            display_unit.try_to_vec()?.len(),
+            &[&[DISPLAY_SEED, multisig_account.key.as_ref(), &[bump]]],
+        )?;
+    }
+    save_resized(display_account, payer, system_program, &display_unit)?;
+
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    let mut data = multisig_account.try_borrow_mut_data()?;
+    save(&multisig, &mut data)?;
+    Ok(())
+}
+
+fn process_set_metadata(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    name: String,
+    uri: String,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let metadata_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !multisig_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if name.len() > MAX_METADATA_NAME_LEN || uri.len() > MAX_METADATA_URI_LEN {
+        return Err(MultisigError::InvalidConfig.into());
+    }
+    let (metadata_key, bump) = find_metadata_address(multisig_account.key, program_id);
+    if metadata_account.key != &metadata_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_config_threshold(&multisig)?;
+
+    let metadata = Metadata {
+        multisig: *multisig_account.key,
+        name,
+        uri,
+    };
+    if metadata_account.owner != program_id {
+        create_pda_account(
+            program_id,
+            payer,
+            metadata_account,
+            system_program,
+            metadata.try_to_vec()?.len(),
+            &[&[METADATA_SEED, multisig_account.key.as_ref(), &[bump]]],
+        )?;
+    }
+    save_resized(metadata_account, payer, system_program, &metadata)?;
+
+    multisig.signers = vec![false; multisig.owners.len()];
+    let mut data = multisig_account.try_borrow_mut_data()?;
+    save(&multisig, &mut data)?;
+    Ok(())
+}
+
+fn process_veto_transaction(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let veto_authority = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+    let transaction_account = next_account_info(account_info_iter)?;
+    let proposer = next_account_info(account_info_iter)?;
+
+    if !veto_authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if multisig_account.owner != program_id || transaction_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !transaction_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    if multisig.veto_authority != Some(*veto_authority.key) {
+        return Err(MultisigError::NotVetoAuthority.into());
+    }
+
+    let transaction = load::<Transaction>(&transaction_account.data.borrow())?;
+    if transaction.multisig != *multisig_account.key {
+        return Err(MultisigError::WrongMultisig.into());
+    }
+    check_transition(&transaction, &multisig, ProposalState::Cancelled)?;
+    if proposer.key != &transaction.proposer {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+
+    msg!("Proposal vetoed by {}", veto_authority.key);
+    close_account(transaction_account, proposer)
+}
+
+fn process_close_transaction(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let transaction_account = next_account_info(account_info_iter)?;
+    let proposer = next_account_info(account_info_iter)?;
+
+    if multisig_account.owner != program_id || transaction_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !transaction_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    let transaction = load::<Transaction>(&transaction_account.data.borrow())?;
+    if transaction.multisig != *multisig_account.key {
+        return Err(MultisigError::WrongMultisig.into());
+    }
+    if proposer.key != &transaction.proposer {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    match transaction.finalized_at(&multisig, now) {
+        Some(finalized_at) if now >= finalized_at.saturating_add(CLOSE_GRACE_PERIOD) => {}
+        Some(finalized_at) => {
+            msg!(
+                "Closable from {}",
+                finalized_at.saturating_add(CLOSE_GRACE_PERIOD)
+            );
+            return Err(MultisigError::ProposalNotClosable.into());
+        }
+        None => {
+            msg!("Proposal can still be approved or executed");
+            return Err(MultisigError::ProposalNotClosable.into());
+        }
+    }
+
+    close_account(transaction_account, proposer)
+}
+
+fn process_update_destination_policy(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    update: impl FnOnce(&mut DestinationPolicy) -> ProgramResult,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let policy_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !multisig_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_config_threshold(&multisig)?;
+
+    let mut policy =
+        match load_destination_policy(program_id, multisig_account.key, policy_account)? {
+            Some(policy) => policy,
+            None => {
+                let (_, bump) = find_destination_policy_address(multisig_account.key, program_id);
+                let policy = DestinationPolicy {
+                    multisig: *multisig_account.key,
+                    mode: DestinationMode::Allowlist,
+                    destinations: vec![],
+                    enforcement: PolicyEnforcement::Enforce,
+                };
+                create_pda_account(
+                    program_id,
+                    payer,
+                    policy_account,
+                    system_program,
+                    policy.try_to_vec()?.len(),
+                    &[&[DESTINATIONS_SEED, multisig_account.key.as_ref(), &[bump]]],
+                )?;
+                policy
+            }
+        };
+    update(&mut policy)?;
+    save_resized(policy_account, payer, system_program, &policy)?;
+
+    // Approvals are consumed, same as after `Execute`
+    multisig.signers = vec![false; multisig.owners.len()];
+    let mut data = multisig_account.try_borrow_mut_data()?;
+    save(&multisig, &mut data)?;
+
+    Ok(())
+}
+
+// Fails with `PolicyViolation` when enforced, only logs the violation in shadow mode
+fn policy_violation(enforcement: PolicyEnforcement, violation: &str) -> ProgramResult {
+    match enforcement {
+        PolicyEnforcement::Enforce => {
+            msg!("Policy violation: {}", violation);
+            Err(MultisigError::PolicyViolation.into())
+        }
+        PolicyEnforcement::Shadow => {
+            msg!("Shadow policy violation: {}", violation);
+            Ok(())
+        }
+    }
+}
+
+// `None` when the multisig never set a policy (nothing at the PDA yet)
+fn load_destination_policy(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    policy_account: &AccountInfo,
+) -> Result<Option<DestinationPolicy>, ProgramError> {
+    let (policy_key, _) = find_destination_policy_address(multisig, program_id);
+    if policy_account.key != &policy_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if policy_account.owner != program_id {
+        return Ok(None);
+    }
+    Ok(Some(load::<DestinationPolicy>(
+        &policy_account.data.borrow(),
+    )?))
+}
+
+fn process_init_guardians(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    guardians: Vec<Pubkey>,
+    recovery_delay: i64,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let guardians_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    // only whoever holds the multisig keypair, i.e. the creator in the creation transaction
+    if !multisig_account.is_signer || !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if guardians.is_empty() || recovery_delay < 0 {
+        return Err(MultisigError::InvalidConfig.into());
+    }
+
+    let (guardians_key, bump) = find_guardians_address(multisig_account.key, program_id);
+    if guardians_account.key != &guardians_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if guardians_account.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let guardians = Guardians {
+        multisig: *multisig_account.key,
+        guardians,
+        recovery_delay,
+    };
+    create_pda_account(
+        program_id,
+        payer,
+        guardians_account,
+        system_program,
+        Guardians::space(guardians.guardians.len()),
+        &[&[GUARDIANS_SEED, multisig_account.key.as_ref(), &[bump]]],
+    )?;
+    let mut data = guardians_account.try_borrow_mut_data()?;
+    save(&guardians, &mut data)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "aggregated-approval")]
+fn process_init_aggregate_key(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    aggregate_key: Pubkey,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let aggregate_key_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    // only whoever holds the multisig keypair, i.e. the creator in the creation transaction
+    if !multisig_account.is_signer || !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let (aggregate_key_address, bump) =
+        find_aggregate_key_address(multisig_account.key, program_id);
+    if aggregate_key_account.key != &aggregate_key_address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if aggregate_key_account.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    let aggregate = AggregateKey {
+        multisig: *multisig_account.key,
+        key: aggregate_key,
+        owner_set_hash: owner_set_hash(&multisig).to_bytes(),
+    };
+    create_pda_account(
+        program_id,
+        payer,
+        aggregate_key_account,
+        system_program,
+        AggregateKey::SPACE,
+        &[&[AGGREGATE_SEED, multisig_account.key.as_ref(), &[bump]]],
+    )?;
+    let mut data = aggregate_key_account.try_borrow_mut_data()?;
+    save(&aggregate, &mut data)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "aggregated-approval")]
+fn process_approve_aggregated(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let transaction_account = next_account_info(account_info_iter)?;
+    let aggregate_key_account = next_account_info(account_info_iter)?;
+    let instructions_sysvar = next_account_info(account_info_iter)?;
+
+    if multisig_account.owner != program_id
+        || transaction_account.owner != program_id
+        || aggregate_key_account.owner != program_id
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (aggregate_key_address, _) = find_aggregate_key_address(multisig_account.key, program_id);
+    if aggregate_key_account.key != &aggregate_key_address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    let aggregate = load::<AggregateKey>(&aggregate_key_account.data.borrow())?;
+    if aggregate.owner_set_hash != owner_set_hash(&multisig).to_bytes() {
+        msg!("Owner set changed since the aggregate key was registered");
+        return Err(MultisigError::OwnerSetChanged.into());
+    }
+
+    let mut transaction = load::<Transaction>(&transaction_account.data.borrow())?;
+    if transaction.multisig != *multisig_account.key {
+        return Err(MultisigError::WrongMultisig.into());
+    }
+    check_transition(&transaction, &multisig, ProposalState::Active)?;
+    match &transaction.action {
+        ProposalAction::DeployProgram {
+            buffer,
+            buffer_hash: expected_hash,
+            ..
+        }
+        | ProposalAction::UpgradeProgram {
+            buffer,
+            buffer_hash: expected_hash,
+            ..
+        } => {
+            let buffer_account = next_account_info(account_info_iter)?;
+            if buffer_account.key != buffer {
+                return Err(MultisigError::AccountMismatch.into());
+            }
+            check_buffer_hash(buffer_account, expected_hash)?;
+        }
+        _ => {}
+    }
+
+    let message = aggregated_approval_message(transaction_account.key, &transaction);
+    check_ed25519_signature(instructions_sysvar, &aggregate.key, message.as_ref())?;
+
+    msg!("Aggregated approval by {}", aggregate.key);
+    transaction.signers = vec![true; transaction.signers.len()];
+    transaction.approved_at = vec![Clock::get()?.unix_timestamp; transaction.signers.len()];
+    let mut data = transaction_account.try_borrow_mut_data()?;
+    save(&transaction, &mut data)?;
+    Ok(())
+}
+
+// The runtime verified every Ed25519 program instruction of the transaction before running this
+// one, what's left is making sure the previous instruction checked `message` signed by `pubkey`.
+// Only the self-contained form of `instruction::ed25519_verify` is accepted
+fn check_ed25519_signature(
+    instructions_sysvar: &AccountInfo,
+    pubkey: &Pubkey,
+    message: &[u8],
+) -> ProgramResult {
+    if instructions_sysvar.key != &sysvar::instructions::id() {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    let current = sysvar::instructions::load_current_index_checked(instructions_sysvar)?;
+    let previous = current.checked_sub(1).ok_or_else(|| {
+        msg!("Missing the Ed25519 signature check");
+        ProgramError::MissingRequiredSignature
+    })?;
+    let verify =
+        sysvar::instructions::load_instruction_at_checked(previous as usize, instructions_sysvar)?;
+    if verify.program_id != ed25519_program::id() {
+        msg!("Missing the Ed25519 signature check");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let data = &verify.data;
+    if data.len() < 16 || data[0] != 1 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let offset = |index: usize| u16::from_le_bytes([data[2 + 2 * index], data[3 + 2 * index]]);
+    // signature, key and message must all come from the verify instruction itself
+    if offset(1) != u16::MAX || offset(3) != u16::MAX || offset(6) != u16::MAX {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let field = |start: u16, len: usize| data.get(start as usize..start as usize + len);
+    if field(offset(2), 32) != Some(pubkey.as_ref())
+        || offset(5) as usize != message.len()
+        || field(offset(4), message.len()) != Some(message)
+    {
+        msg!("Ed25519 check is for another key or message");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+fn process_propose_recovery(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    new_owners: Vec<Pubkey>,
+    new_threshold: u8,
+) -> ProgramResult {
+    let guardian = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+    let guardians_account = next_account_info(account_info_iter)?;
+    let recovery_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !guardian.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    check_owner_set(&new_owners, new_threshold)?;
+
+    let guardians = load_guardians(program_id, multisig_account, guardians_account)?;
+    let guardian_index = guardians
+        .guardians
+        .iter()
+        .position(|key| key == guardian.key)
+        .ok_or(MultisigError::NotGuardian)?;
+
+    let (recovery_key, bump) = find_recovery_address(multisig_account.key, program_id);
+    if recovery_account.key != &recovery_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    // one recovery at a time, a pending one has to be executed or vetoed first
+    if recovery_account.owner == program_id {
+        return Err(MultisigError::RecoveryPending.into());
+    }
+
+    let mut recovery = Recovery {
+        multisig: *multisig_account.key,
+        proposer: *guardian.key,
+        new_owners,
+        new_threshold,
+        approvals: vec![false; guardians.guardians.len()],
+        approved_at: None,
+    };
+    create_pda_account(
+        program_id,
+        guardian,
+        recovery_account,
+        system_program,
+        Recovery::space(recovery.new_owners.len(), guardians.guardians.len()),
+        &[&[RECOVERY_SEED, multisig_account.key.as_ref(), &[bump]]],
+    )?;
+    record_recovery_approval(&guardians, &mut recovery, guardian_index)?;
+    let mut data = recovery_account.try_borrow_mut_data()?;
+    save(&recovery, &mut data)?;
+
+    Ok(())
+}
+
+fn process_approve_recovery(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let guardian = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+    let guardians_account = next_account_info(account_info_iter)?;
+    let recovery_account = next_account_info(account_info_iter)?;
+
+    if !guardian.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let guardians = load_guardians(program_id, multisig_account, guardians_account)?;
+    let mut recovery = load_recovery(program_id, multisig_account, recovery_account)?;
+    let guardian_index = guardians
+        .guardians
+        .iter()
+        .position(|key| key == guardian.key)
+        .ok_or(MultisigError::NotGuardian)?;
+
+    record_recovery_approval(&guardians, &mut recovery, guardian_index)?;
+    let mut data = recovery_account.try_borrow_mut_data()?;
+    save(&recovery, &mut data)?;
+
+    Ok(())
+}
+
+fn process_veto_recovery(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let owner = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+    let recovery_account = next_account_info(account_info_iter)?;
+    let proposer = next_account_info(account_info_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // a single owner is enough, the recovery is meant for owners who can't sign anymore
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    if !multisig.owners.contains(owner.key) {
+        return Err(MultisigError::OwnerNotFound.into());
+    }
+
+    let recovery = load_recovery(program_id, multisig_account, recovery_account)?;
+    if proposer.key != &recovery.proposer {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    msg!("Recovery vetoed by {}", owner.key);
+    close_account(recovery_account, proposer)
+}
+
+fn process_execute_recovery(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let payer = next_account_info(account_info_iter)?;
+    let multisig_account = next_account_info(account_info_iter)?;
+    let guardians_account = next_account_info(account_info_iter)?;
+    let recovery_account = next_account_info(account_info_iter)?;
+    let proposer = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !multisig_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let guardians = load_guardians(program_id, multisig_account, guardians_account)?;
+    let recovery = load_recovery(program_id, multisig_account, recovery_account)?;
+    if proposer.key != &recovery.proposer {
+        return Err(MultisigError::AccountMismatch.into());
+    }
+    let approved_at = recovery.approved_at.ok_or_else(|| {
+        msg!("Recovery doesn't have a guardian supermajority");
+        MultisigError::InsufficientSignatures
+    })?;
+    let now = Clock::get()?.unix_timestamp;
+    if now < approved_at.saturating_add(guardians.recovery_delay) {
+        msg!(
+            "Recovery delay runs until {}",
+            approved_at.saturating_add(guardians.recovery_delay)
+        );
+        return Err(MultisigError::RecoveryNotReady.into());
+    }
+
+    // Approvals of the old owners must not count for whoever takes their index, pending
+    // proposals passed after the fixed accounts are cleared (proposals for a different
+    // number of owners can't be approved or executed anymore anyway)
+    for transaction_account in account_info_iter {
+        if transaction_account.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut transaction = load::<Transaction>(&transaction_account.data.borrow())?;
+        if transaction.multisig != *multisig_account.key {
+            return Err(MultisigError::WrongMultisig.into());
+        }
+        if transaction.executed {
+            continue;
+        }
+        transaction.signers = vec![false; transaction.signers.len()];
+        let mut data = transaction_account.try_borrow_mut_data()?;
+        save(&transaction, &mut data)?;
+    }
+
+    let previous = load::<Multisig>(&multisig_account.data.borrow())?;
+    let mut multisig = Multisig {
+        signers: vec![false; recovery.new_owners.len()],
+        signed_at: vec![0; recovery.new_owners.len()],
+        owners: recovery.new_owners,
+        threshold: recovery.new_threshold,
+        ..previous
+    };
+    multisig.owner_set_changed();
+    save_resized(multisig_account, payer, system_program, &multisig)?;
+
+    msg!("Owner set replaced by guardian recovery");
+    close_account(recovery_account, proposer)
+}
+
+// Writes `value` when its encoded size may differ from the account's, the payer tops up rent
+// when the account grows
+fn save_resized<'a, T: BorshSerialize>(
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    value: &T,
+) -> ProgramResult {
+    let space = value.try_to_vec()?.len();
+    if space != account.data_len() {
+        let shortfall = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(account.lamports());
+        if shortfall > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, account.key, shortfall),
+                &[payer.clone(), account.clone(), system_program.clone()],
+            )?;
+        }
+        account.realloc(space, false)?;
+    }
+    let mut data = account.try_borrow_mut_data()?;
+    save(&value, &mut data)?;
+    Ok(())
+}
+
+fn load_guardians(
+    program_id: &Pubkey,
+    multisig_account: &AccountInfo,
+    guardians_account: &AccountInfo,
+) -> Result<Guardians, ProgramError> {
+    if multisig_account.owner != program_id || guardians_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (guardians_key, _) = find_guardians_address(multisig_account.key, program_id);
+    if guardians_account.key != &guardians_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(load::<Guardians>(&guardians_account.data.borrow())?)
+}
+
+fn load_recovery(
+    program_id: &Pubkey,
+    multisig_account: &AccountInfo,
+    recovery_account: &AccountInfo,
+) -> Result<Recovery, ProgramError> {
+    if recovery_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !recovery_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let (recovery_key, _) = find_recovery_address(multisig_account.key, program_id);
+    if recovery_account.key != &recovery_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    // sized for `approved_at: Some(_)`, there are trailing bytes until then
+    Ok(Recovery::deserialize(
+        &mut &recovery_account.data.borrow()[..],
+    )?)
+}
+
+// Starts the veto window once the supermajority is reached, later approvals don't move it
+fn record_recovery_approval(
+    guardians: &Guardians,
+    recovery: &mut Recovery,
+    guardian_index: usize,
+) -> ProgramResult {
+    recovery.approvals[guardian_index] = true;
+    let approvals = recovery
+        .approvals
+        .iter()
+        .filter(|&&approved| approved)
+        .count();
+    msg!(
+        "Guardian approvals: {}, Required: {}",
+        approvals,
+        guardians.supermajority()
+    );
+    if recovery.approved_at.is_none() && approvals >= guardians.supermajority() {
+        recovery.approved_at = Some(Clock::get()?.unix_timestamp);
+    }
+    Ok(())
+}
+
+// Creates a program owned PDA, also when someone already sent lamports to the address
+fn create_pda_account<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    // the account's seeds, plus the payer's when it is a PDA as well
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let required = Rent::get()?.minimum_balance(space);
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                account.key,
+                required,
+                space as u64,
+                program_id,
+            ),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            signer_seeds,
+        );
+    }
+    let shortfall = required.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        invoke_signed(
+            &system_instruction::transfer(payer.key, account.key, shortfall),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            signer_seeds,
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        signer_seeds,
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, program_id),
+        &[account.clone(), system_program.clone()],
+        signer_seeds,
+    )
+}
+
+// Moves all lamports of a program owned account to `destination` and wipes it
+fn close_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    move_lamports(account, destination, account.lamports())?;
+    account.realloc(0, false)?;
+    account.assign(&system_program::id());
+    Ok(())
+}
+
+fn is_token_program(program_id: &Pubkey) -> bool {
+    program_id == &spl_token::id() || program_id == &spl_token_2022::id()
+}
+
+// Verifies the buffer still holds exactly the bytes the proposal was created for
+fn check_buffer_hash(buffer_account: &AccountInfo, expected_hash: &[u8; 32]) -> ProgramResult {
+    if buffer_account.owner != &bpf_loader_upgradeable::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let actual_hash = buffer_hash(&buffer_account.data.borrow())?;
+    if actual_hash.to_bytes() != *expected_hash {
+        msg!(
+            "Buffer hash mismatch, expected {:?}, found {}",
+            expected_hash,
+            actual_hash
+        );
+        return Err(MultisigError::BufferHashMismatch.into());
+    }
+    Ok(())
+}
+
+// Checks the main vault and the approvals for a lookup table instruction, the vault's bump is
+// returned for signing as table authority
+fn check_lookup_table_authority(
+    program_id: &Pubkey,
+    multisig_account: &AccountInfo,
+    vault: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    if !multisig_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (vault_key, bump) = find_vault_address(multisig_account.key, program_id);
+    if vault.key != &vault_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    check_threshold(&multisig)?;
+    Ok(bump)
+}
+
+// Approvals are consumed, same as after `Execute`
+fn consume_approvals(multisig_account: &AccountInfo) -> ProgramResult {
+    let mut multisig = load::<Multisig>(&multisig_account.data.borrow())?;
+    multisig.signers = vec![false; multisig.owners.len()];
+    let mut data = multisig_account.try_borrow_mut_data()?;
+    save(&multisig, &mut data)?;
+    Ok(())
+}
+
+// The table program CPI, signed by the main vault
+fn invoke_as_vault(
+    instruction: &Instruction,
+    accounts: &[AccountInfo],
+    multisig: &Pubkey,
+    vault_bump: u8,
+) -> ProgramResult {
+    let vault_index = [0];
+    let vault_bump = [vault_bump];
+    let mut signer_seeds = vault_seeds(multisig, &vault_index);
+    signer_seeds.push(&vault_bump);
+    invoke_signed(instruction, accounts, &[&signer_seeds])
+}
+
+fn process_create_lookup_table(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    recent_slot: u64,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+    let lookup_table_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let lookup_table_program = next_account_info(account_info_iter)?;
+
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let bump = check_lookup_table_authority(program_id, multisig_account, vault)?;
+    let (table_key, table_bump) = lookup_table::find_lookup_table_address(vault.key, recent_slot);
+    if lookup_table_account.key != &table_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    invoke_as_vault(
+        &lookup_table::create_lookup_table(
+            &table_key,
+            vault.key,
+            payer.key,
+            recent_slot,
+            table_bump,
+        ),
+        &[
+            lookup_table_account.clone(),
+            vault.clone(),
+            payer.clone(),
+            system_program.clone(),
+            lookup_table_program.clone(),
+        ],
+        multisig_account.key,
+        bump,
+    )?;
+    consume_approvals(multisig_account)
+}
+
+fn process_extend_lookup_table(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+    addresses: &[Pubkey],
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+    let lookup_table_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let lookup_table_program = next_account_info(account_info_iter)?;
+
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let bump = check_lookup_table_authority(program_id, multisig_account, vault)?;
+
+    // the table program rejects tables the vault isn't the authority of
+    invoke_as_vault(
+        &lookup_table::extend_lookup_table(
+            lookup_table_account.key,
+            vault.key,
+            payer.key,
+            addresses,
+        ),
+        &[
+            lookup_table_account.clone(),
+            vault.clone(),
+            payer.clone(),
+            system_program.clone(),
+            lookup_table_program.clone(),
+        ],
+        multisig_account.key,
+        bump,
+    )?;
+    consume_approvals(multisig_account)
+}
+
+fn process_deactivate_lookup_table(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+    let lookup_table_account = next_account_info(account_info_iter)?;
+    let lookup_table_program = next_account_info(account_info_iter)?;
+
+    let bump = check_lookup_table_authority(program_id, multisig_account, vault)?;
+    invoke_as_vault(
+        &lookup_table::deactivate_lookup_table(lookup_table_account.key, vault.key),
+        &[
+            lookup_table_account.clone(),
+            vault.clone(),
+            lookup_table_program.clone(),
+        ],
+        multisig_account.key,
+        bump,
+    )?;
+    consume_approvals(multisig_account)
+}
+
+fn process_close_lookup_table(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+    let lookup_table_account = next_account_info(account_info_iter)?;
+    let lookup_table_program = next_account_info(account_info_iter)?;
+
+    let bump = check_lookup_table_authority(program_id, multisig_account, vault)?;
+    invoke_as_vault(
+        &lookup_table::close_lookup_table(lookup_table_account.key, vault.key, vault.key),
+        &[
+            lookup_table_account.clone(),
+            vault.clone(),
+            lookup_table_program.clone(),
+        ],
+        multisig_account.key,
+        bump,
+    )?;
+    consume_approvals(multisig_account)
+}
+
+fn process_migrate_to_vault(
+    program_id: &Pubkey,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    let multisig_account = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+
+    if multisig_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (vault_key, _) = find_vault_address(multisig_account.key, program_id);
+    if vault.key != &vault_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    // the account must hold a multisig
+    load::<Multisig>(&multisig_account.data.borrow())?;
+
+    let rent_floor = Rent::get()?.minimum_balance(multisig_account.data_len());
+    let amount = multisig_account.lamports().saturating_sub(rent_floor);
+    msg!("Moving {} lamports to the vault", amount);
+    move_lamports(multisig_account, vault, amount)
+}
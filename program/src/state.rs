@@ -0,0 +1,1310 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::bpf_loader_upgradeable::UpgradeableLoaderState;
+use solana_program::hash::{hash, hashv, Hash};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use std::io::{self, Read, Write};
+
+use crate::error::MultisigError;
+use crate::instruction::ProposalAction;
+
+// Largest owner set a multisig can have. Owner lists and approval flags are stored padded to this
+// many entries, so every multisig account has the same size (`Multisig::LEN`) whatever its owner
+// count, and replacing the owner set never has to resize it
+pub const MAX_OWNERS: usize = 16;
+
+// owner counts are stored as a u8
+const _: () = assert!(MAX_OWNERS <= u8::MAX as usize);
+
+// Layout (`Multisig::LEN` bytes):
+//   owners_len u8 | owners [Pubkey; MAX_OWNERS] | threshold u8 | signers [bool; MAX_OWNERS]
+//   | veto_authority tag u8 + Pubkey | paused u8 | emergency_authority tag u8 + Pubkey
+//   | amount_policy tag u8 + limit u64 + large_threshold u8 | approval_ttl_seconds tag u8 + i64
+//   | signed_at [i64; MAX_OWNERS] | owner_set_seqno u32
+//   | outflow_cap tag u8 + limit u64 + period_seconds i64 | spent_this_period u64
+//   | period_start i64 | policy_signer tag u8 + Pubkey | transaction_count u64 | fee_exempt u8
+//   | allow_zero_amount u8 | min_transfer u64 | strict_signing u8 | delegate_signers u16
+// Unused owner slots, approval flags, timestamps and absent authorities are zeroed.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Multisig {
+    pub owners: Vec<Pubkey>,
+    pub threshold: u8,
+    pub signers: Vec<bool>,
+    // can cancel pending proposals, but neither propose nor approve (`SetVetoAuthority`)
+    pub veto_authority: Option<Pubkey>,
+    // `Execute` and `ExecuteTransaction` are refused while set (`SetPaused`)
+    pub paused: bool,
+    // may pause, but not unpause, without owner approvals (`SetEmergencyAuthority`)
+    pub emergency_authority: Option<Pubkey>,
+    // second, higher threshold for large transfers (`SetPolicy`)
+    pub amount_policy: Option<AmountPolicy>,
+    // approvals older than this don't count toward a threshold (`SetApprovalTtl`)
+    pub approval_ttl_seconds: Option<i64>,
+    // unix timestamp of each owner's latest `Sign`, same indexing as `owners`
+    pub signed_at: Vec<i64>,
+    // bumped whenever the owners or a threshold change, proposals made under an older value can't
+    // be approved or executed anymore
+    pub owner_set_seqno: u32,
+    // most lamports that may leave the wallet per period (`SetOutflowCap`)
+    pub outflow_cap: Option<OutflowCap>,
+    // lamports paid out since `period_start`, only counted while a cap is set
+    pub spent_this_period: u64,
+    // unix timestamp the current cap period started at
+    pub period_start: i64,
+    // must co-sign `Execute` and `ExecuteFromVault` on top of the owners' approvals, e.g. a risk
+    // engine's key (`SetPolicySigner`)
+    pub policy_signer: Option<Pubkey>,
+    // proposals made with `ProposeIndexed`, the next one goes to
+    // `find_transaction_address(multisig, transaction_count)`
+    pub transaction_count: u64,
+    // pays no protocol fee on its payouts, only the program's governance sets it (`SetFeeExempt`)
+    pub fee_exempt: bool,
+    // transfers of zero go through, for teams using 0-lamport "signal" proposals
+    // (`SetTransferMinimum`)
+    pub allow_zero_amount: bool,
+    // smallest non-zero lamport transfer, dust below it is refused; 0 for no minimum
+    pub min_transfer: u64,
+    // `Sign` takes the instructions sysvar and refuses transactions that any non-owner signs, so
+    // a relayer can't pay for and reorder owners' approvals (`SetStrictSigning`)
+    pub strict_signing: bool,
+    // bit `i` is set when owner `i`'s pending `Sign` was given by their delegate
+    // (`SignAsDelegate`). Delegates can't change configuration, the setters don't count those
+    // approvals. Every `Sign` writes its owner's bit, so bits of cleared approvals go unread
+    pub delegate_signers: u16,
+}
+
+// Moving more than `limit` lamports out of the wallet takes `large_threshold` approvals instead of
+// the multisig's threshold
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AmountPolicy {
+    pub limit: u64,
+    pub large_threshold: u8,
+}
+
+// `Execute`, `ExecuteFromVault` and executed proposals may move at most `limit` lamports out of
+// the wallet within `period_seconds`. A period starts with the first payout after the previous
+// one ended
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutflowCap {
+    pub limit: u64,
+    pub period_seconds: i64,
+}
+
+impl Multisig {
+    // Space (in bytes) every multisig account is allocated with
+    pub const LEN: usize = 1
+        + 32 * MAX_OWNERS
+        + 1
+        + MAX_OWNERS
+        + 33
+        + 1
+        + 33
+        + 10
+        + 9
+        + 8 * MAX_OWNERS
+        + 4
+        + 17
+        + 8
+        + 8
+        + 33
+        + 8
+        + 1
+        + 1
+        + 8
+        + 1
+        + 2;
+
+    // Byte offsets of the owner list, the approval flags, the approval TTL and timestamps, for
+    // instructions that patch a single approval in place, and of the threshold.
+    // There is no `DISCRIMINATOR`: deployed multisigs start with their owner count, and a prefix
+    // would shift every field of every existing account. `getProgramAccounts` filters match
+    // multisigs by `LEN` and these offsets instead (see the client's `fetch` module); a proposal
+    // can happen to be `LEN` bytes too, so what they return still has to decode
+    pub const OWNERS_OFFSET: usize = 1;
+    pub const THRESHOLD_OFFSET: usize = Self::OWNERS_OFFSET + 32 * MAX_OWNERS;
+    pub const SIGNERS_OFFSET: usize = Self::THRESHOLD_OFFSET + 1;
+    pub const APPROVAL_TTL_OFFSET: usize = Self::SIGNERS_OFFSET + MAX_OWNERS + 33 + 1 + 33 + 10;
+    pub const SIGNED_AT_OFFSET: usize = Self::APPROVAL_TTL_OFFSET + 9;
+    // `Sign` reads the strict mode flag without decoding the account
+    pub const STRICT_SIGNING_OFFSET: usize = Self::LEN - 3;
+    // and writes whether the approval came from a delegate the same way
+    pub const DELEGATE_SIGNERS_OFFSET: usize = Self::LEN - 2;
+
+    // Pending `Sign` approvals, the ones the next threshold-gated instruction consumes
+    pub fn num_approvals(&self) -> usize {
+        self.signers.iter().filter(|&&signed| signed).count()
+    }
+
+    pub fn has_signed(&self, owner: &Pubkey) -> bool {
+        self.owner_index(owner)
+            .is_some_and(|index| self.signers.get(index) == Some(&true))
+    }
+
+    // Whether an approval given at `approved_at` still counts at `now`
+    pub fn approval_is_live(&self, approved_at: i64, now: i64) -> bool {
+        match self.approval_ttl_seconds {
+            Some(ttl) => now.saturating_sub(approved_at) <= ttl,
+            None => true,
+        }
+    }
+
+    // Pending `Sign` approvals that are still within the approval TTL at `now`
+    pub fn live_approvals(&self, now: i64) -> usize {
+        self.signers
+            .iter()
+            .zip(&self.signed_at)
+            .filter(|(&signed, &signed_at)| signed && self.approval_is_live(signed_at, now))
+            .count()
+    }
+
+    // Live approvals given by the owners themselves, the ones configuration changes count
+    pub fn live_owner_approvals(&self, now: i64) -> usize {
+        (0..self.owners.len())
+            .filter(|&index| {
+                self.signers[index]
+                    && self.delegate_signers & (1 << index) == 0
+                    && self.approval_is_live(self.signed_at[index], now)
+            })
+            .count()
+    }
+
+    // Approvals still missing before the threshold is reached, 0 once it is
+    pub fn approvals_remaining(&self) -> usize {
+        (self.threshold as usize).saturating_sub(self.num_approvals())
+    }
+
+    // Records a change of the owners or the threshold, the proposals made before go stale
+    pub fn owner_set_changed(&mut self) {
+        self.owner_set_seqno = self.owner_set_seqno.wrapping_add(1);
+    }
+
+    // Counts a payout of `lamports` at `now` against the outflow cap, starting a new period when
+    // the current one is over. Nothing is counted without a cap
+    pub fn record_outflow(&mut self, lamports: u64, now: i64) -> Result<(), MultisigError> {
+        let Some(cap) = self.outflow_cap else {
+            return Ok(());
+        };
+        if now.saturating_sub(self.period_start) >= cap.period_seconds {
+            self.period_start = now;
+            self.spent_this_period = 0;
+        }
+        self.spent_this_period = self
+            .spent_this_period
+            .checked_add(lamports)
+            .filter(|&spent| spent <= cap.limit)
+            .ok_or(MultisigError::OutflowCapExceeded)?;
+        Ok(())
+    }
+
+    // Lamports that can still leave the wallet at `now` before the cap is hit, `None` without a
+    // cap
+    pub fn outflow_remaining(&self, now: i64) -> Option<u64> {
+        let cap = self.outflow_cap?;
+        if now.saturating_sub(self.period_start) >= cap.period_seconds {
+            return Some(cap.limit);
+        }
+        Some(cap.limit.saturating_sub(self.spent_this_period))
+    }
+
+    // Checks a transfer of `amount` against the zero-amount and dust settings, `lamports` tells
+    // whether it is in lamports, the only unit `min_transfer` applies to
+    pub fn check_transfer_amount(&self, amount: u64, lamports: bool) -> Result<(), MultisigError> {
+        if amount == 0 {
+            return match self.allow_zero_amount {
+                true => Ok(()),
+                false => Err(MultisigError::ZeroAmount),
+            };
+        }
+        if lamports && amount < self.min_transfer {
+            return Err(MultisigError::BelowMinimumTransfer);
+        }
+        Ok(())
+    }
+
+    pub fn owner_index(&self, owner: &Pubkey) -> Option<usize> {
+        self.owners.iter().position(|key| key == owner)
+    }
+
+    // Approvals needed to move `lamports` out of the wallet. The large tier is capped at the owner
+    // count, a recovery may have shrunk the owner set since the policy was set
+    pub fn threshold_for(&self, lamports: u64) -> u8 {
+        match self.amount_policy {
+            Some(policy) if lamports > policy.limit => policy
+                .large_threshold
+                .min(self.owners.len() as u8)
+                .max(self.threshold),
+            _ => self.threshold,
+        }
+    }
+}
+
+const _: () = assert!(Multisig::LEN == 835);
+const _: () = assert!(
+    Multisig::SIGNED_AT_OFFSET + 8 * MAX_OWNERS + 4 + 17 + 8 + 8 + 33 + 8 + 1 + 1 + 8 + 1 + 2
+        == Multisig::LEN
+);
+// one delegate bit per owner slot
+const _: () = assert!(MAX_OWNERS <= u16::BITS as usize);
+
+impl BorshSerialize for Multisig {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.signers.len() != self.owners.len() || self.signed_at.len() != self.owners.len() {
+            return Err(invalid_data("one approval flag and timestamp per owner"));
+        }
+        (self.owners.len() as u8).serialize(writer)?;
+        serialize_padded(&self.owners, writer)?;
+        self.threshold.serialize(writer)?;
+        serialize_padded(&self.signers, writer)?;
+        serialize_fixed_option(&self.veto_authority, writer)?;
+        self.paused.serialize(writer)?;
+        serialize_fixed_option(&self.emergency_authority, writer)?;
+        serialize_fixed_option(&self.amount_policy, writer)?;
+        serialize_fixed_option(&self.approval_ttl_seconds, writer)?;
+        serialize_padded(&self.signed_at, writer)?;
+        self.owner_set_seqno.serialize(writer)?;
+        serialize_fixed_option(&self.outflow_cap, writer)?;
+        self.spent_this_period.serialize(writer)?;
+        self.period_start.serialize(writer)?;
+        serialize_fixed_option(&self.policy_signer, writer)?;
+        self.transaction_count.serialize(writer)?;
+        self.fee_exempt.serialize(writer)?;
+        self.allow_zero_amount.serialize(writer)?;
+        self.min_transfer.serialize(writer)?;
+        self.strict_signing.serialize(writer)?;
+        self.delegate_signers.serialize(writer)
+    }
+}
+
+impl BorshDeserialize for Multisig {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let owners_len = u8::deserialize_reader(reader)? as usize;
+        Ok(Multisig {
+            owners: deserialize_padded(owners_len, reader)?,
+            threshold: u8::deserialize_reader(reader)?,
+            signers: deserialize_padded(owners_len, reader)?,
+            veto_authority: deserialize_fixed_option(reader)?,
+            paused: bool::deserialize_reader(reader)?,
+            emergency_authority: deserialize_fixed_option(reader)?,
+            amount_policy: deserialize_fixed_option(reader)?,
+            approval_ttl_seconds: deserialize_fixed_option(reader)?,
+            signed_at: deserialize_padded(owners_len, reader)?,
+            owner_set_seqno: u32::deserialize_reader(reader)?,
+            outflow_cap: deserialize_fixed_option(reader)?,
+            spent_this_period: u64::deserialize_reader(reader)?,
+            period_start: i64::deserialize_reader(reader)?,
+            policy_signer: deserialize_fixed_option(reader)?,
+            transaction_count: u64::deserialize_reader(reader)?,
+            fee_exempt: bool::deserialize_reader(reader)?,
+            allow_zero_amount: bool::deserialize_reader(reader)?,
+            min_transfer: u64::deserialize_reader(reader)?,
+            strict_signing: bool::deserialize_reader(reader)?,
+            delegate_signers: u16::deserialize_reader(reader)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalStatus {
+    // waiting for `remaining` more approvals (required signers included)
+    Pending { remaining: usize },
+    // enough approvals, `ExecuteTransaction` can run it
+    Ready,
+    Executed,
+    // proposed under an earlier owner set or threshold (`Multisig::owner_set_seqno`), can't be
+    // approved or executed anymore
+    Stale,
+}
+
+// Lifecycle of a proposal. Only `executed` is stored, the rest follows from the account and its
+// multisig: `Draft` is an allocated account `Propose` hasn't written yet, `Active` and `Approved`
+// depend on the live approvals, `Expired` on the clock, and `Cancelled` is a proposal an owner or
+// threshold change made stale or the veto authority vetoed (which closes the account)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalState {
+    Draft,
+    Active,
+    Approved,
+    Executed,
+    Cancelled,
+    Expired,
+}
+
+impl ProposalState {
+    // Whether an instruction moving the proposal to `next` may run, with the error to reject it
+    // with otherwise
+    pub fn check_transition(self, next: ProposalState) -> Result<(), MultisigError> {
+        use ProposalState::*;
+        match (self, next) {
+            (Draft, Active | Approved) => Ok(()),
+            (Active | Approved, Active | Approved) => Ok(()),
+            (Approved, Executed) => Ok(()),
+            (Active, Executed) => Err(MultisigError::InsufficientSignatures),
+            (Executed, _) => Err(MultisigError::AlreadyExecuted),
+            // a veto can drop any proposal that hasn't run
+            (Draft | Active | Approved | Cancelled | Expired, Cancelled) => Ok(()),
+            (Expired, _) => Err(MultisigError::ProposalExpired),
+            (Cancelled, _) => Err(MultisigError::StaleProposal),
+            _ => Err(MultisigError::InvalidTransition),
+        }
+    }
+}
+
+// A proposal (`Propose` instruction) which owners approve individually, the approvals are bound to
+// the proposed `action` and can't be reused for anything else.
+//
+// Layout: multisig | proposer | action (Borsh enum, its size depends on the variant)
+//   | signers_len u8 + signers [bool; MAX_OWNERS] | executed u8 | vault u8
+//   | expires_at tag u8 + i64 | extended u8 | required_signers [bool; MAX_OWNERS]
+//   | approved_at [i64; MAX_OWNERS] | executor_fee u64 | owner_set_seqno u32 | executed_at i64
+//   | executed_slot u64 | executed_by tag u8 + Pubkey | attachment tag u8 + [u8; 32]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transaction {
+    pub multisig: Pubkey,
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    // same indexing as `Multisig::owners`
+    pub signers: Vec<bool>,
+    pub executed: bool,
+    // index of the vault the action draws from and signs as (`find_indexed_vault_address`)
+    pub vault: u8,
+    // unix timestamp after which the proposal can't be approved or executed anymore
+    pub expires_at: Option<i64>,
+    // `ExtendProposal` already pushed `expires_at` back, it only can once
+    pub extended: bool,
+    // owners who must be among the approvers, same indexing as `signers`. The requirement is tied
+    // to the index, a key taking an owner's place (`SwapOwner`) takes it over
+    pub required_signers: Vec<bool>,
+    // unix timestamp of each approval, same indexing as `signers`
+    pub approved_at: Vec<i64>,
+    // lamports the proposal's vault pays whoever executes it through `CrankTransaction`
+    pub executor_fee: u64,
+    // `Multisig::owner_set_seqno` when the proposal was made
+    pub owner_set_seqno: u32,
+    // unix timestamp and slot of the execution, 0 until then
+    pub executed_at: i64,
+    pub executed_slot: u64,
+    // the signer of the `CrankTransaction` that executed the proposal, `None` when it went through
+    // `ExecuteTransaction`, which needs no signer
+    pub executed_by: Option<Pubkey>,
+    // hash of an off-chain document the proposal is about (an invoice, a governance doc on
+    // IPFS or Arweave), logged on execution; part of what owners approve
+    pub attachment: Option<[u8; 32]>,
+}
+
+// Domain and version of `Transaction::digest`. The version changes whenever the digest covers
+// something else, so signatures made for one scheme never verify under another
+pub const PROPOSAL_DIGEST_DOMAIN: &[u8] = b"multisig-proposal";
+pub const PROPOSAL_DIGEST_VERSION: u8 = 2;
+
+impl Transaction {
+    // Space (in bytes) of everything but the action
+    pub const FIXED_LEN: usize = 32
+        + 32
+        + 1
+        + MAX_OWNERS
+        + 1
+        + 1
+        + 9
+        + 1
+        + MAX_OWNERS
+        + 8 * MAX_OWNERS
+        + 8
+        + 4
+        + 8
+        + 8
+        + 33
+        + 33;
+
+    // Space (in bytes) the proposal account must be allocated with
+    pub fn space(action: &ProposalAction) -> usize {
+        Self::FIXED_LEN + action.try_to_vec().unwrap().len()
+    }
+
+    // What an owner approves, signed off-chain for `ApproveWithSignature` and shown by wallets
+    // (hardware ones included) before signing:
+    // hash("multisig-proposal" || version || program_id || multisig || nonce || payload).
+    // The proposal's account address is the nonce, every proposal gets a fresh one; the payload
+    // is the Borsh action, the vault index, the executor fee as little-endian u64 and the
+    // attachment as a tag byte and 32 bytes, zeroed without one
+    pub fn digest(&self, program_id: &Pubkey, transaction_key: &Pubkey) -> Hash {
+        hashv(&[
+            PROPOSAL_DIGEST_DOMAIN,
+            &[PROPOSAL_DIGEST_VERSION],
+            program_id.as_ref(),
+            self.multisig.as_ref(),
+            transaction_key.as_ref(),
+            &self.action.try_to_vec().unwrap(),
+            &[self.vault],
+            &self.executor_fee.to_le_bytes(),
+            &[self.attachment.is_some() as u8],
+            &self.attachment.unwrap_or_default(),
+        ])
+    }
+
+    // The attachment in hex, the way `sha256sum` prints a document's hash
+    pub fn attachment_hex(&self) -> Option<String> {
+        self.attachment.map(|attachment| {
+            attachment
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect()
+        })
+    }
+
+    pub fn num_approvals(&self) -> usize {
+        self.signers.iter().filter(|&&signed| signed).count()
+    }
+
+    // Required signers who haven't approved yet
+    pub fn missing_required_signers(&self) -> usize {
+        self.missing_required(&self.signers)
+    }
+
+    fn missing_required(&self, signers: &[bool]) -> usize {
+        self.required_signers
+            .iter()
+            .zip(signers)
+            .filter(|(&required, &signed)| required && !signed)
+            .count()
+    }
+
+    // Approval flags without the approvals that are older than the multisig's approval TTL at
+    // `now`
+    pub fn live_signers(&self, multisig: &Multisig, now: i64) -> Vec<bool> {
+        self.signers
+            .iter()
+            .zip(&self.approved_at)
+            .map(|(&signed, &approved_at)| signed && multisig.approval_is_live(approved_at, now))
+            .collect()
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now > expires_at)
+    }
+
+    pub fn has_approved(&self, multisig: &Multisig, owner: &Pubkey) -> bool {
+        multisig
+            .owner_index(owner)
+            .is_some_and(|index| self.signers.get(index) == Some(&true))
+    }
+
+    // Where the proposal stands against the current state of its multisig, approvals never expire
+    // here (see `status_at`)
+    pub fn status(&self, multisig: &Multisig) -> ProposalStatus {
+        self.status_with(multisig, &self.signers)
+    }
+
+    // Same at unix time `now`, without the approvals the approval TTL dropped
+    pub fn status_at(&self, multisig: &Multisig, now: i64) -> ProposalStatus {
+        self.status_with(multisig, &self.live_signers(multisig, now))
+    }
+
+    // Lifecycle state at unix time `now`
+    pub fn state_at(&self, multisig: &Multisig, now: i64) -> ProposalState {
+        match self.status_at(multisig, now) {
+            ProposalStatus::Executed => ProposalState::Executed,
+            ProposalStatus::Stale => ProposalState::Cancelled,
+            _ if self.is_expired(now) => ProposalState::Expired,
+            ProposalStatus::Ready => ProposalState::Approved,
+            ProposalStatus::Pending { .. } => ProposalState::Active,
+        }
+    }
+
+    // When the proposal reached a state it can't leave anymore (executed, cancelled or expired),
+    // `None` while it can still be approved or executed. Going stale isn't recorded, a stale
+    // proposal counts from its latest approval
+    pub fn finalized_at(&self, multisig: &Multisig, now: i64) -> Option<i64> {
+        match self.state_at(multisig, now) {
+            ProposalState::Executed => Some(self.executed_at),
+            ProposalState::Cancelled => self.approved_at.iter().copied().max(),
+            ProposalState::Expired => self.expires_at,
+            ProposalState::Draft | ProposalState::Active | ProposalState::Approved => None,
+        }
+    }
+
+    fn status_with(&self, multisig: &Multisig, signers: &[bool]) -> ProposalStatus {
+        if self.executed {
+            ProposalStatus::Executed
+        } else if self.signers.len() != multisig.owners.len()
+            || self.owner_set_seqno != multisig.owner_set_seqno
+        {
+            ProposalStatus::Stale
+        } else {
+            let threshold = multisig.threshold_for(self.action.lamports());
+            let approvals = signers.iter().filter(|&&signed| signed).count();
+            let remaining = (threshold as usize).saturating_sub(approvals);
+            match remaining.max(self.missing_required(signers)) {
+                0 => ProposalStatus::Ready,
+                remaining => ProposalStatus::Pending { remaining },
+            }
+        }
+    }
+}
+
+const _: () = assert!(Transaction::FIXED_LEN == 331);
+
+// Longest push-back of a proposal's expiry `ExtendProposal` allows
+pub const MAX_PROPOSAL_EXTENSION: i64 = 7 * 24 * 60 * 60;
+
+// How long a finalized proposal stays around before `CloseTransaction` may close it
+pub const CLOSE_GRACE_PERIOD: i64 = 7 * 24 * 60 * 60;
+
+impl BorshSerialize for Transaction {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.required_signers.len() != self.signers.len()
+            || self.approved_at.len() != self.signers.len()
+        {
+            return Err(invalid_data(
+                "one required flag and timestamp per approval flag",
+            ));
+        }
+        self.multisig.serialize(writer)?;
+        self.proposer.serialize(writer)?;
+        self.action.serialize(writer)?;
+        (self.signers.len() as u8).serialize(writer)?;
+        serialize_padded(&self.signers, writer)?;
+        self.executed.serialize(writer)?;
+        self.vault.serialize(writer)?;
+        serialize_fixed_option(&self.expires_at, writer)?;
+        self.extended.serialize(writer)?;
+        serialize_padded(&self.required_signers, writer)?;
+        serialize_padded(&self.approved_at, writer)?;
+        self.executor_fee.serialize(writer)?;
+        self.owner_set_seqno.serialize(writer)?;
+        self.executed_at.serialize(writer)?;
+        self.executed_slot.serialize(writer)?;
+        serialize_fixed_option(&self.executed_by, writer)?;
+        serialize_fixed_option(&self.attachment, writer)
+    }
+}
+
+impl BorshDeserialize for Transaction {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let multisig = Pubkey::deserialize_reader(reader)?;
+        let proposer = Pubkey::deserialize_reader(reader)?;
+        let action = ProposalAction::deserialize_reader(reader)?;
+        let signers_len = u8::deserialize_reader(reader)? as usize;
+        Ok(Transaction {
+            multisig,
+            proposer,
+            action,
+            signers: deserialize_padded(signers_len, reader)?,
+            executed: bool::deserialize_reader(reader)?,
+            vault: u8::deserialize_reader(reader)?,
+            expires_at: deserialize_fixed_option(reader)?,
+            extended: bool::deserialize_reader(reader)?,
+            required_signers: deserialize_padded(signers_len, reader)?,
+            approved_at: deserialize_padded(signers_len, reader)?,
+            executor_fee: u64::deserialize_reader(reader)?,
+            owner_set_seqno: u32::deserialize_reader(reader)?,
+            executed_at: i64::deserialize_reader(reader)?,
+            executed_slot: u64::deserialize_reader(reader)?,
+            executed_by: deserialize_fixed_option(reader)?,
+            attachment: deserialize_fixed_option(reader)?,
+        })
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// `items` followed by default values up to `MAX_OWNERS` entries
+fn serialize_padded<T: BorshSerialize + Default, W: Write>(
+    items: &[T],
+    writer: &mut W,
+) -> io::Result<()> {
+    if items.len() > MAX_OWNERS {
+        return Err(invalid_data("more than MAX_OWNERS entries"));
+    }
+    for item in items {
+        item.serialize(writer)?;
+    }
+    for _ in items.len()..MAX_OWNERS {
+        T::default().serialize(writer)?;
+    }
+    Ok(())
+}
+
+// Reads `MAX_OWNERS` entries and keeps the first `len`, the padding must be default values so
+// every state has exactly one encoding
+fn deserialize_padded<T: BorshDeserialize + Default + PartialEq, R: Read>(
+    len: usize,
+    reader: &mut R,
+) -> io::Result<Vec<T>> {
+    if len > MAX_OWNERS {
+        return Err(invalid_data("more than MAX_OWNERS entries"));
+    }
+    let mut items = Vec::with_capacity(len);
+    for i in 0..MAX_OWNERS {
+        let item = T::deserialize_reader(reader)?;
+        if i < len {
+            items.push(item);
+        } else if item != T::default() {
+            return Err(invalid_data("non-zero padding"));
+        }
+    }
+    Ok(items)
+}
+
+// `Option<T>` in a fixed size (33 bytes for a key): the Borsh tag, then the value or zeros
+fn serialize_fixed_option<T: BorshSerialize + Default + Copy, W: Write>(
+    value: &Option<T>,
+    writer: &mut W,
+) -> io::Result<()> {
+    value.is_some().serialize(writer)?;
+    value.unwrap_or_default().serialize(writer)
+}
+
+fn deserialize_fixed_option<T: BorshDeserialize + Default + PartialEq, R: Read>(
+    reader: &mut R,
+) -> io::Result<Option<T>> {
+    let is_some = bool::deserialize_reader(reader)?;
+    let value = T::deserialize_reader(reader)?;
+    match is_some {
+        true => Ok(Some(value)),
+        false if value == T::default() => Ok(None),
+        false => Err(invalid_data("non-zero padding")),
+    }
+}
+
+// Reads a `T` from the start of an account's data. Accounts may be allocated larger than the
+// encoding (reallocated for a bigger layout, or grown by a later version), so zeros after it are
+// ignored; anything else there means the data isn't a `T`
+pub fn load<T: BorshDeserialize>(data: &[u8]) -> io::Result<T> {
+    let mut reader = data;
+    let value = T::deserialize_reader(&mut reader)?;
+    if reader.iter().any(|&byte| byte != 0) {
+        return Err(invalid_data("trailing data after the account state"));
+    }
+    Ok(value)
+}
+
+// Writes `value` to the start of `data` and zeroes the rest, so a shorter encoding leaves no
+// stale bytes behind for `load` to trip over
+pub fn save<T: BorshSerialize>(value: &T, data: &mut [u8]) -> io::Result<()> {
+    let len = data.len();
+    let mut writer = &mut *data;
+    value.serialize(&mut writer)?;
+    let end = len - writer.len();
+    data[end..].fill(0);
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DestinationMode {
+    // `Execute` only pays the listed destinations
+    Allowlist,
+    // `Execute` pays anyone except the listed destinations
+    Denylist,
+}
+
+// How a policy subsystem treats a violation. `Shadow` lets a wallet trial a policy against real
+// activity: violations are logged ("Shadow policy violation: ...") but don't block anything
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyEnforcement {
+    Enforce,
+    Shadow,
+}
+
+// Where `Execute` may send funds, a PDA (`DESTINATIONS_SEED`) maintained by threshold-gated
+// instructions. No account at the address means no restriction
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct DestinationPolicy {
+    pub multisig: Pubkey,
+    pub mode: DestinationMode,
+    pub destinations: Vec<Pubkey>,
+    pub enforcement: PolicyEnforcement,
+}
+
+impl DestinationPolicy {
+    pub fn allows(&self, destination: &Pubkey) -> bool {
+        let listed = self.destinations.contains(destination);
+        match self.mode {
+            DestinationMode::Allowlist => listed,
+            DestinationMode::Denylist => !listed,
+        }
+    }
+}
+
+pub const DESTINATIONS_SEED: &[u8] = b"destinations";
+
+pub fn find_destination_policy_address(multisig: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DESTINATIONS_SEED, multisig.as_ref()], program_id)
+}
+
+// Optional social recovery set of a multisig, a PDA (`GUARDIANS_SEED`) written once right after
+// `Create`. Guardians can't touch funds, they can only replace the owner set through a `Recovery`
+// that owners get `recovery_delay` seconds to veto
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Guardians {
+    pub multisig: Pubkey,
+    pub guardians: Vec<Pubkey>,
+    pub recovery_delay: i64,
+}
+
+impl Guardians {
+    pub fn space(guardians_len: usize) -> usize {
+        let guardians = Guardians {
+            multisig: Pubkey::default(),
+            guardians: vec![Pubkey::default(); guardians_len],
+            recovery_delay: 0,
+        };
+        guardians.try_to_vec().unwrap().len()
+    }
+
+    // Approvals a recovery needs: two thirds of the guardians, rounded up
+    pub fn supermajority(&self) -> usize {
+        (self.guardians.len() * 2).div_ceil(3)
+    }
+}
+
+// Pending owner set replacement (PDA, `RECOVERY_SEED`), at most one per multisig
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Recovery {
+    pub multisig: Pubkey,
+    // paid the rent, gets it back when the recovery is executed or vetoed
+    pub proposer: Pubkey,
+    pub new_owners: Vec<Pubkey>,
+    pub new_threshold: u8,
+    // same indexing as `Guardians::guardians`
+    pub approvals: Vec<bool>,
+    // when the supermajority was reached, the delay runs from here
+    pub approved_at: Option<i64>,
+}
+
+impl Recovery {
+    pub fn space(new_owners_len: usize, guardians_len: usize) -> usize {
+        let recovery = Recovery {
+            multisig: Pubkey::default(),
+            proposer: Pubkey::default(),
+            new_owners: vec![Pubkey::default(); new_owners_len],
+            new_threshold: 0,
+            approvals: vec![false; guardians_len],
+            approved_at: Some(0),
+        };
+        recovery.try_to_vec().unwrap().len()
+    }
+}
+
+pub const GUARDIANS_SEED: &[u8] = b"guardians";
+pub const RECOVERY_SEED: &[u8] = b"recovery";
+
+pub fn find_guardians_address(multisig: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GUARDIANS_SEED, multisig.as_ref()], program_id)
+}
+
+pub fn find_recovery_address(multisig: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RECOVERY_SEED, multisig.as_ref()], program_id)
+}
+
+// Multisigs made with `CreateDerived` sit at a PDA (`DERIVED_MULTISIG_SEED`) of their creator and
+// a 32-byte seed the creator picks, e.g. a deal id, so a program creating many wallets needs no
+// keypairs. The creator is part of the address so nobody else can take it first
+pub const DERIVED_MULTISIG_SEED: &[u8] = b"multisig";
+
+pub fn find_derived_multisig_address(
+    creator: &Pubkey,
+    seed: &[u8; 32],
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DERIVED_MULTISIG_SEED, creator.as_ref(), seed], program_id)
+}
+
+// Proposals made with `ProposeIndexed` sit at a PDA (`TRANSACTION_SEED`) of the multisig and
+// their index, 0 for the first, so clients enumerate them in order without scanning
+pub const TRANSACTION_SEED: &[u8] = b"transaction";
+
+pub fn find_transaction_address(
+    multisig: &Pubkey,
+    index: u64,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[TRANSACTION_SEED, multisig.as_ref(), &index.to_le_bytes()],
+        program_id,
+    )
+}
+
+// Optional on-chain audit trail of a multisig, a PDA (`HISTORY_SEED`) of the multisig created with
+// `InitHistory`. Instructions passing it as their last account (`instruction::with_history`)
+// append an entry once they succeed; only the latest `HISTORY_CAPACITY` entries are kept, the
+// oldest one is overwritten
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct History {
+    pub multisig: Pubkey,
+    pub bump: u8,
+    // entries ever recorded, the next one goes to `entries[count % HISTORY_CAPACITY]`
+    pub count: u64,
+    pub entries: [HistoryEntry; HISTORY_CAPACITY],
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HistoryEntry {
+    // `MultisigInstruction::tag` of the instruction
+    pub instruction: u8,
+    // its first signer, the default key for instructions anyone may send unsigned
+    pub actor: Pubkey,
+    pub slot: u64,
+}
+
+pub const HISTORY_CAPACITY: usize = 32;
+
+impl History {
+    pub const SPACE: usize = 32 + 1 + 8 + HISTORY_CAPACITY * (1 + 32 + 8);
+
+    pub fn new(multisig: Pubkey, bump: u8) -> Self {
+        History {
+            multisig,
+            bump,
+            count: 0,
+            entries: [HistoryEntry::default(); HISTORY_CAPACITY],
+        }
+    }
+
+    pub fn record(&mut self, entry: HistoryEntry) -> Result<(), MultisigError> {
+        self.entries[(self.count % HISTORY_CAPACITY as u64) as usize] = entry;
+        self.count = self
+            .count
+            .checked_add(1)
+            .ok_or(MultisigError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    // The kept entries, oldest first
+    pub fn recent(&self) -> Vec<HistoryEntry> {
+        let kept = self.count.min(HISTORY_CAPACITY as u64);
+        (self.count - kept..self.count)
+            .map(|index| self.entries[(index % HISTORY_CAPACITY as u64) as usize])
+            .collect()
+    }
+}
+
+pub const HISTORY_SEED: &[u8] = b"history";
+
+pub fn find_history_address(multisig: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[HISTORY_SEED, multisig.as_ref()], program_id)
+}
+
+// Program-wide settings of a hosted deployment, the PDA `find_program_config_address`, created
+// by the program's upgrade authority (`InitProgramConfig`) and from then on changed by the
+// `governance` multisig through its proposals (`SetProgramConfig`). On instructions passing the
+// account (`instruction::with_program_config`), which builds with the `program-config` feature
+// require, the program refuses payouts of every wallet while `paused` and takes
+// `protocol_fee_bps` of `Execute` and `ExecuteFromVault` payouts for `fee_destination`. Other
+// builds apply neither to a payout sent without the account
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramConfig {
+    pub governance: Pubkey,
+    pub fee_destination: Pubkey,
+    pub protocol_fee_bps: u16,
+    pub paused: bool,
+    pub bump: u8,
+}
+
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 10_000;
+
+impl ProgramConfig {
+    pub const LEN: usize = 32 + 32 + 2 + 1 + 1;
+
+    // The protocol fee on `lamports`, rounded down
+    pub fn protocol_fee(&self, lamports: u64) -> u64 {
+        (lamports as u128 * self.protocol_fee_bps as u128 / MAX_PROTOCOL_FEE_BPS as u128) as u64
+    }
+}
+
+pub const PROGRAM_CONFIG_SEED: &[u8] = b"config";
+
+pub fn find_program_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROGRAM_CONFIG_SEED], program_id)
+}
+
+// Payment schedule started by an executed `CreateRecurringPayment` proposal, a PDA
+// (`RECURRING_SEED`) of the proposal account. Anyone cranks `ExecuteRecurring` to have the vault
+// pay `amount` lamports once `next_payment_at` has passed; the account is closed back into the
+// vault after the last payment
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct RecurringPayment {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub interval_seconds: i64,
+    pub remaining: u32,
+    pub next_payment_at: i64,
+    // the vault of the proposal, it pays every installment
+    pub vault: u8,
+}
+
+impl RecurringPayment {
+    // fixed size, no vectors
+    pub const SPACE: usize = 32 + 32 + 32 + 8 + 8 + 4 + 8 + 1;
+}
+
+pub const RECURRING_SEED: &[u8] = b"recurring";
+
+pub fn find_recurring_payment_address(transaction: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RECURRING_SEED, transaction.as_ref()], program_id)
+}
+
+// Grant round started by an executed `CreateDistribution` proposal, a PDA (`DISTRIBUTION_SEED`) of
+// the proposal account. The (recipient, amount) pairs of the merkle tree are claimed with
+// `ClaimDistribution`; the vault pays out at most `total_cap` lamports over all claims
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Distribution {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub vault: u8,
+    pub merkle_root: [u8; 32],
+    pub total_cap: u64,
+    pub claimed: u64,
+}
+
+impl Distribution {
+    pub const SPACE: usize = 32 + 32 + 1 + 32 + 8 + 8;
+}
+
+pub const DISTRIBUTION_SEED: &[u8] = b"distribution";
+
+pub fn find_distribution_address(transaction: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DISTRIBUTION_SEED, transaction.as_ref()], program_id)
+}
+
+// A recipient's claim on a distribution, a PDA (`CLAIM_SEED`) of both. Its existence is what
+// stops a second claim
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Claim {
+    pub distribution: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+impl Claim {
+    pub const SPACE: usize = 32 + 32 + 8;
+}
+
+pub const CLAIM_SEED: &[u8] = b"claim";
+
+pub fn find_claim_address(
+    distribution: &Pubkey,
+    recipient: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CLAIM_SEED, distribution.as_ref(), recipient.as_ref()],
+        program_id,
+    )
+}
+
+// Payment history of one payee of a multisig, a PDA (`RECEIPT_LEDGER_SEED`) of the multisig and
+// the destination. Payments made with receipt accounts attached (`execute_with_receipt`,
+// `execute_recurring_with_receipt`) bump it and write a `Receipt` at index `count`, so a payee
+// enumerates everything it was paid by deriving receipts `0..count`
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ReceiptLedger {
+    pub multisig: Pubkey,
+    pub destination: Pubkey,
+    pub count: u64,
+    // lamports received over all receipts
+    pub total: u64,
+}
+
+impl ReceiptLedger {
+    pub const SPACE: usize = 32 + 32 + 8 + 8;
+}
+
+// One payment, a PDA (`RECEIPT_SEED`) of the multisig, the destination and its ledger index
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Receipt {
+    pub multisig: Pubkey,
+    pub destination: Pubkey,
+    pub index: u64,
+    pub amount: u64,
+    // proposal behind a recurring payment, the default key for `Execute`
+    pub transaction: Pubkey,
+    pub paid_at: i64,
+    pub slot: u64,
+}
+
+impl Receipt {
+    pub const SPACE: usize = 32 + 32 + 8 + 8 + 32 + 8 + 8;
+}
+
+pub const RECEIPT_LEDGER_SEED: &[u8] = b"receipts";
+pub const RECEIPT_SEED: &[u8] = b"receipt";
+
+pub fn find_receipt_ledger_address(
+    multisig: &Pubkey,
+    destination: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[RECEIPT_LEDGER_SEED, multisig.as_ref(), destination.as_ref()],
+        program_id,
+    )
+}
+
+pub fn find_receipt_address(
+    multisig: &Pubkey,
+    destination: &Pubkey,
+    index: u64,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            RECEIPT_SEED,
+            multisig.as_ref(),
+            destination.as_ref(),
+            &index.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+// Unit of account the owners want amounts shown in (e.g. USDC), a PDA (`DISPLAY_SEED`) written by
+// the threshold-gated `SetDisplayUnit`. Purely informational for clients, the program never
+// converts amounts
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DisplayUnit {
+    pub multisig: Pubkey,
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub symbol: String,
+}
+
+pub const MAX_DISPLAY_SYMBOL_LEN: usize = 10;
+
+pub const DISPLAY_SEED: &[u8] = b"display";
+
+pub fn find_display_unit_address(multisig: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DISPLAY_SEED, multisig.as_ref()], program_id)
+}
+
+// Label of the wallet for explorers and wallet UIs, a PDA (`METADATA_SEED`) written by the
+// threshold-gated `SetMetadata`. Informational only, like `DisplayUnit`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    pub multisig: Pubkey,
+    pub name: String,
+    // icon or JSON document describing the wallet
+    pub uri: String,
+}
+
+pub const MAX_METADATA_NAME_LEN: usize = 32;
+pub const MAX_METADATA_URI_LEN: usize = 200;
+
+pub const METADATA_SEED: &[u8] = b"metadata";
+
+pub fn find_metadata_address(multisig: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[METADATA_SEED, multisig.as_ref()], program_id)
+}
+
+// Optional notification account of an owner, a PDA (`INBOX_SEED`) of the owner key alone so a
+// wallet app only has to watch that one account, whatever multisigs the owner is part of.
+// `Propose` raises `pending` on the inboxes passed after its accounts, the owner lowers it with
+// `ClearInbox`
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Inbox {
+    pub owner: Pubkey,
+    pub pending: bool,
+    // the latest proposal waiting for the owner
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+}
+
+impl Inbox {
+    pub const SPACE: usize = 32 + 1 + 32 + 32;
+}
+
+pub const INBOX_SEED: &[u8] = b"inbox";
+
+pub fn find_inbox_address(owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[INBOX_SEED, owner.as_ref()], program_id)
+}
+
+// A key an owner lets approve on their behalf until `expires_at`, e.g. a browser wallet's hot key
+// while the owner's cold key stays offline. A PDA (`DELEGATE_SEED`) of the multisig and the owner,
+// so an owner has at most one delegate per multisig; `permissions` is a mask of the `DELEGATE_*`
+// bits. Only the owner's own key sets and revokes it, the delegate can't change any configuration
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct Delegate {
+    pub multisig: Pubkey,
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    pub expires_at: i64,
+    pub permissions: u8,
+}
+
+impl Delegate {
+    pub const SPACE: usize = 32 + 32 + 32 + 8 + 1;
+
+    // Whether the delegate may use `permission` at `now`, with the error to refuse it with
+    pub fn check(&self, permission: u8, now: i64) -> Result<(), MultisigError> {
+        if now > self.expires_at {
+            return Err(MultisigError::DelegateExpired);
+        }
+        if self.permissions & permission != permission {
+            return Err(MultisigError::DelegateNotPermitted);
+        }
+        Ok(())
+    }
+}
+
+// `SignAsDelegate`, the owner's `Sign`
+pub const DELEGATE_SIGN: u8 = 1 << 0;
+// `ApproveAsDelegate`, the owner's `Approve` of proposals that don't change the owner set
+pub const DELEGATE_APPROVE: u8 = 1 << 1;
+pub const DELEGATE_PERMISSIONS: u8 = DELEGATE_SIGN | DELEGATE_APPROVE;
+
+pub const DELEGATE_SEED: &[u8] = b"delegate";
+
+pub fn find_delegate_address(
+    multisig: &Pubkey,
+    owner: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[DELEGATE_SEED, multisig.as_ref(), owner.as_ref()],
+        program_id,
+    )
+}
+
+// Optional list of the multisigs an owner belongs to, a PDA (`OWNER_INDEX_SEED`) of the owner key
+// so wallets find them without scanning the program's accounts. `Create` and owner swaps update
+// the indexes passed to them, `SyncOwnerIndex` catches up with any other change (recovery)
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct OwnerIndex {
+    pub owner: Pubkey,
+    pub multisigs: Vec<Pubkey>,
+}
+
+impl OwnerIndex {
+    pub const SPACE: usize = 32 + 4 + 32 * MAX_INDEXED_MULTISIGS;
+}
+
+pub const MAX_INDEXED_MULTISIGS: usize = 32;
+
+pub const OWNER_INDEX_SEED: &[u8] = b"owner-index";
+
+pub fn find_owner_index_address(owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[OWNER_INDEX_SEED, owner.as_ref()], program_id)
+}
+
+// Experimental (`aggregated-approval` feature): group key of the owners, produced off-chain by a
+// threshold scheme with Ed25519 output (e.g. FROST), a PDA (`AGGREGATE_SEED`) written once right
+// after `Create`. A single signature by it over `aggregated_approval_message` approves a proposal
+// for every owner. The program can't check how the key was generated, only that the owner set is
+// still the one it was registered for
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct AggregateKey {
+    pub multisig: Pubkey,
+    pub key: Pubkey,
+    // `owner_set_hash` at registration, any change of owners or threshold retires the key
+    pub owner_set_hash: [u8; 32],
+}
+
+impl AggregateKey {
+    pub const SPACE: usize = 32 + 32 + 32;
+}
+
+pub const AGGREGATE_SEED: &[u8] = b"aggregate";
+
+pub fn find_aggregate_key_address(multisig: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AGGREGATE_SEED, multisig.as_ref()], program_id)
+}
+
+pub fn owner_set_hash(multisig: &Multisig) -> Hash {
+    let threshold = [multisig.threshold];
+    let mut parts: Vec<&[u8]> = vec![&threshold];
+    parts.extend(multisig.owners.iter().map(|owner| owner.as_ref()));
+    hashv(&parts)
+}
+
+// What the aggregate key signs to approve the proposal at `transaction_key`, binds the proposal
+// account, its multisig and its action
+pub fn aggregated_approval_message(transaction_key: &Pubkey, transaction: &Transaction) -> Hash {
+    hashv(&[
+        b"aggregated-approval",
+        transaction_key.as_ref(),
+        transaction.multisig.as_ref(),
+        &transaction.action.try_to_vec().unwrap(),
+    ])
+}
+
+// seed prefix of the PDA which acts as the multisig's authority (token accounts, CPIs)
+pub const VAULT_SEED: &[u8] = b"vault";
+
+// The main vault, index 0
+pub fn find_vault_address(multisig: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    find_indexed_vault_address(multisig, 0, program_id)
+}
+
+// A multisig has up to 256 vaults, separate balances ("ops", "grants", ...) governed by the same
+// owners. They are plain PDAs, funding the address is all it takes to open one; each proposal
+// names the vault its action draws from
+pub fn find_indexed_vault_address(
+    multisig: &Pubkey,
+    vault: u8,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    let index = [vault];
+    Pubkey::find_program_address(&vault_seeds(multisig, &index), program_id)
+}
+
+// Seeds of vault `index[0]`, bump excluded. The main vault keeps the seeds it had before there
+// were several, so its address didn't change
+pub fn vault_seeds<'a>(multisig: &'a Pubkey, index: &'a [u8; 1]) -> Vec<&'a [u8]> {
+    let mut seeds = vec![VAULT_SEED, multisig.as_ref()];
+    if index[0] != 0 {
+        seeds.push(index);
+    }
+    seeds
+}
+
+// Hash of the program bytes held by an upgradeable loader buffer account (metadata header
+// excluded), this is what approvers of a deploy/upgrade proposal sign off on
+pub fn buffer_hash(buffer_data: &[u8]) -> Result<Hash, ProgramError> {
+    let offset = UpgradeableLoaderState::size_of_buffer_metadata();
+    if buffer_data.len() < offset {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(hash(&buffer_data[offset..]))
+}
+
+// Authority of an upgradeable loader buffer account, read from its bincode encoded
+// `UpgradeableLoaderState::Buffer { authority_address }` header
+pub fn buffer_authority(buffer_data: &[u8]) -> Result<Option<Pubkey>, ProgramError> {
+    if buffer_data.len() < UpgradeableLoaderState::size_of_buffer_metadata()
+        || buffer_data[..4] != [1, 0, 0, 0]
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    match buffer_data[4] {
+        0 => Ok(None),
+        1 => Ok(Some(Pubkey::new_from_array(
+            buffer_data[5..37].try_into().unwrap(),
+        ))),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+// Upgrade authority of an upgradeable program's programdata account, read from its bincode encoded
+// `UpgradeableLoaderState::ProgramData { slot, upgrade_authority_address }` header
+pub fn programdata_upgrade_authority(programdata: &[u8]) -> Result<Option<Pubkey>, ProgramError> {
+    if programdata.len() < UpgradeableLoaderState::size_of_programdata_metadata()
+        || programdata[..4] != [3, 0, 0, 0]
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    match programdata[12] {
+        0 => Ok(None),
+        1 => Ok(Some(Pubkey::new_from_array(
+            programdata[13..45].try_into().unwrap(),
+        ))),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
@@ -0,0 +1,150 @@
+// Instructions of the Metaplex Token Metadata program for NFTs held by a vault: `Transfer` and the
+// `Revoke` of token delegates, with the token record and authorization rules accounts
+// programmable NFTs need. Encoded by hand (Borsh: `u8` instruction and args variants, all
+// little-endian) to keep the Metaplex crates out of the program. Accounts a call doesn't use are
+// passed as the Token Metadata program id, the way the program expects optional accounts
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::{pubkey, system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+
+pub const ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+pub const AUTHORIZATION_RULES_PROGRAM_ID: Pubkey =
+    pubkey!("auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg");
+
+const METADATA_SEED: &[u8] = b"metadata";
+const EDITION_SEED: &[u8] = b"edition";
+const TOKEN_RECORD_SEED: &[u8] = b"token_record";
+
+// instruction variant indexes
+const REVOKE: u8 = 45;
+const TRANSFER: u8 = 49;
+// `TransferArgs::V1`
+const TRANSFER_V1: u8 = 0;
+
+// A token delegate of an NFT, the variant index is the `RevokeArgs` one revoking it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenDelegateRole {
+    Sale = 1,
+    Transfer = 2,
+    Utility = 4,
+    Staking = 5,
+    // the SPL Token delegate of a non-programmable NFT
+    Standard = 6,
+    LockedTransfer = 7,
+}
+
+pub fn find_metadata_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[METADATA_SEED, ID.as_ref(), mint.as_ref()], &ID)
+}
+
+pub fn find_master_edition_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[METADATA_SEED, ID.as_ref(), mint.as_ref(), EDITION_SEED],
+        &ID,
+    )
+}
+
+// The state (and token delegate) of a programmable NFT in one token account
+pub fn find_token_record_address(mint: &Pubkey, token_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            METADATA_SEED,
+            ID.as_ref(),
+            mint.as_ref(),
+            TOKEN_RECORD_SEED,
+            token_account.as_ref(),
+        ],
+        &ID,
+    )
+}
+
+fn optional(account: Option<Pubkey>, is_writable: bool) -> AccountMeta {
+    match account {
+        Some(pubkey) if is_writable => AccountMeta::new(pubkey, false),
+        Some(pubkey) => AccountMeta::new_readonly(pubkey, false),
+        None => AccountMeta::new_readonly(ID, false),
+    }
+}
+
+fn rules_accounts(authorization_rules: Option<&Pubkey>) -> [AccountMeta; 2] {
+    [
+        optional(
+            authorization_rules.map(|_| AUTHORIZATION_RULES_PROGRAM_ID),
+            false,
+        ),
+        optional(authorization_rules.copied(), false),
+    ]
+}
+
+// Moves the NFT `mint` from the associated token account of `owner` (who signs) to the one of
+// `destination_owner`, created on the way if missing; `payer` signs and pays the new accounts.
+// `programmable` NFTs move their token record along and are checked against their
+// `authorization_rules` if they have any
+pub fn transfer(
+    mint: &Pubkey,
+    owner: &Pubkey,
+    destination_owner: &Pubkey,
+    payer: &Pubkey,
+    programmable: bool,
+    authorization_rules: Option<&Pubkey>,
+) -> Instruction {
+    let token = get_associated_token_address(owner, mint);
+    let destination_token = get_associated_token_address(destination_owner, mint);
+    let token_record =
+        |token_account| programmable.then(|| find_token_record_address(mint, token_account).0);
+    let mut accounts = vec![
+        AccountMeta::new(token, false),
+        AccountMeta::new_readonly(*owner, false),
+        AccountMeta::new(destination_token, false),
+        AccountMeta::new_readonly(*destination_owner, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(find_metadata_address(mint).0, false),
+        AccountMeta::new_readonly(find_master_edition_address(mint).0, false),
+        optional(token_record(&token), true),
+        optional(token_record(&destination_token), true),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+    ];
+    accounts.extend(rules_accounts(authorization_rules));
+    // an NFT moves whole, no authorization data
+    let amount: u64 = 1;
+    let data = [&[TRANSFER, TRANSFER_V1][..], &amount.to_le_bytes(), &[0]].concat();
+    Instruction::new_with_bytes(ID, &data, accounts)
+}
+
+// Takes the `role` token delegate of `delegate` off the NFT `mint` in the associated token
+// account of `owner` (who signs), `payer` signs
+pub fn revoke(
+    mint: &Pubkey,
+    owner: &Pubkey,
+    delegate: &Pubkey,
+    payer: &Pubkey,
+    role: TokenDelegateRole,
+    authorization_rules: Option<&Pubkey>,
+) -> Instruction {
+    let token = get_associated_token_address(owner, mint);
+    let token_record =
+        (role != TokenDelegateRole::Standard).then(|| find_token_record_address(mint, &token).0);
+    let mut accounts = vec![
+        // token delegates have no delegate record
+        optional(None, true),
+        AccountMeta::new_readonly(*delegate, false),
+        AccountMeta::new(find_metadata_address(mint).0, false),
+        AccountMeta::new_readonly(find_master_edition_address(mint).0, false),
+        optional(token_record, true),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(token, false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    accounts.extend(rules_accounts(authorization_rules));
+    Instruction::new_with_bytes(ID, &[REVOKE, role as u8], accounts)
+}
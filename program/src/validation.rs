@@ -0,0 +1,497 @@
+// Account expectations of every instruction, checked before the processor runs so a malformed
+// account list fails up front with a message naming the account. Only the fixed accounts the
+// builders in `instruction` emit are described here; variable tails (action specific CPI
+// accounts, pending proposals, inboxes) are left to the processors.
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::{system_program, sysvar};
+
+use crate::error::MultisigError;
+use crate::instruction::MultisigInstruction;
+use crate::lookup_table;
+
+#[derive(Clone, Copy)]
+enum Owner {
+    // state of this program, must already exist
+    Program,
+    // PDAs created by the instruction, wallets, the vault
+    Any,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Account(Owner),
+    // an executable program, at a fixed address when known
+    Program(Option<Pubkey>),
+    Sysvar(Pubkey),
+}
+
+#[derive(Clone, Copy)]
+struct AccountSpec {
+    name: &'static str,
+    signer: bool,
+    writable: bool,
+    // writable or not depending on what the account data says, e.g. the multisig of a proposal
+    // whose action edits it
+    either: bool,
+    kind: Kind,
+}
+
+const fn state(name: &'static str, writable: bool) -> AccountSpec {
+    AccountSpec {
+        name,
+        signer: false,
+        writable,
+        either: false,
+        kind: Kind::Account(Owner::Program),
+    }
+}
+
+const fn any(name: &'static str, writable: bool) -> AccountSpec {
+    AccountSpec {
+        name,
+        signer: false,
+        writable,
+        either: false,
+        kind: Kind::Account(Owner::Any),
+    }
+}
+
+const fn signer(name: &'static str, writable: bool) -> AccountSpec {
+    AccountSpec {
+        name,
+        signer: true,
+        writable,
+        either: false,
+        kind: Kind::Account(Owner::Any),
+    }
+}
+
+const fn program(name: &'static str, key: Option<Pubkey>) -> AccountSpec {
+    AccountSpec {
+        name,
+        signer: false,
+        writable: false,
+        either: false,
+        kind: Kind::Program(key),
+    }
+}
+
+const SYSTEM_PROGRAM: AccountSpec = program("system program", Some(system_program::ID));
+const PAYER: AccountSpec = signer("payer", true);
+const LOOKUP_TABLE_PROGRAM: AccountSpec = program("lookup table program", Some(lookup_table::ID));
+const INSTRUCTIONS_SYSVAR: AccountSpec = AccountSpec {
+    name: "instructions sysvar",
+    signer: false,
+    writable: false,
+    either: false,
+    kind: Kind::Sysvar(sysvar::instructions::ID),
+};
+
+// Instructions whose processors take their accounts as named fields instead of walking an
+// iterator. `from_accounts` checks the fixed accounts against their specs, as `validate_accounts`
+// does for the other instructions, so the processor can't mix up their order or skip a check;
+// accounts after the fixed ones are left in `remaining`
+macro_rules! account_context {
+    ($name:ident { $($field:ident: $spec:expr),+ $(,)? }) => {
+        pub(crate) struct $name<'a, 'info> {
+            $(pub $field: &'a AccountInfo<'info>,)+
+            // not every instruction takes a tail
+            #[allow(dead_code)]
+            pub remaining: &'a [AccountInfo<'info>],
+        }
+
+        impl<'a, 'info> $name<'a, 'info> {
+            const SPECS: &'static [AccountSpec] = &[$($spec),+];
+
+            pub fn from_accounts(
+                program_id: &Pubkey,
+                accounts: &'a [AccountInfo<'info>],
+            ) -> Result<Self, ProgramError> {
+                check_accounts(program_id, Self::SPECS, accounts)?;
+                let [$($field,)+ remaining @ ..] = accounts else {
+                    return Err(ProgramError::NotEnoughAccountKeys);
+                };
+                Ok($name {
+                    $($field,)+
+                    remaining,
+                })
+            }
+        }
+    };
+}
+
+// the owner indexes to list the multisig in follow
+account_context!(CreateAccounts {
+    multisig: state("multisig", true),
+});
+
+// the instructions sysvar and the signing fee payer follow when the multisig is in strict signing
+// mode
+account_context!(SignAccounts {
+    owner: signer("owner", false),
+    multisig: state("multisig", true),
+});
+
+account_context!(SignAsMultisigAccounts {
+    child_owner: signer("child owner", false),
+    child: state("child", true),
+    multisig: state("multisig", true),
+});
+
+account_context!(DepositAccounts {
+    depositor: signer("depositor", true),
+    multisig: state("multisig", false),
+    vault: any("vault", true),
+    system_program: SYSTEM_PROGRAM,
+});
+
+// the threshold-gated settings of a multisig, the payer funds any growth of the account
+account_context!(SetterAccounts {
+    multisig: state("multisig", true),
+    payer: PAYER,
+    system_program: SYSTEM_PROGRAM,
+});
+
+fn expected_accounts(instruction: &MultisigInstruction) -> Vec<AccountSpec> {
+    use MultisigInstruction::*;
+    match instruction {
+        // checked when the processor builds their account context
+        Create { .. }
+        | Sign
+        | SignAsMultisig
+        | Deposit { .. }
+        | SetVetoAuthority { .. }
+        | SetEmergencyAuthority { .. }
+        | SetPolicy { .. }
+        | SetApprovalTtl { .. }
+        | SetOutflowCap { .. }
+        | SetPolicySigner { .. }
+        | SetTransferMinimum { .. }
+        | SetStrictSigning { .. } => Vec::new(),
+        InitProgramConfig { .. } => vec![
+            PAYER,
+            signer("upgrade authority", false),
+            any("programdata", false),
+            any("config", true),
+            SYSTEM_PROGRAM,
+        ],
+        SetProgramConfig { .. } => vec![
+            state("multisig", false),
+            signer("vault", false),
+            state("config", true),
+        ],
+        SetFeeExempt { .. } => vec![
+            // writable when it exempts itself
+            AccountSpec {
+                either: true,
+                ..state("governance", false)
+            },
+            signer("vault", false),
+            state("config", false),
+            state("multisig", true),
+        ],
+        CreateDerived { .. } => vec![
+            PAYER,
+            signer("creator", false),
+            any("multisig", true),
+            SYSTEM_PROGRAM,
+        ],
+        Execute { .. } => vec![
+            state("multisig", true),
+            any("destination", true),
+            SYSTEM_PROGRAM,
+            any("destination policy", false),
+        ],
+        CreateTokenAccount { .. } => vec![
+            state("multisig", true),
+            PAYER,
+            any("vault", false),
+            any("token account", true),
+            any("mint", false),
+            SYSTEM_PROGRAM,
+            program("token program", None),
+            program(
+                "associated token program",
+                Some(spl_associated_token_account::ID),
+            ),
+        ],
+        Propose { .. } | Approve => vec![
+            signer("owner", false),
+            state("multisig", false),
+            state("transaction", true),
+        ],
+        ProposeIndexed { .. } => vec![
+            signer("proposer", true),
+            state("multisig", true),
+            any("transaction", true),
+            SYSTEM_PROGRAM,
+        ],
+        // writable for actions that edit the multisig (`SwapOwner`)
+        ExecuteTransaction => vec![
+            AccountSpec {
+                either: true,
+                ..state("multisig", false)
+            },
+            state("transaction", true),
+            any("vault", true),
+        ],
+        SwapOwner { .. } => vec![state("multisig", true)],
+        InitGuardians { .. } => vec![
+            AccountSpec {
+                signer: true,
+                ..state("multisig", false)
+            },
+            PAYER,
+            any("guardians", true),
+            SYSTEM_PROGRAM,
+        ],
+        ProposeRecovery { .. } => vec![
+            signer("guardian", true),
+            state("multisig", false),
+            state("guardians", false),
+            any("recovery", true),
+            SYSTEM_PROGRAM,
+        ],
+        ApproveRecovery => vec![
+            signer("guardian", false),
+            state("multisig", false),
+            state("guardians", false),
+            state("recovery", true),
+        ],
+        VetoRecovery => vec![
+            signer("owner", false),
+            state("multisig", false),
+            state("recovery", true),
+            any("proposer", true),
+        ],
+        ExecuteRecovery => vec![
+            PAYER,
+            state("multisig", true),
+            state("guardians", false),
+            state("recovery", true),
+            any("proposer", true),
+            SYSTEM_PROGRAM,
+        ],
+        CloseTransaction => vec![
+            state("multisig", false),
+            state("transaction", true),
+            any("proposer", true),
+        ],
+        VetoTransaction => vec![
+            signer("veto authority", false),
+            state("multisig", false),
+            state("transaction", true),
+            any("proposer", true),
+        ],
+        SetDestinationPolicy { .. }
+        | AddDestination { .. }
+        | RemoveDestination { .. }
+        | SetDestinationPolicyEnforcement { .. } => vec![
+            state("multisig", true),
+            PAYER,
+            any("destination policy", true),
+            SYSTEM_PROGRAM,
+        ],
+        SetDisplayUnit { .. } => vec![
+            state("multisig", true),
+            PAYER,
+            any("display unit", true),
+            SYSTEM_PROGRAM,
+        ],
+        SetPaused { .. } => vec![state("multisig", true), signer("authority", false)],
+        ExecuteRecurring => vec![
+            state("multisig", false),
+            state("recurring payment", true),
+            any("vault", true),
+            any("destination", true),
+            SYSTEM_PROGRAM,
+        ],
+        ClaimDistribution { .. } => vec![
+            state("multisig", false),
+            state("distribution", true),
+            any("vault", true),
+            any("recipient", true),
+            any("claim", true),
+            PAYER,
+            SYSTEM_PROGRAM,
+        ],
+        SetDelegate { .. } => vec![
+            signer("owner", true),
+            state("multisig", false),
+            any("delegate record", true),
+            SYSTEM_PROGRAM,
+        ],
+        RevokeDelegate => vec![signer("owner", true), state("delegate record", true)],
+        SignAsDelegate => vec![
+            signer("delegate", false),
+            state("multisig", true),
+            state("delegate record", false),
+        ],
+        ApproveAsDelegate => vec![
+            signer("delegate", false),
+            state("multisig", false),
+            state("transaction", true),
+            state("delegate record", false),
+        ],
+        InitHistory => vec![
+            PAYER,
+            state("multisig", false),
+            any("history", true),
+            SYSTEM_PROGRAM,
+        ],
+        ChangeThreshold { .. } | AddOwner { .. } => {
+            vec![state("multisig", true), signer("vault", false)]
+        }
+        RegisterInbox => vec![signer("owner", true), any("inbox", true), SYSTEM_PROGRAM],
+        ClearInbox => vec![signer("owner", false), state("inbox", true)],
+        ExtendProposal { .. } => vec![state("multisig", false), state("transaction", true)],
+        InitAggregateKey { .. } => vec![
+            AccountSpec {
+                signer: true,
+                ..state("multisig", false)
+            },
+            PAYER,
+            any("aggregate key", true),
+            SYSTEM_PROGRAM,
+        ],
+        ApproveAggregated => vec![
+            state("multisig", false),
+            state("transaction", true),
+            state("aggregate key", false),
+            INSTRUCTIONS_SYSVAR,
+        ],
+        ApproveWithSignature { .. } => vec![
+            state("multisig", false),
+            state("transaction", true),
+            INSTRUCTIONS_SYSVAR,
+        ],
+        CreateLookupTable { .. } | ExtendLookupTable { .. } => vec![
+            state("multisig", true),
+            PAYER,
+            any("vault", false),
+            any("lookup table", true),
+            SYSTEM_PROGRAM,
+            LOOKUP_TABLE_PROGRAM,
+        ],
+        DeactivateLookupTable => vec![
+            state("multisig", true),
+            any("vault", false),
+            any("lookup table", true),
+            LOOKUP_TABLE_PROGRAM,
+        ],
+        CheckApproved { .. } => vec![state("multisig", false)],
+        ExecuteFromVault { .. } => vec![
+            state("multisig", true),
+            any("vault", true),
+            any("destination", true),
+            SYSTEM_PROGRAM,
+            any("destination policy", false),
+        ],
+        MigrateToVault => vec![state("multisig", true), any("vault", true)],
+        RegisterOwnerIndex => vec![
+            signer("owner", true),
+            any("owner index", true),
+            SYSTEM_PROGRAM,
+        ],
+        SyncOwnerIndex => vec![state("owner index", true), any("multisig", false)],
+        UnlistMultisig { .. } => vec![signer("owner", false), state("owner index", true)],
+        SetMetadata { .. } => vec![
+            state("multisig", true),
+            PAYER,
+            any("metadata", true),
+            SYSTEM_PROGRAM,
+        ],
+        CrankTransaction => vec![
+            signer("executor", true),
+            SYSTEM_PROGRAM,
+            AccountSpec {
+                either: true,
+                ..state("multisig", false)
+            },
+            state("transaction", true),
+            any("vault", true),
+        ],
+        CloseLookupTable => vec![
+            state("multisig", true),
+            any("vault", true),
+            any("lookup table", true),
+            LOOKUP_TABLE_PROGRAM,
+        ],
+    }
+}
+
+// Checks signer, writable, owner and executable flags of the fixed accounts of `instruction`.
+// Read-only accounts that aren't signers must also be passed read-only; signers are exempt, the
+// fee payer is always writable
+pub(crate) fn validate_accounts(
+    program_id: &Pubkey,
+    instruction: &MultisigInstruction,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    check_accounts(program_id, &expected_accounts(instruction), accounts)
+}
+
+fn check_accounts(
+    program_id: &Pubkey,
+    expected: &[AccountSpec],
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if accounts.len() < expected.len() {
+        msg!(
+            "Expected at least {} accounts, got {}",
+            expected.len(),
+            accounts.len()
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    for (index, (spec, account)) in expected.iter().zip(accounts).enumerate() {
+        if let Err((problem, err)) = check_account(program_id, spec, account) {
+            msg!(
+                "Account {} ({}) {} {}",
+                index,
+                spec.name,
+                account.key,
+                problem
+            );
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+fn check_account(
+    program_id: &Pubkey,
+    spec: &AccountSpec,
+    account: &AccountInfo,
+) -> Result<(), (&'static str, ProgramError)> {
+    if spec.signer && !account.is_signer {
+        return Err(("must sign", ProgramError::MissingRequiredSignature));
+    }
+    if spec.writable && !account.is_writable {
+        return Err(("must be writable", ProgramError::InvalidAccountData));
+    }
+    if !spec.writable && !spec.signer && !spec.either && account.is_writable {
+        return Err(("must be read-only", ProgramError::InvalidAccountData));
+    }
+    match spec.kind {
+        Kind::Account(Owner::Program) if account.owner != program_id => Err((
+            "must be owned by the program",
+            ProgramError::IncorrectProgramId,
+        )),
+        Kind::Program(key) if !account.executable || key.is_some_and(|key| key != *account.key) => {
+            Err((
+                "is not the expected program",
+                ProgramError::IncorrectProgramId,
+            ))
+        }
+        Kind::Sysvar(key) if *account.key != key => Err((
+            "is not the expected sysvar",
+            MultisigError::AccountMismatch.into(),
+        )),
+        _ => Ok(()),
+    }
+}
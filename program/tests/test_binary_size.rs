@@ -0,0 +1,30 @@
+// Size budget of the compiled program (`cargo build-sbf`). Deploying and upgrading cost rent for
+// every byte, so features must not quietly grow the binary. Ignored by default like the compute
+// budget tests, run it with `cargo build-sbf && cargo test -- --ignored`.
+//
+// `MULTISIG_SIZE_BUDGET` (bytes) overrides the budget, e.g. to check a build with
+// `--features debug-logs` or `aggregated-approval` against its own limit.
+use multisig_test_utils::compiled_program;
+
+const BINARY_SIZE_BUDGET: u64 = 400 * 1024;
+
+#[test]
+#[ignore = "needs the compiled program, run `cargo build-sbf` first"]
+fn test_binary_size_budget() {
+    let path =
+        compiled_program().expect("solana_multisig_wallet.so not found, run `cargo build-sbf`");
+    let budget = std::env::var("MULTISIG_SIZE_BUDGET")
+        .map(|budget| {
+            budget
+                .parse()
+                .expect("MULTISIG_SIZE_BUDGET must be a byte count")
+        })
+        .unwrap_or(BINARY_SIZE_BUDGET);
+    let size = std::fs::metadata(&path).unwrap().len();
+    println!("{}: {size} bytes", path.display());
+    assert!(
+        size <= budget,
+        "the program is {size} bytes, the budget is {budget}; keep formatting and panics off the \
+         instruction paths (see the note in processor.rs) or move the code behind a feature"
+    );
+}
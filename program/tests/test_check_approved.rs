@@ -0,0 +1,101 @@
+// Another program gating its own instruction on the multisig's approvals, through a
+// `CheckApproved` CPI or the linked-in `assert_approved`
+use borsh::{BorshDeserialize, BorshSerialize};
+use multisig_test_utils::{next_blockhash, process, program_test, MultisigHarness};
+use solana_multisig_wallet::{assert_approved, instruction, MultisigError, ThresholdContext};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program::invoke;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{processor, BanksClientError};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+const VIA_CPI: u8 = 0;
+const LINKED: u8 = 1;
+
+// data: how to check, then the Borsh encoded `ThresholdContext`
+fn guarded_program(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let multisig = &accounts[0];
+    let (&how, context) = data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let context = ThresholdContext::try_from_slice(context)?;
+    match how {
+        VIA_CPI => invoke(
+            &instruction::check_approved(&solana_multisig_wallet::id(), multisig.key, context),
+            &[multisig.clone(), accounts[1].clone()],
+        ),
+        _ => assert_approved(&solana_multisig_wallet::id(), multisig, context),
+    }
+}
+
+fn guarded(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    how: u8,
+    context: ThresholdContext,
+) -> Instruction {
+    let data = [vec![how], context.try_to_vec().unwrap()].concat();
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new_readonly(solana_multisig_wallet::id(), false),
+        ],
+    )
+}
+
+fn assert_insufficient(result: Result<(), BanksClientError>) {
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MultisigError::InsufficientSignatures.code())
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_check_approved() {
+    let guarded_id = Pubkey::new_unique();
+    let mut program_test = program_test();
+    program_test.add_program("guarded", guarded_id, processor!(guarded_program));
+    let mut harness = MultisigHarness::start(program_test, 3, 2, 0).await;
+    let multisig = harness.multisig;
+
+    for how in [VIA_CPI, LINKED] {
+        let ix = guarded(&guarded_id, &multisig, how, ThresholdContext::Threshold);
+        assert_insufficient(process(&mut harness.context, ix, &[]).await);
+    }
+
+    harness.sign_as(0).await.unwrap();
+    harness.sign_as(1).await.unwrap();
+    for how in [VIA_CPI, LINKED] {
+        let ix = guarded(&guarded_id, &multisig, how, ThresholdContext::Threshold);
+        process(&mut harness.context, ix, &[]).await.unwrap();
+        let ix = guarded(&guarded_id, &multisig, how, ThresholdContext::AtLeast(3));
+        assert_insufficient(process(&mut harness.context, ix, &[]).await);
+    }
+    let ix = instruction::check_approved(
+        &solana_multisig_wallet::id(),
+        &multisig,
+        ThresholdContext::Amount(1),
+    );
+    process(&mut harness.context, ix, &[]).await.unwrap();
+
+    // checking leaves the approvals in place
+    harness.sign_as(2).await.unwrap();
+    assert_eq!(harness.state().await.signers, vec![true; 3]);
+    next_blockhash(&mut harness.context).await;
+    let ix = guarded(
+        &guarded_id,
+        &multisig,
+        VIA_CPI,
+        ThresholdContext::AtLeast(3),
+    );
+    process(&mut harness.context, ix, &[]).await.unwrap();
+}
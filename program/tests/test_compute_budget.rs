@@ -0,0 +1,68 @@
+// Compute-unit budgets of the hot paths, metered on the compiled program (`cargo build-sbf`).
+// Native builtins aren't metered, so these tests are ignored by default: run them with
+// `cargo build-sbf && cargo test -- --ignored`.
+use multisig_test_utils::compiled_program;
+use solana_multisig_wallet::{instruction, Multisig, MAX_OWNERS};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::ProgramTest;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+
+// `Sign` by the last of `MAX_OWNERS` owners, the worst case of the owner lookup
+const SIGN_COMPUTE_BUDGET: u64 = 6_000;
+
+#[tokio::test]
+#[ignore = "needs the compiled program, run `cargo build-sbf` first"]
+async fn test_sign_compute_budget() {
+    assert!(
+        compiled_program().is_some(),
+        "solana_multisig_wallet.so not found, run `cargo build-sbf`"
+    );
+    let program_id = solana_multisig_wallet::id();
+    let mut program_test = ProgramTest::new("solana_multisig_wallet", program_id, None);
+    program_test.prefer_bpf(true);
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs: Vec<Keypair> = (0..MAX_OWNERS).map(|_| Keypair::new()).collect();
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_keypair = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            solana_sdk::system_instruction::create_account(
+                &context.payer.pubkey(),
+                &multisig_keypair.pubkey(),
+                rent.minimum_balance(Multisig::LEN),
+                Multisig::LEN as u64,
+                &program_id,
+            ),
+            instruction::create(&program_id, &multisig_keypair.pubkey(), owners.clone(), 2),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &multisig_keypair],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let last_owner = owner_keypairs.last().unwrap();
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::sign(
+            &program_id,
+            &last_owner.pubkey(),
+            &multisig_keypair.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, last_owner],
+        recent_blockhash,
+    );
+    let simulation = context.banks_client.simulate_transaction(tx).await.unwrap();
+    simulation.result.unwrap().unwrap();
+    let units = simulation.simulation_details.unwrap().units_consumed;
+    println!("Sign: {units} compute units");
+    assert!(
+        units <= SIGN_COMPUTE_BUDGET,
+        "Sign used {units} compute units, the budget is {SIGN_COMPUTE_BUDGET}"
+    );
+}
@@ -0,0 +1,141 @@
+// Multisigs created at a PDA of their creator by a program, here a launchpad opening one wallet per
+// deal: it signs `CreateDerived` with its own PDA and passes the deal id as the seed
+use multisig_test_utils::{process, program_test};
+use solana_multisig_wallet::instruction;
+use solana_multisig_wallet::state::load;
+use solana_multisig_wallet::{find_derived_multisig_address, Multisig};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program::invoke_signed;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_program_test::processor;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const AUTHORITY_SEED: &[u8] = b"authority";
+
+// Creates the wallet of the deal `data` (the seed) for the owners after the accounts, 2 of them
+// approving
+fn launchpad(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let [payer, authority, multisig, system, multisig_program, owners @ ..] = accounts else {
+        panic!("{} accounts", accounts.len());
+    };
+    let (_, bump) = Pubkey::find_program_address(&[AUTHORITY_SEED], program_id);
+    let owners = owners.iter().map(|owner| *owner.key).collect();
+    let create = instruction::create_derived(
+        multisig_program.key,
+        payer.key,
+        authority.key,
+        data.try_into().unwrap(),
+        owners,
+        2,
+    );
+    invoke_signed(
+        &create,
+        &[
+            payer.clone(),
+            authority.clone(),
+            multisig.clone(),
+            system.clone(),
+        ],
+        &[&[AUTHORITY_SEED, &[bump]]],
+    )
+}
+
+#[tokio::test]
+async fn test_create_derived() {
+    let program_id = solana_multisig_wallet::id();
+    let launchpad_id = Pubkey::new_unique();
+    let mut program_test = program_test();
+    program_test.add_program("launchpad", launchpad_id, processor!(launchpad));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let (authority, _) = Pubkey::find_program_address(&[AUTHORITY_SEED], &launchpad_id);
+    let owners = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+
+    let open_deal = |deal: [u8; 32]| {
+        let (multisig, _) = find_derived_multisig_address(&authority, &deal, &program_id);
+        let mut accounts = vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(authority, false),
+            AccountMeta::new(multisig, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(program_id, false),
+        ];
+        accounts.extend(owners.map(|owner| AccountMeta::new_readonly(owner, false)));
+        (
+            multisig,
+            Instruction::new_with_bytes(launchpad_id, &deal, accounts),
+        )
+    };
+
+    for deal in [[1; 32], [2; 32]] {
+        let (multisig, ix) = open_deal(deal);
+        process(&mut context, ix, &[]).await.unwrap();
+        let account = context
+            .banks_client
+            .get_account(multisig)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.owner, program_id);
+        let multisig = load::<Multisig>(&account.data).unwrap();
+        assert_eq!(multisig.owners, owners);
+        assert_eq!(multisig.threshold, 2);
+    }
+
+    // a deal's wallet is only created once
+    let (_, ix) = open_deal([1; 32]);
+    let err = process(&mut context, ix, &[]).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+    // nor rewritten by a plain `Create`
+    let (multisig, _) = open_deal([1; 32]);
+    let hijack = instruction::create(&program_id, &multisig, vec![payer], 1);
+    let err = process(&mut context, hijack, &[])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+    let account = context
+        .banks_client
+        .get_account(multisig)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(load::<Multisig>(&account.data).unwrap().owners, owners);
+
+    // and only by its creator: the launchpad's address can't be taken by someone else signing
+    let squatter = Keypair::new();
+    let mut ix = instruction::create_derived(
+        &program_id,
+        &payer,
+        &squatter.pubkey(),
+        [3; 32],
+        owners.to_vec(),
+        2,
+    );
+    let (launchpad_address, _) = find_derived_multisig_address(&authority, &[3; 32], &program_id);
+    ix.accounts[2].pubkey = launchpad_address;
+    let err = process(&mut context, ix, &[&squatter])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+}
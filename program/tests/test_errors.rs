@@ -0,0 +1,158 @@
+// Failures clients rely on: the exact `MultisigError` each one returns, and the codes themselves
+use multisig_test_utils::{next_blockhash, process, MultisigHarness};
+use solana_multisig_wallet::{instruction, Multisig, MultisigError, MAX_OWNERS};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::BanksClientError;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+const LAMPORTS: u64 = 1_000_000_000;
+
+fn assert_error(result: Result<(), BanksClientError>, index: u8, expected: MultisigError) {
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(index, InstructionError::Custom(expected.code()))
+    );
+}
+
+// Renumbering breaks every client mapping codes to messages, new errors only go at the end
+#[test]
+fn test_error_codes_are_stable() {
+    let codes = [
+        (0, MultisigError::InvalidThreshold),
+        (1, MultisigError::TooManyOwners),
+        (2, MultisigError::OwnerNotFound),
+        (3, MultisigError::DuplicateOwner),
+        (4, MultisigError::InsufficientSignatures),
+        (5, MultisigError::RequiredSignerMissing),
+        (6, MultisigError::InsufficientBalance),
+        (7, MultisigError::BelowRentFloor),
+        (8, MultisigError::InvalidDestination),
+        (9, MultisigError::AccountMismatch),
+        (10, MultisigError::WrongMultisig),
+        (11, MultisigError::InvalidAction),
+        (12, MultisigError::InvalidExpiry),
+        (13, MultisigError::ProposalExpired),
+        (14, MultisigError::AlreadyExecuted),
+        (15, MultisigError::StaleProposal),
+        (16, MultisigError::NotExtendable),
+        (17, MultisigError::Paused),
+        (18, MultisigError::PolicyViolation),
+        (19, MultisigError::DuplicateDestination),
+        (20, MultisigError::DestinationNotFound),
+        (21, MultisigError::VetoAuthorityIsOwner),
+        (22, MultisigError::NotVetoAuthority),
+        (23, MultisigError::InvalidConfig),
+        (24, MultisigError::NotGuardian),
+        (25, MultisigError::RecoveryPending),
+        (26, MultisigError::RecoveryNotReady),
+        (27, MultisigError::OwnerSetChanged),
+        (28, MultisigError::AlreadyClaimed),
+        (29, MultisigError::InvalidProof),
+        (30, MultisigError::DistributionCapExceeded),
+        (31, MultisigError::PaymentNotDue),
+        (32, MultisigError::TransferFeeTooHigh),
+        (33, MultisigError::InvalidBufferAuthority),
+        (34, MultisigError::BufferHashMismatch),
+        (35, MultisigError::OwnerIndexFull),
+        (36, MultisigError::InvalidTransition),
+        (37, MultisigError::OutflowCapExceeded),
+        (38, MultisigError::DelegateExpired),
+        (39, MultisigError::DelegateNotPermitted),
+        (40, MultisigError::PolicySignerMissing),
+        (41, MultisigError::DecimalsMismatch),
+        (42, MultisigError::ProposalNotClosable),
+        (43, MultisigError::ArithmeticOverflow),
+        (44, MultisigError::ZeroAmount),
+        (45, MultisigError::BelowMinimumTransfer),
+        (46, MultisigError::NonOwnerSigner),
+        (47, MultisigError::AlreadySigned),
+    ];
+    assert_eq!(codes.len(), MultisigError::ALL.len());
+    for (code, err) in codes {
+        assert_eq!(err.code(), code);
+        assert_eq!(MultisigError::from_code(code), Some(err));
+        assert_eq!(ProgramError::from(err), ProgramError::Custom(code));
+    }
+    assert_eq!(MultisigError::from_code(codes.len() as u32), None);
+}
+
+#[tokio::test]
+async fn test_create_errors() {
+    let harness = MultisigHarness::new(1, 1, 0).await;
+    let mut context = harness.context;
+    let program_id = solana_multisig_wallet::id();
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let owners = |count: usize| (0..count).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+    let cases = [
+        (owners(2), 0, MultisigError::InvalidThreshold),
+        (owners(2), 3, MultisigError::InvalidThreshold),
+        (owners(MAX_OWNERS + 1), 1, MultisigError::TooManyOwners),
+        // a quorum of one key listed twice could never be reached
+        (
+            vec![Pubkey::new_unique(); 2],
+            2,
+            MultisigError::DuplicateOwner,
+        ),
+    ];
+    for (owners, threshold, expected) in cases {
+        let multisig = Keypair::new();
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                solana_sdk::system_instruction::create_account(
+                    &context.payer.pubkey(),
+                    &multisig.pubkey(),
+                    rent.minimum_balance(Multisig::LEN),
+                    Multisig::LEN as u64,
+                    &program_id,
+                ),
+                instruction::create(&program_id, &multisig.pubkey(), owners, threshold),
+            ],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &multisig],
+            context.last_blockhash,
+        );
+        let result = context.banks_client.process_transaction(tx).await;
+        assert_error(result, 1, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_sign_and_execute_errors() {
+    let program_id = solana_multisig_wallet::id();
+    let mut harness = MultisigHarness::new(2, 2, LAMPORTS).await;
+    let multisig = harness.multisig;
+    let destination = Pubkey::new_unique();
+
+    let stranger = Keypair::new();
+    let ix = instruction::sign(&program_id, &stranger.pubkey(), &multisig);
+    let result = process(&mut harness.context, ix, &[&stranger]).await;
+    assert_error(result, 0, MultisigError::OwnerNotFound);
+
+    harness.sign_as(0).await.unwrap();
+    // a second `Sign` is refused rather than recorded again
+    next_blockhash(&mut harness.context).await;
+    let result = harness.sign_as(0).await;
+    assert_error(result, 0, MultisigError::AlreadySigned);
+    let result = harness.execute(10_000_000, &destination).await;
+    assert_error(result, 0, MultisigError::InsufficientSignatures);
+
+    harness.sign_as(1).await.unwrap();
+    let result = harness.execute(10_000_000, &multisig).await;
+    assert_error(result, 0, MultisigError::InvalidDestination);
+    let balance = harness.balance(&multisig).await;
+    let result = harness.execute(balance + 1, &destination).await;
+    assert_error(result, 0, MultisigError::InsufficientBalance);
+    let result = harness.execute(u64::MAX, &destination).await;
+    assert_error(result, 0, MultisigError::InsufficientBalance);
+    let result = harness.execute(LAMPORTS + 1, &destination).await;
+    assert_error(result, 0, MultisigError::BelowRentFloor);
+
+    // everything above the rent-exempt minimum can go
+    harness.execute(LAMPORTS, &destination).await.unwrap();
+    assert_eq!(harness.balance(&destination).await, LAMPORTS);
+}
@@ -0,0 +1,39 @@
+use borsh::BorshDeserialize;
+use solana_multisig_wallet::{fixtures, Multisig, Transaction};
+use std::path::PathBuf;
+
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+}
+
+#[test]
+fn test_committed_fixtures_are_up_to_date() {
+    let fixtures = fixtures::all();
+    for fixture in &fixtures {
+        let committed = std::fs::read(fixtures_dir().join(format!("{}.bin", fixture.name)))
+            .unwrap_or_else(|err| panic!("{}: {err}", fixture.name));
+        assert_eq!(
+            committed, fixture.data,
+            "{} is stale, run `cargo run --example fixtures`",
+            fixture.name
+        );
+
+        // and the bytes decode back with the program's own types
+        match fixture.kind {
+            "multisig" => {
+                Multisig::try_from_slice(&committed).unwrap();
+            }
+            "transaction" => {
+                Transaction::try_from_slice(&committed).unwrap();
+            }
+            kind => panic!("unknown fixture kind {kind}"),
+        }
+    }
+
+    let manifest = std::fs::read_to_string(fixtures_dir().join("manifest.json")).unwrap();
+    assert_eq!(
+        manifest,
+        fixtures::manifest(&fixtures),
+        "manifest.json is stale, run `cargo run --example fixtures`"
+    );
+}
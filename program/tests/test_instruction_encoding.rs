@@ -0,0 +1,318 @@
+// The instruction wire format deployed clients rely on: the tag of every instruction and the exact
+// bytes of the common ones
+use borsh::BorshSerialize;
+use solana_multisig_wallet::fixtures::key;
+use solana_multisig_wallet::instruction::{
+    MultisigInstruction, ThresholdContext, MAX_INSTRUCTION_TAG, PACKED_FORMAT_TAG, RESERVED_TAGS,
+};
+use solana_multisig_wallet::{DestinationMode, PolicyEnforcement, ProposalAction};
+use solana_program::program_error::ProgramError;
+
+// Renumbering breaks every deployed client, new instructions only go at the end
+#[test]
+fn test_instruction_tags_are_stable() {
+    let tags = [
+        (
+            0,
+            MultisigInstruction::Create {
+                owners: vec![key(1)],
+                threshold: 1,
+            },
+        ),
+        (1, MultisigInstruction::Sign),
+        (
+            2,
+            MultisigInstruction::Execute {
+                amount: 1,
+                destination: key(2),
+            },
+        ),
+        (3, MultisigInstruction::CreateTokenAccount { mint: key(3) }),
+        (
+            4,
+            MultisigInstruction::Propose {
+                action: ProposalAction::StakeDeactivate { stake: key(4) },
+                vault: 0,
+                expires_at: None,
+                required_signers: vec![],
+                executor_fee: 0,
+                attachment: None,
+            },
+        ),
+        (5, MultisigInstruction::Approve),
+        (6, MultisigInstruction::ExecuteTransaction),
+        (
+            7,
+            MultisigInstruction::SwapOwner {
+                old_owner: key(1),
+                new_owner: key(2),
+            },
+        ),
+        (
+            8,
+            MultisigInstruction::InitGuardians {
+                guardians: vec![key(5)],
+                recovery_delay: 60,
+            },
+        ),
+        (
+            9,
+            MultisigInstruction::ProposeRecovery {
+                new_owners: vec![key(6)],
+                new_threshold: 1,
+            },
+        ),
+        (10, MultisigInstruction::ApproveRecovery),
+        (11, MultisigInstruction::VetoRecovery),
+        (12, MultisigInstruction::ExecuteRecovery),
+        (
+            13,
+            MultisigInstruction::SetVetoAuthority {
+                veto_authority: None,
+            },
+        ),
+        (14, MultisigInstruction::VetoTransaction),
+        (
+            15,
+            MultisigInstruction::SetDestinationPolicy {
+                mode: DestinationMode::Allowlist,
+                destinations: vec![],
+            },
+        ),
+        (
+            16,
+            MultisigInstruction::AddDestination {
+                destination: key(7),
+            },
+        ),
+        (
+            17,
+            MultisigInstruction::RemoveDestination {
+                destination: key(7),
+            },
+        ),
+        (
+            18,
+            MultisigInstruction::SetDestinationPolicyEnforcement {
+                enforcement: PolicyEnforcement::Shadow,
+            },
+        ),
+        (19, MultisigInstruction::SetPaused { paused: true }),
+        (
+            20,
+            MultisigInstruction::SetEmergencyAuthority {
+                emergency_authority: None,
+            },
+        ),
+        (21, MultisigInstruction::ExecuteRecurring),
+        (
+            22,
+            MultisigInstruction::InitAggregateKey {
+                aggregate_key: key(8),
+            },
+        ),
+        (23, MultisigInstruction::ApproveAggregated),
+        (24, MultisigInstruction::RegisterInbox),
+        (25, MultisigInstruction::ClearInbox),
+        (26, MultisigInstruction::ExtendProposal { seconds: 60 }),
+        (27, MultisigInstruction::SetPolicy { policy: None }),
+        (
+            28,
+            MultisigInstruction::SetDisplayUnit {
+                mint: key(9),
+                decimals: 6,
+                symbol: "USDC".to_string(),
+            },
+        ),
+        (
+            29,
+            MultisigInstruction::SetApprovalTtl { ttl_seconds: None },
+        ),
+        (
+            30,
+            MultisigInstruction::ClaimDistribution {
+                amount: 1,
+                proof: vec![],
+            },
+        ),
+        (
+            31,
+            MultisigInstruction::CreateLookupTable { recent_slot: 9 },
+        ),
+        (
+            32,
+            MultisigInstruction::ExtendLookupTable { addresses: vec![] },
+        ),
+        (33, MultisigInstruction::DeactivateLookupTable),
+        (34, MultisigInstruction::CloseLookupTable),
+        (
+            35,
+            MultisigInstruction::CheckApproved {
+                context: ThresholdContext::Threshold,
+            },
+        ),
+        (
+            36,
+            MultisigInstruction::ExecuteFromVault {
+                amount: 1,
+                destination: key(2),
+            },
+        ),
+        (37, MultisigInstruction::MigrateToVault),
+        (38, MultisigInstruction::CrankTransaction),
+        (39, MultisigInstruction::RegisterOwnerIndex),
+        (40, MultisigInstruction::SyncOwnerIndex),
+        (
+            41,
+            MultisigInstruction::UnlistMultisig { multisig: key(10) },
+        ),
+        (
+            42,
+            MultisigInstruction::SetMetadata {
+                name: "Treasury".to_string(),
+                uri: String::new(),
+            },
+        ),
+        (
+            43,
+            MultisigInstruction::ApproveWithSignature { owner: key(11) },
+        ),
+        (44, MultisigInstruction::SetOutflowCap { cap: None }),
+        (
+            45,
+            MultisigInstruction::SetDelegate {
+                delegate: key(12),
+                expires_at: 1_700_000_000,
+                permissions: 1,
+            },
+        ),
+        (46, MultisigInstruction::RevokeDelegate),
+        (47, MultisigInstruction::SignAsDelegate),
+        (48, MultisigInstruction::ApproveAsDelegate),
+        (
+            49,
+            MultisigInstruction::SetPolicySigner {
+                policy_signer: Some(key(13)),
+            },
+        ),
+        (
+            50,
+            MultisigInstruction::ProposeIndexed {
+                action: ProposalAction::StakeDeactivate { stake: key(14) },
+                vault: 0,
+                expires_at: None,
+                required_signers: vec![],
+                executor_fee: 0,
+                attachment: None,
+            },
+        ),
+        (51, MultisigInstruction::CloseTransaction),
+        (52, MultisigInstruction::InitHistory),
+        (53, MultisigInstruction::ChangeThreshold { threshold: 2 }),
+        (54, MultisigInstruction::AddOwner { owner: key(4) }),
+        (
+            55,
+            MultisigInstruction::CreateDerived {
+                seed: [7; 32],
+                owners: vec![key(1)],
+                threshold: 1,
+            },
+        ),
+        (56, MultisigInstruction::Deposit { amount: 5 }),
+        (
+            57,
+            MultisigInstruction::InitProgramConfig {
+                governance: key(1),
+                fee_destination: key(2),
+                protocol_fee_bps: 30,
+            },
+        ),
+        (
+            58,
+            MultisigInstruction::SetProgramConfig {
+                governance: key(1),
+                fee_destination: key(2),
+                protocol_fee_bps: 30,
+                paused: true,
+            },
+        ),
+        (59, MultisigInstruction::SetFeeExempt { exempt: true }),
+        (
+            60,
+            MultisigInstruction::SetTransferMinimum {
+                allow_zero_amount: true,
+                min_transfer: 10_000,
+            },
+        ),
+        (61, MultisigInstruction::SetStrictSigning { strict: true }),
+        (62, MultisigInstruction::SignAsMultisig),
+    ];
+    for (tag, instruction) in &tags {
+        assert_eq!(instruction.tag(), *tag);
+        let debug = format!("{instruction:?}");
+        assert_eq!(debug.split(' ').next(), Some(instruction.name()));
+        let data = instruction.try_to_vec().unwrap();
+        assert_eq!(data[0], *tag);
+        assert_eq!(MultisigInstruction::decode(&data).unwrap().tag(), *tag);
+    }
+    assert_eq!(MultisigInstruction::NAMES.len(), tags.len());
+    let next_tag = tags.len() as u8;
+    assert!(next_tag <= MAX_INSTRUCTION_TAG);
+    assert!(MultisigInstruction::decode(&[next_tag]).is_err());
+}
+
+#[test]
+fn test_instruction_bytes() {
+    assert_eq!(MultisigInstruction::Sign.try_to_vec().unwrap(), vec![1]);
+    assert_eq!(MultisigInstruction::Approve.try_to_vec().unwrap(), vec![5]);
+    let mut execute = vec![2, 0x40, 0x42, 0x0f, 0, 0, 0, 0, 0];
+    execute.extend_from_slice(key(2).as_ref());
+    assert_eq!(
+        MultisigInstruction::Execute {
+            amount: 1_000_000,
+            destination: key(2),
+        }
+        .try_to_vec()
+        .unwrap(),
+        execute
+    );
+    assert_eq!(
+        MultisigInstruction::SetPaused { paused: true }
+            .try_to_vec()
+            .unwrap(),
+        vec![19, 1]
+    );
+}
+
+#[test]
+fn test_decode() {
+    // fields a newer client appends are ignored
+    assert!(matches!(
+        MultisigInstruction::decode(&[1, 0xaa, 0xbb]),
+        Ok(MultisigInstruction::Sign)
+    ));
+    assert!(matches!(
+        MultisigInstruction::decode(&[19, 1, 0xaa]),
+        Ok(MultisigInstruction::SetPaused { paused: true })
+    ));
+
+    // truncated fields, reserved tags and the packed encoding aren't Borsh
+    assert_eq!(
+        MultisigInstruction::decode(&[2, 0x40]).unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+    for tag in [
+        *RESERVED_TAGS.start(),
+        *RESERVED_TAGS.end(),
+        PACKED_FORMAT_TAG,
+    ] {
+        assert_eq!(
+            MultisigInstruction::decode(&[tag, 1]).unwrap_err(),
+            ProgramError::InvalidInstructionData
+        );
+    }
+    assert_eq!(
+        MultisigInstruction::decode(&[]).unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+}
@@ -0,0 +1,180 @@
+use borsh::BorshSerialize;
+use solana_multisig_wallet::fixtures::key;
+use solana_multisig_wallet::layouts::{self, AccountLayout, Base, Field};
+use solana_multisig_wallet::{Multisig, OutflowCap, ProposalAction, Transaction, MAX_OWNERS};
+use std::path::PathBuf;
+
+fn layouts_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("layouts")
+}
+
+fn layout(name: &str) -> AccountLayout {
+    layouts::accounts()
+        .into_iter()
+        .find(|account| account.name == name)
+        .unwrap()
+}
+
+// The bytes of field `name` in `data`, `action_len` is the encoded size of a proposal's action
+fn field<'a>(layout: &AccountLayout, data: &'a [u8], name: &str, action_len: usize) -> &'a [u8] {
+    let Field {
+        base, offset, len, ..
+    } = layout
+        .fields
+        .iter()
+        .find(|field| field.name == name)
+        .unwrap_or_else(|| panic!("no field {name} in {}", layout.name));
+    let start = match base {
+        Base::Start => *offset,
+        Base::ActionEnd => 64 + action_len + offset,
+    };
+    &data[start..start + len]
+}
+
+#[test]
+fn test_committed_layouts_are_up_to_date() {
+    let json = std::fs::read_to_string(layouts_dir().join("layouts.json")).unwrap();
+    assert_eq!(
+        json,
+        layouts::json(),
+        "layouts.json is stale, run `cargo run --example layouts`"
+    );
+    let typescript = std::fs::read_to_string(layouts_dir().join("layouts.ts")).unwrap();
+    assert_eq!(
+        typescript,
+        layouts::typescript(),
+        "layouts.ts is stale, run `cargo run --example layouts`"
+    );
+}
+
+#[test]
+fn test_fields_cover_the_accounts() {
+    for account in layouts::accounts() {
+        // contiguous from each base, nothing left over
+        let mut expected = (Base::Start, 0);
+        for field in &account.fields {
+            if field.base != expected.0 {
+                expected = (field.base, 0);
+            }
+            assert_eq!(field.offset, expected.1, "{}.{}", account.name, field.name);
+            expected.1 += field.len;
+        }
+        let total: usize = account.fields.iter().map(|field| field.len).sum();
+        assert_eq!(total, account.len, "{}", account.name);
+    }
+}
+
+#[test]
+fn test_multisig_layout() {
+    let multisig = Multisig {
+        owners: vec![key(1), key(2), key(3)],
+        threshold: 2,
+        signers: vec![false, true, false],
+        veto_authority: Some(key(40)),
+        paused: true,
+        emergency_authority: None,
+        amount_policy: None,
+        approval_ttl_seconds: Some(3_600),
+        signed_at: vec![0, 1_700_000_000, 0],
+        owner_set_seqno: 7,
+        outflow_cap: Some(OutflowCap {
+            limit: 5_000,
+            period_seconds: 86_400,
+        }),
+        spent_this_period: 1_200,
+        period_start: 1_700_000_000,
+        policy_signer: Some(key(42)),
+        transaction_count: 9,
+        fee_exempt: true,
+        allow_zero_amount: true,
+        min_transfer: 10_000,
+        strict_signing: true,
+        delegate_signers: 0b10,
+    };
+    let data = multisig.try_to_vec().unwrap();
+    let layout = layout("Multisig");
+    let field = |name| field(&layout, &data, name, 0);
+
+    assert_eq!(field("owners_len"), [3]);
+    assert_eq!(&field("owners")[32..64], key(2).as_ref());
+    assert_eq!(field("threshold"), [2]);
+    assert_eq!(&field("signers")[..3], [0, 1, 0]);
+    assert_eq!(field("veto_authority")[0], 1);
+    assert_eq!(&field("veto_authority")[1..], key(40).as_ref());
+    assert_eq!(field("paused"), [1]);
+    assert_eq!(field("emergency_authority"), [0; 33]);
+    assert_eq!(field("amount_policy"), [0; 10]);
+    assert_eq!(field("approval_ttl_seconds")[0], 1);
+    assert_eq!(&field("approval_ttl_seconds")[1..], 3_600i64.to_le_bytes());
+    assert_eq!(&field("signed_at")[8..16], 1_700_000_000i64.to_le_bytes());
+    assert_eq!(field("owner_set_seqno"), 7u32.to_le_bytes());
+    assert_eq!(field("outflow_cap")[0], 1);
+    assert_eq!(&field("outflow_cap")[1..9], 5_000u64.to_le_bytes());
+    assert_eq!(&field("outflow_cap")[9..], 86_400i64.to_le_bytes());
+    assert_eq!(field("spent_this_period"), 1_200u64.to_le_bytes());
+    assert_eq!(field("period_start"), 1_700_000_000i64.to_le_bytes());
+    assert_eq!(field("policy_signer")[0], 1);
+    assert_eq!(&field("policy_signer")[1..], key(42).as_ref());
+    assert_eq!(field("transaction_count"), 9u64.to_le_bytes());
+    assert_eq!(field("fee_exempt"), [1]);
+    assert_eq!(field("allow_zero_amount"), [1]);
+    assert_eq!(field("min_transfer"), 10_000u64.to_le_bytes());
+    assert_eq!(field("strict_signing"), [1]);
+    assert_eq!(field("delegate_signers"), [0b10, 0]);
+}
+
+#[test]
+fn test_transaction_layout() {
+    let action = ProposalAction::StakeWithdraw {
+        stake: key(20),
+        destination: key(21),
+        lamports: 5,
+    };
+    let action_len = action.try_to_vec().unwrap().len();
+    let transaction = Transaction {
+        multisig: key(10),
+        proposer: key(1),
+        action,
+        signers: vec![true, false, true],
+        executed: false,
+        vault: 2,
+        expires_at: Some(1_700_000_900),
+        extended: true,
+        required_signers: vec![false, false, true],
+        approved_at: vec![1_700_000_000, 0, 1_700_000_300],
+        executor_fee: 5_000,
+        owner_set_seqno: 3,
+        executed_at: 1_700_001_000,
+        executed_slot: 42,
+        executed_by: Some(key(4)),
+        attachment: Some([7; 32]),
+    };
+    let data = transaction.try_to_vec().unwrap();
+    let layout = layout("Transaction");
+    assert_eq!(data.len(), layout.len + action_len);
+    let field = |name| field(&layout, &data, name, action_len);
+
+    assert_eq!(field("multisig"), key(10).as_ref());
+    assert_eq!(field("proposer"), key(1).as_ref());
+    assert_eq!(field("signers_len"), [3]);
+    assert_eq!(field("signers")[..3], [1, 0, 1]);
+    assert_eq!(field("signers")[3..], [0; MAX_OWNERS - 3]);
+    assert_eq!(field("executed"), [0]);
+    assert_eq!(field("vault"), [2]);
+    assert_eq!(field("expires_at")[0], 1);
+    assert_eq!(&field("expires_at")[1..], 1_700_000_900i64.to_le_bytes());
+    assert_eq!(field("extended"), [1]);
+    assert_eq!(field("required_signers")[..3], [0, 0, 1]);
+    assert_eq!(
+        &field("approved_at")[16..24],
+        1_700_000_300i64.to_le_bytes()
+    );
+    assert_eq!(field("executor_fee"), 5_000u64.to_le_bytes());
+    assert_eq!(field("owner_set_seqno"), 3u32.to_le_bytes());
+    assert_eq!(field("executed_at"), 1_700_001_000i64.to_le_bytes());
+    assert_eq!(field("executed_slot"), 42u64.to_le_bytes());
+    assert_eq!(field("executed_by")[0], 1);
+    assert_eq!(&field("executed_by")[1..], key(4).as_ref());
+    assert_eq!(field("attachment")[0], 1);
+    assert_eq!(field("attachment")[1..], [7; 32]);
+}
@@ -0,0 +1,68 @@
+use solana_multisig_wallet::fixtures::key;
+use solana_multisig_wallet::merkle::{self, MerkleTree};
+use solana_program::pubkey::Pubkey;
+
+fn grants(count: u8) -> Vec<(Pubkey, u64)> {
+    (0..count)
+        .map(|index| (key(index + 1), 1_000 * (index as u64 + 1)))
+        .collect()
+}
+
+#[test]
+fn test_every_proof_verifies() {
+    // odd counts leave a node without a sibling on some level
+    for count in 1..=9 {
+        let grants = grants(count);
+        let tree = MerkleTree::new(&grants).unwrap();
+        assert_eq!(tree.len(), grants.len());
+        for (index, (recipient, amount)) in grants.iter().enumerate() {
+            let proof = tree.proof(index).unwrap();
+            let leaf = merkle::leaf(recipient, *amount);
+            assert!(
+                merkle::verify(&tree.root(), leaf, &proof),
+                "{count} {index}"
+            );
+        }
+        assert_eq!(tree.proof(grants.len()), None);
+    }
+}
+
+#[test]
+fn test_known_root() {
+    let grants = grants(3);
+    let [a, b, c] = [0, 1, 2].map(|index| merkle::leaf(&grants[index].0, grants[index].1));
+    let tree = MerkleTree::new(&grants).unwrap();
+    assert_eq!(
+        tree.root(),
+        merkle::hash_pair(&merkle::hash_pair(&a, &b), &c)
+    );
+    assert_eq!(tree.proof(0).unwrap(), vec![b, c]);
+    // `c` has no sibling on the leaf level
+    assert_eq!(tree.proof(2).unwrap(), vec![merkle::hash_pair(&a, &b)]);
+    assert_eq!(MerkleTree::new(&grants[..1]).unwrap().root(), a);
+}
+
+#[test]
+fn test_rejects_tampered_claims() {
+    let grants = grants(4);
+    let tree = MerkleTree::new(&grants).unwrap();
+    let (recipient, amount) = grants[1];
+    let proof = tree.proof(1).unwrap();
+
+    assert!(!merkle::verify(
+        &tree.root(),
+        merkle::leaf(&recipient, amount + 1),
+        &proof
+    ));
+    assert!(!merkle::verify(
+        &tree.root(),
+        merkle::leaf(&key(99), amount),
+        &proof
+    ));
+    assert!(!merkle::verify(
+        &tree.root(),
+        merkle::leaf(&recipient, amount),
+        &proof[1..]
+    ));
+    assert!(MerkleTree::new(&[]).is_none());
+}
@@ -0,0 +1,5064 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use multisig_test_utils::{
+    compiled_program, fund, next_blockhash, process, program_test, setup_multisig, sign_as,
+    MultisigHarness,
+};
+use solana_multisig_wallet::instruction::ProposeOptions;
+use solana_multisig_wallet::lookup_table;
+use solana_multisig_wallet::merkle::MerkleTree;
+use solana_multisig_wallet::state::load;
+use solana_multisig_wallet::{
+    find_claim_address, find_delegate_address, find_destination_policy_address,
+    find_display_unit_address, find_distribution_address, find_history_address, find_inbox_address,
+    find_indexed_vault_address, find_metadata_address, find_owner_index_address,
+    find_receipt_address, find_receipt_ledger_address, find_recovery_address,
+    find_recurring_payment_address, find_transaction_address, find_vault_address, instruction,
+    AmountPolicy, Claim, Delegate, DestinationMode, DestinationPolicy, DisplayUnit, Distribution,
+    ExecutionStrategy, History, Inbox, Metadata, Multisig, MultisigError, MultisigInstruction,
+    OutflowCap, OwnerIndex, PolicyEnforcement, ProposalAction, ProposalStatus, Receipt,
+    ReceiptLedger, RecurringPayment, VoteAuthority, CLOSE_GRACE_PERIOD, DELEGATE_PERMISSIONS,
+    DELEGATE_SIGN, MAX_METADATA_NAME_LEN, MAX_METADATA_URI_LEN, MAX_PROPOSAL_EXTENSION,
+};
+use solana_program::instruction::AccountMeta;
+use solana_program::pubkey::Pubkey;
+use solana_program::slot_hashes::SlotHashes;
+use solana_program::system_program;
+use solana_program_test::{ProgramTest, ProgramTestBanksClientExt, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use std::str::FromStr;
+
+#[cfg(feature = "aggregated-approval")]
+use solana_multisig_wallet::aggregated_approval_message;
+
+fn custom(err: MultisigError) -> InstructionError {
+    InstructionError::Custom(err.code())
+}
+
+// Calculates space (in bytes)
+fn calculate_space(multisig: &Multisig) -> usize {
+    let mut space_buffer = vec![];
+    // `serialize` => convert strut to bytes
+    multisig.serialize(&mut space_buffer).unwrap();
+    // find/calculate space for storing `multisig` bytes on Solana (as it can only store bytes)
+    // this is needed for allocating the correct amount of storage since we pay for it
+    space_buffer.len()
+}
+
+fn debug_print(text: &str) {
+    println!("=== {text} ===");
+}
+
+#[tokio::test]
+async fn test_complete_multisig_flow() {
+    debug_print("Starting test_complete_multisig_flow");
+    debug_print("1. CREATE TRANSACTION");
+
+    // this one is actually used in code
+    let program_id = Pubkey::from_str("mw45AnZJJU8iUMkRNgytM11J7b4VAi6ptzViWHJ9mbD").unwrap();
+    // initialize program test environment
+    let program_test = program_test();
+
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let owner1_keypair = Keypair::new();
+    let owner2_keypair = Keypair::new();
+    let owner3_keypair = Keypair::new();
+
+    let owners = vec![
+        owner1_keypair.pubkey(),
+        owner2_keypair.pubkey(),
+        owner3_keypair.pubkey(),
+    ];
+    // number of signatures required to execute transaction
+    let num_signatures = 2u8;
+
+    let multisig = Multisig {
+        owners: owners.clone(), // owners' public keys
+        threshold: num_signatures,
+        // initialize all signatures as false (a fresh multisig transaction where no owner has signed yet)
+        signers: vec![false; owners.len()],
+        veto_authority: None,
+        paused: false,
+        emergency_authority: None,
+        amount_policy: None,
+        approval_ttl_seconds: None,
+        signed_at: vec![0; owners.len()],
+        owner_set_seqno: 0,
+        outflow_cap: None,
+        spent_this_period: 0,
+        period_start: 0,
+        policy_signer: None,
+        transaction_count: 0,
+        fee_exempt: false,
+        allow_zero_amount: false,
+        min_transfer: 0,
+        strict_signing: false,
+        delegate_signers: 0,
+    };
+
+    let space = calculate_space(&multisig);
+
+    // `rent` holds Solana's rent formula
+    let rent = context.banks_client.get_rent().await.unwrap();
+    // find how much to pay for rent based on our `multisig` data size
+    let rent_cost = rent.minimum_balance(space);
+
+    // the amount (in lamports) we want to transfer later (in these tests to another account)
+    let transfer_amount = 50;
+    // total amount (like a prepaid card, activation + spending). Need both.
+    let lamports = rent_cost + transfer_amount;
+
+    let multisig_keypair = Keypair::new();
+    let multisig_key = multisig_keypair.pubkey();
+    // this creates ONLY the basic account structure
+    let create_account_instr = solana_sdk::system_instruction::create_account(
+        &context.payer.pubkey(), // test wallet payer
+        &multisig_key,           // new account address
+        lamports,                // initial deposit amount
+        space as u64,            // storage space needed
+        &program_id,             // program which owns this account
+    );
+
+    // Create multisig instruction
+    let multisig_create_instr = MultisigInstruction::Create {
+        owners: owners.clone(),    // who can sign
+        threshold: num_signatures, // how many can sign
+    };
+
+    let create_instruction_bytes = multisig_create_instr.try_to_vec().unwrap();
+
+    // create a new multisig (group) account by combining three essential pieces
+    let create_multisig_instr = solana_sdk::instruction::Instruction::new_with_bytes(
+        program_id,                // which program to run / will handle instructions
+        &create_instruction_bytes, // group account details
+        vec![
+            // accounts to work with
+            AccountMeta::new(multisig_key, false), // new account that will be created, but can also be written to, check `new(...)`
+            AccountMeta::new_readonly(system_program::id(), false), // system program
+        ],
+    );
+
+    // Create and send transaction
+    let recent_blockhash = context.last_blockhash;
+    let transaction = Transaction::new_signed_with_payer(
+        // instructions to execute in order
+        // first is like setting up an empty account & the second sets up multisig features
+        &[create_account_instr, create_multisig_instr],
+        Some(&context.payer.pubkey()), // test wallet paying for fees
+        &[&context.payer, &multisig_keypair], // required signatures
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Verify multisig account was created correctly
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let stored_multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert_eq!(stored_multisig.owners, owners, "Owners don't match");
+    assert_eq!(
+        stored_multisig.threshold, num_signatures,
+        "Threshold doesn't match"
+    );
+    assert_eq!(
+        stored_multisig.signers,
+        vec![false; owners.len()],
+        "Signers not properly initialized"
+    );
+    debug_print("1. CREATE TRANSACTION - DONE");
+
+    // ---------------------------------------------------------------------
+    // create END
+    // ---------------------------------------------------------------------
+    debug_print("2. SIGN TRANSACTION");
+
+    // 2. SIGN TRANSACTION
+    let sign_instr_bytes = MultisigInstruction::Sign.try_to_vec().unwrap();
+
+    // Create sign instruction. All params are passed to `process_instruction()`
+    let sign_instr = solana_sdk::instruction::Instruction::new_with_bytes(
+        program_id,        // program that will process this signing instruction
+        &sign_instr_bytes, // serialized "Sign" enum variant
+        vec![
+            // these accounts are
+            // person signing the tx, must be in owners list of multisig_key AND must sign the tx
+            AccountMeta::new_readonly(owner1_keypair.pubkey(), true),
+            // multisig account being signed, doesn't sign, but is writeable inside new()
+            AccountMeta::new(multisig_key, false),
+        ],
+    );
+
+    // Print initial state (useful for debugging)
+    let initial_account = ctx_get_account(&mut context, multisig_key).await;
+    let initial_multisig = Multisig::try_from_slice(&initial_account.data).unwrap();
+    println!("\n=== Before Signing ===");
+    println!("Initial multisig state: {:?}", initial_multisig);
+
+    // Create and send sign transaction
+    let recent_blockhash = context.last_blockhash;
+    let sign_transaction = Transaction::new_signed_with_payer(
+        &[sign_instr],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &owner1_keypair], // Both payer and owner1 need to sign
+        recent_blockhash,
+    );
+
+    // Process the sign transaction
+    context
+        .banks_client
+        .process_transaction(sign_transaction)
+        .await
+        .unwrap();
+
+    // Verify the state after signing
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let stored_multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+
+    // Verify the signing state
+    println!("\n=== After Signing ===");
+    println!("Stored multisig: {:?}", stored_multisig);
+
+    assert_eq!(
+        stored_multisig.owners, owners,
+        "Owners don't match after signing"
+    );
+    assert_eq!(
+        stored_multisig.threshold, num_signatures,
+        "Threshold doesn't match after signing"
+    );
+    assert!(stored_multisig.signers[0], "First signer should be true"); // as we already invoked `Sign` instruction above
+    assert!(!stored_multisig.signers[1], "Second signer should be false");
+
+    // Optional: Verify account data length hasn't changed
+    assert_eq!(
+        multisig_account.data.len(),
+        space,
+        "Account data length changed unexpectedly"
+    );
+    debug_print("2. SIGN TRANSACTION - DONE");
+
+    // ---------------------------------------------------------------------
+    // sign END
+    // ---------------------------------------------------------------------
+    debug_print("3. EXECUTE TRANSACTION");
+
+    // first we create the destination account
+    let destination_keypair = Keypair::new();
+    let recipient_key = destination_keypair.pubkey();
+
+    // minimum_balance(0) is the minimum possible rent cost (there is no data storage), it just holds SOL
+    let destination_minimum_rent = rent.minimum_balance(0);
+
+    // let's create a new account to receive SOL from the multisig execution
+    let create_destination_account_instr = solana_sdk::system_instruction::create_account(
+        &context.payer.pubkey(),
+        &recipient_key,           // new account address
+        destination_minimum_rent, // lamports to ensure rent-exempt
+        0,                        // space (0 because just holding SOL, no program data needed)
+        &system_program::id(),    // owner (system program owns SOL accounts)
+    );
+
+    // create and send transaction to create destination account
+    let create_dest_tx = Transaction::new_signed_with_payer(
+        &[create_destination_account_instr],
+        Some(&context.payer.pubkey()),
+        &[
+            // payer must sign because it's paying for fees (tx fee & initial rent-exempt balance)
+            // system program needs proof that the payer authorized this payment
+            &context.payer,
+            // new account must sign for its creation, signature proves you have the private key for this new account
+            &destination_keypair,
+        ],
+        // prevents replay attacks, also block hashes are only valid for a limited time window ~2 minutes (150 blocks)
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(create_dest_tx)
+        .await
+        .unwrap();
+
+    // verify destination account was created
+    let dest_account = ctx_get_account(&mut context, recipient_key).await;
+    assert_eq!(
+        dest_account.lamports, destination_minimum_rent,
+        "Destination account should be rent-exempt"
+    );
+
+    // ----------------------------------------------------------------
+    // destination account created, next testing multisig transfer flow
+    // ----------------------------------------------------------------
+
+    // create execute instruction (`Execute` variant of MultisigInstruction enum)
+    let multisig_instr_execute = MultisigInstruction::Execute {
+        amount: transfer_amount,
+        destination: recipient_key,
+    };
+    let execute_instruction_data = multisig_instr_execute.try_to_vec().unwrap();
+
+    // represents the instruction to execute the multisig transfer
+    // first execute attempt (should fail due to insufficient signatures)
+    println!("\n=== Calling `Execute` instruction (should fail as multisig_key owner didn't sign tx) ===");
+
+    let multisig_execute_instr = solana_sdk::instruction::Instruction::new_with_bytes(
+        program_id,
+        &execute_instruction_data,
+        vec![
+            // `is_signer = false` means this account must be signed at transaction level (but later only payer signs tx)
+            AccountMeta::new(multisig_key, false), // will fail, owner didn't sign
+            AccountMeta::new(recipient_key, false), // signature not needed
+            // system program never signs,
+            // needed for native SOL transfers
+            AccountMeta::new_readonly(system_program::id(), false),
+            // destination policy, never set for this multisig
+            AccountMeta::new_readonly(
+                find_destination_policy_address(&multisig_key, &program_id).0,
+                false,
+            ),
+        ],
+    );
+    let multisig_execute_tx = Transaction::new_signed_with_payer(
+        &[multisig_execute_instr],
+        Some(&context.payer.pubkey()),
+        &[&context.payer], // only payer signs here, hence it will fail
+        context.last_blockhash,
+    );
+    // submit tx
+    let result = context
+        .banks_client
+        .process_transaction(multisig_execute_tx)
+        .await;
+    assert!(
+        result.is_err(),
+        "`Execute` should fail with insufficient signatures as multisig_key has not yet signed"
+    );
+
+    // Second owner signs
+    println!("\n=== Second Owner Signing ===");
+    let sign_ix_2 = solana_sdk::instruction::Instruction::new_with_bytes(
+        program_id,
+        &sign_instr_bytes,
+        vec![
+            // order matters (because of `process_sign()` logic)
+            AccountMeta::new_readonly(owner2_keypair.pubkey(), true),
+            AccountMeta::new(multisig_key, false),
+        ],
+    );
+
+    // Get fresh blockhash
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+
+    let sign_transaction_2 = Transaction::new_signed_with_payer(
+        &[sign_ix_2],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &owner2_keypair],
+        recent_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(sign_transaction_2)
+        .await
+        .unwrap();
+
+    // Record initial balances
+    let initial_multisig_balance = ctx_get_account(&mut context, multisig_key).await.lamports;
+    let initial_destination_balance = ctx_get_account(&mut context, recipient_key).await.lamports;
+
+    // Get fresh blockhash for final execute
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+
+    // Final execute attempt (should succeed)
+    println!("\n=== Attempting Execute (Should Succeed) ===");
+    let execute_ix_2 = solana_sdk::instruction::Instruction::new_with_bytes(
+        program_id,
+        &execute_instruction_data,
+        vec![
+            AccountMeta::new(multisig_key, false),
+            AccountMeta::new(recipient_key, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(
+                find_destination_policy_address(&multisig_key, &program_id).0,
+                false,
+            ),
+        ],
+    );
+
+    let execute_tx_2 = Transaction::new_signed_with_payer(
+        &[execute_ix_2],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(execute_tx_2)
+        .await
+        .unwrap();
+
+    // Verify final state
+    let final_multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let final_destination_account = ctx_get_account(&mut context, recipient_key).await;
+
+    // Verify balances
+    assert_eq!(
+        final_destination_account.lamports,
+        initial_destination_balance + transfer_amount,
+        "Destination balance incorrect"
+    );
+
+    assert_eq!(
+        final_multisig_account.lamports,
+        initial_multisig_balance - transfer_amount,
+        "Multisig balance incorrect"
+    );
+
+    // Verify signatures were reset
+    let final_multisig = Multisig::try_from_slice(&final_multisig_account.data).unwrap();
+    assert!(
+        final_multisig.signers.iter().all(|&signed| !signed),
+        "Signatures should be reset after execution"
+    );
+
+    debug_print("3. EXECUTE TRANSACTION - DONE");
+}
+
+async fn ctx_get_account(context: &mut ProgramTestContext, address: Pubkey) -> Account {
+    context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_create_token_account() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner1_keypair = Keypair::new();
+    let owner2_keypair = Keypair::new();
+    let owners = vec![owner1_keypair.pubkey(), owner2_keypair.pubkey()];
+    let multisig_key = setup_multisig(&mut context, &owners, 1, 0).await;
+
+    // a fresh mint the treasury wants to start receiving
+    let mint_keypair = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let create_mint_tx = Transaction::new_signed_with_payer(
+        &[
+            solana_sdk::system_instruction::create_account(
+                &context.payer.pubkey(),
+                &mint_keypair.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint_keypair.pubkey(),
+                &context.payer.pubkey(),
+                None,
+                6,
+            )
+            .unwrap(),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &mint_keypair],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(create_mint_tx)
+        .await
+        .unwrap();
+
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+    let token_account_key = spl_associated_token_account::get_associated_token_address(
+        &vault_key,
+        &mint_keypair.pubkey(),
+    );
+    let create_token_account_ix = solana_sdk::instruction::Instruction::new_with_bytes(
+        program_id,
+        &MultisigInstruction::CreateTokenAccount {
+            mint: mint_keypair.pubkey(),
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(multisig_key, false),
+            AccountMeta::new(context.payer.pubkey(), true),
+            AccountMeta::new_readonly(vault_key, false),
+            AccountMeta::new(token_account_key, false),
+            AccountMeta::new_readonly(mint_keypair.pubkey(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+    );
+
+    // no approvals yet, must fail
+    let tx = Transaction::new_signed_with_payer(
+        std::slice::from_ref(&create_token_account_ix),
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+
+    sign_as(&mut context, multisig_key, &owner1_keypair).await;
+
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[create_token_account_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let token_account = ctx_get_account(&mut context, token_account_key).await;
+    let token_account = spl_token::state::Account::unpack(&token_account.data).unwrap();
+    assert_eq!(
+        token_account.owner, vault_key,
+        "ATA should be owned by the vault PDA"
+    );
+    assert_eq!(token_account.mint, mint_keypair.pubkey());
+
+    // approvals are consumed
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let stored_multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert!(stored_multisig.signers.iter().all(|&signed| !signed));
+}
+
+#[tokio::test]
+async fn test_token_2022_transfer_with_fee() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner1_keypair = Keypair::new();
+    let owner2_keypair = Keypair::new();
+    let owners = vec![owner1_keypair.pubkey(), owner2_keypair.pubkey()];
+    let multisig_key = setup_multisig(&mut context, &owners, 2, 0).await;
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+
+    // Token-2022 mint charging a 1% transfer fee
+    let mint_keypair = Keypair::new();
+    let mint_key = mint_keypair.pubkey();
+    let mint_len = spl_token_2022::extension::ExtensionType::get_account_len::<
+        spl_token_2022::state::Mint,
+    >(&[spl_token_2022::extension::ExtensionType::TransferFeeConfig]);
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let recipient = Pubkey::new_unique();
+    let vault_token_account =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &vault_key,
+            &mint_key,
+            &spl_token_2022::id(),
+        );
+    let recipient_token_account =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &recipient,
+            &mint_key,
+            &spl_token_2022::id(),
+        );
+    let setup_tx = Transaction::new_signed_with_payer(
+        &[
+            solana_sdk::system_instruction::create_account(
+                &context.payer.pubkey(),
+                &mint_key,
+                rent.minimum_balance(mint_len),
+                mint_len as u64,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config(
+                &spl_token_2022::id(),
+                &mint_key,
+                None,
+                None,
+                100,
+                u64::MAX,
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint(
+                &spl_token_2022::id(),
+                &mint_key,
+                &context.payer.pubkey(),
+                None,
+                6,
+            )
+            .unwrap(),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &context.payer.pubkey(),
+                &vault_key,
+                &mint_key,
+                &spl_token_2022::id(),
+            ),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &context.payer.pubkey(),
+                &recipient,
+                &mint_key,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::instruction::mint_to(
+                &spl_token_2022::id(),
+                &mint_key,
+                &vault_token_account,
+                &context.payer.pubkey(),
+                &[],
+                1_000,
+            )
+            .unwrap(),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &mint_keypair],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(setup_tx)
+        .await
+        .unwrap();
+
+    let action = ProposalAction::TransferToken {
+        token_program: spl_token_2022::id(),
+        mint: mint_key,
+        destination: recipient_token_account,
+        amount: 500,
+        max_fee: 5,
+        expected_decimals: 6,
+    };
+    let transaction_key =
+        propose(&mut context, multisig_key, &owner1_keypair, action.clone()).await;
+    let execute_ix =
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &action);
+
+    // only the proposer approved so far
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        std::slice::from_ref(&execute_ix),
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+
+    let approve_tx = Transaction::new_signed_with_payer(
+        &[instruction::approve(
+            &program_id,
+            &owner2_keypair.pubkey(),
+            &multisig_key,
+            &transaction_key,
+            &action,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &owner2_keypair],
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(approve_tx)
+        .await
+        .unwrap();
+
+    // same message as the failed attempt, needs a new blockhash
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&recent_blockhash)
+        .await
+        .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[execute_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let recipient_account = ctx_get_account(&mut context, recipient_token_account).await;
+    let recipient_state = spl_token_2022::extension::StateWithExtensions::<
+        spl_token_2022::state::Account,
+    >::unpack(&recipient_account.data)
+    .unwrap();
+    assert_eq!(
+        recipient_state.base.amount, 495,
+        "1% fee should be withheld"
+    );
+
+    let vault_account = ctx_get_account(&mut context, vault_token_account).await;
+    let vault_state = spl_token_2022::extension::StateWithExtensions::<
+        spl_token_2022::state::Account,
+    >::unpack(&vault_account.data)
+    .unwrap();
+    assert_eq!(vault_state.base.amount, 500);
+
+    // an amount worked out for 9 decimals on the 6-decimal mint, a thousand times too much, is
+    // refused however many approvals it has
+    let mismatched = ProposalAction::TransferToken {
+        token_program: spl_token_2022::id(),
+        mint: mint_key,
+        destination: recipient_token_account,
+        amount: 500,
+        max_fee: 5,
+        expected_decimals: 9,
+    };
+    let transaction_key = propose(
+        &mut context,
+        multisig_key,
+        &owner1_keypair,
+        mismatched.clone(),
+    )
+    .await;
+    approve_as(
+        &mut context,
+        multisig_key,
+        transaction_key,
+        &mismatched,
+        &owner2_keypair,
+    )
+    .await;
+    let execute_ix =
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &mismatched);
+    let result = process(&mut context, execute_ix, &[]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::DecimalsMismatch))
+    );
+    let vault_account = ctx_get_account(&mut context, vault_token_account).await;
+    let vault_state = spl_token_2022::extension::StateWithExtensions::<
+        spl_token_2022::state::Account,
+    >::unpack(&vault_account.data)
+    .unwrap();
+    assert_eq!(vault_state.base.amount, 500);
+}
+
+#[tokio::test]
+async fn test_upgrade_proposal_buffer_change_resets_approvals() {
+    let program_id = solana_multisig_wallet::id();
+    let mut program_test = program_test();
+
+    // the multisig address is needed for the buffer authority, so create it up front
+    let owner1_keypair = Keypair::new();
+    let owner2_keypair = Keypair::new();
+    let owner3_keypair = Keypair::new();
+    let owners = vec![
+        owner1_keypair.pubkey(),
+        owner2_keypair.pubkey(),
+        owner3_keypair.pubkey(),
+    ];
+    let multisig_key = Pubkey::new_unique();
+    let multisig = Multisig {
+        owners: owners.clone(),
+        threshold: 3,
+        signers: vec![false; owners.len()],
+        veto_authority: None,
+        paused: false,
+        emergency_authority: None,
+        amount_policy: None,
+        approval_ttl_seconds: None,
+        signed_at: vec![0; owners.len()],
+        owner_set_seqno: 0,
+        outflow_cap: None,
+        spent_this_period: 0,
+        period_start: 0,
+        policy_signer: None,
+        transaction_count: 0,
+        fee_exempt: false,
+        allow_zero_amount: false,
+        min_transfer: 0,
+        strict_signing: false,
+        delegate_signers: 0,
+    };
+    program_test.add_account(
+        multisig_key,
+        Account {
+            lamports: 1_000_000_000,
+            data: multisig.try_to_vec().unwrap(),
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+
+    let buffer_account = |authority: &Pubkey, program_bytes: &[u8]| {
+        let mut data = vec![1, 0, 0, 0, 1];
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(program_bytes);
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: solana_program::bpf_loader_upgradeable::id(),
+            ..Account::default()
+        }
+    };
+    let buffer_key = Pubkey::new_unique();
+    let foreign_buffer_key = Pubkey::new_unique();
+    program_test.add_account(buffer_key, buffer_account(&vault_key, b"approved bytes"));
+    program_test.add_account(
+        foreign_buffer_key,
+        buffer_account(&owner1_keypair.pubkey(), b"approved bytes"),
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let approved_hash = solana_program::hash::hash(b"approved bytes").to_bytes();
+    let upgrade = |buffer: Pubkey| ProposalAction::UpgradeProgram {
+        program: Pubkey::new_unique(),
+        buffer,
+        buffer_hash: approved_hash,
+    };
+
+    // a buffer the developer can still rewrite is refused
+    let space = solana_multisig_wallet::Transaction::space(&upgrade(buffer_key));
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let transaction_keypair = Keypair::new();
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            solana_sdk::system_instruction::create_account(
+                &context.payer.pubkey(),
+                &transaction_keypair.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &program_id,
+            ),
+            instruction::propose(
+                &program_id,
+                &owner1_keypair.pubkey(),
+                &multisig_key,
+                &transaction_keypair.pubkey(),
+                upgrade(foreign_buffer_key),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &owner1_keypair, &transaction_keypair],
+        context.last_blockhash,
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+
+    let action = upgrade(buffer_key);
+    let transaction_key =
+        propose(&mut context, multisig_key, &owner1_keypair, action.clone()).await;
+    approve_as(
+        &mut context,
+        multisig_key,
+        transaction_key,
+        &action,
+        &owner2_keypair,
+    )
+    .await;
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    assert_eq!(proposal.signers, vec![true, true, false]);
+
+    // the buffer bytes change before the last approval
+    context.set_account(
+        &buffer_key,
+        &buffer_account(&vault_key, b"swapped bytes!").into(),
+    );
+    approve_as(
+        &mut context,
+        multisig_key,
+        transaction_key,
+        &action,
+        &owner3_keypair,
+    )
+    .await;
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    assert_eq!(
+        proposal.signers,
+        vec![false; 3],
+        "approvals must not survive a buffer change"
+    );
+}
+
+#[tokio::test]
+async fn test_stake_split_and_withdraw() {
+    use solana_program::stake;
+
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key = setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 0).await;
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+
+    // an undelegated stake account fully controlled by the vault
+    let stake_keypair = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    // serialized size of a stake account's state
+    let stake_rent = rent.minimum_balance(200);
+    let stake_lamports = stake_rent + 2_000_000;
+    let tx = Transaction::new_signed_with_payer(
+        &stake::instruction::create_account(
+            &context.payer.pubkey(),
+            &stake_keypair.pubkey(),
+            &stake::state::Authorized::auto(&vault_key),
+            &stake::state::Lockup::default(),
+            stake_lamports,
+        ),
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_keypair],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // split half of it into a new stake account
+    let split_keypair = Keypair::new();
+    let tx = Transaction::new_signed_with_payer(
+        &[solana_sdk::system_instruction::transfer(
+            &context.payer.pubkey(),
+            &split_keypair.pubkey(),
+            stake_rent,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let split = ProposalAction::StakeSplit {
+        stake: stake_keypair.pubkey(),
+        split_stake: split_keypair.pubkey(),
+        lamports: 1_000_000,
+    };
+    let transaction_key = propose(&mut context, multisig_key, &owner_keypair, split.clone()).await;
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::execute_transaction(
+            &program_id,
+            &multisig_key,
+            &transaction_key,
+            &split,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &split_keypair],
+        recent_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let split_account = ctx_get_account(&mut context, split_keypair.pubkey()).await;
+    assert_eq!(split_account.owner, stake::program::id());
+    assert_eq!(split_account.lamports, stake_rent + 1_000_000);
+
+    // withdraw from the original stake account
+    let destination = Pubkey::new_unique();
+    let withdraw = ProposalAction::StakeWithdraw {
+        stake: stake_keypair.pubkey(),
+        destination,
+        lamports: 1_000_000,
+    };
+    let transaction_key =
+        propose(&mut context, multisig_key, &owner_keypair, withdraw.clone()).await;
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::execute_transaction(
+            &program_id,
+            &multisig_key,
+            &transaction_key,
+            &withdraw,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let destination_account = ctx_get_account(&mut context, destination).await;
+    assert_eq!(destination_account.lamports, 1_000_000);
+    let stake_account = ctx_get_account(&mut context, stake_keypair.pubkey()).await;
+    assert_eq!(stake_account.lamports, stake_rent);
+}
+
+#[tokio::test]
+async fn test_vote_authorize_and_withdraw() {
+    use solana_program::vote;
+    use solana_program::vote::instruction::CreateVoteAccountConfig;
+    use solana_program::vote::state::{VoteInit, VoteState, VoteStateVersions};
+
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key = setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 0).await;
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+
+    // a vote account with the vault as both voter and withdrawer, in the current layout
+    let vote_keypair = Keypair::new();
+    let node_keypair = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let space = VoteStateVersions::vote_state_size_of(true);
+    let vote_rent = rent.minimum_balance(space);
+    let tx = Transaction::new_signed_with_payer(
+        &vote::instruction::create_account_with_config(
+            &context.payer.pubkey(),
+            &vote_keypair.pubkey(),
+            &VoteInit {
+                node_pubkey: node_keypair.pubkey(),
+                authorized_voter: vault_key,
+                authorized_withdrawer: vault_key,
+                commission: 10,
+            },
+            vote_rent + 2_000_000,
+            CreateVoteAccountConfig {
+                space: space as u64,
+                ..CreateVoteAccountConfig::default()
+            },
+        ),
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &vote_keypair, &node_keypair],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let destination = Pubkey::new_unique();
+    let withdraw = ProposalAction::VoteWithdraw {
+        vote: vote_keypair.pubkey(),
+        destination,
+        lamports: 1_000_000,
+    };
+    assert_eq!(withdraw.lamports(), 1_000_000);
+    assert_eq!(withdraw.destination(), Some(destination));
+    let transaction_key =
+        propose(&mut context, multisig_key, &owner_keypair, withdraw.clone()).await;
+    let execute =
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &withdraw);
+    process(&mut context, execute, &[]).await.unwrap();
+    let destination_account = ctx_get_account(&mut context, destination).await;
+    assert_eq!(destination_account.lamports, 1_000_000);
+
+    // the voter moves from the next epoch on, the withdrawer right away
+    let new_voter = Pubkey::new_unique();
+    let new_withdrawer = Pubkey::new_unique();
+    for (new_authority, authority) in [
+        (new_voter, VoteAuthority::Voter),
+        (new_withdrawer, VoteAuthority::Withdrawer),
+    ] {
+        let action = ProposalAction::VoteAuthorize {
+            vote: vote_keypair.pubkey(),
+            new_authority,
+            authority,
+        };
+        let transaction_key =
+            propose(&mut context, multisig_key, &owner_keypair, action.clone()).await;
+        let execute =
+            instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &action);
+        process(&mut context, execute, &[]).await.unwrap();
+    }
+    let vote_account = ctx_get_account(&mut context, vote_keypair.pubkey()).await;
+    let vote_state = VoteState::deserialize(&vote_account.data).unwrap();
+    assert_eq!(vote_state.authorized_withdrawer, new_withdrawer);
+    assert_eq!(
+        vote_state
+            .authorized_voters()
+            .last()
+            .map(|(_, voter)| *voter),
+        Some(new_voter)
+    );
+
+    // the vault holds neither authority any more
+    let withdraw = ProposalAction::VoteWithdraw {
+        vote: vote_keypair.pubkey(),
+        destination,
+        lamports: 1,
+    };
+    let transaction_key =
+        propose(&mut context, multisig_key, &owner_keypair, withdraw.clone()).await;
+    let execute =
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &withdraw);
+    assert!(process(&mut context, execute, &[]).await.is_err());
+}
+
+// Native CPIs of program-test only grow account data, so closing the temporary WSOL account panics
+// there; the whole payout only runs on the compiled program
+#[tokio::test]
+#[ignore = "needs the compiled program, run `cargo build-sbf` first"]
+async fn test_transfer_wrapped_sol() {
+    assert!(
+        compiled_program().is_some(),
+        "solana_multisig_wallet.so not found, run `cargo build-sbf`"
+    );
+    let program_id = solana_multisig_wallet::id();
+    let mut program_test = ProgramTest::new("solana_multisig_wallet", program_id, None);
+    program_test.prefer_bpf(true);
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key = setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 0).await;
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+
+    // fund the vault and give the recipient a WSOL token account
+    let native_mint = spl_token::native_mint::id();
+    let recipient = Pubkey::new_unique();
+    let recipient_token_account =
+        spl_associated_token_account::get_associated_token_address(&recipient, &native_mint);
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            solana_sdk::system_instruction::transfer(
+                &context.payer.pubkey(),
+                &vault_key,
+                1_000_000_000,
+            ),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &context.payer.pubkey(),
+                &recipient,
+                &native_mint,
+                &spl_token::id(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let action = ProposalAction::TransferWrappedSol {
+        destination: recipient_token_account,
+        amount: 250_000_000,
+    };
+    let transaction_key = propose(&mut context, multisig_key, &owner_keypair, action.clone()).await;
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::execute_transaction(
+            &program_id,
+            &multisig_key,
+            &transaction_key,
+            &action,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let recipient_account = ctx_get_account(&mut context, recipient_token_account).await;
+    let recipient_state = spl_token::state::Account::unpack(&recipient_account.data).unwrap();
+    assert_eq!(recipient_state.amount, 250_000_000);
+
+    // the temporary WSOL account is closed and its rent is back in the vault
+    let vault_account = ctx_get_account(&mut context, vault_key).await;
+    assert_eq!(vault_account.lamports, 750_000_000);
+    let wrapped_sol_account =
+        spl_associated_token_account::get_associated_token_address(&vault_key, &native_mint);
+    assert!(context
+        .banks_client
+        .get_account(wrapped_sol_account)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+// The wrapping half of `TransferWrappedSol` as a batch, natively: the vault funds its WSOL account,
+// `SyncNative` turns the lamports into tokens and they reach the recipient
+#[tokio::test]
+async fn test_wrap_sol_from_vault() {
+    let program_id = solana_multisig_wallet::id();
+    let mut context = program_test().start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key = setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 0).await;
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+    fund(&mut context, &vault_key, 1_000_000_000).await;
+
+    let native_mint = spl_token::native_mint::id();
+    let recipient = Pubkey::new_unique();
+    let recipient_token_account =
+        spl_associated_token_account::get_associated_token_address(&recipient, &native_mint);
+    let ix = spl_associated_token_account::instruction::create_associated_token_account(
+        &context.payer.pubkey(),
+        &recipient,
+        &native_mint,
+        &spl_token::id(),
+    );
+    process(&mut context, ix, &[]).await.unwrap();
+
+    let wrapped_sol_account =
+        spl_associated_token_account::get_associated_token_address(&vault_key, &native_mint);
+    let action = ProposalAction::Batch {
+        instructions: vec![
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &vault_key,
+                &vault_key,
+                &native_mint,
+                &spl_token::id(),
+            )
+            .into(),
+            solana_sdk::system_instruction::transfer(&vault_key, &wrapped_sol_account, 250_000_000)
+                .into(),
+            spl_token::instruction::sync_native(&spl_token::id(), &wrapped_sol_account)
+                .unwrap()
+                .into(),
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                &wrapped_sol_account,
+                &native_mint,
+                &recipient_token_account,
+                &vault_key,
+                &[],
+                250_000_000,
+                spl_token::native_mint::DECIMALS,
+            )
+            .unwrap()
+            .into(),
+        ],
+    };
+    let transaction_key = propose(&mut context, multisig_key, &owner_keypair, action.clone()).await;
+    let execute =
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &action);
+    process(&mut context, execute, &[]).await.unwrap();
+
+    let recipient_account = ctx_get_account(&mut context, recipient_token_account).await;
+    let recipient_state = spl_token::state::Account::unpack(&recipient_account.data).unwrap();
+    assert_eq!(recipient_state.amount, 250_000_000);
+    // the vault's WSOL account holds nothing but its rent
+    let wrapped_sol = ctx_get_account(&mut context, wrapped_sol_account).await;
+    let wrapped_sol_state = spl_token::state::Account::unpack(&wrapped_sol.data).unwrap();
+    assert_eq!(wrapped_sol_state.amount, 0);
+    assert_eq!(wrapped_sol_state.owner, vault_key);
+    assert!(wrapped_sol_state.is_native());
+}
+
+#[tokio::test]
+async fn test_set_upgrade_authority() {
+    use solana_program::bpf_loader_upgradeable;
+
+    let program_id = solana_multisig_wallet::id();
+    let mut program_test = program_test();
+
+    let owner_keypair = Keypair::new();
+    let multisig_key = Pubkey::new_unique();
+    let multisig = Multisig {
+        owners: vec![owner_keypair.pubkey()],
+        threshold: 1,
+        signers: vec![false],
+        veto_authority: None,
+        paused: false,
+        emergency_authority: None,
+        amount_policy: None,
+        approval_ttl_seconds: None,
+        signed_at: vec![0],
+        owner_set_seqno: 0,
+        outflow_cap: None,
+        spent_this_period: 0,
+        period_start: 0,
+        policy_signer: None,
+        transaction_count: 0,
+        fee_exempt: false,
+        allow_zero_amount: false,
+        min_transfer: 0,
+        strict_signing: false,
+        delegate_signers: 0,
+    };
+    program_test.add_account(
+        multisig_key,
+        Account {
+            lamports: 1_000_000_000,
+            data: multisig.try_to_vec().unwrap(),
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+
+    // an upgradeable program whose upgrade authority is the vault
+    let managed_program = Pubkey::new_unique();
+    let (programdata_key, _) =
+        Pubkey::find_program_address(&[managed_program.as_ref()], &bpf_loader_upgradeable::id());
+    let mut program_data = vec![2, 0, 0, 0];
+    program_data.extend_from_slice(programdata_key.as_ref());
+    program_test.add_account(
+        managed_program,
+        Account {
+            lamports: 1_000_000_000,
+            data: program_data,
+            owner: bpf_loader_upgradeable::id(),
+            executable: true,
+            ..Account::default()
+        },
+    );
+    let programdata = |authority: Option<&Pubkey>| {
+        let mut data = vec![3, 0, 0, 0];
+        data.extend_from_slice(&0u64.to_le_bytes());
+        match authority {
+            Some(authority) => {
+                data.push(1);
+                data.extend_from_slice(authority.as_ref());
+            }
+            None => data.push(0),
+        }
+        data
+    };
+    program_test.add_account(
+        programdata_key,
+        Account {
+            lamports: 1_000_000_000,
+            data: programdata(Some(&vault_key)),
+            owner: bpf_loader_upgradeable::id(),
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let new_authority = Pubkey::new_unique();
+    let action = ProposalAction::SetUpgradeAuthority {
+        program: managed_program,
+        new_authority: Some(new_authority),
+    };
+    let transaction_key = propose(&mut context, multisig_key, &owner_keypair, action.clone()).await;
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::execute_transaction(
+            &program_id,
+            &multisig_key,
+            &transaction_key,
+            &action,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let programdata_account = ctx_get_account(&mut context, programdata_key).await;
+    assert_eq!(
+        &programdata_account.data[..45],
+        &programdata(Some(&new_authority))[..],
+        "upgrade authority should have moved to the new key"
+    );
+
+    // the vault isn't the authority anymore, a second hand-over must fail
+    let action = ProposalAction::SetUpgradeAuthority {
+        program: managed_program,
+        new_authority: None,
+    };
+    let transaction_key = propose(&mut context, multisig_key, &owner_keypair, action.clone()).await;
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::execute_transaction(
+            &program_id,
+            &multisig_key,
+            &transaction_key,
+            &action,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+}
+
+#[tokio::test]
+async fn test_swap_owner() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 2, 0).await;
+    let new_owner = Keypair::new();
+
+    // a pending proposal approved by the owner being rotated out
+    let action = ProposalAction::StakeDeactivate {
+        stake: Pubkey::new_unique(),
+    };
+    let transaction_key = propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[0],
+        action.clone(),
+    )
+    .await;
+    approve_as(
+        &mut context,
+        multisig_key,
+        transaction_key,
+        &action,
+        &owner_keypairs[1],
+    )
+    .await;
+
+    let swap_ix = instruction::swap_owner(
+        &program_id,
+        &multisig_key,
+        &owners[1],
+        &new_owner.pubkey(),
+        &[transaction_key],
+    );
+
+    // needs the threshold like any other configuration change
+    sign_as(&mut context, multisig_key, &owner_keypairs[0]).await;
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        std::slice::from_ref(&swap_ix),
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+
+    sign_as(&mut context, multisig_key, &owner_keypairs[2]).await;
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&recent_blockhash)
+        .await
+        .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert_eq!(
+        multisig.owners,
+        vec![owners[0], new_owner.pubkey(), owners[2]]
+    );
+    assert_eq!(multisig.signers, vec![false; 3]);
+
+    // the old key's approval doesn't carry over, the proposer's does
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    assert_eq!(proposal.signers, vec![true, false, false]);
+
+    // made under the previous owner set, it can't gather approvals anymore
+    assert_eq!(multisig.owner_set_seqno, 1);
+    assert_eq!(proposal.owner_set_seqno, 0);
+    let ix = instruction::approve(
+        &program_id,
+        &new_owner.pubkey(),
+        &multisig_key,
+        &transaction_key,
+        &action,
+    );
+    let err = process(&mut context, ix, &[&new_owner])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, custom(MultisigError::StaleProposal))
+    );
+
+    // swapping in a key that already is an owner is rejected
+    sign_as(&mut context, multisig_key, &owner_keypairs[0]).await;
+    sign_as(&mut context, multisig_key, &owner_keypairs[2]).await;
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::swap_owner(
+            &program_id,
+            &multisig_key,
+            &owners[0],
+            &owners[2],
+            &[],
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+}
+
+#[tokio::test]
+async fn test_swap_owner_proposal() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 2, 0).await;
+    let new_owner = Pubkey::new_unique();
+
+    // a pending proposal approved by the owner being rotated out
+    let pending_action = ProposalAction::StakeDeactivate {
+        stake: Pubkey::new_unique(),
+    };
+    let pending_key = propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[2],
+        pending_action,
+    )
+    .await;
+
+    // swapping in a key that already is an owner is rejected up front
+    let duplicate = ProposalAction::SwapOwner {
+        old_owner: owners[2],
+        new_owner: owners[1],
+    };
+    let space = solana_multisig_wallet::Transaction::space(&duplicate);
+    let transaction_keypair = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let payer = context.payer.pubkey();
+    process(
+        &mut context,
+        solana_sdk::system_instruction::create_account(
+            &payer,
+            &transaction_keypair.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &program_id,
+        ),
+        &[&transaction_keypair],
+    )
+    .await
+    .unwrap();
+    let result = process(
+        &mut context,
+        instruction::propose(
+            &program_id,
+            &owners[0],
+            &multisig_key,
+            &transaction_keypair.pubkey(),
+            duplicate,
+        ),
+        &[&owner_keypairs[0]],
+    )
+    .await;
+    assert!(result.is_err());
+
+    let action = ProposalAction::SwapOwner {
+        old_owner: owners[2],
+        new_owner,
+    };
+    let transaction_key = propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[0],
+        action.clone(),
+    )
+    .await;
+    approve_as(
+        &mut context,
+        multisig_key,
+        transaction_key,
+        &action,
+        &owner_keypairs[1],
+    )
+    .await;
+
+    let mut execute =
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &action);
+    assert!(execute.accounts[0].is_writable);
+    execute.accounts.push(AccountMeta::new(pending_key, false));
+    process(&mut context, execute, &[]).await.unwrap();
+
+    // same owner count and threshold, one key replaced in place
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert_eq!(multisig.owners, vec![owners[0], owners[1], new_owner]);
+    assert_eq!(multisig.threshold, 2);
+    let pending = get_proposal(&mut context, pending_key).await;
+    assert_eq!(pending.signers, vec![false, false, false]);
+
+    // a swap whose old owner was replaced in the meantime fails at execution
+    let stale = ProposalAction::SwapOwner {
+        old_owner: owners[1],
+        new_owner: Pubkey::new_unique(),
+    };
+    let stale_key = propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[0],
+        stale.clone(),
+    )
+    .await;
+    approve_as(
+        &mut context,
+        multisig_key,
+        stale_key,
+        &stale,
+        &owner_keypairs[1],
+    )
+    .await;
+    let swap_back = ProposalAction::SwapOwner {
+        old_owner: owners[1],
+        new_owner: owners[2],
+    };
+    let swap_back_key = propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[0],
+        swap_back.clone(),
+    )
+    .await;
+    approve_as(
+        &mut context,
+        multisig_key,
+        swap_back_key,
+        &swap_back,
+        &owner_keypairs[1],
+    )
+    .await;
+    process(
+        &mut context,
+        instruction::execute_transaction(&program_id, &multisig_key, &swap_back_key, &swap_back),
+        &[],
+    )
+    .await
+    .unwrap();
+    let result = process(
+        &mut context,
+        instruction::execute_transaction(&program_id, &multisig_key, &stale_key, &stale),
+        &[],
+    )
+    .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_veto_authority() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 2, 0).await;
+    let veto_keypair = Keypair::new();
+    let proposer = &owner_keypairs[0];
+
+    // an owner can't hold the veto
+    sign_as(&mut context, multisig_key, &owner_keypairs[0]).await;
+    sign_as(&mut context, multisig_key, &owner_keypairs[1]).await;
+    let payer = context.payer.pubkey();
+    let result = process(
+        &mut context,
+        instruction::set_veto_authority(&program_id, &payer, &multisig_key, Some(owners[1])),
+        &[],
+    )
+    .await;
+    assert!(result.is_err());
+
+    // the key goes into the reserved slot, the account keeps its size
+    process(
+        &mut context,
+        instruction::set_veto_authority(
+            &program_id,
+            &payer,
+            &multisig_key,
+            Some(veto_keypair.pubkey()),
+        ),
+        &[],
+    )
+    .await
+    .unwrap();
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert_eq!(multisig.veto_authority, Some(veto_keypair.pubkey()));
+    assert_eq!(multisig.signers, vec![false; 2]);
+
+    let action = ProposalAction::StakeDeactivate {
+        stake: Pubkey::new_unique(),
+    };
+    let transaction_key = propose(&mut context, multisig_key, proposer, action.clone()).await;
+
+    // no approving with the veto key
+    let result = process(
+        &mut context,
+        instruction::approve(
+            &program_id,
+            &veto_keypair.pubkey(),
+            &multisig_key,
+            &transaction_key,
+            &action,
+        ),
+        &[&veto_keypair],
+    )
+    .await;
+    assert!(result.is_err());
+
+    // only the veto authority can veto
+    let result = process(
+        &mut context,
+        instruction::veto_transaction(
+            &program_id,
+            &owners[1],
+            &multisig_key,
+            &transaction_key,
+            &proposer.pubkey(),
+        ),
+        &[&owner_keypairs[1]],
+    )
+    .await;
+    assert!(result.is_err());
+
+    let proposal_rent = ctx_get_account(&mut context, transaction_key)
+        .await
+        .lamports;
+    process(
+        &mut context,
+        instruction::veto_transaction(
+            &program_id,
+            &veto_keypair.pubkey(),
+            &multisig_key,
+            &transaction_key,
+            &proposer.pubkey(),
+        ),
+        &[&veto_keypair],
+    )
+    .await
+    .unwrap();
+    assert!(context
+        .banks_client
+        .get_account(transaction_key)
+        .await
+        .unwrap()
+        .is_none());
+    let proposer_account = ctx_get_account(&mut context, proposer.pubkey()).await;
+    assert_eq!(proposer_account.lamports, proposal_rent);
+}
+
+#[tokio::test]
+async fn test_destination_policy() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key =
+        setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 1_000_000_000).await;
+    let payer = context.payer.pubkey();
+    let treasury = Pubkey::new_unique();
+    let stranger = Pubkey::new_unique();
+    fund(&mut context, &treasury, 1_000_000).await;
+    fund(&mut context, &stranger, 1_000_000).await;
+
+    // policy changes need the threshold
+    let allow_treasury = instruction::set_destination_policy(
+        &program_id,
+        &payer,
+        &multisig_key,
+        DestinationMode::Allowlist,
+        vec![treasury],
+    );
+    assert!(process(&mut context, allow_treasury.clone(), &[])
+        .await
+        .is_err());
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    next_blockhash(&mut context).await;
+    process(&mut context, allow_treasury, &[]).await.unwrap();
+
+    next_blockhash(&mut context).await;
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let result = process(
+        &mut context,
+        instruction::execute(&program_id, &multisig_key, 100, &stranger),
+        &[],
+    )
+    .await;
+    assert!(result.is_err());
+    process(
+        &mut context,
+        instruction::execute(&program_id, &multisig_key, 100, &treasury),
+        &[],
+    )
+    .await
+    .unwrap();
+    let treasury_account = ctx_get_account(&mut context, treasury).await;
+    assert_eq!(treasury_account.lamports, 1_000_100);
+
+    // flip to a denylist of the stranger, entries are edited one at a time
+    next_blockhash(&mut context).await;
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    process(
+        &mut context,
+        instruction::set_destination_policy(
+            &program_id,
+            &payer,
+            &multisig_key,
+            DestinationMode::Denylist,
+            vec![],
+        ),
+        &[],
+    )
+    .await
+    .unwrap();
+    next_blockhash(&mut context).await;
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    process(
+        &mut context,
+        instruction::add_destination(&program_id, &payer, &multisig_key, &stranger),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let (policy_key, _) = find_destination_policy_address(&multisig_key, &program_id);
+    let policy_account = ctx_get_account(&mut context, policy_key).await;
+    let policy = DestinationPolicy::try_from_slice(&policy_account.data).unwrap();
+    assert_eq!(policy.mode, DestinationMode::Denylist);
+    assert_eq!(policy.destinations, vec![stranger]);
+
+    next_blockhash(&mut context).await;
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let result = process(
+        &mut context,
+        instruction::execute(&program_id, &multisig_key, 100, &stranger),
+        &[],
+    )
+    .await;
+    assert!(result.is_err());
+    process(
+        &mut context,
+        instruction::execute(&program_id, &multisig_key, 200, &treasury),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // in shadow mode the denylisted destination is only logged
+    next_blockhash(&mut context).await;
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    process(
+        &mut context,
+        instruction::set_destination_policy_enforcement(
+            &program_id,
+            &payer,
+            &multisig_key,
+            PolicyEnforcement::Shadow,
+        ),
+        &[],
+    )
+    .await
+    .unwrap();
+    next_blockhash(&mut context).await;
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    process(
+        &mut context,
+        instruction::execute(&program_id, &multisig_key, 100, &stranger),
+        &[],
+    )
+    .await
+    .unwrap();
+    let stranger_account = ctx_get_account(&mut context, stranger).await;
+    assert_eq!(stranger_account.lamports, 1_000_100);
+}
+
+#[tokio::test]
+async fn test_invalid_destinations() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key =
+        setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 1_000_000_000).await;
+    let invalid_destination = |index: u8| {
+        TransactionError::InstructionError(index, custom(MultisigError::InvalidDestination))
+    };
+
+    // the multisig paying itself
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let result = process(
+        &mut context,
+        instruction::execute(&program_id, &multisig_key, 100, &multisig_key),
+        &[],
+    )
+    .await;
+    assert_eq!(result.unwrap_err().unwrap(), invalid_destination(0));
+
+    // proposals paying the multisig, their own account or the program
+    let transaction_keypair = Keypair::new();
+    for destination in [multisig_key, transaction_keypair.pubkey(), program_id] {
+        let actions = [
+            ProposalAction::TransferWrappedSol {
+                destination,
+                amount: 100,
+            },
+            ProposalAction::CreateRecurringPayment {
+                destination,
+                amount: 100,
+                interval_seconds: 60,
+                count: 2,
+            },
+        ];
+        for action in actions {
+            let result = try_propose(
+                &mut context,
+                multisig_key,
+                &owner_keypair,
+                &transaction_keypair,
+                action,
+                ProposeOptions::default(),
+            )
+            .await;
+            // the proposal account is created by the first instruction
+            assert_eq!(result.unwrap_err().unwrap(), invalid_destination(1));
+        }
+    }
+
+    // anything else still goes through
+    let destination = Pubkey::new_unique();
+    let action = ProposalAction::TransferWrappedSol {
+        destination,
+        amount: 100,
+    };
+    try_propose(
+        &mut context,
+        multisig_key,
+        &owner_keypair,
+        &transaction_keypair,
+        action,
+        ProposeOptions::default(),
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_execute_needs_threshold() {
+    let mut harness = MultisigHarness::new(3, 2, 1_000_000_000).await;
+    let destination = Pubkey::new_unique();
+
+    harness.sign_as(0).await.unwrap();
+    assert!(harness.execute(10_000_000, &destination).await.is_err());
+    // a second `Sign` by the same owner is refused, and doesn't count twice
+    next_blockhash(&mut harness.context).await;
+    assert_eq!(
+        harness.sign_as(0).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::AlreadySigned))
+    );
+    assert!(harness.execute(10_000_000, &destination).await.is_err());
+
+    harness.sign_as(2).await.unwrap();
+    harness.execute(10_000_000, &destination).await.unwrap();
+    assert_eq!(harness.balance(&destination).await, 10_000_000);
+    assert_eq!(harness.state().await.signers, vec![false; 3]);
+}
+
+#[tokio::test]
+async fn test_packed_instructions() {
+    let program_id = solana_multisig_wallet::id();
+    let mut harness = MultisigHarness::new(2, 2, 1_000_000_000).await;
+    let multisig = harness.multisig;
+    let destination = Pubkey::new_unique();
+
+    for owner in &harness.owners {
+        let ix = instruction::sign_packed(&program_id, &owner.pubkey(), &multisig);
+        assert_eq!(ix.data, [0xff, 1]);
+        process(&mut harness.context, ix, &[owner]).await.unwrap();
+    }
+    assert_eq!(harness.state().await.signers, vec![true; 2]);
+
+    // an unknown packed instruction, and an amount cut short
+    let mut ix = instruction::execute_packed(&program_id, &multisig, 10_000_000, &destination);
+    assert_eq!(ix.data.len(), 10);
+    for data in [vec![0xff, 9], ix.data[..9].to_vec()] {
+        let bad = solana_program::instruction::Instruction { data, ..ix.clone() };
+        let err = process(&mut harness.context, bad, &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+        );
+    }
+
+    // the packed `Execute` pays the destination account, like its Borsh form
+    ix.data = instruction::execute_packed(&program_id, &multisig, 10_000_000, &destination).data;
+    process(&mut harness.context, ix, &[]).await.unwrap();
+    assert_eq!(harness.balance(&destination).await, 10_000_000);
+    assert_eq!(harness.state().await.signers, vec![false; 2]);
+}
+
+#[tokio::test]
+async fn test_pause() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key =
+        setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 1_000_000_000).await;
+    let payer = context.payer.pubkey();
+    let emergency_keypair = Keypair::new();
+    let destination = Pubkey::new_unique();
+    fund(&mut context, &destination, 1_000_000).await;
+
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    process(
+        &mut context,
+        instruction::set_emergency_authority(
+            &program_id,
+            &payer,
+            &multisig_key,
+            Some(emergency_keypair.pubkey()),
+        ),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // the emergency key freezes the wallet without any approvals
+    process(
+        &mut context,
+        instruction::set_paused(
+            &program_id,
+            &emergency_keypair.pubkey(),
+            &multisig_key,
+            true,
+        ),
+        &[&emergency_keypair],
+    )
+    .await
+    .unwrap();
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert!(multisig.paused);
+    assert_eq!(
+        multisig.emergency_authority,
+        Some(emergency_keypair.pubkey())
+    );
+
+    // only the owners can lift the freeze
+    let result = process(
+        &mut context,
+        instruction::set_paused(
+            &program_id,
+            &emergency_keypair.pubkey(),
+            &multisig_key,
+            false,
+        ),
+        &[&emergency_keypair],
+    )
+    .await;
+    assert!(result.is_err());
+
+    // signing still works, executing doesn't
+    next_blockhash(&mut context).await;
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let execute = instruction::execute(&program_id, &multisig_key, 100, &destination);
+    assert!(process(&mut context, execute.clone(), &[]).await.is_err());
+
+    // the approval lets anyone unpause
+    process(
+        &mut context,
+        instruction::set_paused(&program_id, &payer, &multisig_key, false),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    next_blockhash(&mut context).await;
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    process(&mut context, execute, &[]).await.unwrap();
+    let destination_account = ctx_get_account(&mut context, destination).await;
+    assert_eq!(destination_account.lamports, 1_000_100);
+}
+
+#[tokio::test]
+async fn test_amount_policy() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 1, 1_000_000_000).await;
+    let payer = context.payer.pubkey();
+    let destination = Pubkey::new_unique();
+    fund(&mut context, &destination, 1_000_000).await;
+
+    // the large tier can't ask for more approvals than there are owners
+    sign_as(&mut context, multisig_key, &owner_keypairs[0]).await;
+    let too_strict = instruction::set_policy(
+        &program_id,
+        &payer,
+        &multisig_key,
+        Some(AmountPolicy {
+            limit: 1_000,
+            large_threshold: 4,
+        }),
+    );
+    assert!(process(&mut context, too_strict, &[]).await.is_err());
+    process(
+        &mut context,
+        instruction::set_policy(
+            &program_id,
+            &payer,
+            &multisig_key,
+            Some(AmountPolicy {
+                limit: 1_000,
+                large_threshold: 2,
+            }),
+        ),
+        &[],
+    )
+    .await
+    .unwrap();
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert_eq!(multisig.threshold_for(1_001), 2);
+
+    // up to the limit a single approval still does
+    sign_as(&mut context, multisig_key, &owner_keypairs[0]).await;
+    process(
+        &mut context,
+        instruction::execute(&program_id, &multisig_key, 1_000, &destination),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    next_blockhash(&mut context).await;
+    sign_as(&mut context, multisig_key, &owner_keypairs[0]).await;
+    let large = instruction::execute(&program_id, &multisig_key, 5_000, &destination);
+    assert!(process(&mut context, large.clone(), &[]).await.is_err());
+    sign_as(&mut context, multisig_key, &owner_keypairs[1]).await;
+    next_blockhash(&mut context).await;
+    process(&mut context, large, &[]).await.unwrap();
+    let destination_account = ctx_get_account(&mut context, destination).await;
+    assert_eq!(destination_account.lamports, 1_006_000);
+}
+
+#[tokio::test]
+async fn test_display_unit() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key = setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 0).await;
+    let payer = context.payer.pubkey();
+    let usdc = Pubkey::new_unique();
+
+    let set_usdc =
+        instruction::set_display_unit(&program_id, &payer, &multisig_key, &usdc, 6, "USDC");
+    assert!(process(&mut context, set_usdc.clone(), &[]).await.is_err());
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let too_long =
+        instruction::set_display_unit(&program_id, &payer, &multisig_key, &usdc, 6, "NOT A SYMBOL");
+    assert!(process(&mut context, too_long, &[]).await.is_err());
+    next_blockhash(&mut context).await;
+    process(&mut context, set_usdc, &[]).await.unwrap();
+
+    let (display_key, _) = find_display_unit_address(&multisig_key, &program_id);
+    let display_account = ctx_get_account(&mut context, display_key).await;
+    let unit = DisplayUnit::try_from_slice(&display_account.data).unwrap();
+    assert_eq!(
+        unit,
+        DisplayUnit {
+            multisig: multisig_key,
+            mint: usdc,
+            decimals: 6,
+            symbol: "USDC".to_string(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_metadata() {
+    let program_id = solana_multisig_wallet::id();
+    let mut context = program_test().start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key = setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 0).await;
+    let payer = context.payer.pubkey();
+    let (metadata_key, _) = find_metadata_address(&multisig_key, &program_id);
+    let uri = "https://example.com/treasury.png";
+
+    let set = instruction::set_metadata(&program_id, &payer, &multisig_key, "Treasury", uri);
+    let err = process(&mut context, set.clone(), &[])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, custom(MultisigError::InsufficientSignatures))
+    );
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let long_name = "n".repeat(MAX_METADATA_NAME_LEN + 1);
+    let long_uri = "u".repeat(MAX_METADATA_URI_LEN + 1);
+    for (name, uri) in [(long_name.as_str(), uri), ("Treasury", long_uri.as_str())] {
+        let ix = instruction::set_metadata(&program_id, &payer, &multisig_key, name, uri);
+        let err = process(&mut context, ix, &[]).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, custom(MultisigError::InvalidConfig))
+        );
+    }
+    next_blockhash(&mut context).await;
+    process(&mut context, set, &[]).await.unwrap();
+    let account = ctx_get_account(&mut context, metadata_key).await;
+    assert_eq!(
+        load::<Metadata>(&account.data).unwrap(),
+        Metadata {
+            multisig: multisig_key,
+            name: "Treasury".to_string(),
+            uri: uri.to_string(),
+        }
+    );
+
+    // renaming resizes the account, a new approval is needed
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let rename = instruction::set_metadata(
+        &program_id,
+        &payer,
+        &multisig_key,
+        "Operations treasury",
+        "",
+    );
+    process(&mut context, rename, &[]).await.unwrap();
+    let account = ctx_get_account(&mut context, metadata_key).await;
+    let metadata = load::<Metadata>(&account.data).unwrap();
+    assert_eq!(metadata.name, "Operations treasury");
+    assert!(metadata.uri.is_empty());
+    assert_eq!(account.data.len(), metadata.try_to_vec().unwrap().len());
+}
+
+#[tokio::test]
+async fn test_outflow_cap() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key =
+        setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 1_000_000_000).await;
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+    let payer = context.payer.pubkey();
+    let destination = Pubkey::new_unique();
+    fund(&mut context, &vault_key, 1_000_000_000).await;
+    fund(&mut context, &destination, 1_000_000).await;
+    let cap = |limit, period_seconds| {
+        Some(OutflowCap {
+            limit,
+            period_seconds,
+        })
+    };
+
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let result = process(
+        &mut context,
+        instruction::set_outflow_cap(&program_id, &payer, &multisig_key, cap(1_000, 0)),
+        &[],
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::InvalidConfig))
+    );
+    process(
+        &mut context,
+        instruction::set_outflow_cap(&program_id, &payer, &multisig_key, cap(1_000, 3_600)),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let mut clock: solana_program::clock::Clock = context.banks_client.get_sysvar().await.unwrap();
+    let start = clock.unix_timestamp;
+
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let execute = instruction::execute(&program_id, &multisig_key, 600, &destination);
+    process(&mut context, execute, &[]).await.unwrap();
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let execute = instruction::execute_from_vault(&program_id, &multisig_key, 500, &destination);
+    let result = process(&mut context, execute.clone(), &[]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::OutflowCapExceeded))
+    );
+
+    // proposals count too, with the transfers of a batch
+    let action = ProposalAction::Batch {
+        instructions: vec![
+            solana_sdk::system_instruction::transfer(&vault_key, &destination, 400).into(),
+        ],
+    };
+    let transaction_key = propose(&mut context, multisig_key, &owner_keypair, action.clone()).await;
+    let execute_proposal =
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &action);
+    process(&mut context, execute_proposal, &[]).await.unwrap();
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert_eq!(multisig.spent_this_period, 1_000);
+    assert_eq!(multisig.outflow_remaining(start), Some(0));
+
+    // until the period rolls over
+    clock.unix_timestamp = start + 3_600;
+    context.set_sysvar(&clock);
+    next_blockhash(&mut context).await;
+    process(&mut context, execute, &[]).await.unwrap();
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert_eq!(multisig.period_start, start + 3_600);
+    assert_eq!(multisig.spent_this_period, 500);
+
+    // lifting the cap clears the count
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    process(
+        &mut context,
+        instruction::set_outflow_cap(&program_id, &payer, &multisig_key, None),
+        &[],
+    )
+    .await
+    .unwrap();
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert_eq!(multisig.outflow_cap, None);
+    assert_eq!(multisig.spent_this_period, 0);
+}
+
+#[tokio::test]
+async fn test_transfer_minimum() {
+    let program_id = solana_multisig_wallet::id();
+    let mut context = program_test().start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key =
+        setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 1_000_000_000).await;
+    let payer = context.payer.pubkey();
+    let destination = Pubkey::new_unique();
+    fund(&mut context, &destination, 1_000_000).await;
+    let withdraw = |lamports| ProposalAction::StakeWithdraw {
+        stake: Pubkey::new_unique(),
+        destination,
+        lamports,
+    };
+
+    // zero-amount transfers and proposals are refused by default
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let execute = instruction::execute(&program_id, &multisig_key, 0, &destination);
+    let result = process(&mut context, execute.clone(), &[]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::ZeroAmount))
+    );
+    let result = try_propose(
+        &mut context,
+        multisig_key,
+        &owner_keypair,
+        &Keypair::new(),
+        withdraw(0),
+        ProposeOptions::default(),
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(1, custom(MultisigError::ZeroAmount))
+    );
+
+    process(
+        &mut context,
+        instruction::set_transfer_minimum(&program_id, &payer, &multisig_key, true, 10_000),
+        &[],
+    )
+    .await
+    .unwrap();
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert!(multisig.allow_zero_amount);
+    assert_eq!(multisig.min_transfer, 10_000);
+
+    // dust below the minimum is refused, signal transactions of zero go through
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let dust = instruction::execute(&program_id, &multisig_key, 9_999, &destination);
+    let result = process(&mut context, dust, &[]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::BelowMinimumTransfer))
+    );
+    let result = try_propose(
+        &mut context,
+        multisig_key,
+        &owner_keypair,
+        &Keypair::new(),
+        withdraw(9_999),
+        ProposeOptions::default(),
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(1, custom(MultisigError::BelowMinimumTransfer))
+    );
+    process(&mut context, execute, &[]).await.unwrap();
+    propose(&mut context, multisig_key, &owner_keypair, withdraw(0)).await;
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let execute = instruction::execute(&program_id, &multisig_key, 10_000, &destination);
+    process(&mut context, execute, &[]).await.unwrap();
+    assert_eq!(
+        ctx_get_account(&mut context, destination).await.lamports,
+        1_010_000
+    );
+}
+
+#[tokio::test]
+async fn test_strict_signing() {
+    let program_id = solana_multisig_wallet::id();
+    let mut context = program_test().start_with_context().await;
+
+    let owners = [Keypair::new(), Keypair::new()];
+    let keys: Vec<Pubkey> = owners.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &keys, 1, 1_000_000_000).await;
+    let payer = context.payer.pubkey();
+    fund(&mut context, &keys[1], 1_000_000_000).await;
+
+    sign_as(&mut context, multisig_key, &owners[0]).await;
+    process(
+        &mut context,
+        instruction::set_strict_signing(&program_id, &payer, &multisig_key, true),
+        &[],
+    )
+    .await
+    .unwrap();
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert!(multisig.strict_signing);
+    assert_eq!(multisig_account.data[Multisig::STRICT_SIGNING_OFFSET], 1);
+
+    // the instructions sysvar is required
+    let sign = instruction::sign(&program_id, &keys[1], &multisig_key);
+    let result = process(&mut context, sign, &[&owners[1]]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+
+    // so is the fee payer
+    let mut sign = instruction::sign_strict(&program_id, &keys[1], &multisig_key, &keys[1]);
+    sign.accounts.pop();
+    let result = process(&mut context, sign, &[&owners[1]]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+
+    // a relayer paying for the owner's `Sign` is named as the payer, and refused
+    let sign = instruction::sign_strict(&program_id, &keys[1], &multisig_key, &payer);
+    let result = process(&mut context, sign, &[&owners[1]]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::NonOwnerSigner))
+    );
+
+    // a relayer signing anything in the same transaction is refused
+    let sign = instruction::sign_strict(&program_id, &keys[1], &multisig_key, &keys[1]);
+    let relayed = Transaction::new_signed_with_payer(
+        &[
+            solana_sdk::system_instruction::transfer(&payer, &Pubkey::new_unique(), 1_000_000),
+            sign.clone(),
+        ],
+        Some(&payer),
+        &[&context.payer, &owners[1]],
+        context.last_blockhash,
+    );
+    let result = context.banks_client.process_transaction(relayed).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(1, custom(MultisigError::NonOwnerSigner))
+    );
+
+    // paid and signed by the owner alone it goes through
+    let owner_paid = Transaction::new_signed_with_payer(
+        &[sign],
+        Some(&keys[1]),
+        &[&owners[1]],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(owner_paid)
+        .await
+        .unwrap();
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert_eq!(multisig.signers, vec![false, true]);
+}
+
+#[tokio::test]
+async fn test_multisig_owner() {
+    let program_id = solana_multisig_wallet::id();
+    let mut context = program_test().start_with_context().await;
+
+    // a department wallet owning the treasury, next to a plain key
+    let department_owner = Keypair::new();
+    let department =
+        setup_multisig(&mut context, &[department_owner.pubkey()], 1, 1_000_000_000).await;
+    let (department_vault, _) = find_vault_address(&department, &program_id);
+    let officer = Keypair::new();
+    let treasury = setup_multisig(
+        &mut context,
+        &[department_vault, officer.pubkey()],
+        1,
+        1_000_000_000,
+    )
+    .await;
+
+    // the department's approvals stand in for its vault's signature
+    let sign = instruction::sign_as_multisig(
+        &program_id,
+        &department_owner.pubkey(),
+        &department,
+        &treasury,
+    );
+    let result = process(&mut context, sign.clone(), &[&department_owner]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::InsufficientSignatures))
+    );
+    sign_as(&mut context, department, &department_owner).await;
+    // only one of the department's owners spends them
+    let outsider = Keypair::new();
+    let hijack =
+        instruction::sign_as_multisig(&program_id, &outsider.pubkey(), &department, &treasury);
+    let result = process(&mut context, hijack, &[&outsider]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::OwnerNotFound))
+    );
+    // the refused transaction would otherwise be resent as is
+    next_blockhash(&mut context).await;
+    process(&mut context, sign, &[&department_owner])
+        .await
+        .unwrap();
+    let treasury_account = ctx_get_account(&mut context, treasury).await;
+    let treasury_state = Multisig::try_from_slice(&treasury_account.data).unwrap();
+    assert_eq!(treasury_state.signers, vec![true, false]);
+    // and are spent doing so
+    let department_account = ctx_get_account(&mut context, department).await;
+    let department_state = Multisig::try_from_slice(&department_account.data).unwrap();
+    assert_eq!(department_state.signers, vec![false]);
+
+    let destination = Pubkey::new_unique();
+    let execute = instruction::execute(&program_id, &treasury, 1_000_000, &destination);
+    process(&mut context, execute, &[]).await.unwrap();
+    assert_eq!(
+        ctx_get_account(&mut context, destination).await.lamports,
+        1_000_000
+    );
+
+    // a multisig that isn't an owner, or the treasury approving itself, is refused
+    let other_owner = Keypair::new();
+    let other = setup_multisig(&mut context, &[other_owner.pubkey()], 1, 0).await;
+    sign_as(&mut context, other, &other_owner).await;
+    let sign = instruction::sign_as_multisig(&program_id, &other_owner.pubkey(), &other, &treasury);
+    let result = process(&mut context, sign, &[&other_owner]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::OwnerNotFound))
+    );
+    sign_as(&mut context, treasury, &officer).await;
+    let sign = instruction::sign_as_multisig(&program_id, &officer.pubkey(), &treasury, &treasury);
+    let result = process(&mut context, sign, &[&officer]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::AccountMismatch))
+    );
+}
+
+#[tokio::test]
+async fn test_policy_signer() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key =
+        setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 1_000_000_000).await;
+    let payer = context.payer.pubkey();
+    let destination = Pubkey::new_unique();
+    fund(&mut context, &destination, 1_000_000).await;
+    let risk_engine = Keypair::new();
+
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    process(
+        &mut context,
+        instruction::set_policy_signer(
+            &program_id,
+            &payer,
+            &multisig_key,
+            Some(risk_engine.pubkey()),
+        ),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // the owners' approvals alone don't do anymore
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let execute = instruction::execute(&program_id, &multisig_key, 1_000, &destination);
+    let result = process(&mut context, execute.clone(), &[]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::PolicySignerMissing))
+    );
+    let impostor = Keypair::new();
+    let cosigned = instruction::with_policy_signer(execute.clone(), &impostor.pubkey());
+    let result = process(&mut context, cosigned, &[&impostor]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::PolicySignerMissing))
+    );
+
+    // the co-signature goes before the receipt accounts
+    let execute_with_receipt = instruction::execute_with_receipt(
+        &program_id,
+        &payer,
+        &multisig_key,
+        1_000,
+        &destination,
+        0,
+    );
+    let cosigned = instruction::with_policy_signer(execute_with_receipt, &risk_engine.pubkey());
+    process(&mut context, cosigned, &[&risk_engine])
+        .await
+        .unwrap();
+    assert_eq!(
+        context.banks_client.get_balance(destination).await.unwrap(),
+        1_001_000
+    );
+    let (ledger_key, _) = find_receipt_ledger_address(&multisig_key, &destination, &program_id);
+    let ledger =
+        load::<ReceiptLedger>(&ctx_get_account(&mut context, ledger_key).await.data).unwrap();
+    assert_eq!(ledger.total, 1_000);
+
+    // turned off, the owners are enough again
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    process(
+        &mut context,
+        instruction::set_policy_signer(&program_id, &payer, &multisig_key, None),
+        &[],
+    )
+    .await
+    .unwrap();
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    process(&mut context, execute, &[]).await.unwrap();
+    assert_eq!(
+        context.banks_client.get_balance(destination).await.unwrap(),
+        1_002_000
+    );
+}
+
+#[tokio::test]
+async fn test_propose_indexed() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owners = [Keypair::new(), Keypair::new()];
+    let owner_keys: Vec<Pubkey> = owners.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owner_keys, 2, 1_000_000_000).await;
+    for owner in &owner_keys {
+        fund(&mut context, owner, 1_000_000_000).await;
+    }
+    let propose = |proposer: &Pubkey, index: u64, stake: Pubkey| {
+        instruction::propose_indexed(
+            &program_id,
+            proposer,
+            &multisig_key,
+            index,
+            ProposalAction::StakeDeactivate { stake },
+            ProposeOptions::default(),
+        )
+    };
+
+    // numbered in order, each by whoever proposes
+    let stakes = [Pubkey::new_unique(), Pubkey::new_unique()];
+    for (index, (owner, stake)) in owners.iter().zip(stakes).enumerate() {
+        let ix = propose(&owner.pubkey(), index as u64, stake);
+        process(&mut context, ix, &[owner]).await.unwrap();
+    }
+    for (index, (owner, stake)) in owners.iter().zip(stakes).enumerate() {
+        let (address, _) = find_transaction_address(&multisig_key, index as u64, &program_id);
+        let transaction = load::<solana_multisig_wallet::Transaction>(
+            &ctx_get_account(&mut context, address).await.data,
+        )
+        .unwrap();
+        assert_eq!(transaction.multisig, multisig_key);
+        assert_eq!(transaction.proposer, owner.pubkey());
+        assert_eq!(
+            transaction.action,
+            ProposalAction::StakeDeactivate { stake }
+        );
+        assert_eq!(transaction.num_approvals(), 1);
+    }
+    let multisig =
+        load::<Multisig>(&ctx_get_account(&mut context, multisig_key).await.data).unwrap();
+    assert_eq!(multisig.transaction_count, 2);
+
+    // a taken or skipped number, or a stranger, and the count stays
+    for index in [1, 3] {
+        let result = process(
+            &mut context,
+            propose(&owner_keys[0], index, stakes[0]),
+            &[&owners[0]],
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+        );
+    }
+    let stranger = Keypair::new();
+    fund(&mut context, &stranger.pubkey(), 1_000_000_000).await;
+    let result = process(
+        &mut context,
+        propose(&stranger.pubkey(), 2, stakes[0]),
+        &[&stranger],
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::OwnerNotFound))
+    );
+    let multisig =
+        load::<Multisig>(&ctx_get_account(&mut context, multisig_key).await.data).unwrap();
+    assert_eq!(multisig.transaction_count, 2);
+}
+
+#[tokio::test]
+async fn test_close_transaction() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 2, 1_000_000_000).await;
+    let proposer = owners[0];
+    let not_closable =
+        TransactionError::InstructionError(0, custom(MultisigError::ProposalNotClosable));
+
+    // a pending proposal stays
+    let pending_action = ProposalAction::StakeDeactivate {
+        stake: Pubkey::new_unique(),
+    };
+    let pending_key = propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[0],
+        pending_action,
+    )
+    .await;
+    let close_pending =
+        instruction::close_transaction(&program_id, &multisig_key, &pending_key, &proposer);
+    let result = process(&mut context, close_pending.clone(), &[]).await;
+    assert_eq!(result.unwrap_err().unwrap(), not_closable);
+
+    // executing a swap finalizes it and makes the pending one stale
+    let action = ProposalAction::SwapOwner {
+        old_owner: owners[1],
+        new_owner: Pubkey::new_unique(),
+    };
+    let swap_key = propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[0],
+        action.clone(),
+    )
+    .await;
+    approve_as(
+        &mut context,
+        multisig_key,
+        swap_key,
+        &action,
+        &owner_keypairs[1],
+    )
+    .await;
+    let execute = instruction::execute_transaction(&program_id, &multisig_key, &swap_key, &action);
+    process(&mut context, execute, &[]).await.unwrap();
+    let executed = get_proposal(&mut context, swap_key).await;
+    let executed_at = executed.executed_at;
+    assert!(executed_at > 0 && executed.executed_slot > 0);
+    // anyone may send `ExecuteTransaction`, it has no executor to record
+    assert_eq!(executed.executed_by, None);
+
+    // both wait out the grace period
+    let close_swap =
+        instruction::close_transaction(&program_id, &multisig_key, &swap_key, &proposer);
+    for close in [close_pending.clone(), close_swap.clone()] {
+        let result = process(&mut context, close, &[]).await;
+        assert_eq!(result.unwrap_err().unwrap(), not_closable);
+    }
+    let mut clock = context
+        .banks_client
+        .get_sysvar::<solana_program::clock::Clock>()
+        .await
+        .unwrap();
+    clock.unix_timestamp = executed_at + CLOSE_GRACE_PERIOD;
+    context.set_sysvar(&clock);
+    next_blockhash(&mut context).await;
+
+    // the rent only goes back to the proposer
+    let stranger = Pubkey::new_unique();
+    let result = process(
+        &mut context,
+        instruction::close_transaction(&program_id, &multisig_key, &swap_key, &stranger),
+        &[],
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::AccountMismatch))
+    );
+
+    let balance = context.banks_client.get_balance(proposer).await.unwrap();
+    let mut rent = 0;
+    for (transaction_key, close) in [(pending_key, close_pending), (swap_key, close_swap)] {
+        rent += ctx_get_account(&mut context, transaction_key)
+            .await
+            .lamports;
+        process(&mut context, close, &[]).await.unwrap();
+        assert!(context
+            .banks_client
+            .get_account(transaction_key)
+            .await
+            .unwrap()
+            .is_none());
+    }
+    assert_eq!(
+        context.banks_client.get_balance(proposer).await.unwrap(),
+        balance + rent
+    );
+}
+
+#[tokio::test]
+async fn test_history() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 2, 1_000_000_000).await;
+    let other_multisig = setup_multisig(&mut context, &owners, 2, 0).await;
+    let payer = context.payer.pubkey();
+    for multisig in [multisig_key, other_multisig] {
+        process(
+            &mut context,
+            instruction::init_history(&program_id, &payer, &multisig),
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+    let (history_key, _) = find_history_address(&multisig_key, &program_id);
+    let history = load::<History>(&ctx_get_account(&mut context, history_key).await.data).unwrap();
+    assert_eq!(history.multisig, multisig_key);
+    assert_eq!(history.count, 0);
+
+    // instructions passing the history record themselves, `Propose` doesn't mistake it for an
+    // inbox
+    let sign = instruction::sign(&program_id, &owners[1], &multisig_key);
+    process(
+        &mut context,
+        instruction::with_history(sign.clone(), &multisig_key),
+        &[&owner_keypairs[1]],
+    )
+    .await
+    .unwrap();
+    let transaction_keypair = Keypair::new();
+    let action = ProposalAction::StakeDeactivate {
+        stake: Pubkey::new_unique(),
+    };
+    let space = solana_multisig_wallet::Transaction::space(&action);
+    let rent = context.banks_client.get_rent().await.unwrap();
+    process(
+        &mut context,
+        solana_sdk::system_instruction::create_account(
+            &payer,
+            &transaction_keypair.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &program_id,
+        ),
+        &[&transaction_keypair],
+    )
+    .await
+    .unwrap();
+    let propose = instruction::propose(
+        &program_id,
+        &owners[0],
+        &multisig_key,
+        &transaction_keypair.pubkey(),
+        action,
+    );
+    process(
+        &mut context,
+        instruction::with_history(propose.clone(), &multisig_key),
+        &[&owner_keypairs[0]],
+    )
+    .await
+    .unwrap();
+
+    // the history of a multisig the instruction doesn't involve is refused
+    next_blockhash(&mut context).await;
+    let result = process(
+        &mut context,
+        instruction::with_history(sign.clone(), &other_multisig),
+        &[&owner_keypairs[1]],
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::AccountMismatch))
+    );
+    // and leaving it out records nothing
+    process(
+        &mut context,
+        instruction::sign(&program_id, &owners[0], &multisig_key),
+        &[&owner_keypairs[0]],
+    )
+    .await
+    .unwrap();
+
+    let history = load::<History>(&ctx_get_account(&mut context, history_key).await.data).unwrap();
+    let recorded: Vec<(u8, Pubkey)> = history
+        .recent()
+        .iter()
+        .map(|entry| (entry.instruction, entry.actor))
+        .collect();
+    let tag = |ix: &solana_program::instruction::Instruction| {
+        MultisigInstruction::decode(&ix.data).unwrap().tag()
+    };
+    assert_eq!(
+        recorded,
+        [(tag(&sign), owners[1]), (tag(&propose), owners[0])]
+    );
+    assert!(history.recent().iter().all(|entry| entry.slot > 0));
+}
+
+#[tokio::test]
+async fn test_delegate() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 2, 0).await;
+    let owner = &owner_keypairs[0];
+    fund(&mut context, &owner.pubkey(), 1_000_000_000).await;
+    let hot_key = Keypair::new();
+    let (record_key, _) = find_delegate_address(&multisig_key, &owner.pubkey(), &program_id);
+    let clock: solana_program::clock::Clock = context.banks_client.get_sysvar().await.unwrap();
+    let expires_at = clock.unix_timestamp + 3_600;
+    let set_delegate = |expires_at, permissions| {
+        instruction::set_delegate(
+            &program_id,
+            &owner.pubkey(),
+            &multisig_key,
+            &hot_key.pubkey(),
+            expires_at,
+            permissions,
+        )
+    };
+
+    for (expires_at, permissions) in [
+        (expires_at, 0),
+        (expires_at, 1 << 7),
+        (clock.unix_timestamp - 1, DELEGATE_SIGN),
+    ] {
+        let result = process(
+            &mut context,
+            set_delegate(expires_at, permissions),
+            &[owner],
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, custom(MultisigError::InvalidConfig))
+        );
+    }
+    let outsider = Keypair::new();
+    fund(&mut context, &outsider.pubkey(), 1_000_000_000).await;
+    let not_owner = instruction::set_delegate(
+        &program_id,
+        &outsider.pubkey(),
+        &multisig_key,
+        &hot_key.pubkey(),
+        expires_at,
+        DELEGATE_SIGN,
+    );
+    let result = process(&mut context, not_owner, &[&outsider]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::OwnerNotFound))
+    );
+
+    process(
+        &mut context,
+        set_delegate(expires_at, DELEGATE_PERMISSIONS),
+        &[owner],
+    )
+    .await
+    .unwrap();
+    let record = load::<Delegate>(&ctx_get_account(&mut context, record_key).await.data).unwrap();
+    assert_eq!(record.owner, owner.pubkey());
+    assert_eq!(record.delegate, hot_key.pubkey());
+
+    // the hot key signs for its owner, and only for them
+    let sign = instruction::sign_as_delegate(
+        &program_id,
+        &hot_key.pubkey(),
+        &owner.pubkey(),
+        &multisig_key,
+    );
+    process(&mut context, sign.clone(), &[&hot_key])
+        .await
+        .unwrap();
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert_eq!(multisig.signers, vec![true, false, false]);
+    let other_owner =
+        instruction::sign_as_delegate(&program_id, &hot_key.pubkey(), &owners[1], &multisig_key);
+    assert!(process(&mut context, other_owner, &[&hot_key])
+        .await
+        .is_err());
+
+    // its approval doesn't count toward configuration changes
+    sign_as(&mut context, multisig_key, &owner_keypairs[1]).await;
+    let payer = context.payer.pubkey();
+    let set_ttl = instruction::set_approval_ttl(&program_id, &payer, &multisig_key, None);
+    let result = process(&mut context, set_ttl.clone(), &[]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::InsufficientSignatures))
+    );
+    // until the owner signs themselves
+    sign_as(&mut context, multisig_key, owner).await;
+    next_blockhash(&mut context).await;
+    process(&mut context, set_ttl, &[]).await.unwrap();
+
+    // and approves proposals, but not owner set changes
+    let action = ProposalAction::StakeDeactivate {
+        stake: Pubkey::new_unique(),
+    };
+    let transaction_key = propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[1],
+        action.clone(),
+    )
+    .await;
+    let approve = instruction::approve_as_delegate(
+        &program_id,
+        &hot_key.pubkey(),
+        &owner.pubkey(),
+        &multisig_key,
+        &transaction_key,
+        &action,
+    );
+    process(&mut context, approve, &[&hot_key]).await.unwrap();
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    assert_eq!(proposal.signers, vec![true, true, false]);
+
+    let swap = ProposalAction::SwapOwner {
+        old_owner: owners[2],
+        new_owner: hot_key.pubkey(),
+    };
+    let swap_key = propose(&mut context, multisig_key, &owner_keypairs[1], swap.clone()).await;
+    let approve_swap = instruction::approve_as_delegate(
+        &program_id,
+        &hot_key.pubkey(),
+        &owner.pubkey(),
+        &multisig_key,
+        &swap_key,
+        &swap,
+    );
+    let result = process(&mut context, approve_swap, &[&hot_key]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::DelegateNotPermitted))
+    );
+
+    // nor a batch calling back into the program to change the multisig's settings
+    let batch = ProposalAction::Batch {
+        instructions: vec![instruction::change_threshold(&program_id, &multisig_key, 1).into()],
+    };
+    let batch_key = propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[1],
+        batch.clone(),
+    )
+    .await;
+    let approve_batch = instruction::approve_as_delegate(
+        &program_id,
+        &hot_key.pubkey(),
+        &owner.pubkey(),
+        &multisig_key,
+        &batch_key,
+        &batch,
+    );
+    let result = process(&mut context, approve_batch, &[&hot_key]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::DelegateNotPermitted))
+    );
+
+    // a sign-only key can't approve proposals
+    process(
+        &mut context,
+        set_delegate(expires_at, DELEGATE_SIGN),
+        &[owner],
+    )
+    .await
+    .unwrap();
+    let other_key = propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[1],
+        action.clone(),
+    )
+    .await;
+    let approve = instruction::approve_as_delegate(
+        &program_id,
+        &hot_key.pubkey(),
+        &owner.pubkey(),
+        &multisig_key,
+        &other_key,
+        &action,
+    );
+    let result = process(&mut context, approve, &[&hot_key]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::DelegateNotPermitted))
+    );
+
+    // past its expiry the key is useless
+    let mut expired = clock.clone();
+    expired.unix_timestamp = expires_at + 1;
+    context.set_sysvar(&expired);
+    next_blockhash(&mut context).await;
+    let result = process(&mut context, sign.clone(), &[&hot_key]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::DelegateExpired))
+    );
+
+    // revoking closes the record and refunds the owner
+    let balance = context
+        .banks_client
+        .get_balance(owner.pubkey())
+        .await
+        .unwrap();
+    let rent = ctx_get_account(&mut context, record_key).await.lamports;
+    process(
+        &mut context,
+        instruction::revoke_delegate(&program_id, &owner.pubkey(), &multisig_key),
+        &[owner],
+    )
+    .await
+    .unwrap();
+    assert!(context
+        .banks_client
+        .get_account(record_key)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        context
+            .banks_client
+            .get_balance(owner.pubkey())
+            .await
+            .unwrap(),
+        balance + rent
+    );
+}
+
+#[tokio::test]
+async fn test_approval_ttl() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key =
+        setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 1_000_000_000).await;
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+    let payer = context.payer.pubkey();
+    let destination = Pubkey::new_unique();
+    fund(&mut context, &vault_key, 1_000_000_000).await;
+    fund(&mut context, &destination, 1_000_000).await;
+
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    assert!(process(
+        &mut context,
+        instruction::set_approval_ttl(&program_id, &payer, &multisig_key, Some(0)),
+        &[],
+    )
+    .await
+    .is_err());
+    process(
+        &mut context,
+        instruction::set_approval_ttl(&program_id, &payer, &multisig_key, Some(60)),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let mut clock: solana_program::clock::Clock = context.banks_client.get_sysvar().await.unwrap();
+    let start = clock.unix_timestamp;
+
+    // a `Sign` older than the TTL doesn't count, signing again renews it
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    let action = ProposalAction::CreateRecurringPayment {
+        destination,
+        amount: 1_000,
+        interval_seconds: 60,
+        count: 1,
+    };
+    let transaction_key = propose(&mut context, multisig_key, &owner_keypair, action.clone()).await;
+    clock.unix_timestamp = start + 61;
+    context.set_sysvar(&clock);
+    next_blockhash(&mut context).await;
+    let execute = instruction::execute(&program_id, &multisig_key, 100, &destination);
+    let result = process(&mut context, execute.clone(), &[]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::InsufficientSignatures))
+    );
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    next_blockhash(&mut context).await;
+    process(&mut context, execute, &[]).await.unwrap();
+
+    // same for the proposer's approval of a proposal
+    let execute_proposal =
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &action);
+    assert!(process(&mut context, execute_proposal.clone(), &[])
+        .await
+        .is_err());
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert_eq!(
+        proposal.status_at(&multisig, start + 61),
+        ProposalStatus::Pending { remaining: 1 }
+    );
+    approve_as(
+        &mut context,
+        multisig_key,
+        transaction_key,
+        &action,
+        &owner_keypair,
+    )
+    .await;
+    next_blockhash(&mut context).await;
+    process(&mut context, execute_proposal, &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_recurring_payment() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key = setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 0).await;
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+    let destination = Pubkey::new_unique();
+    fund(&mut context, &vault_key, 1_000_000_000).await;
+    fund(&mut context, &destination, 1_000_000).await;
+
+    // nothing to schedule
+    let zero_count = ProposalAction::CreateRecurringPayment {
+        destination,
+        amount: 1_000,
+        interval_seconds: 60,
+        count: 0,
+    };
+    let space = solana_multisig_wallet::Transaction::space(&zero_count);
+    let transaction_keypair = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let payer = context.payer.pubkey();
+    process(
+        &mut context,
+        solana_sdk::system_instruction::create_account(
+            &payer,
+            &transaction_keypair.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &program_id,
+        ),
+        &[&transaction_keypair],
+    )
+    .await
+    .unwrap();
+    let result = process(
+        &mut context,
+        instruction::propose(
+            &program_id,
+            &owner_keypair.pubkey(),
+            &multisig_key,
+            &transaction_keypair.pubkey(),
+            zero_count,
+        ),
+        &[&owner_keypair],
+    )
+    .await;
+    assert!(result.is_err());
+
+    let action = ProposalAction::CreateRecurringPayment {
+        destination,
+        amount: 1_000,
+        interval_seconds: 60,
+        count: 2,
+    };
+    let transaction_key = propose(&mut context, multisig_key, &owner_keypair, action.clone()).await;
+    process(
+        &mut context,
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &action),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let (recurring_key, _) = find_recurring_payment_address(&transaction_key, &program_id);
+    let recurring_account = ctx_get_account(&mut context, recurring_key).await;
+    assert_eq!(recurring_account.data.len(), RecurringPayment::SPACE);
+    let recurring = RecurringPayment::try_from_slice(&recurring_account.data).unwrap();
+    assert_eq!(recurring.remaining, 2);
+
+    // not due before the first interval has passed
+    let crank = instruction::execute_recurring(
+        &program_id,
+        &multisig_key,
+        &transaction_key,
+        0,
+        &destination,
+    );
+    assert!(process(&mut context, crank.clone(), &[]).await.is_err());
+
+    for payment in 1..=2 {
+        let mut clock: solana_program::clock::Clock =
+            context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp = recurring.next_payment_at + 60 * (payment - 1);
+        context.set_sysvar(&clock);
+        next_blockhash(&mut context).await;
+        process(&mut context, crank.clone(), &[]).await.unwrap();
+
+        let destination_account = ctx_get_account(&mut context, destination).await;
+        assert_eq!(
+            destination_account.lamports,
+            1_000_000 + 1_000 * payment as u64
+        );
+    }
+
+    // the schedule is done, its rent went back to the vault
+    assert!(context
+        .banks_client
+        .get_account(recurring_key)
+        .await
+        .unwrap()
+        .is_none());
+    let vault_account = ctx_get_account(&mut context, vault_key).await;
+    assert_eq!(vault_account.lamports, 1_000_000_000 - 2_000);
+    next_blockhash(&mut context).await;
+    assert!(process(&mut context, crank, &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_distribution() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key = setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 0).await;
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+    let payer = context.payer.pubkey();
+    fund(&mut context, &vault_key, 1_000_000_000).await;
+    let recipients = [
+        (Pubkey::new_unique(), 100_000),
+        (Pubkey::new_unique(), 200_000),
+        (Pubkey::new_unique(), 300_000),
+    ];
+    for (recipient, _) in &recipients {
+        fund(&mut context, recipient, 1_000_000).await;
+    }
+
+    let tree = MerkleTree::new(&recipients).unwrap();
+
+    // the cap only covers two of the three grants
+    let action = ProposalAction::CreateDistribution {
+        merkle_root: tree.root(),
+        total_cap: 450_000,
+    };
+    let transaction_key = propose(&mut context, multisig_key, &owner_keypair, action.clone()).await;
+    process(
+        &mut context,
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &action),
+        &[],
+    )
+    .await
+    .unwrap();
+    let (distribution_key, _) = find_distribution_address(&transaction_key, &program_id);
+    let distribution_account = ctx_get_account(&mut context, distribution_key).await;
+    assert_eq!(distribution_account.data.len(), Distribution::SPACE);
+
+    let claim = |index: usize, amount: u64| {
+        instruction::claim_distribution(
+            &program_id,
+            &payer,
+            &multisig_key,
+            &transaction_key,
+            0,
+            &recipients[index].0,
+            amount,
+            tree.proof(index).unwrap(),
+        )
+    };
+    // the amount is part of the leaf
+    assert!(process(&mut context, claim(0, 150_000), &[]).await.is_err());
+    process(&mut context, claim(0, 100_000), &[]).await.unwrap();
+    process(&mut context, claim(2, 300_000), &[]).await.unwrap();
+    next_blockhash(&mut context).await;
+    assert!(process(&mut context, claim(0, 100_000), &[]).await.is_err());
+    assert!(process(&mut context, claim(1, 200_000), &[]).await.is_err());
+
+    for (recipient, amount) in [recipients[0], recipients[2]] {
+        let account = ctx_get_account(&mut context, recipient).await;
+        assert_eq!(account.lamports, 1_000_000 + amount);
+        let (claim_key, _) = find_claim_address(&distribution_key, &recipient, &program_id);
+        let claim =
+            Claim::try_from_slice(&ctx_get_account(&mut context, claim_key).await.data).unwrap();
+        assert_eq!(claim.amount, amount);
+    }
+    let distribution_account = ctx_get_account(&mut context, distribution_key).await;
+    let distribution = Distribution::try_from_slice(&distribution_account.data).unwrap();
+    assert_eq!(distribution.claimed, 400_000);
+}
+
+#[tokio::test]
+async fn test_execution_receipts() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key =
+        setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 1_000_000_000).await;
+    let payer = context.payer.pubkey();
+    let destination = Pubkey::new_unique();
+    fund(&mut context, &destination, 1_000_000).await;
+
+    // without the receipt accounts nothing is recorded
+    sign_as(&mut context, multisig_key, &owner_keypair).await;
+    process(
+        &mut context,
+        instruction::execute(&program_id, &multisig_key, 100, &destination),
+        &[],
+    )
+    .await
+    .unwrap();
+    let (ledger_key, _) = find_receipt_ledger_address(&multisig_key, &destination, &program_id);
+    assert!(context
+        .banks_client
+        .get_account(ledger_key)
+        .await
+        .unwrap()
+        .is_none());
+
+    for (index, amount) in [(0, 200), (1, 300)] {
+        next_blockhash(&mut context).await;
+        sign_as(&mut context, multisig_key, &owner_keypair).await;
+        // the receipt has to be the ledger's next one
+        let skipped = instruction::execute_with_receipt(
+            &program_id,
+            &payer,
+            &multisig_key,
+            amount,
+            &destination,
+            index + 1,
+        );
+        assert!(process(&mut context, skipped, &[]).await.is_err());
+        process(
+            &mut context,
+            instruction::execute_with_receipt(
+                &program_id,
+                &payer,
+                &multisig_key,
+                amount,
+                &destination,
+                index,
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+
+    let ledger_account = ctx_get_account(&mut context, ledger_key).await;
+    let ledger = ReceiptLedger::try_from_slice(&ledger_account.data).unwrap();
+    assert_eq!(ledger.count, 2);
+    assert_eq!(ledger.total, 500);
+    for (index, amount) in [(0, 200), (1, 300)] {
+        let (receipt_key, _) =
+            find_receipt_address(&multisig_key, &destination, index, &program_id);
+        let receipt_account = ctx_get_account(&mut context, receipt_key).await;
+        let receipt = Receipt::try_from_slice(&receipt_account.data).unwrap();
+        assert_eq!(receipt.multisig, multisig_key);
+        assert_eq!(receipt.destination, destination);
+        assert_eq!(receipt.index, index);
+        assert_eq!(receipt.amount, amount);
+        assert_eq!(receipt.transaction, Pubkey::default());
+    }
+    let destination_account = ctx_get_account(&mut context, destination).await;
+    assert_eq!(destination_account.lamports, 1_000_600);
+}
+
+#[tokio::test]
+async fn test_indexed_vaults() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key = setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 0).await;
+    let (main_vault, _) = find_vault_address(&multisig_key, &program_id);
+    let (grants_vault, _) = find_indexed_vault_address(&multisig_key, 2, &program_id);
+    let destination = Pubkey::new_unique();
+    fund(&mut context, &main_vault, 1_000_000_000).await;
+    fund(&mut context, &grants_vault, 500_000_000).await;
+    fund(&mut context, &destination, 1_000_000).await;
+
+    let action = ProposalAction::CreateRecurringPayment {
+        destination,
+        amount: 1_000,
+        interval_seconds: 60,
+        count: 1,
+    };
+    let transaction_key = propose_from_vault(
+        &mut context,
+        multisig_key,
+        &owner_keypair,
+        2,
+        action.clone(),
+    )
+    .await;
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    assert_eq!(proposal.vault, 2);
+
+    // the main vault can't stand in for the proposal's vault
+    let err = process(
+        &mut context,
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &action),
+        &[],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+    process(
+        &mut context,
+        instruction::execute_transaction_from_vault(
+            &program_id,
+            &multisig_key,
+            &transaction_key,
+            2,
+            &action,
+        ),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let (recurring_key, _) = find_recurring_payment_address(&transaction_key, &program_id);
+    let recurring_account = ctx_get_account(&mut context, recurring_key).await;
+    let recurring = RecurringPayment::try_from_slice(&recurring_account.data).unwrap();
+    assert_eq!(recurring.vault, 2);
+    let mut clock: solana_program::clock::Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = recurring.next_payment_at;
+    context.set_sysvar(&clock);
+    process(
+        &mut context,
+        instruction::execute_recurring(
+            &program_id,
+            &multisig_key,
+            &transaction_key,
+            2,
+            &destination,
+        ),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // the grants vault paid the payment and the schedule's rent, the main vault is untouched
+    let destination_account = ctx_get_account(&mut context, destination).await;
+    assert_eq!(destination_account.lamports, 1_001_000);
+    let grants_account = ctx_get_account(&mut context, grants_vault).await;
+    assert_eq!(grants_account.lamports, 500_000_000 - 1_000);
+    let main_account = ctx_get_account(&mut context, main_vault).await;
+    assert_eq!(main_account.lamports, 1_000_000_000);
+}
+
+#[tokio::test]
+async fn test_required_signers() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 2, 0).await;
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+    fund(&mut context, &vault_key, 1_000_000_000).await;
+    let cfo = &owner_keypairs[2];
+
+    let action = ProposalAction::CreateRecurringPayment {
+        destination: Pubkey::new_unique(),
+        amount: 1_000,
+        interval_seconds: 60,
+        count: 1,
+    };
+    let options = ProposeOptions {
+        required_signers: vec![cfo.pubkey()],
+        ..ProposeOptions::default()
+    };
+    let transaction_key = propose_with_options(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[0],
+        action.clone(),
+        options,
+    )
+    .await;
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    assert_eq!(proposal.required_signers, vec![false, false, true]);
+
+    // the threshold is met, but without the CFO
+    approve_as(
+        &mut context,
+        multisig_key,
+        transaction_key,
+        &action,
+        &owner_keypairs[1],
+    )
+    .await;
+    let execute =
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &action);
+    let err = process(&mut context, execute.clone(), &[])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, custom(MultisigError::RequiredSignerMissing))
+    );
+
+    approve_as(&mut context, multisig_key, transaction_key, &action, cfo).await;
+    next_blockhash(&mut context).await;
+    process(&mut context, execute, &[]).await.unwrap();
+    assert!(get_proposal(&mut context, transaction_key).await.executed);
+}
+
+#[tokio::test]
+async fn test_proposal_expiry_extension() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs = [
+        Keypair::new(),
+        Keypair::new(),
+        Keypair::new(),
+        Keypair::new(),
+    ];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 4, 0).await;
+    let [proposer, second, third, fourth] = &owner_keypairs;
+
+    let mut clock: solana_program::clock::Clock = context.banks_client.get_sysvar().await.unwrap();
+    let expires_at = clock.unix_timestamp + 100;
+    let action = ProposalAction::StakeDeactivate {
+        stake: Pubkey::new_unique(),
+    };
+    let options = ProposeOptions {
+        expires_at: Some(expires_at),
+        ..ProposeOptions::default()
+    };
+    let transaction_key = propose_with_options(
+        &mut context,
+        multisig_key,
+        proposer,
+        action.clone(),
+        options,
+    )
+    .await;
+
+    let extend = |extenders: &[&Keypair], seconds| {
+        let keys: Vec<Pubkey> = extenders.iter().map(|owner| owner.pubkey()).collect();
+        instruction::extend_proposal(&program_id, &multisig_key, &transaction_key, &keys, seconds)
+    };
+    // two owners of a 4-of-4 are neither threshold minus one nor the proposer plus one
+    let cases = [
+        (
+            extend(&[second, third], 3600),
+            vec![second, third],
+            custom(MultisigError::InsufficientSignatures),
+        ),
+        (
+            extend(&[proposer, second], MAX_PROPOSAL_EXTENSION + 1),
+            vec![proposer, second],
+            custom(MultisigError::InvalidExpiry),
+        ),
+    ];
+    for (ix, signers, expected) in cases {
+        let err = process(&mut context, ix, &signers)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, TransactionError::InstructionError(0, expected));
+    }
+
+    process(
+        &mut context,
+        extend(&[proposer, second], 3600),
+        &[proposer, second],
+    )
+    .await
+    .unwrap();
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    assert_eq!(proposal.expires_at, Some(expires_at + 3600));
+    assert!(proposal.extended);
+
+    // only once, even by threshold minus one owners
+    let err = process(
+        &mut context,
+        extend(&[second, third, fourth], 3600),
+        &[second, third, fourth],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, custom(MultisigError::NotExtendable))
+    );
+
+    // still open past the original expiry, closed after the extended one
+    clock.unix_timestamp = expires_at + 1;
+    context.set_sysvar(&clock);
+    approve_as(&mut context, multisig_key, transaction_key, &action, second).await;
+    clock.unix_timestamp = expires_at + 3601;
+    context.set_sysvar(&clock);
+    let approve = instruction::approve(
+        &program_id,
+        &third.pubkey(),
+        &multisig_key,
+        &transaction_key,
+        &action,
+    );
+    assert!(process(&mut context, approve, &[third]).await.is_err());
+}
+
+#[cfg(feature = "aggregated-approval")]
+#[tokio::test]
+async fn test_aggregated_approval() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    // stands in for the owners' off-chain group key, its signatures are plain Ed25519 ones
+    let group_keypair = Keypair::new();
+
+    let multisig_keypair = Keypair::new();
+    let multisig_key = multisig_keypair.pubkey();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            solana_sdk::system_instruction::create_account(
+                &context.payer.pubkey(),
+                &multisig_key,
+                rent.minimum_balance(Multisig::LEN),
+                Multisig::LEN as u64,
+                &program_id,
+            ),
+            instruction::create(&program_id, &multisig_key, owners.clone(), 2),
+            instruction::init_aggregate_key(
+                &program_id,
+                &context.payer.pubkey(),
+                &multisig_key,
+                &group_keypair.pubkey(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &multisig_keypair],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let action = ProposalAction::StakeDeactivate {
+        stake: Pubkey::new_unique(),
+    };
+    let transaction_key = propose(&mut context, multisig_key, &owner_keypairs[0], action).await;
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    let message = aggregated_approval_message(&transaction_key, &proposal);
+    let sign = |keypair: &Keypair, message: &[u8]| -> [u8; 64] {
+        keypair.sign_message(message).as_ref().try_into().unwrap()
+    };
+
+    // the approval alone, without the signature check before it
+    let instructions = instruction::approve_aggregated(
+        &program_id,
+        &group_keypair.pubkey(),
+        &sign(&group_keypair, message.as_ref()),
+        &transaction_key,
+        &proposal,
+    );
+    let result = process(&mut context, instructions[1].clone(), &[]).await;
+    assert!(result.is_err());
+
+    // a valid signature, but not by the registered key
+    let outsider = Keypair::new();
+    let instructions = instruction::approve_aggregated(
+        &program_id,
+        &outsider.pubkey(),
+        &sign(&outsider, message.as_ref()),
+        &transaction_key,
+        &proposal,
+    );
+    assert!(process_all(&mut context, &instructions).await.is_err());
+
+    // signed for another proposal
+    let other_message = aggregated_approval_message(&Pubkey::new_unique(), &proposal);
+    let instructions = instruction::approve_aggregated(
+        &program_id,
+        &group_keypair.pubkey(),
+        &sign(&group_keypair, other_message.as_ref()),
+        &transaction_key,
+        &proposal,
+    );
+    assert!(process_all(&mut context, &instructions).await.is_err());
+
+    let instructions = instruction::approve_aggregated(
+        &program_id,
+        &group_keypair.pubkey(),
+        &sign(&group_keypair, message.as_ref()),
+        &transaction_key,
+        &proposal,
+    );
+    process_all(&mut context, &instructions).await.unwrap();
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    assert_eq!(proposal.signers, vec![true; 3]);
+    assert_eq!(proposal.status(&multisig), ProposalStatus::Ready);
+}
+
+async fn process_all(
+    context: &mut ProgramTestContext,
+    instructions: &[solana_sdk::instruction::Instruction],
+) -> Result<(), solana_program_test::BanksClientError> {
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await
+}
+
+#[tokio::test]
+async fn test_approve_with_signature() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 2, 0).await;
+
+    let action = ProposalAction::StakeDeactivate {
+        stake: Pubkey::new_unique(),
+    };
+    let transaction_key = propose(&mut context, multisig_key, &owner_keypairs[0], action).await;
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    let owner = &owner_keypairs[1];
+    let digest = proposal.digest(&program_id, &transaction_key);
+    let sign = |keypair: &Keypair, message: &[u8]| -> [u8; 64] {
+        keypair.sign_message(message).as_ref().try_into().unwrap()
+    };
+
+    // the approval alone, without the signature check before it
+    let instructions = instruction::approve_with_signature(
+        &program_id,
+        &owner.pubkey(),
+        &sign(owner, digest.as_ref()),
+        &transaction_key,
+        &proposal,
+    );
+    assert!(process(&mut context, instructions[1].clone(), &[])
+        .await
+        .is_err());
+
+    // a valid signature, by someone who isn't an owner
+    let outsider = Keypair::new();
+    let instructions = instruction::approve_with_signature(
+        &program_id,
+        &outsider.pubkey(),
+        &sign(&outsider, digest.as_ref()),
+        &transaction_key,
+        &proposal,
+    );
+    let err = process_all(&mut context, &instructions)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, custom(MultisigError::OwnerNotFound))
+    );
+
+    // the owner's signature, but for another proposal
+    let other_digest = proposal.digest(&program_id, &Pubkey::new_unique());
+    let instructions = instruction::approve_with_signature(
+        &program_id,
+        &owner.pubkey(),
+        &sign(owner, other_digest.as_ref()),
+        &transaction_key,
+        &proposal,
+    );
+    assert!(process_all(&mut context, &instructions).await.is_err());
+
+    // the relayer (the payer) submits the owner's signature, the owner signs no transaction
+    let instructions = instruction::approve_with_signature(
+        &program_id,
+        &owner.pubkey(),
+        &sign(owner, digest.as_ref()),
+        &transaction_key,
+        &proposal,
+    );
+    process_all(&mut context, &instructions).await.unwrap();
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    assert_eq!(proposal.signers, vec![true, true, false]);
+    assert_eq!(proposal.status(&multisig), ProposalStatus::Ready);
+
+    // replayed later, the signature doesn't renew the approval's age
+    let mut clock: solana_program::clock::Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += 3_600;
+    context.set_sysvar(&clock);
+    next_blockhash(&mut context).await;
+    process_all(&mut context, &instructions).await.unwrap();
+    let replayed = get_proposal(&mut context, transaction_key).await;
+    assert_eq!(replayed.approved_at, proposal.approved_at);
+}
+
+#[tokio::test]
+async fn test_owner_inbox() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let outsider = Keypair::new();
+    for key in [owners[1], outsider.pubkey()] {
+        fund(&mut context, &key, 1_000_000_000).await;
+    }
+    let multisig_key = setup_multisig(&mut context, &owners, 2, 0).await;
+
+    for keypair in [&owner_keypairs[1], &outsider] {
+        let ix = instruction::register_inbox(&program_id, &keypair.pubkey());
+        process(&mut context, ix, &[keypair]).await.unwrap();
+    }
+    let (inbox_key, _) = find_inbox_address(&owners[1], &program_id);
+    let get_inbox = |account: Account| Inbox::try_from_slice(&account.data).unwrap();
+    assert!(!get_inbox(ctx_get_account(&mut context, inbox_key).await).pending);
+
+    let action = ProposalAction::StakeDeactivate {
+        stake: Pubkey::new_unique(),
+    };
+    let space = solana_multisig_wallet::Transaction::space(&action);
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let payer = context.payer.pubkey();
+    let propose_with = |notify: Vec<Pubkey>| {
+        let transaction_keypair = Keypair::new();
+        let instructions = [
+            solana_sdk::system_instruction::create_account(
+                &payer,
+                &transaction_keypair.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &program_id,
+            ),
+            instruction::propose_and_notify(
+                &program_id,
+                &owners[0],
+                &multisig_key,
+                &transaction_keypair.pubkey(),
+                action.clone(),
+                ProposeOptions::default(),
+                &notify,
+            ),
+        ];
+        (instructions, transaction_keypair)
+    };
+
+    // only owners of the multisig can be notified
+    let (instructions, transaction_keypair) = propose_with(vec![outsider.pubkey()]);
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &owner_keypairs[0], &transaction_keypair],
+        recent_blockhash,
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+
+    let (instructions, transaction_keypair) = propose_with(vec![owners[1]]);
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &owner_keypairs[0], &transaction_keypair],
+        recent_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let inbox = get_inbox(ctx_get_account(&mut context, inbox_key).await);
+    assert!(inbox.pending);
+    assert_eq!(inbox.multisig, multisig_key);
+    assert_eq!(inbox.transaction, transaction_keypair.pubkey());
+
+    // nobody but the owner clears it
+    let mut clear = instruction::clear_inbox(&program_id, &owners[1]);
+    clear.accounts[0].pubkey = outsider.pubkey();
+    assert!(process(&mut context, clear, &[&outsider]).await.is_err());
+    let clear = instruction::clear_inbox(&program_id, &owners[1]);
+    process(&mut context, clear, &[&owner_keypairs[1]])
+        .await
+        .unwrap();
+    assert!(!get_inbox(ctx_get_account(&mut context, inbox_key).await).pending);
+}
+
+#[tokio::test]
+async fn test_owner_index() {
+    let program_id = solana_multisig_wallet::id();
+    let mut harness = MultisigHarness::new(2, 2, 0).await;
+    let first = harness.multisig;
+    let owner = harness.owners[0].insecure_clone();
+    fund(&mut harness.context, &owner.pubkey(), 1_000_000_000).await;
+    let (index_key, _) = find_owner_index_address(&owner.pubkey(), &program_id);
+    let listed = |account: Account| load::<OwnerIndex>(&account.data).unwrap().multisigs;
+
+    // only multisigs the owner belongs to can be listed
+    let other_owners = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let unrelated = setup_multisig(&mut harness.context, &other_owners, 1, 0).await;
+    let ix = instruction::register_owner_index(&program_id, &owner.pubkey(), &[first, unrelated]);
+    let err = process(&mut harness.context, ix, &[&owner])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, custom(MultisigError::OwnerNotFound))
+    );
+    let ix = instruction::register_owner_index(&program_id, &owner.pubkey(), &[first]);
+    process(&mut harness.context, ix, &[&owner]).await.unwrap();
+    let account = ctx_get_account(&mut harness.context, index_key).await;
+    assert_eq!(listed(account), vec![first]);
+
+    // `Create` lists the new multisig
+    let rent = harness.context.banks_client.get_rent().await.unwrap();
+    let second = Keypair::new();
+    let owners = vec![owner.pubkey(), Pubkey::new_unique()];
+    let mut create = instruction::create(&program_id, &second.pubkey(), owners, 1);
+    create.accounts.extend(instruction::owner_index_accounts(
+        &program_id,
+        &[owner.pubkey()],
+    ));
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            solana_sdk::system_instruction::create_account(
+                &harness.context.payer.pubkey(),
+                &second.pubkey(),
+                rent.minimum_balance(Multisig::LEN),
+                Multisig::LEN as u64,
+                &program_id,
+            ),
+            create,
+        ],
+        Some(&harness.context.payer.pubkey()),
+        &[&harness.context.payer, &second],
+        harness.context.last_blockhash,
+    );
+    harness
+        .context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+    let account = ctx_get_account(&mut harness.context, index_key).await;
+    assert_eq!(listed(account), vec![first, second.pubkey()]);
+
+    // swapped out of the first one, the replacement's index catches up through a sync
+    let replacement = Keypair::new();
+    fund(&mut harness.context, &replacement.pubkey(), 1_000_000_000).await;
+    let ix = instruction::register_owner_index(&program_id, &replacement.pubkey(), &[]);
+    process(&mut harness.context, ix, &[&replacement])
+        .await
+        .unwrap();
+    harness.sign_as(0).await.unwrap();
+    harness.sign_as(1).await.unwrap();
+    let mut swap = instruction::swap_owner(
+        &program_id,
+        &first,
+        &owner.pubkey(),
+        &replacement.pubkey(),
+        &[],
+    );
+    swap.accounts.extend(instruction::owner_index_accounts(
+        &program_id,
+        &[owner.pubkey()],
+    ));
+    process(&mut harness.context, swap, &[]).await.unwrap();
+    let account = ctx_get_account(&mut harness.context, index_key).await;
+    assert_eq!(listed(account), vec![second.pubkey()]);
+    let (replacement_index, _) = find_owner_index_address(&replacement.pubkey(), &program_id);
+    let account = ctx_get_account(&mut harness.context, replacement_index).await;
+    assert!(listed(account).is_empty());
+    for owner in [replacement.pubkey(), owner.pubkey()] {
+        let ix = instruction::sync_owner_index(&program_id, &owner, &first);
+        process(&mut harness.context, ix, &[]).await.unwrap();
+    }
+    let account = ctx_get_account(&mut harness.context, replacement_index).await;
+    assert_eq!(listed(account), vec![first]);
+    let account = ctx_get_account(&mut harness.context, index_key).await;
+    assert_eq!(listed(account), vec![second.pubkey()]);
+
+    // the owner hides a multisig they didn't ask to be part of
+    let mut unlist = instruction::unlist_multisig(&program_id, &owner.pubkey(), &second.pubkey());
+    unlist.accounts[0].pubkey = replacement.pubkey();
+    assert!(process(&mut harness.context, unlist, &[&replacement])
+        .await
+        .is_err());
+    let unlist = instruction::unlist_multisig(&program_id, &owner.pubkey(), &second.pubkey());
+    process(&mut harness.context, unlist, &[&owner])
+        .await
+        .unwrap();
+    let account = ctx_get_account(&mut harness.context, index_key).await;
+    assert!(listed(account).is_empty());
+}
+
+#[tokio::test]
+async fn test_account_validation() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypair = Keypair::new();
+    let multisig_key =
+        setup_multisig(&mut context, &[owner_keypair.pubkey()], 1, 1_000_000_000).await;
+    let destination = Pubkey::new_unique();
+    fund(&mut context, &destination, 1_000_000).await;
+
+    // each case breaks one account of an otherwise valid instruction
+    let mut read_only_multisig =
+        instruction::sign(&program_id, &owner_keypair.pubkey(), &multisig_key);
+    read_only_multisig.accounts[1].is_writable = false;
+    let mut writable_multisig = instruction::approve(
+        &program_id,
+        &owner_keypair.pubkey(),
+        &multisig_key,
+        &Pubkey::new_unique(),
+        &ProposalAction::StakeDeactivate {
+            stake: Pubkey::new_unique(),
+        },
+    );
+    writable_multisig.accounts[1].is_writable = true;
+    let mut fake_system_program =
+        instruction::execute(&program_id, &multisig_key, 100, &destination);
+    fake_system_program.accounts[2].pubkey = Pubkey::new_unique();
+    let mut foreign_multisig =
+        instruction::sign(&program_id, &owner_keypair.pubkey(), &destination);
+    foreign_multisig.accounts[1].pubkey = destination;
+    let mut missing_account = instruction::execute(&program_id, &multisig_key, 100, &destination);
+    missing_account.accounts.pop();
+    // instructions whose processors take an account context
+    let mut setter_fake_system_program =
+        instruction::set_strict_signing(&program_id, &owner_keypair.pubkey(), &multisig_key, true);
+    setter_fake_system_program.accounts[2].pubkey = Pubkey::new_unique();
+    let mut deposit_missing_account =
+        instruction::deposit(&program_id, &owner_keypair.pubkey(), &multisig_key, 100);
+    deposit_missing_account.accounts.truncate(2);
+
+    let owner: &[&Keypair] = &[&owner_keypair];
+    let cases = [
+        (
+            read_only_multisig,
+            owner,
+            InstructionError::InvalidAccountData,
+        ),
+        (
+            writable_multisig,
+            owner,
+            InstructionError::InvalidAccountData,
+        ),
+        (
+            fake_system_program,
+            &[],
+            InstructionError::IncorrectProgramId,
+        ),
+        (
+            foreign_multisig,
+            owner,
+            InstructionError::IncorrectProgramId,
+        ),
+        (missing_account, &[], InstructionError::NotEnoughAccountKeys),
+        (
+            setter_fake_system_program,
+            owner,
+            InstructionError::IncorrectProgramId,
+        ),
+        (
+            deposit_missing_account,
+            owner,
+            InstructionError::NotEnoughAccountKeys,
+        ),
+    ];
+    for (ix, signers, expected) in cases {
+        let err = process(&mut context, ix, signers)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, TransactionError::InstructionError(0, expected));
+    }
+}
+
+#[tokio::test]
+async fn test_guardian_recovery() {
+    let program_id = solana_multisig_wallet::id();
+    let program_test = program_test();
+    let mut context = program_test.start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let guardian_keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let guardians: Vec<Pubkey> = guardian_keypairs.iter().map(|g| g.pubkey()).collect();
+    for guardian in &guardians {
+        fund(&mut context, guardian, 1_000_000_000).await;
+    }
+
+    // guardians are set up in the creation transaction
+    let multisig_keypair = Keypair::new();
+    let multisig_key = multisig_keypair.pubkey();
+    let space = Multisig::LEN;
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            solana_sdk::system_instruction::create_account(
+                &context.payer.pubkey(),
+                &multisig_key,
+                rent.minimum_balance(space),
+                space as u64,
+                &program_id,
+            ),
+            instruction::create(&program_id, &multisig_key, owners.clone(), 2),
+            instruction::init_guardians(
+                &program_id,
+                &context.payer.pubkey(),
+                &multisig_key,
+                guardians.clone(),
+                3600,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &multisig_keypair],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // a proposal the lost owners approved, must not survive the recovery
+    let action = ProposalAction::StakeDeactivate {
+        stake: Pubkey::new_unique(),
+    };
+    let pending_key = propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[0],
+        action.clone(),
+    )
+    .await;
+
+    let new_owner_keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let new_owners: Vec<Pubkey> = new_owner_keypairs.iter().map(|o| o.pubkey()).collect();
+    let propose_ix = instruction::propose_recovery(
+        &program_id,
+        &guardians[0],
+        &multisig_key,
+        new_owners.clone(),
+        2,
+    );
+    let execute_ix = instruction::execute_recovery(
+        &program_id,
+        &context.payer.pubkey(),
+        &multisig_key,
+        &guardians[0],
+        &[pending_key],
+    );
+
+    // only guardians can start a recovery
+    let outsider = Keypair::new();
+    fund(&mut context, &outsider.pubkey(), 1_000_000_000).await;
+    let result = process(
+        &mut context,
+        instruction::propose_recovery(
+            &program_id,
+            &outsider.pubkey(),
+            &multisig_key,
+            new_owners.clone(),
+            2,
+        ),
+        &[&outsider],
+    )
+    .await;
+    assert!(result.is_err());
+
+    // one of three guardians is not a supermajority
+    process(&mut context, propose_ix.clone(), &[&guardian_keypairs[0]])
+        .await
+        .unwrap();
+    assert!(process(&mut context, execute_ix.clone(), &[])
+        .await
+        .is_err());
+
+    // a single owner vetoes, the proposer gets the rent back
+    process(
+        &mut context,
+        instruction::veto_recovery(&program_id, &owners[1], &multisig_key, &guardians[0]),
+        &[&owner_keypairs[1]],
+    )
+    .await
+    .unwrap();
+    let (recovery_key, _) = find_recovery_address(&multisig_key, &program_id);
+    assert!(context
+        .banks_client
+        .get_account(recovery_key)
+        .await
+        .unwrap()
+        .is_none());
+
+    // second attempt reaches the supermajority, the delay starts
+    next_blockhash(&mut context).await;
+    process(&mut context, propose_ix, &[&guardian_keypairs[0]])
+        .await
+        .unwrap();
+    process(
+        &mut context,
+        instruction::approve_recovery(&program_id, &guardians[2], &multisig_key),
+        &[&guardian_keypairs[2]],
+    )
+    .await
+    .unwrap();
+    next_blockhash(&mut context).await;
+    assert!(process(&mut context, execute_ix.clone(), &[])
+        .await
+        .is_err());
+
+    let mut clock: solana_program::clock::Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += 3600;
+    context.set_sysvar(&clock);
+    next_blockhash(&mut context).await;
+    process(&mut context, execute_ix, &[]).await.unwrap();
+
+    let multisig_account = ctx_get_account(&mut context, multisig_key).await;
+    assert_eq!(multisig_account.data.len(), Multisig::LEN);
+    let multisig = Multisig::try_from_slice(&multisig_account.data).unwrap();
+    assert_eq!(multisig.owners, new_owners);
+    assert_eq!(multisig.threshold, 2);
+    assert_eq!(multisig.signers, vec![false; 3]);
+
+    // the old proposal is dead, the new owners run the wallet
+    let proposal = get_proposal(&mut context, pending_key).await;
+    assert_eq!(proposal.signers, vec![false; 2]);
+    let result = process(
+        &mut context,
+        instruction::approve(
+            &program_id,
+            &new_owners[0],
+            &multisig_key,
+            &pending_key,
+            &action,
+        ),
+        &[&new_owner_keypairs[0]],
+    )
+    .await;
+    assert!(result.is_err());
+    sign_as(&mut context, multisig_key, &new_owner_keypairs[0]).await;
+}
+
+// Creates, extends and deactivates a lookup table of the vault through the multisig, then has the
+// owners approve closing it once the deactivation is out of `SlotHashes`; the table and its rent
+async fn deactivated_lookup_table(harness: &mut MultisigHarness) -> (Pubkey, u64) {
+    let program_id = solana_multisig_wallet::id();
+    let multisig = harness.multisig;
+    let payer = harness.context.payer.pubkey();
+    let (vault, _) = find_vault_address(&multisig, &program_id);
+
+    // the table's slot has to be in `SlotHashes`, so one before the current bank's
+    harness.context.warp_to_slot(10).unwrap();
+    let recent_slot = 9;
+    let (table, _) = lookup_table::find_lookup_table_address(&vault, recent_slot);
+    let create = instruction::create_lookup_table(&program_id, &payer, &multisig, recent_slot);
+    let result = process(&mut harness.context, create.clone(), &[]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::InsufficientSignatures))
+    );
+
+    harness.sign_as(0).await.unwrap();
+    harness.sign_as(1).await.unwrap();
+    process(&mut harness.context, create, &[]).await.unwrap();
+    let account = ctx_get_account(&mut harness.context, table).await;
+    assert_eq!(account.owner, lookup_table::ID);
+    assert_eq!(
+        lookup_table::lookup_table_addresses(&account.data),
+        Some(vec![])
+    );
+    assert_eq!(harness.state().await.signers, vec![false; 2]);
+
+    // every change of the table takes a new round of approvals
+    let addresses = vec![multisig, vault, Pubkey::new_unique()];
+    let extend =
+        instruction::extend_lookup_table(&program_id, &payer, &multisig, &table, addresses.clone());
+    let result = process(&mut harness.context, extend.clone(), &[]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::InsufficientSignatures))
+    );
+    next_blockhash(&mut harness.context).await;
+    harness.sign_as(0).await.unwrap();
+    harness.sign_as(1).await.unwrap();
+    process(&mut harness.context, extend, &[]).await.unwrap();
+    let account = ctx_get_account(&mut harness.context, table).await;
+    assert_eq!(
+        lookup_table::lookup_table_addresses(&account.data),
+        Some(addresses)
+    );
+    let table_rent = account.lamports;
+
+    next_blockhash(&mut harness.context).await;
+    harness.sign_as(0).await.unwrap();
+    harness.sign_as(1).await.unwrap();
+    let deactivate = instruction::deactivate_lookup_table(&program_id, &multisig, &table);
+    process(&mut harness.context, deactivate, &[])
+        .await
+        .unwrap();
+
+    // closing waits for the deactivation to leave `SlotHashes`
+    let close = instruction::close_lookup_table(&program_id, &multisig, &table);
+    next_blockhash(&mut harness.context).await;
+    harness.sign_as(0).await.unwrap();
+    harness.sign_as(1).await.unwrap();
+    let result = process(&mut harness.context, close.clone(), &[]).await;
+    assert!(result.is_err());
+    // `SlotHashes` holds the last 512 slots that had a bank, far more than warping produces
+    harness.context.warp_to_slot(600).unwrap();
+    harness.context.set_sysvar(&SlotHashes::new(&[]));
+    next_blockhash(&mut harness.context).await;
+    // the refused close left the approvals pending, they can't be given twice
+    assert_eq!(harness.state().await.signers, vec![true; 2]);
+    assert_eq!(
+        harness.sign_as(0).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::AlreadySigned))
+    );
+    (table, table_rent)
+}
+
+#[tokio::test]
+async fn test_lookup_table_lifecycle() {
+    let mut harness = MultisigHarness::new(2, 2, 10_000_000).await;
+    deactivated_lookup_table(&mut harness).await;
+}
+
+// Native CPIs of program-test only grow account data, so closing the table panics there
+#[tokio::test]
+#[ignore = "needs the compiled program, run `cargo build-sbf` first"]
+async fn test_close_lookup_table() {
+    assert!(
+        compiled_program().is_some(),
+        "solana_multisig_wallet.so not found, run `cargo build-sbf`"
+    );
+    let program_id = solana_multisig_wallet::id();
+    let mut program_test = ProgramTest::new("solana_multisig_wallet", program_id, None);
+    program_test.prefer_bpf(true);
+    let mut harness = MultisigHarness::start(program_test, 2, 2, 10_000_000).await;
+    let (table, table_rent) = deactivated_lookup_table(&mut harness).await;
+    let (vault, _) = find_vault_address(&harness.multisig, &program_id);
+
+    let vault_before = harness.balance(&vault).await;
+    let close = instruction::close_lookup_table(&program_id, &harness.multisig, &table);
+    process(&mut harness.context, close, &[]).await.unwrap();
+    assert!(harness
+        .context
+        .banks_client
+        .get_account(table)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(harness.balance(&vault).await, vault_before + table_rent);
+}
+
+#[tokio::test]
+async fn test_migrate_to_vault() {
+    let program_id = solana_multisig_wallet::id();
+    let mut harness = MultisigHarness::new(2, 2, 100_000_000).await;
+    let multisig = harness.multisig;
+    let (vault, _) = find_vault_address(&multisig, &program_id);
+    let destination = Pubkey::new_unique();
+    let rent = harness.balance(&multisig).await - 100_000_000;
+
+    // anyone can move the funds, they stay the owners'
+    let ix = instruction::migrate_to_vault(&program_id, &multisig);
+    process(&mut harness.context, ix, &[]).await.unwrap();
+    assert_eq!(harness.balance(&multisig).await, rent);
+    assert_eq!(harness.balance(&vault).await, 100_000_000);
+
+    let ix = instruction::execute_from_vault(&program_id, &multisig, 10_000_000, &destination);
+    let result = process(&mut harness.context, ix.clone(), &[]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::InsufficientSignatures))
+    );
+    harness.sign_as(0).await.unwrap();
+    harness.sign_as(1).await.unwrap();
+    let result = harness.execute(10_000_000, &destination).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::InsufficientBalance))
+    );
+    let too_much =
+        instruction::execute_from_vault(&program_id, &multisig, 100_000_001, &destination);
+    let result = process(&mut harness.context, too_much, &[]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::InsufficientBalance))
+    );
+
+    process(&mut harness.context, ix, &[]).await.unwrap();
+    assert_eq!(harness.balance(&destination).await, 10_000_000);
+    assert_eq!(harness.balance(&vault).await, 90_000_000);
+    assert_eq!(harness.balance(&multisig).await, rent);
+    assert_eq!(harness.state().await.signers, vec![false; 2]);
+}
+
+#[tokio::test]
+async fn test_execution_strategies() {
+    let program_id = solana_multisig_wallet::id();
+    let mut harness = MultisigHarness::new(2, 2, 100_000_000).await;
+    let multisig = harness.multisig;
+    let (vault, _) = find_vault_address(&multisig, &program_id);
+    fund(&mut harness.context, &vault, 100_000_000).await;
+    let destination = Pubkey::new_unique();
+
+    // the same payout, debited from the multisig account or moved out of the vault by the
+    // System Program
+    for (strategy, source) in [
+        (ExecutionStrategy::DirectLamports, multisig),
+        (ExecutionStrategy::SystemTransfer, vault),
+    ] {
+        let ix = instruction::execute_with_strategy(
+            &program_id,
+            &multisig,
+            10_000_000,
+            &destination,
+            strategy,
+        );
+        let result = process(&mut harness.context, ix.clone(), &[]).await;
+        assert_eq!(
+            result.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, custom(MultisigError::InsufficientSignatures))
+        );
+        harness.sign_as(0).await.unwrap();
+        harness.sign_as(1).await.unwrap();
+        let source_before = harness.balance(&source).await;
+        let destination_before = harness.balance(&destination).await;
+        process(&mut harness.context, ix, &[]).await.unwrap();
+        assert_eq!(harness.balance(&source).await, source_before - 10_000_000);
+        assert_eq!(
+            harness.balance(&destination).await,
+            destination_before + 10_000_000
+        );
+        assert_eq!(harness.state().await.signers, vec![false; 2]);
+    }
+    assert_eq!(harness.balance(&vault).await, 90_000_000);
+
+    // only the direct path guards the multisig account's rent
+    harness.sign_as(0).await.unwrap();
+    harness.sign_as(1).await.unwrap();
+    let result = harness.execute(90_000_001, &destination).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, custom(MultisigError::BelowRentFloor))
+    );
+    let empty_vault = instruction::execute_with_strategy(
+        &program_id,
+        &multisig,
+        90_000_000,
+        &destination,
+        ExecutionStrategy::SystemTransfer,
+    );
+    process(&mut harness.context, empty_vault, &[])
+        .await
+        .unwrap();
+    assert_eq!(harness.balance(&vault).await, 0);
+}
+
+#[tokio::test]
+async fn test_executor_fee() {
+    let program_id = solana_multisig_wallet::id();
+    let mut context = program_test().start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 2, 0).await;
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+    fund(&mut context, &vault_key, 1_000_000_000).await;
+
+    let action = ProposalAction::CreateRecurringPayment {
+        destination: Pubkey::new_unique(),
+        amount: 1_000,
+        interval_seconds: 60,
+        count: 1,
+    };
+    let options = ProposeOptions {
+        executor_fee: 5_000_000,
+        ..ProposeOptions::default()
+    };
+    let transaction_key = propose_with_options(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[0],
+        action.clone(),
+        options,
+    )
+    .await;
+    assert_eq!(
+        get_proposal(&mut context, transaction_key)
+            .await
+            .executor_fee,
+        5_000_000
+    );
+
+    // a keeper outside the owner set, the approvals still decide
+    let keeper = Keypair::new();
+    let crank = instruction::crank_transaction(
+        &program_id,
+        &keeper.pubkey(),
+        &multisig_key,
+        &transaction_key,
+        0,
+        &action,
+    );
+    let err = process(&mut context, crank.clone(), &[&keeper])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, custom(MultisigError::InsufficientSignatures))
+    );
+
+    approve_as(
+        &mut context,
+        multisig_key,
+        transaction_key,
+        &action,
+        &owner_keypairs[1],
+    )
+    .await;
+    let vault_before = ctx_get_account(&mut context, vault_key).await.lamports;
+    next_blockhash(&mut context).await;
+    process(&mut context, crank, &[&keeper]).await.unwrap();
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    assert!(proposal.executed);
+    assert_eq!(proposal.executed_by, Some(keeper.pubkey()));
+    let clock = context
+        .banks_client
+        .get_sysvar::<solana_program::clock::Clock>()
+        .await
+        .unwrap();
+    assert_eq!(
+        (proposal.executed_slot, proposal.executed_at),
+        (clock.slot, clock.unix_timestamp)
+    );
+    let keeper_balance = context
+        .banks_client
+        .get_balance(keeper.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(keeper_balance, 5_000_000);
+    let recurring_rent = context
+        .banks_client
+        .get_rent()
+        .await
+        .unwrap()
+        .minimum_balance(RecurringPayment::SPACE);
+    let vault_after = ctx_get_account(&mut context, vault_key).await.lamports;
+    assert_eq!(vault_before - vault_after, 5_000_000 + recurring_rent);
+}
+
+#[tokio::test]
+async fn test_attachment() {
+    let program_id = solana_multisig_wallet::id();
+    let mut context = program_test().start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 1, 0).await;
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+    fund(&mut context, &vault_key, 1_000_000_000).await;
+
+    let action = ProposalAction::CreateRecurringPayment {
+        destination: Pubkey::new_unique(),
+        amount: 1_000,
+        interval_seconds: 60,
+        count: 1,
+    };
+    let attachment = solana_program::hash::hash(b"invoice #42").to_bytes();
+    let options = ProposeOptions {
+        attachment: Some(attachment),
+        ..ProposeOptions::default()
+    };
+    let transaction_key = propose_with_options(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[0],
+        action.clone(),
+        options,
+    )
+    .await;
+    let proposal = get_proposal(&mut context, transaction_key).await;
+    assert_eq!(proposal.attachment, Some(attachment));
+
+    // executing logs the hash of the document the owners approved
+    let execute =
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &action);
+    next_blockhash(&mut context).await;
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[execute],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    let result = context
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await
+        .unwrap();
+    assert_eq!(result.result, Ok(()));
+    let expected = format!(
+        "Program log: Attachment: {}",
+        proposal.attachment_hex().unwrap()
+    );
+    assert!(result.metadata.unwrap().log_messages.contains(&expected));
+}
+
+#[tokio::test]
+async fn test_batch_proposal() {
+    let program_id = solana_multisig_wallet::id();
+    let mut context = program_test().start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 2, 0).await;
+    let (vault_key, _) = find_vault_address(&multisig_key, &program_id);
+    fund(&mut context, &vault_key, 1_000_000_000).await;
+
+    // an empty batch, or one calling back into the multisig program, isn't proposable
+    let reentrant = instruction::sign(&program_id, &owners[0], &multisig_key);
+    for instructions in [vec![], vec![reentrant.into()]] {
+        let err = try_propose(
+            &mut context,
+            multisig_key,
+            &owner_keypairs[0],
+            &Keypair::new(),
+            ProposalAction::Batch { instructions },
+            ProposeOptions::default(),
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(1, custom(MultisigError::InvalidAction))
+        );
+    }
+
+    let destinations = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let action = ProposalAction::Batch {
+        instructions: vec![
+            solana_sdk::system_instruction::transfer(&vault_key, &destinations[0], 1_000_000)
+                .into(),
+            solana_sdk::system_instruction::transfer(&vault_key, &destinations[1], 2_000_000)
+                .into(),
+        ],
+    };
+    // the plain transfers count toward the amount policy
+    assert_eq!(action.lamports(), 3_000_000);
+    let transaction_key = propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[0],
+        action.clone(),
+    )
+    .await;
+    approve_as(
+        &mut context,
+        multisig_key,
+        transaction_key,
+        &action,
+        &owner_keypairs[1],
+    )
+    .await;
+
+    // each instruction's accounts are checked before anything runs
+    let execute =
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &action);
+    let mut incomplete = execute.clone();
+    incomplete
+        .accounts
+        .retain(|meta| meta.pubkey != destinations[1]);
+    let err = process(&mut context, incomplete, &[])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, custom(MultisigError::AccountMismatch))
+    );
+
+    let vault_before = ctx_get_account(&mut context, vault_key).await.lamports;
+    process(&mut context, execute, &[]).await.unwrap();
+    assert!(get_proposal(&mut context, transaction_key).await.executed);
+    for (destination, lamports) in destinations.iter().zip([1_000_000, 2_000_000]) {
+        let account = ctx_get_account(&mut context, *destination).await;
+        assert_eq!(account.lamports, lamports);
+    }
+    let vault_after = ctx_get_account(&mut context, vault_key).await.lamports;
+    assert_eq!(vault_before - vault_after, 3_000_000);
+}
+
+#[tokio::test]
+async fn test_config_change_proposal() {
+    let program_id = solana_multisig_wallet::id();
+    let mut context = program_test().start_with_context().await;
+
+    let owner_keypairs = [Keypair::new(), Keypair::new()];
+    let owners: Vec<Pubkey> = owner_keypairs.iter().map(|owner| owner.pubkey()).collect();
+    let multisig_key = setup_multisig(&mut context, &owners, 2, 0).await;
+    let other_multisig = setup_multisig(&mut context, &owners, 2, 0).await;
+    let new_owner = Pubkey::new_unique();
+
+    // only the multisig's own threshold and owners can be changed through a batch
+    let foreign = instruction::change_threshold(&program_id, &other_multisig, 1);
+    let err = try_propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[0],
+        &Keypair::new(),
+        ProposalAction::Batch {
+            instructions: vec![foreign.into()],
+        },
+        ProposeOptions::default(),
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, custom(MultisigError::InvalidAction))
+    );
+
+    // nobody but the vault can authorize the change
+    let mut unsigned = instruction::change_threshold(&program_id, &multisig_key, 1);
+    unsigned.accounts[1].is_signer = false;
+    let err = process(&mut context, unsigned, &[])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    let action = ProposalAction::Batch {
+        instructions: vec![
+            instruction::add_owner(&program_id, &multisig_key, &new_owner).into(),
+            instruction::change_threshold(&program_id, &multisig_key, 3).into(),
+        ],
+    };
+    let transaction_key = propose(
+        &mut context,
+        multisig_key,
+        &owner_keypairs[0],
+        action.clone(),
+    )
+    .await;
+    assert_eq!(
+        instruction::propose_add_owner(
+            &program_id,
+            &owners[0],
+            &multisig_key,
+            &transaction_key,
+            &new_owner
+        ),
+        instruction::propose(
+            &program_id,
+            &owners[0],
+            &multisig_key,
+            &transaction_key,
+            ProposalAction::Batch {
+                instructions: vec![
+                    instruction::add_owner(&program_id, &multisig_key, &new_owner).into()
+                ],
+            }
+        )
+    );
+    let execute =
+        instruction::execute_transaction(&program_id, &multisig_key, &transaction_key, &action);
+    assert!(process(&mut context, execute.clone(), &[]).await.is_err());
+    approve_as(
+        &mut context,
+        multisig_key,
+        transaction_key,
+        &action,
+        &owner_keypairs[1],
+    )
+    .await;
+    process(&mut context, execute, &[]).await.unwrap();
+
+    let account = ctx_get_account(&mut context, multisig_key).await;
+    let multisig = load::<Multisig>(&account.data).unwrap();
+    assert_eq!(multisig.owners, [owners[0], owners[1], new_owner]);
+    assert_eq!(multisig.threshold, 3);
+    // the proposals made under the old owner set went stale
+    assert_eq!(multisig.owner_set_seqno, 2);
+    assert!(get_proposal(&mut context, transaction_key).await.executed);
+}
+
+async fn approve_as(
+    context: &mut ProgramTestContext,
+    multisig_key: Pubkey,
+    transaction_key: Pubkey,
+    action: &ProposalAction,
+    owner: &Keypair,
+) {
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::approve(
+            &solana_multisig_wallet::id(),
+            &owner.pubkey(),
+            &multisig_key,
+            &transaction_key,
+            action,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, owner],
+        recent_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+}
+
+async fn get_proposal(
+    context: &mut ProgramTestContext,
+    transaction_key: Pubkey,
+) -> solana_multisig_wallet::Transaction {
+    let account = ctx_get_account(context, transaction_key).await;
+    solana_multisig_wallet::Transaction::try_from_slice(&account.data).unwrap()
+}
+
+// Creates a proposal account for `action`, returns its address
+async fn propose(
+    context: &mut ProgramTestContext,
+    multisig_key: Pubkey,
+    proposer: &Keypair,
+    action: ProposalAction,
+) -> Pubkey {
+    propose_from_vault(context, multisig_key, proposer, 0, action).await
+}
+
+async fn propose_from_vault(
+    context: &mut ProgramTestContext,
+    multisig_key: Pubkey,
+    proposer: &Keypair,
+    vault: u8,
+    action: ProposalAction,
+) -> Pubkey {
+    let options = ProposeOptions {
+        vault,
+        ..ProposeOptions::default()
+    };
+    propose_with_options(context, multisig_key, proposer, action, options).await
+}
+
+async fn propose_with_options(
+    context: &mut ProgramTestContext,
+    multisig_key: Pubkey,
+    proposer: &Keypair,
+    action: ProposalAction,
+    options: ProposeOptions,
+) -> Pubkey {
+    let transaction_keypair = Keypair::new();
+    try_propose(
+        context,
+        multisig_key,
+        proposer,
+        &transaction_keypair,
+        action,
+        options,
+    )
+    .await
+    .unwrap();
+    transaction_keypair.pubkey()
+}
+
+// Creates the proposal account `transaction_keypair` and proposes `action` in it
+async fn try_propose(
+    context: &mut ProgramTestContext,
+    multisig_key: Pubkey,
+    proposer: &Keypair,
+    transaction_keypair: &Keypair,
+    action: ProposalAction,
+    options: ProposeOptions,
+) -> Result<(), solana_program_test::BanksClientError> {
+    let program_id = solana_multisig_wallet::id();
+    let space = solana_multisig_wallet::Transaction::space(&action);
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            solana_sdk::system_instruction::create_account(
+                &context.payer.pubkey(),
+                &transaction_keypair.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &program_id,
+            ),
+            instruction::propose_with_options(
+                &program_id,
+                &proposer.pubkey(),
+                &multisig_key,
+                &transaction_keypair.pubkey(),
+                action,
+                options,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, proposer, transaction_keypair],
+        recent_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
@@ -0,0 +1,354 @@
+// The program-wide `ProgramConfig` of a hosted deployment: created by the upgrade authority, then
+// changed by its governance multisig's proposals, which can pause the payouts of every wallet and
+// exempt wallets from the protocol fee
+use multisig_test_utils::{
+    next_blockhash, process, program_test, setup_multisig, sign_as, MultisigHarness,
+};
+use solana_multisig_wallet::instruction::{self, MultisigInstruction};
+use solana_multisig_wallet::state::load;
+use solana_multisig_wallet::{
+    find_program_config_address, find_vault_address, MultisigError, ProgramConfig,
+    Transaction as Proposal,
+};
+use solana_program::bpf_loader_upgradeable;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{BanksClientError, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::TransactionError;
+
+// A harness whose multisig is to govern the config, with the programdata of an upgradeable
+// deployment (`ProgramData { slot, authority }`) naming the returned upgrade authority
+async fn start() -> (MultisigHarness, Keypair) {
+    let upgrade_authority = Keypair::new();
+    let mut program_test = program_test();
+    let (programdata, _) = Pubkey::find_program_address(
+        &[solana_multisig_wallet::id().as_ref()],
+        &bpf_loader_upgradeable::id(),
+    );
+    let mut data = vec![3, 0, 0, 0];
+    data.extend(0u64.to_le_bytes());
+    data.push(1);
+    data.extend(upgrade_authority.pubkey().to_bytes());
+    program_test.add_account(
+        programdata,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: bpf_loader_upgradeable::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let harness = MultisigHarness::start(program_test, 2, 2, 0).await;
+    (harness, upgrade_authority)
+}
+
+fn init_program_config(
+    harness: &MultisigHarness,
+    upgrade_authority: &Pubkey,
+    fee_destination: &Pubkey,
+    protocol_fee_bps: u16,
+) -> Instruction {
+    instruction::init_program_config(
+        &solana_multisig_wallet::id(),
+        &harness.context.payer.pubkey(),
+        upgrade_authority,
+        &harness.multisig,
+        fee_destination,
+        protocol_fee_bps,
+    )
+}
+
+#[tokio::test]
+async fn test_program_config() {
+    let program_id = solana_multisig_wallet::id();
+    let (mut harness, upgrade_authority) = start().await;
+    let governance = harness.multisig;
+    let fee_destination = Pubkey::new_unique();
+
+    // only the upgrade authority creates the config
+    let impostor = Keypair::new();
+    let init = init_program_config(&harness, &impostor.pubkey(), &fee_destination, 0);
+    let err = process(&mut harness.context, init, &[&impostor])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    let init = init_program_config(&harness, &upgrade_authority.pubkey(), &fee_destination, 0);
+    process(&mut harness.context, init, &[&upgrade_authority])
+        .await
+        .unwrap();
+    let (config_key, _) = find_program_config_address(&program_id);
+    let config = read_config(&mut harness, config_key).await;
+    assert_eq!(config.governance, governance);
+    assert!(!config.paused);
+
+    // a wallet of the deployment pays out while the program runs
+    let owner = Keypair::new();
+    let wallet = setup_multisig(&mut harness.context, &[owner.pubkey()], 1, 1_000_000_000).await;
+    let destination = Pubkey::new_unique();
+    let payout = || {
+        instruction::with_program_config(instruction::execute(
+            &program_id,
+            &wallet,
+            100_000_000,
+            &destination,
+        ))
+    };
+    sign_as(&mut harness.context, wallet, &owner).await;
+    process(&mut harness.context, payout(), &[]).await.unwrap();
+
+    // the governance pauses every wallet through a proposal
+    let proposer = harness.owners[0].pubkey();
+    let propose = |transaction: &Keypair, config: &ProgramConfig, paused| {
+        instruction::propose_program_paused(
+            &program_id,
+            &proposer,
+            &transaction.pubkey(),
+            config,
+            paused,
+        )
+    };
+    let transaction_keypair = Keypair::new();
+    let proposal = propose(&transaction_keypair, &config, true);
+    execute_governance_proposal(&mut harness, &transaction_keypair, proposal).await;
+    assert!(read_config(&mut harness, config_key).await.paused);
+
+    sign_as(&mut harness.context, wallet, &owner).await;
+    let err = process(&mut harness.context, payout(), &[])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MultisigError::Paused as u32)
+        )
+    );
+    // leaving the config out only works where the deployment doesn't require it
+    let result = process(
+        &mut harness.context,
+        instruction::execute(&program_id, &wallet, 100_000_000, &destination),
+        &[],
+    )
+    .await;
+    assert_eq!(result.is_ok(), !cfg!(feature = "program-config"));
+
+    // the governance's own proposals still execute, unpausing the program
+    let transaction_keypair = Keypair::new();
+    let config = read_config(&mut harness, config_key).await;
+    let proposal = propose(&transaction_keypair, &config, false);
+    execute_governance_proposal(&mut harness, &transaction_keypair, proposal).await;
+    assert!(!read_config(&mut harness, config_key).await.paused);
+    sign_as(&mut harness.context, wallet, &owner).await;
+    process(&mut harness.context, payout(), &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_protocol_fee() {
+    let program_id = solana_multisig_wallet::id();
+    let (mut harness, upgrade_authority) = start().await;
+    let fee_destination = Pubkey::new_unique();
+    // 0.3%
+    let init = init_program_config(&harness, &upgrade_authority.pubkey(), &fee_destination, 30);
+    process(&mut harness.context, init, &[&upgrade_authority])
+        .await
+        .unwrap();
+    let (config_key, _) = find_program_config_address(&program_id);
+    let config = read_config(&mut harness, config_key).await;
+    assert_eq!(config.protocol_fee(1_000_000_000), 3_000_000);
+
+    let owner = Keypair::new();
+    let wallet = setup_multisig(&mut harness.context, &[owner.pubkey()], 1, 5_000_000_000).await;
+    let (vault, _) = find_vault_address(&wallet, &program_id);
+    let payer = harness.context.payer.pubkey();
+    process(
+        &mut harness.context,
+        system_instruction::transfer(&payer, &vault, 5_000_000_000),
+        &[],
+    )
+    .await
+    .unwrap();
+    let destination = Pubkey::new_unique();
+
+    // the fee destination has to come along
+    sign_as(&mut harness.context, wallet, &owner).await;
+    let execute = instruction::execute(&program_id, &wallet, 1_000_000_000, &destination);
+    let err = process(
+        &mut harness.context,
+        instruction::with_program_config(execute.clone()),
+        &[],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MultisigError::AccountMismatch as u32)
+        )
+    );
+
+    // out of the multisig account and out of the vault, the destination gets the rest. The
+    // refused payout left its approval pending for the first
+    let execute_from_vault =
+        instruction::execute_from_vault(&program_id, &wallet, 1_000_000_000, &destination);
+    for (index, payout) in [execute, execute_from_vault].into_iter().enumerate() {
+        if index > 0 {
+            next_blockhash(&mut harness.context).await;
+            sign_as(&mut harness.context, wallet, &owner).await;
+        }
+        let payout = instruction::with_protocol_fee(payout, &config);
+        process(&mut harness.context, payout, &[]).await.unwrap();
+    }
+    assert_eq!(harness.balance(&destination).await, 2 * 997_000_000);
+    assert_eq!(harness.balance(&fee_destination).await, 2 * 3_000_000);
+
+    // the governance exempts the wallet
+    let transaction_keypair = Keypair::new();
+    let proposal = instruction::propose_fee_exempt(
+        &program_id,
+        &harness.owners[0].pubkey(),
+        &transaction_keypair.pubkey(),
+        &config,
+        &wallet,
+        true,
+    );
+    execute_governance_proposal(&mut harness, &transaction_keypair, proposal).await;
+    next_blockhash(&mut harness.context).await;
+    sign_as(&mut harness.context, wallet, &owner).await;
+    let payout = instruction::with_protocol_fee(
+        instruction::execute_from_vault(&program_id, &wallet, 1_000_000_000, &destination),
+        &config,
+    );
+    process(&mut harness.context, payout, &[]).await.unwrap();
+    assert_eq!(
+        harness.balance(&destination).await,
+        2 * 997_000_000 + 1_000_000_000
+    );
+    assert_eq!(harness.balance(&fee_destination).await, 2 * 3_000_000);
+
+    // only the governance decides who is exempt, not the wallet itself
+    let transaction_keypair = Keypair::new();
+    let proposal = instruction::propose_config_change(
+        &program_id,
+        &owner.pubkey(),
+        &wallet,
+        &transaction_keypair.pubkey(),
+        vec![instruction::set_fee_exempt(
+            &program_id,
+            &wallet,
+            &wallet,
+            false,
+        )],
+    );
+    let err = execute_proposal(
+        &mut harness.context,
+        wallet,
+        &[owner],
+        &transaction_keypair,
+        proposal,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MultisigError::WrongMultisig as u32)
+        )
+    );
+}
+
+// Creates the proposal account `transaction_keypair` and sends `propose` (a proposal for it by the
+// first of `owners`), then executes it with the approvals of the others
+async fn execute_proposal(
+    context: &mut ProgramTestContext,
+    multisig: Pubkey,
+    owners: &[Keypair],
+    transaction_keypair: &Keypair,
+    propose: Instruction,
+) -> Result<(), BanksClientError> {
+    let program_id = solana_multisig_wallet::id();
+    let transaction_key = transaction_keypair.pubkey();
+    let MultisigInstruction::Propose { action, .. } =
+        MultisigInstruction::decode(&propose.data).unwrap()
+    else {
+        panic!("not a proposal");
+    };
+    let space = Proposal::space(&action);
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let create = system_instruction::create_account(
+        &context.payer.pubkey(),
+        &transaction_key,
+        rent.minimum_balance(space),
+        space as u64,
+        &program_id,
+    );
+    process(context, create, &[transaction_keypair])
+        .await
+        .unwrap();
+    process(context, propose, &[&owners[0]]).await.unwrap();
+    for approver in &owners[1..] {
+        let approve = instruction::approve(
+            &program_id,
+            &approver.pubkey(),
+            &multisig,
+            &transaction_key,
+            &action,
+        );
+        process(context, approve, &[approver]).await.unwrap();
+    }
+    // the config is both an account of the proposal and the one the execution is checked against
+    let execute = instruction::with_program_config(instruction::execute_transaction(
+        &program_id,
+        &multisig,
+        &transaction_key,
+        &action,
+    ));
+    process(context, execute, &[]).await
+}
+
+// `execute_proposal` of the governance multisig, the harness's
+async fn execute_governance_proposal(
+    harness: &mut MultisigHarness,
+    transaction_keypair: &Keypair,
+    propose: Instruction,
+) {
+    let owners: Vec<Keypair> = harness
+        .owners
+        .iter()
+        .map(|owner| owner.insecure_clone())
+        .collect();
+    execute_proposal(
+        &mut harness.context,
+        harness.multisig,
+        &owners,
+        transaction_keypair,
+        propose,
+    )
+    .await
+    .unwrap();
+}
+
+async fn read_config(harness: &mut MultisigHarness, config_key: Pubkey) -> ProgramConfig {
+    let account = harness
+        .context
+        .banks_client
+        .get_account(config_key)
+        .await
+        .unwrap()
+        .unwrap();
+    load::<ProgramConfig>(&account.data).unwrap()
+}
@@ -0,0 +1,80 @@
+// The program deployed under a key other than its `declare_id!` one: refused unless built with
+// `custom-program-id`, in which case every address is derived from the key it runs under
+use multisig_test_utils::process;
+use solana_multisig_wallet::instruction::{self, ProposeOptions};
+use solana_multisig_wallet::{
+    find_transaction_address, find_vault_address, process_instruction, ProposalAction,
+};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::TransactionError;
+
+#[tokio::test]
+async fn test_fork_program_id() {
+    let program_id = Pubkey::new_unique();
+    let program_test = ProgramTest::new(
+        "solana-multisig-wallet",
+        program_id,
+        processor!(process_instruction),
+    );
+    let mut context = program_test.start_with_context().await;
+    let owner = Keypair::new();
+    let multisig_keypair = Keypair::new();
+    let multisig = multisig_keypair.pubkey();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let payer = context.payer.pubkey();
+    let create_account = system_instruction::create_account(
+        &payer,
+        &multisig,
+        rent.minimum_balance(solana_multisig_wallet::Multisig::LEN),
+        solana_multisig_wallet::Multisig::LEN as u64,
+        &program_id,
+    );
+    process(&mut context, create_account, &[&multisig_keypair])
+        .await
+        .unwrap();
+
+    let create = instruction::create(&program_id, &multisig, vec![owner.pubkey()], 1);
+    let result = process(&mut context, create, &[]).await;
+    if !cfg!(feature = "custom-program-id") {
+        assert_eq!(
+            result.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+        );
+        return;
+    }
+    result.unwrap();
+
+    // the vault and the proposal PDA belong to the fork
+    let (vault, _) = find_vault_address(&multisig, &program_id);
+    process(
+        &mut context,
+        system_instruction::transfer(&payer, &owner.pubkey(), 1_000_000_000),
+        &[],
+    )
+    .await
+    .unwrap();
+    let propose = instruction::propose_indexed(
+        &program_id,
+        &owner.pubkey(),
+        &multisig,
+        0,
+        ProposalAction::Batch {
+            instructions: vec![system_instruction::transfer(&vault, &owner.pubkey(), 1).into()],
+        },
+        ProposeOptions::default(),
+    );
+    process(&mut context, propose, &[&owner]).await.unwrap();
+    let (transaction, _) = find_transaction_address(&multisig, 0, &program_id);
+    let account = context
+        .banks_client
+        .get_account(transaction)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, program_id);
+}
@@ -0,0 +1,219 @@
+// Property tests: arbitrary instruction data, and random Create/Sign/Execute sequences run against
+// the program and a model of it
+use borsh::{BorshDeserialize, BorshSerialize};
+use multisig_test_utils::{fund, next_blockhash, process, program_test};
+use proptest::prelude::*;
+use solana_multisig_wallet::instruction::PACKED_FORMAT_TAG;
+use solana_multisig_wallet::processor::process_instruction;
+use solana_multisig_wallet::{instruction, Multisig, MultisigInstruction};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use std::collections::HashSet;
+
+// Instruction data: random bytes, or a random tail behind a valid Borsh variant index or the packed tag
+fn instruction_data() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        prop::collection::vec(any::<u8>(), 0..200),
+        (0..40u8, prop::collection::vec(any::<u8>(), 0..200))
+            .prop_map(|(variant, tail)| { [vec![variant], tail].concat() }),
+        prop::collection::vec(any::<u8>(), 0..12)
+            .prop_map(|tail| [vec![PACKED_FORMAT_TAG], tail].concat()),
+    ]
+}
+
+proptest! {
+    // whatever the data, decoding and account checks reject it with an error rather than a panic
+    #[test]
+    fn test_arbitrary_instruction_data(data in instruction_data()) {
+        prop_assert!(process_instruction(&solana_multisig_wallet::id(), &[], &data).is_err());
+    }
+
+    // Borsh decoding is exact: what decodes encodes back to the same bytes
+    #[test]
+    fn test_instruction_round_trip(data in instruction_data()) {
+        if data.first() != Some(&PACKED_FORMAT_TAG) {
+            if let Ok(instruction) = MultisigInstruction::try_from_slice(&data) {
+                prop_assert_eq!(instruction.try_to_vec().unwrap(), data);
+            }
+        }
+    }
+
+    // `decode` ignores trailing bytes: what it accepts encodes back to a prefix of the data
+    #[test]
+    fn test_decode_ignores_trailing_bytes(data in instruction_data()) {
+        if let Ok(instruction) = MultisigInstruction::decode(&data) {
+            prop_assert!(data.starts_with(&instruction.try_to_vec().unwrap()));
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    // `Sign` by the owner at this index, by a key that isn't an owner past the last one
+    Sign(usize),
+    Execute(u64),
+}
+
+const EXTRA_LAMPORTS: u64 = 1_000_000;
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => (0..5usize).prop_map(Op::Sign),
+        1 => (0..EXTRA_LAMPORTS / 2).prop_map(Op::Execute),
+    ]
+}
+
+// What the program should do: which owners approved, and the lamports the multisig can pay out
+struct Model {
+    threshold: usize,
+    signed: HashSet<usize>,
+    available: u64,
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(24))]
+
+    // lamports only leave with at least `threshold` distinct owners approving, and the signer
+    // flags always line up with the owners
+    #[test]
+    fn test_operation_sequences(
+        owner_count in 1..5usize,
+        threshold in 0..6u8,
+        ops in prop::collection::vec(op(), 1..12),
+    ) {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(run_sequence(owner_count, threshold, ops))?;
+    }
+}
+
+async fn run_sequence(
+    owner_count: usize,
+    threshold: u8,
+    ops: Vec<Op>,
+) -> Result<(), TestCaseError> {
+    let program_id = solana_multisig_wallet::id();
+    let mut context = program_test().start_with_context().await;
+    let owners: Vec<Keypair> = (0..owner_count).map(|_| Keypair::new()).collect();
+    let stranger = Keypair::new();
+    let destination = Pubkey::new_unique();
+    fund(&mut context, &destination, 1_000_000_000).await;
+
+    let multisig = Keypair::new();
+    let keys = owners.iter().map(|owner| owner.pubkey()).collect();
+    let created = create(&mut context, &multisig, keys, threshold).await;
+    prop_assert_eq!(
+        created,
+        threshold >= 1 && threshold as usize <= owner_count,
+        "Create with {} owners and threshold {}",
+        owner_count,
+        threshold
+    );
+    if !created {
+        return Ok(());
+    }
+    let multisig = multisig.pubkey();
+    let mut model = Model {
+        threshold: threshold as usize,
+        signed: HashSet::new(),
+        available: EXTRA_LAMPORTS,
+    };
+
+    // a repeated instruction is a repeated transaction until the blockhash moves on
+    let mut sent = HashSet::new();
+    for op in ops {
+        let (ix, signers) = match op {
+            Op::Sign(index) => {
+                let signer = owners.get(index).unwrap_or(&stranger);
+                let ix = instruction::sign(&program_id, &signer.pubkey(), &multisig);
+                (ix, vec![signer])
+            }
+            Op::Execute(amount) => {
+                let ix = instruction::execute(&program_id, &multisig, amount, &destination);
+                (ix, vec![])
+            }
+        };
+        if !sent.insert((ix.data.clone(), ix.accounts[0].pubkey)) {
+            next_blockhash(&mut context).await;
+            sent.clear();
+            sent.insert((ix.data.clone(), ix.accounts[0].pubkey));
+        }
+        let result = process(&mut context, ix, &signers).await;
+        match op {
+            Op::Sign(index) => {
+                // owners that already approved are refused
+                let allowed = index < owner_count && !model.signed.contains(&index);
+                prop_assert_eq!(result.is_ok(), allowed);
+                if allowed {
+                    model.signed.insert(index);
+                }
+            }
+            Op::Execute(amount) => {
+                let allowed = model.signed.len() >= model.threshold && amount <= model.available;
+                prop_assert_eq!(result.is_ok(), allowed, "Execute of {}", amount);
+                if allowed {
+                    model.signed.clear();
+                    model.available -= amount;
+                }
+            }
+        }
+
+        let lamports = balance(&mut context, &multisig).await;
+        prop_assert_eq!(lamports, rent(&mut context).await + model.available);
+        let state = state(&mut context, &multisig).await;
+        prop_assert_eq!(state.signers.len(), state.owners.len());
+        let signed: HashSet<usize> = (0..owner_count).filter(|&i| state.signers[i]).collect();
+        prop_assert_eq!(&signed, &model.signed);
+    }
+    Ok(())
+}
+
+async fn create(
+    context: &mut ProgramTestContext,
+    multisig: &Keypair,
+    owners: Vec<Pubkey>,
+    threshold: u8,
+) -> bool {
+    let program_id = solana_multisig_wallet::id();
+    let lamports = rent(context).await + EXTRA_LAMPORTS;
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            solana_sdk::system_instruction::create_account(
+                &context.payer.pubkey(),
+                &multisig.pubkey(),
+                lamports,
+                Multisig::LEN as u64,
+                &program_id,
+            ),
+            instruction::create(&program_id, &multisig.pubkey(), owners, threshold),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, multisig],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.is_ok()
+}
+
+async fn rent(context: &mut ProgramTestContext) -> u64 {
+    let rent = context.banks_client.get_rent().await.unwrap();
+    rent.minimum_balance(Multisig::LEN)
+}
+
+async fn balance(context: &mut ProgramTestContext, address: &Pubkey) -> u64 {
+    context.banks_client.get_balance(*address).await.unwrap()
+}
+
+async fn state(context: &mut ProgramTestContext, multisig: &Pubkey) -> Multisig {
+    let account = context
+        .banks_client
+        .get_account(*multisig)
+        .await
+        .unwrap()
+        .unwrap();
+    solana_multisig_wallet::state::load(&account.data).unwrap()
+}
@@ -0,0 +1,141 @@
+// A proposal's CPI target calling back into the multisig program to execute the same proposal
+// again. The runtime refuses a CPI back into a program already running (and `solana-program-test`
+// panics on it), so the target runs the program's processor directly on the accounts it was given,
+// which is what such a CPI would see: the proposal is marked executed before the first CPI, the
+// second execution fails and the whole transaction with it
+use borsh::BorshSerialize;
+use multisig_test_utils::{process, program_test, MultisigHarness};
+use solana_multisig_wallet::instruction::{self, MultisigInstruction, ProposeOptions};
+use solana_multisig_wallet::{
+    find_vault_address, MultisigError, ProposalAction, Transaction as Proposal,
+};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::processor;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::TransactionError;
+
+// `ExecuteTransaction` on the accounts it was given
+fn reentrant_program(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let data = MultisigInstruction::ExecuteTransaction
+        .try_to_vec()
+        .unwrap();
+    solana_multisig_wallet::process_instruction(&solana_multisig_wallet::id(), accounts, &data)
+}
+
+#[tokio::test]
+async fn test_execute_cannot_reenter() {
+    let reentrant_id = Pubkey::new_unique();
+    let mut program_test = program_test();
+    program_test.add_program("reentrant", reentrant_id, processor!(reentrant_program));
+    let mut harness = MultisigHarness::start(program_test, 2, 2, 0).await;
+    let program_id = solana_multisig_wallet::id();
+    let multisig = harness.multisig;
+    let (vault, _) = find_vault_address(&multisig, &program_id);
+    let payer = harness.context.payer.pubkey();
+    process(
+        &mut harness.context,
+        system_instruction::transfer(&payer, &vault, 1_000_000_000),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // pays out, then has the target execute the proposal a second time
+    let transaction_keypair = Keypair::new();
+    let transaction_key = transaction_keypair.pubkey();
+    let destination = Pubkey::new_unique();
+    let reenter = Instruction::new_with_bytes(
+        reentrant_id,
+        &[],
+        vec![
+            AccountMeta::new_readonly(multisig, false),
+            AccountMeta::new(transaction_key, false),
+            AccountMeta::new(vault, false),
+        ],
+    );
+    let action = ProposalAction::Batch {
+        instructions: vec![
+            system_instruction::transfer(&vault, &destination, 500_000_000).into(),
+            reenter.into(),
+        ],
+    };
+
+    let space = Proposal::space(&action);
+    let rent = harness.context.banks_client.get_rent().await.unwrap();
+    process(
+        &mut harness.context,
+        system_instruction::create_account(
+            &payer,
+            &transaction_key,
+            rent.minimum_balance(space),
+            space as u64,
+            &program_id,
+        ),
+        &[&transaction_keypair],
+    )
+    .await
+    .unwrap();
+    let [proposer, approver] = [0, 1].map(|index| harness.owners[index].insecure_clone());
+    let propose = instruction::propose_with_options(
+        &program_id,
+        &proposer.pubkey(),
+        &multisig,
+        &transaction_key,
+        action.clone(),
+        ProposeOptions::default(),
+    );
+    process(&mut harness.context, propose, &[&proposer])
+        .await
+        .unwrap();
+    let approve = instruction::approve(
+        &program_id,
+        &approver.pubkey(),
+        &multisig,
+        &transaction_key,
+        &action,
+    );
+    process(&mut harness.context, approve, &[&approver])
+        .await
+        .unwrap();
+
+    let vault_before = harness.balance(&vault).await;
+    // the builder leaves out the accounts the execution already takes, the executor passes them
+    // again for the target
+    let mut execute =
+        instruction::execute_transaction(&program_id, &multisig, &transaction_key, &action);
+    execute.accounts.extend([
+        AccountMeta::new_readonly(multisig, false),
+        AccountMeta::new(transaction_key, false),
+    ]);
+    let result = process(&mut harness.context, execute, &[]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MultisigError::AlreadyExecuted as u32)
+        )
+    );
+
+    // rolled back as a whole: still executable once, nothing paid
+    let account = harness
+        .context
+        .banks_client
+        .get_account(transaction_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let proposal = solana_multisig_wallet::state::load::<Proposal>(&account.data).unwrap();
+    assert!(!proposal.executed);
+    assert_eq!(harness.balance(&vault).await, vault_before);
+    assert_eq!(harness.balance(&destination).await, 0);
+}
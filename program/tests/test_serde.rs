@@ -0,0 +1,42 @@
+// JSON through the `serde` feature carries every field: decoding it gives back the same Borsh bytes
+#![cfg(feature = "serde")]
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_multisig_wallet::event::DepositEvent;
+use solana_multisig_wallet::{fixtures, Multisig, Transaction};
+use solana_program::pubkey::Pubkey;
+
+fn round_trip<T>(data: &[u8]) -> Vec<u8>
+where
+    T: BorshSerialize + BorshDeserialize + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let state = T::try_from_slice(data).unwrap();
+    let json = serde_json::to_string(&state).unwrap();
+    serde_json::from_str::<T>(&json)
+        .unwrap()
+        .try_to_vec()
+        .unwrap()
+}
+
+#[test]
+fn test_fixtures_round_trip() {
+    for fixture in fixtures::all() {
+        let data = match fixture.kind {
+            "multisig" => round_trip::<Multisig>(&fixture.data),
+            _ => round_trip::<Transaction>(&fixture.data),
+        };
+        assert_eq!(data, fixture.data, "{}", fixture.name);
+    }
+}
+
+#[test]
+fn test_event_round_trip() {
+    let event = DepositEvent {
+        multisig: Pubkey::new_unique(),
+        vault: Pubkey::new_unique(),
+        depositor: Pubkey::new_unique(),
+        amount: 1_000_000,
+    };
+    let json = serde_json::to_value(event).unwrap();
+    assert_eq!(json["amount"], 1_000_000);
+    assert_eq!(serde_json::from_value::<DepositEvent>(json).unwrap(), event);
+}
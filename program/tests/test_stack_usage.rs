@@ -0,0 +1,123 @@
+// Stack headroom of the deepest paths. SBF call frames are 4 KB, so the worst case only runs on
+// the compiled program and is ignored by default: run it with
+// `cargo build-sbf && cargo test -- --ignored`.
+use multisig_test_utils::{compiled_program, process, setup_multisig};
+use solana_multisig_wallet::instruction::ProposeOptions;
+use solana_multisig_wallet::{
+    find_vault_address, instruction, MultisigInstruction, ProposalAction, MAX_OWNERS,
+};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::ProgramTest;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use std::mem::size_of;
+
+// Actions are copied around by value while a proposal is processed, keep them well under a frame
+#[test]
+fn test_action_sizes() {
+    assert!(size_of::<ProposalAction>() <= 256);
+    assert!(size_of::<MultisigInstruction>() <= 512);
+    assert!(size_of::<solana_multisig_wallet::Transaction>() <= 512);
+}
+
+// A wrapped SOL transfer approved by all of `MAX_OWNERS` owners: the largest approval set, and the
+// deepest CPI chain (associated token program, token program, system program). Native CPIs of
+// program-test only grow account data, closing the wrapped SOL account panics there, so the path
+// only runs on the compiled program
+#[tokio::test]
+#[ignore = "needs the compiled program, run `cargo build-sbf` first"]
+async fn test_worst_case_execute() {
+    assert!(
+        compiled_program().is_some(),
+        "solana_multisig_wallet.so not found, run `cargo build-sbf`"
+    );
+    let program_id = solana_multisig_wallet::id();
+    let mut program_test = ProgramTest::new("solana_multisig_wallet", program_id, None);
+    program_test.prefer_bpf(true);
+    let mut context = program_test.start_with_context().await;
+
+    let owners: Vec<Keypair> = (0..MAX_OWNERS).map(|_| Keypair::new()).collect();
+    let keys: Vec<Pubkey> = owners.iter().map(|owner| owner.pubkey()).collect();
+    let multisig = setup_multisig(&mut context, &keys, MAX_OWNERS as u8, 0).await;
+    let (vault, _) = find_vault_address(&multisig, &program_id);
+
+    let native_mint = spl_token::native_mint::id();
+    let recipient = Pubkey::new_unique();
+    let recipient_token_account =
+        spl_associated_token_account::get_associated_token_address(&recipient, &native_mint);
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            solana_sdk::system_instruction::transfer(
+                &context.payer.pubkey(),
+                &vault,
+                1_000_000_000,
+            ),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &context.payer.pubkey(),
+                &recipient,
+                &native_mint,
+                &spl_token::id(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let action = ProposalAction::TransferWrappedSol {
+        destination: recipient_token_account,
+        amount: 250_000_000,
+    };
+    let transaction_keypair = Keypair::new();
+    let space = solana_multisig_wallet::Transaction::space(&action);
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            solana_sdk::system_instruction::create_account(
+                &context.payer.pubkey(),
+                &transaction_keypair.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &program_id,
+            ),
+            instruction::propose_with_options(
+                &program_id,
+                &owners[0].pubkey(),
+                &multisig,
+                &transaction_keypair.pubkey(),
+                action.clone(),
+                ProposeOptions::default(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &owners[0], &transaction_keypair],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let transaction = transaction_keypair.pubkey();
+
+    for owner in &owners[1..] {
+        let ix = instruction::approve(
+            &program_id,
+            &owner.pubkey(),
+            &multisig,
+            &transaction,
+            &action,
+        );
+        process(&mut context, ix, &[owner]).await.unwrap();
+    }
+    let ix = instruction::execute_transaction(&program_id, &multisig, &transaction, &action);
+    process(&mut context, ix, &[]).await.unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(recipient_token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = spl_token::state::Account::unpack(&account.data).unwrap();
+    assert_eq!(state.amount, 250_000_000);
+}
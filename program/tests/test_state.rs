@@ -0,0 +1,508 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_multisig_wallet::fixtures::key;
+use solana_multisig_wallet::state::{load, save};
+use solana_multisig_wallet::{
+    find_indexed_vault_address, find_vault_address, AmountPolicy, History, HistoryEntry, Multisig,
+    MultisigError, OutflowCap, ProgramConfig, ProposalAction, ProposalState, ProposalStatus,
+    Transaction, HISTORY_CAPACITY, MAX_OWNERS, MAX_PROTOCOL_FEE_BPS, PROPOSAL_DIGEST_VERSION,
+    VAULT_SEED,
+};
+use solana_program::hash::hash;
+use solana_program::pubkey::Pubkey;
+
+fn multisig(signers: [bool; 3]) -> Multisig {
+    Multisig {
+        owners: vec![key(1), key(2), key(3)],
+        threshold: 2,
+        signers: signers.to_vec(),
+        veto_authority: None,
+        paused: false,
+        emergency_authority: None,
+        amount_policy: None,
+        approval_ttl_seconds: None,
+        signed_at: vec![0; 3],
+        owner_set_seqno: 0,
+        outflow_cap: None,
+        spent_this_period: 0,
+        period_start: 0,
+        policy_signer: None,
+        transaction_count: 0,
+        fee_exempt: false,
+        allow_zero_amount: false,
+        min_transfer: 0,
+        strict_signing: false,
+        delegate_signers: 0,
+    }
+}
+
+fn proposal(signers: Vec<bool>, executed: bool) -> Transaction {
+    let signers_len = signers.len();
+    Transaction {
+        multisig: key(10),
+        proposer: key(1),
+        action: ProposalAction::StakeDeactivate { stake: key(20) },
+        signers,
+        executed,
+        vault: 0,
+        expires_at: None,
+        extended: false,
+        required_signers: vec![false; signers_len],
+        approved_at: vec![0; signers_len],
+        executor_fee: 0,
+        owner_set_seqno: 0,
+        executed_at: 0,
+        executed_slot: 0,
+        executed_by: None,
+        attachment: None,
+    }
+}
+
+#[test]
+fn test_approval_queries() {
+    let one_signed = multisig([false, true, false]);
+    assert_eq!(one_signed.num_approvals(), 1);
+    assert_eq!(one_signed.approvals_remaining(), 1);
+    assert!(one_signed.has_signed(&key(2)));
+    assert!(!one_signed.has_signed(&key(1)));
+    assert!(!one_signed.has_signed(&key(9)));
+
+    let over_threshold = multisig([true, true, true]);
+    assert_eq!(over_threshold.approvals_remaining(), 0);
+
+    let state = multisig([false, false, false]);
+    let pending = proposal(vec![true, false, false], false);
+    assert_eq!(pending.num_approvals(), 1);
+    assert!(pending.has_approved(&state, &key(1)));
+    assert!(!pending.has_approved(&state, &key(2)));
+    assert_eq!(
+        pending.status(&state),
+        ProposalStatus::Pending { remaining: 1 }
+    );
+    assert_eq!(
+        proposal(vec![true, false, true], false).status(&state),
+        ProposalStatus::Ready
+    );
+    assert_eq!(
+        proposal(vec![true, true, false], true).status(&state),
+        ProposalStatus::Executed
+    );
+    // a required signer holds it back whatever the approval count
+    let cfo_required = Transaction {
+        required_signers: vec![false, true, false],
+        ..proposal(vec![true, false, true], false)
+    };
+    assert_eq!(
+        cfo_required.status(&state),
+        ProposalStatus::Pending { remaining: 1 }
+    );
+    // large transfers wait for the higher tier of the amount policy
+    let tiered = Multisig {
+        amount_policy: Some(AmountPolicy {
+            limit: 1_000,
+            large_threshold: 3,
+        }),
+        ..multisig([false, false, false])
+    };
+    assert_eq!(tiered.threshold_for(1_000), 2);
+    assert_eq!(tiered.threshold_for(1_001), 3);
+    let large = Transaction {
+        action: ProposalAction::TransferWrappedSol {
+            destination: key(30),
+            amount: 5_000,
+        },
+        ..proposal(vec![true, false, true], false)
+    };
+    assert_eq!(
+        large.status(&tiered),
+        ProposalStatus::Pending { remaining: 1 }
+    );
+    assert_eq!(large.status(&state), ProposalStatus::Ready);
+    // approvals older than the TTL drop out at execution time
+    let with_ttl = Multisig {
+        approval_ttl_seconds: Some(100),
+        ..multisig([false, false, false])
+    };
+    let aged = Transaction {
+        approved_at: vec![1_000, 0, 1_050],
+        ..proposal(vec![true, false, true], false)
+    };
+    assert_eq!(aged.status(&with_ttl), ProposalStatus::Ready);
+    assert_eq!(aged.status_at(&with_ttl, 1_100), ProposalStatus::Ready);
+    assert_eq!(
+        aged.status_at(&with_ttl, 1_101),
+        ProposalStatus::Pending { remaining: 1 }
+    );
+    assert_eq!(aged.status_at(&state, 1_000_000), ProposalStatus::Ready);
+    // proposed before the owner set changed size
+    assert_eq!(
+        proposal(vec![true, true], false).status(&state),
+        ProposalStatus::Stale
+    );
+    // or before an owner swap kept its size
+    let swapped = Multisig {
+        owner_set_seqno: 1,
+        ..multisig([false, false, false])
+    };
+    assert_eq!(
+        proposal(vec![true, false, true], false).status(&swapped),
+        ProposalStatus::Stale
+    );
+}
+
+#[test]
+fn test_proposal_lifecycle() {
+    let state = multisig([false, false, false]);
+    let active = proposal(vec![true, false, false], false);
+    assert_eq!(active.state_at(&state, 0), ProposalState::Active);
+    assert_eq!(
+        proposal(vec![true, true, false], false).state_at(&state, 0),
+        ProposalState::Approved
+    );
+    assert_eq!(
+        proposal(vec![true, true, false], true).state_at(&state, 0),
+        ProposalState::Executed
+    );
+    assert_eq!(
+        proposal(vec![true, true], false).state_at(&state, 0),
+        ProposalState::Cancelled
+    );
+    let expiring = Transaction {
+        expires_at: Some(500),
+        ..proposal(vec![true, true, false], false)
+    };
+    assert_eq!(expiring.state_at(&state, 500), ProposalState::Approved);
+    assert_eq!(expiring.state_at(&state, 501), ProposalState::Expired);
+
+    // only final states have a finalization time
+    assert_eq!(active.finalized_at(&state, 0), None);
+    assert_eq!(expiring.finalized_at(&state, 500), None);
+    assert_eq!(expiring.finalized_at(&state, 501), Some(500));
+    let executed = Transaction {
+        executed_at: 700,
+        ..proposal(vec![true, true, false], true)
+    };
+    assert_eq!(executed.finalized_at(&state, 0), Some(700));
+    let stale = Transaction {
+        approved_at: vec![100, 300],
+        ..proposal(vec![true, true], false)
+    };
+    assert_eq!(stale.finalized_at(&state, 0), Some(300));
+
+    use ProposalState::*;
+    assert_eq!(Draft.check_transition(Active), Ok(()));
+    assert_eq!(Active.check_transition(Approved), Ok(()));
+    assert_eq!(Approved.check_transition(Executed), Ok(()));
+    assert_eq!(Expired.check_transition(Cancelled), Ok(()));
+    assert_eq!(
+        Active.check_transition(Executed),
+        Err(MultisigError::InsufficientSignatures)
+    );
+    assert_eq!(
+        Executed.check_transition(Cancelled),
+        Err(MultisigError::AlreadyExecuted)
+    );
+    assert_eq!(
+        Expired.check_transition(Active),
+        Err(MultisigError::ProposalExpired)
+    );
+    assert_eq!(
+        Cancelled.check_transition(Executed),
+        Err(MultisigError::StaleProposal)
+    );
+    assert_eq!(
+        Draft.check_transition(Executed),
+        Err(MultisigError::InvalidTransition)
+    );
+    assert_eq!(
+        Approved.check_transition(Draft),
+        Err(MultisigError::InvalidTransition)
+    );
+}
+
+#[test]
+fn test_proposal_digest() {
+    let program_id = key(30);
+    let transaction_key = key(11);
+    let transaction = proposal(vec![true, false, false], false);
+    let digest = transaction.digest(&program_id, &transaction_key);
+
+    // the documented preimage, for wallets that rebuild it to show what they sign
+    let mut preimage = b"multisig-proposal".to_vec();
+    preimage.push(PROPOSAL_DIGEST_VERSION);
+    preimage.extend_from_slice(program_id.as_ref());
+    preimage.extend_from_slice(key(10).as_ref());
+    preimage.extend_from_slice(transaction_key.as_ref());
+    preimage.extend_from_slice(&transaction.action.try_to_vec().unwrap());
+    preimage.push(0);
+    preimage.extend_from_slice(&0u64.to_le_bytes());
+    preimage.push(0);
+    preimage.extend_from_slice(&[0; 32]);
+    assert_eq!(digest, hash(&preimage));
+
+    // approvals and the expiry aren't part of what owners approve
+    let mut approved = proposal(vec![true; 3], false);
+    approved.expires_at = Some(100);
+    approved.approved_at = vec![1, 2, 3];
+    assert_eq!(approved.digest(&program_id, &transaction_key), digest);
+
+    assert_ne!(transaction.digest(&key(31), &transaction_key), digest);
+    assert_ne!(transaction.digest(&program_id, &key(12)), digest);
+    let changes: [fn(&mut Transaction); 6] = [
+        |transaction| transaction.multisig = key(12),
+        |transaction| transaction.action = ProposalAction::StakeDeactivate { stake: key(21) },
+        |transaction| transaction.vault = 1,
+        |transaction| transaction.executor_fee = 5000,
+        |transaction| transaction.attachment = Some([1; 32]),
+        // an all-zero hash is still an attachment
+        |transaction| transaction.attachment = Some([0; 32]),
+    ];
+    for change in changes {
+        let mut changed = proposal(vec![true, false, false], false);
+        change(&mut changed);
+        assert_ne!(changed.digest(&program_id, &transaction_key), digest);
+    }
+}
+
+#[test]
+fn test_fixed_size_encoding() {
+    let small = multisig([false, true, false]);
+    let full = Multisig {
+        owners: (0..MAX_OWNERS as u8).map(key).collect(),
+        signers: vec![true; MAX_OWNERS],
+        signed_at: (1..=MAX_OWNERS as i64).collect(),
+        veto_authority: Some(key(40)),
+        approval_ttl_seconds: Some(3_600),
+        owner_set_seqno: u32::MAX,
+        outflow_cap: Some(OutflowCap {
+            limit: 5_000,
+            period_seconds: 86_400,
+        }),
+        spent_this_period: 1_200,
+        period_start: 1_700_000_000,
+        policy_signer: Some(key(42)),
+        fee_exempt: true,
+        allow_zero_amount: true,
+        min_transfer: u64::MAX,
+        emergency_authority: Some(key(41)),
+        amount_policy: Some(AmountPolicy {
+            limit: 1_000,
+            large_threshold: 3,
+        }),
+        ..multisig([false; 3])
+    };
+    for state in [&small, &full] {
+        let data = state.try_to_vec().unwrap();
+        assert_eq!(data.len(), Multisig::LEN);
+        let decoded = Multisig::try_from_slice(&data).unwrap();
+        assert_eq!(decoded.owners, state.owners);
+        assert_eq!(decoded.signers, state.signers);
+        assert_eq!(decoded.veto_authority, state.veto_authority);
+        assert_eq!(decoded.emergency_authority, state.emergency_authority);
+        assert_eq!(decoded.amount_policy, state.amount_policy);
+        assert_eq!(decoded.approval_ttl_seconds, state.approval_ttl_seconds);
+        assert_eq!(decoded.signed_at, state.signed_at);
+        assert_eq!(decoded.owner_set_seqno, state.owner_set_seqno);
+        assert_eq!(decoded.outflow_cap, state.outflow_cap);
+        assert_eq!(decoded.spent_this_period, state.spent_this_period);
+        assert_eq!(decoded.period_start, state.period_start);
+        assert_eq!(decoded.policy_signer, state.policy_signer);
+        assert_eq!(decoded.fee_exempt, state.fee_exempt);
+        assert_eq!(decoded.allow_zero_amount, state.allow_zero_amount);
+        assert_eq!(decoded.min_transfer, state.min_transfer);
+    }
+
+    let too_many = Multisig {
+        owners: (0..=MAX_OWNERS as u8).map(key).collect(),
+        signers: vec![false; MAX_OWNERS + 1],
+        ..multisig([false; 3])
+    };
+    assert!(too_many.try_to_vec().is_err());
+
+    // padding must stay zeroed, each state has a single encoding
+    let mut data = small.try_to_vec().unwrap();
+    data[1 + 32 * 3] = 1;
+    assert!(Multisig::try_from_slice(&data).is_err());
+
+    let action = ProposalAction::StakeDeactivate { stake: key(20) };
+    for signers in [vec![true], vec![true, false, true]] {
+        let data = proposal(signers.clone(), false).try_to_vec().unwrap();
+        assert_eq!(data.len(), Transaction::space(&action));
+        assert_eq!(Transaction::try_from_slice(&data).unwrap().signers, signers);
+    }
+}
+
+#[test]
+fn test_padded_accounts() {
+    // a proposal account reallocated larger than its encoding
+    let transaction = proposal(vec![true, false, true], false);
+    let mut data = transaction.try_to_vec().unwrap();
+    let len = data.len();
+    data.resize(len + 64, 0);
+    assert!(Transaction::try_from_slice(&data).is_err());
+    assert_eq!(
+        load::<Transaction>(&data).unwrap().signers,
+        transaction.signers
+    );
+
+    // leftovers of a longer encoding are cleared on save, anything non-zero there is rejected
+    data[len + 10] = 7;
+    assert!(load::<Transaction>(&data).is_err());
+    save(&transaction, &mut data).unwrap();
+    assert!(data[len..].iter().all(|&byte| byte == 0));
+    assert_eq!(
+        load::<Transaction>(&data).unwrap().signers,
+        transaction.signers
+    );
+
+    let mut short = vec![0; len - 1];
+    assert!(save(&transaction, &mut short).is_err());
+}
+
+#[test]
+fn test_vault_addresses() {
+    let program_id = solana_multisig_wallet::id();
+    let multisig = key(10);
+    // the main vault kept its address when indexed vaults were added
+    let (main, _) = Pubkey::find_program_address(&[VAULT_SEED, multisig.as_ref()], &program_id);
+    assert_eq!(find_vault_address(&multisig, &program_id).0, main);
+    assert_eq!(
+        find_indexed_vault_address(&multisig, 0, &program_id).0,
+        main
+    );
+
+    let vaults: Vec<Pubkey> = (0..=u8::MAX)
+        .map(|index| find_indexed_vault_address(&multisig, index, &program_id).0)
+        .collect();
+    for (i, vault) in vaults.iter().enumerate() {
+        assert!(!vaults[i + 1..].contains(vault));
+    }
+}
+
+#[test]
+fn test_history_ring() {
+    let mut history = History::new(key(10), 255);
+    assert_eq!(history.try_to_vec().unwrap().len(), History::SPACE);
+    assert!(history.recent().is_empty());
+
+    let entry = |slot| HistoryEntry {
+        instruction: 1,
+        actor: key(1),
+        slot,
+    };
+    for slot in 0..3 {
+        history.record(entry(slot)).unwrap();
+    }
+    let slots: Vec<u64> = history.recent().iter().map(|entry| entry.slot).collect();
+    assert_eq!(slots, [0, 1, 2]);
+
+    // past the capacity the oldest entries give way
+    for slot in 3..HISTORY_CAPACITY as u64 + 5 {
+        history.record(entry(slot)).unwrap();
+    }
+    let slots: Vec<u64> = history.recent().iter().map(|entry| entry.slot).collect();
+    assert_eq!(slots, (5..HISTORY_CAPACITY as u64 + 5).collect::<Vec<_>>());
+    assert_eq!(history.count, HISTORY_CAPACITY as u64 + 5);
+}
+
+#[test]
+fn test_filter_offsets() {
+    let state = multisig([false, true, false]);
+    let data = state.try_to_vec().unwrap();
+    assert_eq!(data[Multisig::THRESHOLD_OFFSET], state.threshold);
+    for (slot, owner) in state.owners.iter().enumerate() {
+        let offset = Multisig::OWNERS_OFFSET + 32 * slot;
+        assert_eq!(&data[offset..offset + 32], owner.as_ref());
+    }
+    assert_eq!(data[Multisig::SIGNERS_OFFSET + 1], 1);
+
+    let mut state = multisig([true, false, false]);
+    state.approval_ttl_seconds = Some(3_600);
+    state.signed_at[0] = 42;
+    let data = state.try_to_vec().unwrap();
+    assert_eq!(data[Multisig::APPROVAL_TTL_OFFSET], 1);
+    assert_eq!(
+        data[Multisig::APPROVAL_TTL_OFFSET + 1..][..8],
+        3_600i64.to_le_bytes()
+    );
+    assert_eq!(data[Multisig::SIGNED_AT_OFFSET..][..8], 42i64.to_le_bytes());
+}
+
+#[test]
+fn test_transfer_minimum() {
+    // zero is refused by default, dust only once a minimum is set
+    let mut state = multisig([false; 3]);
+    assert_eq!(
+        state.check_transfer_amount(0, true),
+        Err(MultisigError::ZeroAmount)
+    );
+    assert_eq!(state.check_transfer_amount(1, true), Ok(()));
+
+    state.min_transfer = 10_000;
+    assert_eq!(
+        state.check_transfer_amount(9_999, true),
+        Err(MultisigError::BelowMinimumTransfer)
+    );
+    assert_eq!(state.check_transfer_amount(10_000, true), Ok(()));
+    assert_eq!(state.check_transfer_amount(u64::MAX, true), Ok(()));
+    // the minimum is in lamports, token amounts aren't held to it
+    assert_eq!(state.check_transfer_amount(1, false), Ok(()));
+    assert_eq!(
+        state.check_transfer_amount(0, false),
+        Err(MultisigError::ZeroAmount)
+    );
+
+    // signal transactions of zero, whatever the minimum
+    state.allow_zero_amount = true;
+    assert_eq!(state.check_transfer_amount(0, true), Ok(()));
+    assert_eq!(
+        state.check_transfer_amount(1, true),
+        Err(MultisigError::BelowMinimumTransfer)
+    );
+}
+
+#[test]
+fn test_arithmetic_boundaries() {
+    // the whole of u64 fits under a cap as large, one lamport more doesn't
+    let mut capped = multisig([false; 3]);
+    capped.outflow_cap = Some(OutflowCap {
+        limit: u64::MAX,
+        period_seconds: 60,
+    });
+    capped.record_outflow(0, 100).unwrap();
+    assert_eq!(capped.spent_this_period, 0);
+    capped.record_outflow(u64::MAX, 100).unwrap();
+    assert_eq!(capped.outflow_remaining(100), Some(0));
+    assert_eq!(
+        capped.record_outflow(1, 100),
+        Err(MultisigError::OutflowCapExceeded)
+    );
+    // a period starting at the far end of time doesn't overflow either
+    capped.record_outflow(1, i64::MAX).unwrap();
+    assert_eq!(capped.period_start, i64::MAX);
+
+    let config = |protocol_fee_bps| ProgramConfig {
+        governance: key(1),
+        fee_destination: key(2),
+        protocol_fee_bps,
+        paused: false,
+        bump: 255,
+    };
+    assert_eq!(config(30).protocol_fee(0), 0);
+    assert_eq!(config(30).protocol_fee(u64::MAX), 55_340_232_221_128_654);
+    assert_eq!(
+        config(MAX_PROTOCOL_FEE_BPS).protocol_fee(u64::MAX),
+        u64::MAX
+    );
+
+    let mut history = History::new(key(10), 255);
+    history.count = u64::MAX;
+    let entry = HistoryEntry {
+        instruction: 1,
+        actor: key(1),
+        slot: 0,
+    };
+    assert_eq!(
+        history.record(entry),
+        Err(MultisigError::ArithmeticOverflow)
+    );
+}
@@ -0,0 +1,257 @@
+// NFTs held by the vault, moved and un-delegated through `Batch` proposals of Token Metadata
+// instructions. The Token Metadata program is stood in for by a program checking the accounts
+// and that the vault signs
+use multisig_test_utils::{process, program_test, MultisigHarness};
+use solana_multisig_wallet::instruction::{self, MultisigInstruction, ProposeOptions};
+use solana_multisig_wallet::token_metadata::{
+    self, find_master_edition_address, find_metadata_address, find_token_record_address,
+    TokenDelegateRole, AUTHORIZATION_RULES_PROGRAM_ID,
+};
+use solana_multisig_wallet::{find_vault_address, ProposalAction, Transaction as Proposal};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::processor;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use spl_associated_token_account::get_associated_token_address;
+
+const TRANSFER: u8 = 49;
+const REVOKE: u8 = 45;
+
+// The owner of the NFT (the vault) signs as authority and payer
+fn mock_token_metadata(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let (authority, payer, owner) = match data.first() {
+        Some(&TRANSFER) if accounts.len() == 17 => (&accounts[9], &accounts[10], &accounts[1]),
+        Some(&REVOKE) if accounts.len() == 14 => (&accounts[7], &accounts[8], &accounts[7]),
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+    if !authority.is_signer || !payer.is_signer || !payer.is_writable || authority.key != owner.key
+    {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_transfer_accounts() {
+    let [mint, owner, destination_owner, payer, rules] = [(); 5].map(|_| Pubkey::new_unique());
+    let token = get_associated_token_address(&owner, &mint);
+    let destination_token = get_associated_token_address(&destination_owner, &mint);
+
+    let ix = token_metadata::transfer(
+        &mint,
+        &owner,
+        &destination_owner,
+        &payer,
+        true,
+        Some(&rules),
+    );
+    assert_eq!(ix.program_id, token_metadata::ID);
+    assert_eq!(ix.data, [TRANSFER, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(
+        keys[..11],
+        [
+            token,
+            owner,
+            destination_token,
+            destination_owner,
+            mint,
+            find_metadata_address(&mint).0,
+            find_master_edition_address(&mint).0,
+            find_token_record_address(&mint, &token).0,
+            find_token_record_address(&mint, &destination_token).0,
+            owner,
+            payer,
+        ]
+    );
+    assert_eq!(keys[15..], [AUTHORIZATION_RULES_PROGRAM_ID, rules]);
+    let signers: Vec<usize> = (0..keys.len())
+        .filter(|&index| ix.accounts[index].is_signer)
+        .collect();
+    assert_eq!(signers, [9, 10]);
+    for index in [0, 2, 5, 7, 8, 10] {
+        assert!(ix.accounts[index].is_writable, "{index}");
+    }
+
+    // without token records or rules the optional accounts are the program id
+    let ix = token_metadata::transfer(&mint, &owner, &destination_owner, &payer, false, None);
+    for index in [7, 8, 15, 16] {
+        assert_eq!(ix.accounts[index].pubkey, token_metadata::ID, "{index}");
+        assert!(!ix.accounts[index].is_writable, "{index}");
+    }
+}
+
+#[test]
+fn test_revoke_accounts() {
+    let [mint, owner, delegate, payer] = [(); 4].map(|_| Pubkey::new_unique());
+    let token = get_associated_token_address(&owner, &mint);
+
+    let ix = token_metadata::revoke(
+        &mint,
+        &owner,
+        &delegate,
+        &payer,
+        TokenDelegateRole::Transfer,
+        None,
+    );
+    assert_eq!(ix.data, [REVOKE, 2]);
+    assert_eq!(ix.accounts.len(), 14);
+    assert_eq!(ix.accounts[0].pubkey, token_metadata::ID);
+    assert_eq!(ix.accounts[1].pubkey, delegate);
+    assert_eq!(
+        ix.accounts[4].pubkey,
+        find_token_record_address(&mint, &token).0
+    );
+    assert_eq!(ix.accounts[6].pubkey, token);
+    assert!(ix.accounts[7].is_signer && ix.accounts[7].pubkey == owner);
+
+    // the SPL Token delegate of a plain NFT has no token record
+    let ix = token_metadata::revoke(
+        &mint,
+        &owner,
+        &delegate,
+        &payer,
+        TokenDelegateRole::Standard,
+        None,
+    );
+    assert_eq!(ix.data, [REVOKE, 6]);
+    assert_eq!(ix.accounts[4].pubkey, token_metadata::ID);
+}
+
+// Creates the proposal account `transaction_keypair` and sends `propose` (a `propose_nft_*`
+// instruction for it), returns the proposed action
+async fn propose(
+    harness: &mut MultisigHarness,
+    transaction_keypair: &Keypair,
+    propose: Instruction,
+) -> ProposalAction {
+    let action = match MultisigInstruction::decode(&propose.data).unwrap() {
+        MultisigInstruction::Propose { action, .. } => action,
+        other => panic!("{other:?}"),
+    };
+    let transaction_key = transaction_keypair.pubkey();
+    let space = Proposal::space(&action);
+    let rent = harness.context.banks_client.get_rent().await.unwrap();
+    let create = system_instruction::create_account(
+        &harness.context.payer.pubkey(),
+        &transaction_key,
+        rent.minimum_balance(space),
+        space as u64,
+        &solana_multisig_wallet::id(),
+    );
+    process(&mut harness.context, create, &[transaction_keypair])
+        .await
+        .unwrap();
+    let proposer = harness.owners[0].insecure_clone();
+    process(&mut harness.context, propose, &[&proposer])
+        .await
+        .unwrap();
+    action
+}
+
+#[tokio::test]
+async fn test_nft_custody() {
+    let mut program_test = program_test();
+    program_test.add_program(
+        "token_metadata",
+        token_metadata::ID,
+        processor!(mock_token_metadata),
+    );
+    let mut harness = MultisigHarness::start(program_test, 2, 2, 0).await;
+    let program_id = solana_multisig_wallet::id();
+    let multisig = harness.multisig;
+    let (vault, _) = find_vault_address(&multisig, &program_id);
+    let [mint, destination_owner, delegate, rules] = [(); 4].map(|_| Pubkey::new_unique());
+    let transaction_keypairs = [Keypair::new(), Keypair::new()];
+
+    let proposals = [
+        instruction::propose_nft_transfer(
+            &program_id,
+            &harness.owners[0].pubkey(),
+            &multisig,
+            &transaction_keypairs[0].pubkey(),
+            &mint,
+            &destination_owner,
+            true,
+            Some(&rules),
+        ),
+        instruction::propose_nft_revoke(
+            &program_id,
+            &harness.owners[0].pubkey(),
+            &multisig,
+            &transaction_keypairs[1].pubkey(),
+            &mint,
+            &delegate,
+            TokenDelegateRole::Utility,
+            None,
+        ),
+    ];
+    for (transaction_keypair, proposal) in transaction_keypairs.iter().zip(proposals) {
+        let action = propose(&mut harness, transaction_keypair, proposal).await;
+        let transaction_key = transaction_keypair.pubkey();
+        let ProposalAction::Batch { instructions } = &action else {
+            panic!("{action:?}");
+        };
+        assert_eq!(instructions[0].program_id, token_metadata::ID);
+
+        // nothing moves before the threshold is met
+        let execute =
+            instruction::execute_transaction(&program_id, &multisig, &transaction_key, &action);
+        assert!(execute
+            .accounts
+            .iter()
+            .all(|meta| meta.pubkey != vault || !meta.is_signer));
+        assert!(process(&mut harness.context, execute.clone(), &[])
+            .await
+            .is_err());
+
+        let approver = harness.owners[1].insecure_clone();
+        let approve = instruction::approve(
+            &program_id,
+            &approver.pubkey(),
+            &multisig,
+            &transaction_key,
+            &action,
+        );
+        process(&mut harness.context, approve, &[&approver])
+            .await
+            .unwrap();
+        process(&mut harness.context, execute, &[]).await.unwrap();
+    }
+
+    // the builders only differ from a hand-built batch in the proposal account
+    let transfer = token_metadata::transfer(&mint, &vault, &destination_owner, &vault, false, None);
+    let action = ProposalAction::Batch {
+        instructions: vec![transfer.into()],
+    };
+    let transaction = Pubkey::new_unique();
+    assert_eq!(
+        instruction::propose_nft_transfer(
+            &program_id,
+            &harness.owners[0].pubkey(),
+            &multisig,
+            &transaction,
+            &mint,
+            &destination_owner,
+            false,
+            None,
+        ),
+        instruction::propose_with_options(
+            &program_id,
+            &harness.owners[0].pubkey(),
+            &multisig,
+            &transaction,
+            action,
+            ProposeOptions::default(),
+        )
+    );
+}
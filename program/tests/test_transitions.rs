@@ -0,0 +1,211 @@
+// The pure state transitions of `multisig`, without a validator
+use proptest::prelude::*;
+use solana_multisig_wallet::multisig::{
+    apply_execute, apply_sign, apply_sign_in_place, execute_needs_clock,
+};
+use solana_multisig_wallet::state::{load, save};
+use solana_multisig_wallet::{AmountPolicy, Multisig, MultisigError, OutflowCap, MAX_OWNERS};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+fn multisig(owners: &[Pubkey], threshold: u8) -> Multisig {
+    Multisig {
+        owners: owners.to_vec(),
+        threshold,
+        signers: vec![false; owners.len()],
+        veto_authority: None,
+        paused: false,
+        emergency_authority: None,
+        amount_policy: None,
+        approval_ttl_seconds: None,
+        signed_at: vec![0; owners.len()],
+        owner_set_seqno: 0,
+        outflow_cap: None,
+        spent_this_period: 0,
+        period_start: 0,
+        policy_signer: None,
+        transaction_count: 0,
+        fee_exempt: false,
+        allow_zero_amount: false,
+        min_transfer: 0,
+        strict_signing: false,
+        delegate_signers: 0,
+    }
+}
+
+fn owners(count: usize) -> Vec<Pubkey> {
+    (0..count).map(|_| Pubkey::new_unique()).collect()
+}
+
+#[test]
+fn test_apply_sign() {
+    let owners = owners(3);
+    let mut state = multisig(&owners, 2);
+    apply_sign(&mut state, &owners[1], 100, false).unwrap();
+    assert_eq!(state.signers, vec![false, true, false]);
+    assert_eq!(state.signed_at, vec![0, 100, 0]);
+
+    assert_eq!(
+        apply_sign(&mut state, &Pubkey::new_unique(), 100, false),
+        Err(MultisigError::OwnerNotFound)
+    );
+    // a pending approval isn't given twice, until it outlives the TTL
+    assert_eq!(
+        apply_sign(&mut state, &owners[1], 200, false),
+        Err(MultisigError::AlreadySigned)
+    );
+    state.approval_ttl_seconds = Some(60);
+    assert_eq!(
+        apply_sign(&mut state, &owners[1], 160, false),
+        Err(MultisigError::AlreadySigned)
+    );
+    apply_sign(&mut state, &owners[1], 161, false).unwrap();
+    assert_eq!(state.signed_at, vec![0, 161, 0]);
+
+    // a delegate's approval counts, except toward configuration changes
+    apply_sign(&mut state, &owners[2], 161, true).unwrap();
+    assert_eq!(state.delegate_signers, 0b100);
+    assert_eq!(state.live_approvals(161), 2);
+    assert_eq!(state.live_owner_approvals(161), 1);
+    // the owner can replace it with their own, the delegate can't sign again
+    assert_eq!(
+        apply_sign(&mut state, &owners[2], 170, true),
+        Err(MultisigError::AlreadySigned)
+    );
+    apply_sign(&mut state, &owners[2], 170, false).unwrap();
+    assert_eq!(state.delegate_signers, 0);
+    assert_eq!(state.live_owner_approvals(170), 2);
+
+    // the in-place version refuses data that isn't a multisig
+    let mut data = vec![0; Multisig::LEN - 1];
+    assert_eq!(
+        apply_sign_in_place(&mut data, &owners[0], 0, false),
+        Err(ProgramError::InvalidAccountData)
+    );
+    let mut data = vec![0; Multisig::LEN];
+    data[0] = MAX_OWNERS as u8 + 1;
+    assert_eq!(
+        apply_sign_in_place(&mut data, &owners[0], 0, false),
+        Err(ProgramError::InvalidAccountData)
+    );
+
+    // accounts allocated larger than the state are patched like `save` writes them
+    let mut data = vec![0; Multisig::LEN + 64];
+    save(&multisig(&owners, 2), &mut data).unwrap();
+    apply_sign_in_place(&mut data, &owners[0], 5, false).unwrap();
+    let patched = load::<Multisig>(&data).unwrap();
+    assert_eq!(patched.signers, vec![true, false, false]);
+    assert_eq!(patched.signed_at, vec![5, 0, 0]);
+}
+
+#[test]
+fn test_apply_execute() {
+    let owners = owners(3);
+    let mut state = multisig(&owners, 2);
+    assert_eq!(
+        apply_execute(&mut state, 10, 0),
+        Err(MultisigError::InsufficientSignatures)
+    );
+    apply_sign(&mut state, &owners[0], 0, false).unwrap();
+    apply_sign(&mut state, &owners[2], 0, false).unwrap();
+    apply_execute(&mut state, 10, 0).unwrap();
+    // approvals are spent
+    assert_eq!(state.num_approvals(), 0);
+    assert_eq!(
+        apply_execute(&mut state, 10, 0),
+        Err(MultisigError::InsufficientSignatures)
+    );
+
+    let approve = |state: &mut Multisig, now| {
+        for owner in &owners {
+            apply_sign(state, owner, now, false).unwrap();
+        }
+    };
+
+    // paused wallets pay nothing, whatever the approvals
+    state.paused = true;
+    approve(&mut state, 0);
+    assert_eq!(apply_execute(&mut state, 10, 0), Err(MultisigError::Paused));
+    assert_eq!(state.num_approvals(), 3);
+    state.paused = false;
+
+    // large amounts take the policy's threshold
+    state.amount_policy = Some(AmountPolicy {
+        limit: 100,
+        large_threshold: 3,
+    });
+    state.signers = vec![true, true, false];
+    assert_eq!(
+        apply_execute(&mut state, 101, 0),
+        Err(MultisigError::InsufficientSignatures)
+    );
+    apply_execute(&mut state, 100, 0).unwrap();
+
+    // approvals past the TTL don't count
+    state.approval_ttl_seconds = Some(60);
+    assert!(execute_needs_clock(&state));
+    approve(&mut state, 1_000);
+    assert_eq!(
+        apply_execute(&mut state, 10, 1_061),
+        Err(MultisigError::InsufficientSignatures)
+    );
+    apply_execute(&mut state, 10, 1_060).unwrap();
+    state.approval_ttl_seconds = None;
+
+    // transfer settings and the outflow cap
+    approve(&mut state, 0);
+    assert_eq!(
+        apply_execute(&mut state, 0, 0),
+        Err(MultisigError::ZeroAmount)
+    );
+    state.outflow_cap = Some(OutflowCap {
+        limit: 50,
+        period_seconds: 3_600,
+    });
+    apply_execute(&mut state, 40, 0).unwrap();
+    approve(&mut state, 0);
+    assert_eq!(
+        apply_execute(&mut state, 20, 10),
+        Err(MultisigError::OutflowCapExceeded)
+    );
+    // a new period starts once the current one is over
+    apply_execute(&mut state, 20, 3_600).unwrap();
+    assert_eq!(state.spent_this_period, 20);
+    assert_eq!(state.period_start, 3_600);
+}
+
+proptest! {
+    // patching the account data in place is the same as decoding, signing and encoding it
+    #[test]
+    fn test_sign_in_place_matches_apply_sign(
+        owner_count in 1..=MAX_OWNERS,
+        signer in 0..MAX_OWNERS + 1,
+        signed in prop::collection::vec(any::<bool>(), MAX_OWNERS),
+        signed_at in prop::collection::vec(any::<i64>(), MAX_OWNERS),
+        ttl in prop::option::of(0..1_000i64),
+        delegates in any::<u16>(),
+        by_delegate in any::<bool>(),
+        now in any::<i64>(),
+    ) {
+        let owners = owners(owner_count);
+        let mut state = multisig(&owners, 1);
+        state.signers = signed[..owner_count].to_vec();
+        state.signed_at = signed_at[..owner_count].to_vec();
+        state.approval_ttl_seconds = ttl;
+        state.delegate_signers = delegates;
+        let mut data = vec![0; Multisig::LEN];
+        save(&state, &mut data).unwrap();
+
+        let signer = owners.get(signer).copied().unwrap_or_else(Pubkey::new_unique);
+        let in_place = apply_sign_in_place(&mut data, &signer, now, by_delegate);
+        let decoded = apply_sign(&mut state, &signer, now, by_delegate);
+        prop_assert_eq!(in_place.is_ok(), decoded.is_ok());
+        if let Err(err) = decoded {
+            prop_assert_eq!(in_place.unwrap_err(), ProgramError::from(err));
+        }
+        let patched = load::<Multisig>(&data).unwrap();
+        prop_assert_eq!(patched.signers, state.signers);
+        prop_assert_eq!(patched.signed_at, state.signed_at);
+        prop_assert_eq!(patched.delegate_signers, state.delegate_signers);
+    }
+}
@@ -0,0 +1,199 @@
+// Python bindings (`import multisig_wallet`) for building instructions and decoding accounts,
+// built with `maturin build` from this directory.
+//
+// Keys are base58 strings, instructions are returned as
+// `{"program_id": str, "accounts": [{"pubkey", "is_signer", "is_writable"}], "data": bytes}`
+// and decoded accounts as dicts shaped like the `fields` of `fixtures/manifest.json`.
+// `program_id` defaults to the id the program is built with.
+use borsh::BorshDeserialize;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+use solana_multisig_wallet::{fixtures, instruction, Multisig, Transaction};
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+
+fn parse_pubkey(value: &str) -> PyResult<Pubkey> {
+    Pubkey::from_str(value).map_err(|err| PyValueError::new_err(format!("{value}: {err}")))
+}
+
+fn program_id_or_default(program_id: Option<&str>) -> PyResult<Pubkey> {
+    program_id.map_or(Ok(solana_multisig_wallet::id()), parse_pubkey)
+}
+
+fn instruction_to_dict(py: Python<'_>, ix: Instruction) -> PyResult<PyObject> {
+    let accounts = PyList::empty(py);
+    for meta in ix.accounts {
+        let account = PyDict::new(py);
+        account.set_item("pubkey", meta.pubkey.to_string())?;
+        account.set_item("is_signer", meta.is_signer)?;
+        account.set_item("is_writable", meta.is_writable)?;
+        accounts.append(account)?;
+    }
+    let dict = PyDict::new(py);
+    dict.set_item("program_id", ix.program_id.to_string())?;
+    dict.set_item("accounts", accounts)?;
+    dict.set_item("data", PyBytes::new(py, &ix.data))?;
+    Ok(dict.into())
+}
+
+fn json_to_object(py: Python<'_>, json: String) -> PyResult<PyObject> {
+    Ok(py.import("json")?.call_method1("loads", (json,))?.into())
+}
+
+fn decode<T: BorshDeserialize>(data: &[u8]) -> PyResult<T> {
+    T::try_from_slice(data).map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+#[pyfunction]
+#[pyo3(signature = (multisig, owners, threshold, program_id=None))]
+fn create(
+    py: Python<'_>,
+    multisig: &str,
+    owners: Vec<&str>,
+    threshold: u8,
+    program_id: Option<&str>,
+) -> PyResult<PyObject> {
+    let owners = owners
+        .into_iter()
+        .map(parse_pubkey)
+        .collect::<PyResult<Vec<_>>>()?;
+    let ix = instruction::create(
+        &program_id_or_default(program_id)?,
+        &parse_pubkey(multisig)?,
+        owners,
+        threshold,
+    );
+    instruction_to_dict(py, ix)
+}
+
+#[pyfunction]
+#[pyo3(signature = (owner, multisig, program_id=None))]
+fn sign(
+    py: Python<'_>,
+    owner: &str,
+    multisig: &str,
+    program_id: Option<&str>,
+) -> PyResult<PyObject> {
+    let ix = instruction::sign(
+        &program_id_or_default(program_id)?,
+        &parse_pubkey(owner)?,
+        &parse_pubkey(multisig)?,
+    );
+    instruction_to_dict(py, ix)
+}
+
+#[pyfunction]
+#[pyo3(signature = (multisig, amount, destination, program_id=None))]
+fn execute(
+    py: Python<'_>,
+    multisig: &str,
+    amount: u64,
+    destination: &str,
+    program_id: Option<&str>,
+) -> PyResult<PyObject> {
+    let ix = instruction::execute(
+        &program_id_or_default(program_id)?,
+        &parse_pubkey(multisig)?,
+        amount,
+        &parse_pubkey(destination)?,
+    );
+    instruction_to_dict(py, ix)
+}
+
+// `transaction_data` is the proposal account's data, it provides the multisig and the
+// action the approval is bound to
+#[pyfunction]
+#[pyo3(signature = (owner, transaction, transaction_data, program_id=None))]
+fn approve(
+    py: Python<'_>,
+    owner: &str,
+    transaction: &str,
+    transaction_data: &[u8],
+    program_id: Option<&str>,
+) -> PyResult<PyObject> {
+    let proposal: Transaction = decode(transaction_data)?;
+    let ix = instruction::approve(
+        &program_id_or_default(program_id)?,
+        &parse_pubkey(owner)?,
+        &proposal.multisig,
+        &parse_pubkey(transaction)?,
+        &proposal.action,
+    );
+    instruction_to_dict(py, ix)
+}
+
+// The proposal's `Transaction::digest`, the 32 bytes an owner signs to approve it off-chain
+#[pyfunction]
+#[pyo3(signature = (transaction, transaction_data, program_id=None))]
+fn proposal_digest<'py>(
+    py: Python<'py>,
+    transaction: &str,
+    transaction_data: &[u8],
+    program_id: Option<&str>,
+) -> PyResult<&'py PyBytes> {
+    let proposal: Transaction = decode(transaction_data)?;
+    let digest = proposal.digest(
+        &program_id_or_default(program_id)?,
+        &parse_pubkey(transaction)?,
+    );
+    Ok(PyBytes::new(py, digest.as_ref()))
+}
+
+#[pyfunction]
+#[pyo3(signature = (transaction, transaction_data, program_id=None))]
+fn execute_transaction(
+    py: Python<'_>,
+    transaction: &str,
+    transaction_data: &[u8],
+    program_id: Option<&str>,
+) -> PyResult<PyObject> {
+    let proposal: Transaction = decode(transaction_data)?;
+    let ix = instruction::execute_transaction(
+        &program_id_or_default(program_id)?,
+        &proposal.multisig,
+        &parse_pubkey(transaction)?,
+        &proposal.action,
+    );
+    instruction_to_dict(py, ix)
+}
+
+#[pyfunction]
+#[pyo3(signature = (multisig, program_id=None))]
+fn find_vault_address(multisig: &str, program_id: Option<&str>) -> PyResult<(String, u8)> {
+    let (vault, bump) = solana_multisig_wallet::find_vault_address(
+        &parse_pubkey(multisig)?,
+        &program_id_or_default(program_id)?,
+    );
+    Ok((vault.to_string(), bump))
+}
+
+#[pyfunction]
+fn decode_multisig(py: Python<'_>, data: &[u8]) -> PyResult<PyObject> {
+    let multisig: Multisig = decode(data)?;
+    json_to_object(py, fixtures::multisig_json(&multisig))
+}
+
+#[pyfunction]
+fn decode_transaction(py: Python<'_>, data: &[u8]) -> PyResult<PyObject> {
+    let transaction: Transaction = decode(data)?;
+    json_to_object(py, fixtures::transaction_json(&transaction))
+}
+
+#[pymodule]
+fn multisig_wallet(_py: Python<'_>, module: &PyModule) -> PyResult<()> {
+    module.add("PROGRAM_ID", solana_multisig_wallet::id().to_string())?;
+    module.add("MULTISIG_LEN", Multisig::LEN)?;
+    module.add("MAX_OWNERS", solana_multisig_wallet::MAX_OWNERS)?;
+    module.add_function(wrap_pyfunction!(create, module)?)?;
+    module.add_function(wrap_pyfunction!(sign, module)?)?;
+    module.add_function(wrap_pyfunction!(execute, module)?)?;
+    module.add_function(wrap_pyfunction!(approve, module)?)?;
+    module.add_function(wrap_pyfunction!(proposal_digest, module)?)?;
+    module.add_function(wrap_pyfunction!(execute_transaction, module)?)?;
+    module.add_function(wrap_pyfunction!(find_vault_address, module)?)?;
+    module.add_function(wrap_pyfunction!(decode_multisig, module)?)?;
+    module.add_function(wrap_pyfunction!(decode_transaction, module)?)?;
+    Ok(())
+}
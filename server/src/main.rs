@@ -0,0 +1,281 @@
+// Reference HTTP/JSON backend for web frontends, a thin layer over the RPC node and the crate's
+// instruction builders (so also a worked example of the client API).
+//
+//   GET  /multisigs?owner=<pubkey>       multisigs the key is an owner of
+//   GET  /multisigs/<pubkey>             decoded multisig
+//   GET  /multisigs/<pubkey>/proposals   decoded proposals of the multisig
+//   POST /build/sign     {"owner", "multisig"}
+//   POST /build/approve  {"owner", "transaction"}
+//   POST /build/execute  {"fee_payer", "transaction"}
+//
+// Build endpoints answer `{"transaction": <base64>, "blockhash"}`: an unsigned, bincode encoded
+// transaction paid by the owner / fee payer, for the frontend's wallet to sign and send. Decoded
+// accounts have the same shape as the `fields` in `fixtures/manifest.json`.
+use base64::Engine;
+use borsh::BorshDeserialize;
+use clap::Parser;
+use serde_json::{json, Value};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_multisig_wallet::{fixtures, instruction, Multisig, Transaction};
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::message::Message;
+use std::fmt::Display;
+use std::str::FromStr;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+#[derive(Parser)]
+#[command(
+    name = "multisig-server",
+    about = "HTTP/JSON backend for multisig frontends"
+)]
+struct Args {
+    /// RPC endpoint
+    #[arg(long, short = 'u', default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+    /// Multisig program id
+    #[arg(long, default_value_t = solana_multisig_wallet::id())]
+    program_id: Pubkey,
+}
+
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+type ApiResult = Result<Value, ApiError>;
+
+fn bad_request(message: impl Display) -> ApiError {
+    ApiError {
+        status: 400,
+        message: message.to_string(),
+    }
+}
+
+fn not_found(message: impl Display) -> ApiError {
+    ApiError {
+        status: 404,
+        message: message.to_string(),
+    }
+}
+
+fn upstream(err: impl Display) -> ApiError {
+    ApiError {
+        status: 502,
+        message: err.to_string(),
+    }
+}
+
+struct Api {
+    rpc: RpcClient,
+    program_id: Pubkey,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let args = Args::parse();
+    let api = Api {
+        rpc: RpcClient::new_with_commitment(args.url, CommitmentConfig::confirmed()),
+        program_id: args.program_id,
+    };
+    let server = Server::http(&args.listen)?;
+    println!("Listening on http://{}", args.listen);
+
+    for mut request in server.incoming_requests() {
+        let (status, body) = match api.handle(&mut request) {
+            Ok(body) => (200, body),
+            Err(err) => (err.status, json!({ "error": err.message })),
+        };
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "application/json").unwrap())
+            .with_header(Header::from_bytes("Access-Control-Allow-Origin", "*").unwrap());
+        if let Err(err) = request.respond(response) {
+            eprintln!("failed to respond: {err}");
+        }
+    }
+    Ok(())
+}
+
+impl Api {
+    fn handle(&self, request: &mut Request) -> ApiResult {
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        match (request.method(), segments.as_slice()) {
+            (Method::Get, ["multisigs"]) => {
+                let owner = query_param(query, "owner")
+                    .ok_or_else(|| bad_request("missing `owner` query parameter"))?;
+                self.multisigs_of(&parse_pubkey(owner)?)
+            }
+            (Method::Get, ["multisigs", multisig]) => self.multisig(&parse_pubkey(multisig)?),
+            (Method::Get, ["multisigs", multisig, "proposals"]) => {
+                self.proposals(&parse_pubkey(multisig)?)
+            }
+            (Method::Post, ["build", action]) => {
+                let action = action.to_string();
+                let body = read_json(request)?;
+                self.build(&action, &body)
+            }
+            _ => Err(not_found(format!("no route for {path}"))),
+        }
+    }
+
+    // Owners sit at varying offsets of the owner list, so this decodes every multisig account
+    fn multisigs_of(&self, owner: &Pubkey) -> ApiResult {
+        let accounts = self
+            .rpc
+            .get_program_accounts(&self.program_id)
+            .map_err(upstream)?;
+        let multisigs: Vec<Value> = accounts
+            .into_iter()
+            .filter_map(|(key, account)| {
+                let multisig = Multisig::try_from_slice(&account.data).ok()?;
+                multisig
+                    .owners
+                    .contains(owner)
+                    .then(|| with_address(key, fixtures::multisig_json(&multisig)))
+            })
+            .collect();
+        Ok(Value::Array(multisigs))
+    }
+
+    fn multisig(&self, multisig: &Pubkey) -> ApiResult {
+        let multisig_state = self.fetch_multisig(multisig)?;
+        Ok(with_address(
+            *multisig,
+            fixtures::multisig_json(&multisig_state),
+        ))
+    }
+
+    // Proposals start with the multisig key, a memcmp at offset 0 finds them
+    fn proposals(&self, multisig: &Pubkey) -> ApiResult {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                0,
+                multisig.as_ref(),
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let accounts = self
+            .rpc
+            .get_program_accounts_with_config(&self.program_id, config)
+            .map_err(upstream)?;
+        let proposals: Vec<Value> = accounts
+            .into_iter()
+            .filter_map(|(key, account)| {
+                let transaction = Transaction::try_from_slice(&account.data).ok()?;
+                Some(with_address(key, fixtures::transaction_json(&transaction)))
+            })
+            .collect();
+        Ok(Value::Array(proposals))
+    }
+
+    fn build(&self, action: &str, body: &Value) -> ApiResult {
+        let (ix, fee_payer) = match action {
+            "sign" => {
+                let owner = body_pubkey(body, "owner")?;
+                let multisig = body_pubkey(body, "multisig")?;
+                let ix = match self.fetch_multisig(&multisig)?.strict_signing {
+                    true => instruction::sign_strict(&self.program_id, &owner, &multisig, &owner),
+                    false => instruction::sign(&self.program_id, &owner, &multisig),
+                };
+                (ix, owner)
+            }
+            "approve" => {
+                let owner = body_pubkey(body, "owner")?;
+                let transaction = body_pubkey(body, "transaction")?;
+                let proposal = self.fetch_transaction(&transaction)?;
+                let ix = instruction::approve(
+                    &self.program_id,
+                    &owner,
+                    &proposal.multisig,
+                    &transaction,
+                    &proposal.action,
+                );
+                (ix, owner)
+            }
+            "execute" => {
+                let fee_payer = body_pubkey(body, "fee_payer")?;
+                let transaction = body_pubkey(body, "transaction")?;
+                let proposal = self.fetch_transaction(&transaction)?;
+                let ix = instruction::execute_transaction(
+                    &self.program_id,
+                    &proposal.multisig,
+                    &transaction,
+                    &proposal.action,
+                );
+                (ix, fee_payer)
+            }
+            _ => return Err(not_found(format!("unknown build action {action}"))),
+        };
+        self.unsigned_transaction(ix, &fee_payer)
+    }
+
+    fn unsigned_transaction(&self, ix: Instruction, fee_payer: &Pubkey) -> ApiResult {
+        let blockhash = self.rpc.get_latest_blockhash().map_err(upstream)?;
+        let message = Message::new_with_blockhash(&[ix], Some(fee_payer), &blockhash);
+        let transaction = solana_sdk::transaction::Transaction::new_unsigned(message);
+        let bytes = bincode::serialize(&transaction).map_err(upstream)?;
+        Ok(json!({
+            "transaction": base64::engine::general_purpose::STANDARD.encode(bytes),
+            "blockhash": blockhash.to_string(),
+        }))
+    }
+
+    fn fetch_multisig(&self, multisig: &Pubkey) -> Result<Multisig, ApiError> {
+        let data = self.rpc.get_account_data(multisig).map_err(not_found)?;
+        Multisig::try_from_slice(&data).map_err(|err| bad_request(format!("not a multisig: {err}")))
+    }
+
+    fn fetch_transaction(&self, transaction: &Pubkey) -> Result<Transaction, ApiError> {
+        let data = self.rpc.get_account_data(transaction).map_err(not_found)?;
+        Transaction::try_from_slice(&data)
+            .map_err(|err| bad_request(format!("not a proposal: {err}")))
+    }
+}
+
+// Decoded account JSON with its `address` added
+fn with_address(address: Pubkey, fields: String) -> Value {
+    let mut value: Value = serde_json::from_str(&fields).expect("fixtures emit valid JSON");
+    value["address"] = Value::String(address.to_string());
+    value
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey, ApiError> {
+    Pubkey::from_str(value).map_err(|err| bad_request(format!("{value}: {err}")))
+}
+
+fn body_pubkey(body: &Value, field: &str) -> Result<Pubkey, ApiError> {
+    let value = body[field]
+        .as_str()
+        .ok_or_else(|| bad_request(format!("missing `{field}`")))?;
+    parse_pubkey(value)
+}
+
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find_map(|(key, value)| (key == name).then_some(value))
+}
+
+fn read_json(request: &mut Request) -> Result<Value, ApiError> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(bad_request)?;
+    serde_json::from_str(&body).map_err(bad_request)
+}
@@ -0,0 +1,205 @@
+// ProgramTest helpers for the multisig program, for its own integration tests and for programs
+// that integrate with it. The free functions work on any `ProgramTestContext`; `MultisigHarness`
+// bundles a context with one multisig and its owner keys for the common case.
+use solana_multisig_wallet::state::load;
+use solana_multisig_wallet::{instruction, process_instruction, Multisig};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{
+    processor, BanksClientError, ProgramTest, ProgramTestBanksClientExt, ProgramTestContext,
+};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use std::path::PathBuf;
+
+// The multisig program at its declared id, running natively
+pub fn program_test() -> ProgramTest {
+    ProgramTest::new(
+        "solana-multisig-wallet",
+        solana_multisig_wallet::id(),
+        processor!(process_instruction),
+    )
+}
+
+// The program built by `cargo build-sbf`, `None` when it hasn't been built. Native builtins aren't
+// metered, so compute and size budgets can only be checked against it
+pub fn compiled_program() -> Option<PathBuf> {
+    ["SBF_OUT_DIR", "BPF_OUT_DIR"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok().map(PathBuf::from))
+        .chain([PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../target/deploy")])
+        .map(|dir| dir.join("solana_multisig_wallet.so"))
+        .find(|path| path.exists())
+}
+
+// Sends `ix` paid by the context payer, `signers` sign on top. A transaction identical to one
+// already processed is sent on a newer blockhash, so it runs again instead of returning the
+// earlier result
+pub async fn process(
+    context: &mut ProgramTestContext,
+    ix: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let mut recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    loop {
+        let tx = Transaction::new_signed_with_payer(
+            std::slice::from_ref(&ix),
+            Some(&context.payer.pubkey()),
+            &all_signers,
+            recent_blockhash,
+        );
+        let status = context
+            .banks_client
+            .get_transaction_status(tx.signatures[0])
+            .await
+            .unwrap();
+        if status.is_none() {
+            return context.banks_client.process_transaction(tx).await;
+        }
+        recent_blockhash = context
+            .banks_client
+            .get_new_latest_blockhash(&recent_blockhash)
+            .await
+            .unwrap();
+    }
+}
+
+// Waits for a blockhash newer than anything used so far, resending an identical message would
+// otherwise just return the earlier result
+pub async fn next_blockhash(context: &mut ProgramTestContext) {
+    let current = context.banks_client.get_latest_blockhash().await.unwrap();
+    context
+        .banks_client
+        .get_new_latest_blockhash(&current)
+        .await
+        .unwrap();
+}
+
+pub async fn fund(context: &mut ProgramTestContext, account: &Pubkey, lamports: u64) {
+    let ix = system_instruction::transfer(&context.payer.pubkey(), account, lamports);
+    process(context, ix, &[]).await.unwrap();
+}
+
+// Creates and initializes a multisig account funded with `extra_lamports` on top of rent
+pub async fn setup_multisig(
+    context: &mut ProgramTestContext,
+    owners: &[Pubkey],
+    threshold: u8,
+    extra_lamports: u64,
+) -> Pubkey {
+    let program_id = solana_multisig_wallet::id();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let multisig_keypair = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &multisig_keypair.pubkey(),
+                rent.minimum_balance(Multisig::LEN) + extra_lamports,
+                Multisig::LEN as u64,
+                &program_id,
+            ),
+            instruction::create(
+                &program_id,
+                &multisig_keypair.pubkey(),
+                owners.to_vec(),
+                threshold,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &multisig_keypair],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    multisig_keypair.pubkey()
+}
+
+pub async fn sign_as(context: &mut ProgramTestContext, multisig: Pubkey, owner: &Keypair) {
+    let ix = instruction::sign(&solana_multisig_wallet::id(), &owner.pubkey(), &multisig);
+    process(context, ix, &[owner]).await.unwrap();
+}
+
+// A started program test with one multisig, its owners' keys and the lamports it holds
+pub struct MultisigHarness {
+    pub context: ProgramTestContext,
+    pub multisig: Pubkey,
+    pub owners: Vec<Keypair>,
+}
+
+impl MultisigHarness {
+    // `owner_count` fresh owners, `threshold` of them needed, `lamports` on top of rent
+    pub async fn new(owner_count: usize, threshold: u8, lamports: u64) -> Self {
+        Self::start(program_test(), owner_count, threshold, lamports).await
+    }
+
+    // Same as `new` on a program test the caller prepared, e.g. with its own program added
+    pub async fn start(
+        program_test: ProgramTest,
+        owner_count: usize,
+        threshold: u8,
+        lamports: u64,
+    ) -> Self {
+        let mut context = program_test.start_with_context().await;
+        let owners: Vec<Keypair> = (0..owner_count).map(|_| Keypair::new()).collect();
+        let keys: Vec<Pubkey> = owners.iter().map(|owner| owner.pubkey()).collect();
+        let multisig = setup_multisig(&mut context, &keys, threshold, lamports).await;
+        Self {
+            context,
+            multisig,
+            owners,
+        }
+    }
+
+    // `Sign` by the owner at `index`
+    pub async fn sign_as(&mut self, index: usize) -> Result<(), BanksClientError> {
+        let owner = &self.owners[index];
+        let ix = instruction::sign(
+            &solana_multisig_wallet::id(),
+            &owner.pubkey(),
+            &self.multisig,
+        );
+        process(&mut self.context, ix, &[owner]).await
+    }
+
+    // `Execute` paying `amount` lamports from the multisig to `destination`
+    pub async fn execute(
+        &mut self,
+        amount: u64,
+        destination: &Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let ix = instruction::execute(
+            &solana_multisig_wallet::id(),
+            &self.multisig,
+            amount,
+            destination,
+        );
+        process(&mut self.context, ix, &[]).await
+    }
+
+    pub async fn state(&mut self) -> Multisig {
+        let account = self
+            .context
+            .banks_client
+            .get_account(self.multisig)
+            .await
+            .unwrap()
+            .unwrap();
+        load(&account.data).unwrap()
+    }
+
+    pub async fn balance(&mut self, address: &Pubkey) -> u64 {
+        self.context
+            .banks_client
+            .get_balance(*address)
+            .await
+            .unwrap()
+    }
+}